@@ -56,6 +56,53 @@ export async function readSingleFileFromMultipart(
   return { file: filePart, fields };
 }
 
+/**
+ * Read string fields and at most one file from a Fastify multipart request,
+ * for endpoints where the file may instead be referenced by a field (e.g. an
+ * upload session id).
+ *
+ * - The file is read into memory as soon as it is seen, so fields sent after
+ *   it are still captured.
+ * - Returns `file: undefined` when the request carries no such file field.
+ */
+export async function readFieldsAndOptionalFile(
+  req: FastifyRequest,
+  options: {
+    fieldName: string;
+    captureFields: string[];
+  },
+): Promise<{
+  file?: { filename: string; mimetype: string; buffer: Buffer };
+  fields: Record<string, string | undefined>;
+}> {
+  const fastReq: any = req as any;
+  const parts = fastReq.parts?.();
+  if (!parts) {
+    throw new Error("Multipart support is not configured");
+  }
+
+  let file: { filename: string; mimetype: string; buffer: Buffer } | undefined;
+  const fields: Record<string, string | undefined> = {};
+
+  for await (const part of parts) {
+    if (part.type === "file") {
+      if (part.fieldname === options.fieldName && !file) {
+        file = { filename: part.filename, mimetype: part.mimetype, buffer: await part.toBuffer() };
+      } else {
+        // Drain parts we don't want so the stream can finish
+        await part.toBuffer();
+      }
+      continue;
+    }
+
+    if (part.type === "field" && options.captureFields.includes(part.fieldname)) {
+      fields[part.fieldname] = String(part.value);
+    }
+  }
+
+  return { file, fields };
+}
+
 /**
  * Read all matching file fields from a Fastify multipart request.
  *
@@ -69,6 +116,7 @@ export async function readMultipleFilesFromMultipart(
   options?: {
     fieldName?: string; // if omitted, capture all file parts
     captureFields?: string[];
+    allowNoFiles?: boolean; // files may instead be referenced by a captured field
   },
 ): Promise<{
   files: UploadedFilePart[];
@@ -100,7 +148,7 @@ export async function readMultipleFilesFromMultipart(
     }
   }
 
-  if (!files.length) {
+  if (!files.length && !options?.allowNoFiles) {
     throw new Error("No files uploaded");
   }
 
@@ -27,7 +27,7 @@ import {
 } from "./dto/document-import.dto";
 import { StagedDocumentStatus, DocumentScanJobStatus } from "@prisma/client";
 import * as fs from "fs";
-import { readSingleFileFromMultipart } from "../../infra/uploads/multipart";
+import { readFieldsAndOptionalFile, readSingleFileFromMultipart } from "../../infra/uploads/multipart";
 import { RequiresModule } from "../billing/module.guard";
 
 @RequiresModule('DOCUMENTS')
@@ -61,24 +61,57 @@ export class DocumentImportController {
     @Req() req: any,
     @Body()
     body: {
-      html_content: string;
+      html_content?: string;
+      /** Completed upload session holding the HTML, for documents too large to inline */
+      html_upload_id?: string;
       title: string;
       category?: string;
       original_format?: string;
       word_count?: number;
       folder_name?: string;
       breadcrumb?: string[];
+      metadata?: Record<string, unknown>;
     }
   ) {
     const actor = req.user as AuthenticatedUser;
     return this.documentImport.uploadHtmlFromApplet(actor, {
       htmlContent: body.html_content,
+      htmlUploadId: body.html_upload_id,
       title: body.title,
       category: body.category,
       originalFormat: body.original_format || "unknown",
       wordCount: body.word_count || 0,
       folderName: body.folder_name || "Local Upload Files",
       breadcrumb: body.breadcrumb || [],
+      metadata: body.metadata,
+    });
+  }
+
+  /**
+   * Attach the original source file to a document uploaded via upload-html.
+   * POST /document-import/documents/:id/original
+   * Expects multipart/form-data with:
+   *   - sha256: hex SHA-256 of the original
+   *   - size: its size in bytes
+   *   - file: the original, or
+   *   - uploadId: a completed upload session holding it (large files)
+   */
+  @UseGuards(JwtAuthGuard, RolesGuard)
+  @Roles(Role.ADMIN, Role.OWNER)
+  @Post("documents/:id/original")
+  async uploadOriginalFile(@Req() req: FastifyRequest, @Param("id") id: string) {
+    const actor = (req as any).user as AuthenticatedUser;
+
+    const { file, fields } = await readFieldsAndOptionalFile(req, {
+      fieldName: "file",
+      captureFields: ["sha256", "size", "uploadId"],
+    });
+
+    return this.documentImport.attachOriginalFile(actor, id, {
+      sha256: fields.sha256 || "",
+      size: Number(fields.size),
+      file: file && { fileName: file.filename, mimeType: file.mimetype, buffer: file.buffer },
+      uploadId: fields.uploadId,
     });
   }
 
@@ -0,0 +1,166 @@
+import { test } from "node:test";
+import * as assert from "node:assert/strict";
+import { createHash } from "node:crypto";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import { BadRequestException, NotFoundException, UnprocessableEntityException } from "@nestjs/common";
+import { DocumentImportService } from "./document-import.service";
+import type { AuthenticatedUser } from "../auth/jwt.strategy";
+
+const actor = { userId: "user-1", companyId: "company-1" } as AuthenticatedUser;
+const contents = Buffer.from("%PDF-1.7 lease original");
+const sha256 = createHash("sha256").update(contents).digest("hex");
+
+/** The service over in-memory fakes, recording what it stored and wrote. */
+function setup() {
+  const updates: any[] = [];
+  const stored: any[] = [];
+  const created: any[] = [];
+  const prisma = {
+    documentScanJob: {
+      findFirst: async () => ({ id: "job-1" }),
+      update: async (args: any) => args,
+    },
+    stagedDocument: {
+      create: async ({ data }: any) => {
+        created.push(data);
+        return { id: "doc-1", ...data };
+      },
+      findFirst: async ({ where }: any) =>
+        where.id === "doc-1" && where.companyId === "company-1" ? { id: "doc-1" } : null,
+      update: async (args: any) => {
+        updates.push(args);
+        return args;
+      },
+    },
+  };
+  const storage = {
+    uploadBuffer: async (options: any) => {
+      stored.push(options);
+      return `s3://bucket/${options.key}`;
+    },
+    // Every stored file downloads as the same small HTML page
+    downloadToTmp: async (uri: string) => {
+      const tmp = path.join(os.tmpdir(), `document-import-spec-${process.pid}-${path.basename(uri)}`);
+      await fs.promises.writeFile(tmp, "<html><body><p>Large scope</p></body></html>");
+      return tmp;
+    },
+  };
+  const sessions = {
+    findCompleted: (who: AuthenticatedUser, uploadId: string) => {
+      if (who.userId !== "user-1") return null;
+      if (uploadId === "up-1") {
+        return { fileUri: "s3://bucket/sessions/up-1/lease.pdf", fileName: "lease.pdf", contentType: "application/pdf", size: contents.length, sha256 };
+      }
+      if (uploadId === "up-html") {
+        return { fileUri: "s3://bucket/sessions/up-html/scope.html", fileName: "scope.html", contentType: "text/html", size: 46, sha256 };
+      }
+      return null;
+    },
+  };
+  const service = new DocumentImportService(prisma as any, storage as any, sessions as any);
+  return { service, updates, stored, created };
+}
+
+const appletUpload = {
+  title: "Scope",
+  originalFormat: "docx",
+  wordCount: 2,
+  folderName: "Jobs",
+  breadcrumb: ["Jobs", "scope.docx"],
+};
+
+test("upload-html reads large HTML back from its upload session", async () => {
+  const { service, created } = setup();
+  await service.uploadHtmlFromApplet(actor, { ...appletUpload, htmlUploadId: "up-html" });
+
+  assert.equal(created.length, 1);
+  assert.equal(created[0].htmlContent, "<html><body><p>Large scope</p></body></html>");
+  assert.equal(created[0].textContent, "Large scope");
+  assert.equal(created[0].fileSize, BigInt(created[0].htmlContent.length));
+});
+
+test("upload-html refuses a session that isn't the actor's or carries no HTML", async () => {
+  const { service, created } = setup();
+  await assert.rejects(
+    service.uploadHtmlFromApplet({ ...actor, userId: "user-2" }, { ...appletUpload, htmlUploadId: "up-html" }),
+    BadRequestException,
+  );
+  await assert.rejects(service.uploadHtmlFromApplet(actor, appletUpload), BadRequestException);
+  assert.equal(created.length, 0);
+});
+
+test("an inline original is verified, stored and linked", async () => {
+  const { service, updates, stored } = setup();
+  const result = await service.attachOriginalFile(actor, "doc-1", {
+    sha256: sha256.toUpperCase(),
+    size: contents.length,
+    file: { fileName: "Lease (signed).pdf", mimeType: "application/pdf", buffer: contents },
+  });
+
+  assert.equal(stored.length, 1);
+  assert.equal(stored[0].key, "document-import/company-1/doc-1/original/Lease__signed_.pdf");
+  assert.equal(result.fileUri, `s3://bucket/${stored[0].key}`);
+  assert.equal(updates.length, 1);
+  assert.deepEqual(updates[0].where, { id: "doc-1" });
+  assert.equal(updates[0].data.originalFileUri, result.fileUri);
+  assert.equal(updates[0].data.originalSha256, sha256);
+  assert.equal(updates[0].data.originalSize, BigInt(contents.length));
+});
+
+test("an inline original that doesn't match its hash or size is refused", async () => {
+  const { service, updates, stored } = setup();
+  const file = { fileName: "lease.pdf", mimeType: "application/pdf", buffer: contents };
+
+  await assert.rejects(
+    service.attachOriginalFile(actor, "doc-1", { sha256: "0".repeat(64), size: contents.length, file }),
+    UnprocessableEntityException,
+  );
+  await assert.rejects(
+    service.attachOriginalFile(actor, "doc-1", { sha256, size: contents.length + 1, file }),
+    UnprocessableEntityException,
+  );
+  assert.equal(stored.length, 0);
+  assert.equal(updates.length, 0);
+});
+
+test("a large original is taken from its completed upload session", async () => {
+  const { service, updates, stored } = setup();
+  const result = await service.attachOriginalFile(actor, "doc-1", { sha256, size: contents.length, uploadId: "up-1" });
+
+  // Already in storage: linked, not copied
+  assert.equal(stored.length, 0);
+  assert.equal(result.fileUri, "s3://bucket/sessions/up-1/lease.pdf");
+  assert.equal(updates[0].data.originalFileName, "lease.pdf");
+
+  // Unknown, someone else's, or not matching what was declared
+  await assert.rejects(
+    service.attachOriginalFile(actor, "doc-1", { sha256, size: contents.length, uploadId: "up-2" }),
+    BadRequestException,
+  );
+  const other = { ...actor, userId: "user-2" };
+  await assert.rejects(
+    service.attachOriginalFile(other, "doc-1", { sha256, size: contents.length, uploadId: "up-1" }),
+    BadRequestException,
+  );
+  await assert.rejects(
+    service.attachOriginalFile(actor, "doc-1", { sha256, size: 1, uploadId: "up-1" }),
+    UnprocessableEntityException,
+  );
+  assert.equal(updates.length, 1);
+});
+
+test("documents of another company and requests without a file are refused", async () => {
+  const { service } = setup();
+  const file = { fileName: "lease.pdf", mimeType: "application/pdf", buffer: contents };
+
+  await assert.rejects(
+    service.attachOriginalFile({ ...actor, companyId: "company-2" }, "doc-1", { sha256, size: contents.length, file }),
+    NotFoundException,
+  );
+  await assert.rejects(
+    service.attachOriginalFile(actor, "doc-1", { sha256, size: contents.length }),
+    BadRequestException,
+  );
+});
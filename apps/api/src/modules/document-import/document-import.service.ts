@@ -1,9 +1,12 @@
-import { Injectable, Logger, NotFoundException, ForbiddenException } from "@nestjs/common";
+import { Injectable, Logger, NotFoundException, ForbiddenException, BadRequestException, UnprocessableEntityException } from "@nestjs/common";
 import { PrismaService } from "../../infra/prisma/prisma.service";
+import { ObjectStorageService } from "../../infra/storage/object-storage.service";
+import { UploadSessionsService } from "../uploads/upload-sessions.service";
 import { AuthenticatedUser } from "../auth/jwt.strategy";
-import { StagedDocumentStatus, DocumentScanJobStatus, HtmlConversionStatus, DocumentTypeGuess } from "@prisma/client";
+import { Prisma, StagedDocumentStatus, DocumentScanJobStatus, HtmlConversionStatus, DocumentTypeGuess } from "@prisma/client";
 import * as fs from "fs";
 import * as path from "path";
+import { createHash } from "crypto";
 import { promisify } from "util";
 import * as mammoth from "mammoth";
 import pdfParse from "pdf-parse";
@@ -81,7 +84,11 @@ const MANUAL_KEYWORDS = [
 export class DocumentImportService {
   private readonly logger = new Logger(DocumentImportService.name);
 
-  constructor(private readonly prisma: PrismaService) {}
+  constructor(
+    private readonly prisma: PrismaService,
+    private readonly storage: ObjectStorageService,
+    private readonly uploadSessions: UploadSessionsService,
+  ) {}
 
   // --- Document Classification ---
 
@@ -1732,15 +1739,20 @@ ${bodyHtml}
   async uploadHtmlFromApplet(
     actor: AuthenticatedUser,
     data: {
-      htmlContent: string;
+      htmlContent?: string;
+      htmlUploadId?: string;
       title: string;
       category?: string;
       originalFormat: string;
       wordCount: number;
       folderName: string;
       breadcrumb: string[];
+      /** Author, dates and keywords the app read from the original file */
+      metadata?: Record<string, unknown>;
     }
   ) {
+    const htmlContent = await this.resolveAppletHtml(actor, data);
+
     // One scan job per unique folder path (preserves disk folder grouping)
     const scanPath = `Local Upload: ${data.folderName}`;
 
@@ -1783,20 +1795,21 @@ ${bodyHtml}
         originalPath: breadcrumb.join("/"),
         breadcrumb,
         fileType: data.originalFormat,
-        fileSize: BigInt(data.htmlContent.length),
+        fileSize: BigInt(htmlContent.length),
         mimeType: "text/html",
         status: StagedDocumentStatus.ACTIVE,
         scannedByUserId: actor.userId,
         displayTitle: data.title,
         category: data.category || "local-upload",
         tags: ["local-upload", `format:${data.originalFormat}`],
-        htmlContent: data.htmlContent,
-        textContent: data.htmlContent.replace(/<[^>]*>/g, " ").replace(/\s+/g, " ").trim().slice(0, 50000),
+        htmlContent,
+        textContent: htmlContent.replace(/<[^>]*>/g, " ").replace(/\s+/g, " ").trim().slice(0, 50000),
         conversionStatus: HtmlConversionStatus.COMPLETED,
         convertedAt: new Date(),
         documentTypeGuess: DocumentTypeGuess.UNKNOWN,
         classificationScore: 0,
         classificationReason: `Uploaded from Nexus Utilities App (${data.originalFormat}, ${data.wordCount} words)`,
+        metadata: data.metadata ? (data.metadata as Prisma.InputJsonValue) : undefined,
       },
     });
 
@@ -1821,6 +1834,111 @@ ${bodyHtml}
     };
   }
 
+  /**
+   * The HTML sent inline, or read back from the completed upload session the
+   * app used for documents too large to send in one request.
+   */
+  private async resolveAppletHtml(
+    actor: AuthenticatedUser,
+    data: { htmlContent?: string; htmlUploadId?: string },
+  ): Promise<string> {
+    if (data.htmlUploadId) {
+      const upload = this.uploadSessions.findCompleted(actor, data.htmlUploadId);
+      if (!upload) {
+        throw new BadRequestException("Upload session not found or not completed");
+      }
+      const tmpPath = await this.storage.downloadToTmp(upload.fileUri);
+      try {
+        return await readFile(tmpPath, "utf-8");
+      } finally {
+        fs.promises.rm(tmpPath, { force: true }).catch(() => undefined);
+      }
+    }
+    if (typeof data.htmlContent !== "string") {
+      throw new BadRequestException("html_content or html_upload_id is required");
+    }
+    return data.htmlContent;
+  }
+
+  // ==================== Original File from Nexus Utilities App ====================
+
+  /**
+   * Attach the original source file to a document uploaded with
+   * uploadHtmlFromApplet. The file arrives inline or as a completed upload
+   * session; either way it must match the declared SHA-256 and size.
+   */
+  async attachOriginalFile(
+    actor: AuthenticatedUser,
+    documentId: string,
+    data: {
+      sha256: string;
+      size: number;
+      file?: { fileName: string; mimeType: string; buffer: Buffer };
+      uploadId?: string;
+    }
+  ) {
+    const doc = await this.prisma.stagedDocument.findFirst({
+      where: { id: documentId, companyId: actor.companyId },
+      select: { id: true },
+    });
+    if (!doc) {
+      throw new NotFoundException("Document not found");
+    }
+
+    const sha256 = (data.sha256 || "").toLowerCase();
+    if (!/^[0-9a-f]{64}$/.test(sha256)) {
+      throw new BadRequestException("sha256 must be a hex SHA-256 digest");
+    }
+    if (!Number.isSafeInteger(data.size) || data.size < 0) {
+      throw new BadRequestException("size must be a non-negative integer");
+    }
+
+    let fileUri: string;
+    let fileName: string;
+    if (data.uploadId) {
+      const upload = this.uploadSessions.findCompleted(actor, data.uploadId);
+      if (!upload) {
+        throw new BadRequestException("Upload session not found or not completed");
+      }
+      if (upload.sha256 !== sha256 || upload.size !== data.size) {
+        throw new UnprocessableEntityException("Upload session does not match the declared sha256 and size");
+      }
+      fileUri = upload.fileUri;
+      fileName = upload.fileName;
+    } else if (data.file) {
+      const received = createHash("sha256").update(data.file.buffer).digest("hex");
+      if (data.file.buffer.length !== data.size || received !== sha256) {
+        throw new UnprocessableEntityException(
+          `File does not match: declared ${sha256} (${data.size} bytes), received ${received} (${data.file.buffer.length} bytes)`,
+        );
+      }
+      fileName = data.file.fileName || "original";
+      const safeName = fileName.replace(/[^a-zA-Z0-9_.-]/g, "_");
+      fileUri = await this.storage.uploadBuffer({
+        key: ["document-import", actor.companyId, doc.id, "original", safeName].join("/"),
+        buffer: data.file.buffer,
+        contentType: data.file.mimeType || "application/octet-stream",
+      });
+    } else {
+      throw new BadRequestException("Expected a file or an uploadId");
+    }
+
+    await this.prisma.stagedDocument.update({
+      where: { id: doc.id },
+      data: {
+        originalFileUri: fileUri,
+        originalFileName: fileName,
+        originalSha256: sha256,
+        originalSize: BigInt(data.size),
+        originalUploadedAt: new Date(),
+      },
+    });
+
+    this.logger.log(`Original attached to ${doc.id}: ${fileName} (${data.size} bytes) → ${fileUri}`);
+
+    return { id: doc.id, fileUri, fileName, size: data.size, sha256 };
+  }
+
   // ==================== Create Document From Scratch ====================
 
   /**
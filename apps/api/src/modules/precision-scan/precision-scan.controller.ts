@@ -1,4 +1,5 @@
 import {
+  BadRequestException,
   Body,
   Controller,
   Get,
@@ -15,6 +16,9 @@ import { CombinedAuthGuard, Roles, Role } from '../auth/auth.guards';
 import { AuthenticatedUser } from '../auth/jwt.strategy';
 import { PrecisionScanService } from './precision-scan.service';
 
+/** Model formats with a URL field on PrecisionScan */
+const MODEL_FORMATS = ['usdz', 'obj', 'dae', 'stl', 'gltf', 'glb', 'step', 'skp'];
+
 @Controller('precision-scans')
 export class PrecisionScanController {
   private readonly logger = new Logger(PrecisionScanController.name);
@@ -109,6 +113,8 @@ export class PrecisionScanController {
    *
    * NexBRIDGE sends files as multipart parts named `file_usdz`, `file_obj`,
    * `file_dae`, etc. plus optional `analysis` (JSON) and `jobId` (text).
+   * A model too large for one request is sent ahead through an upload
+   * session instead, and referenced here by `upload_usdz`, `upload_obj`, etc.
    */
   @UseGuards(CombinedAuthGuard)
   @Roles(Role.OWNER, Role.ADMIN, Role.MEMBER)
@@ -117,9 +123,18 @@ export class PrecisionScanController {
     @Req() req: any,
     @Param('scanId') scanId: string,
   ) {
+    const uploadFields = MODEL_FORMATS.map((format) => `upload_${format}`);
     const { files, fields } = await readMultipleFilesFromMultipart(req, {
-      captureFields: ['jobId'],
+      captureFields: ['jobId', ...uploadFields],
+      allowNoFiles: true,
     });
+    const uploads = MODEL_FORMATS.filter((format) => fields[`upload_${format}`]).map((format) => ({
+      format,
+      uploadId: fields[`upload_${format}`]!,
+    }));
+    if (!files.length && !uploads.length) {
+      throw new BadRequestException('No files uploaded');
+    }
 
     this.logger.log(
       `Upload for scan ${scanId}: ${files.length} files (meshJob=${fields.jobId ?? 'unknown'})`,
@@ -133,7 +148,10 @@ export class PrecisionScanController {
       })),
     );
 
-    return this.scans.uploadScanFiles(scanId, filePayloads);
+    return this.scans.uploadScanFiles(scanId, filePayloads, {
+      actor: req.user as AuthenticatedUser,
+      uploads,
+    });
   }
 
   /**
@@ -0,0 +1,74 @@
+import { test } from 'node:test';
+import * as assert from 'node:assert/strict';
+import { BadRequestException, NotFoundException } from '@nestjs/common';
+import { PrecisionScanService } from './precision-scan.service';
+import type { AuthenticatedUser } from '../auth/jwt.strategy';
+
+const actor = { userId: 'user-1', companyId: 'company-1' } as AuthenticatedUser;
+
+/** The service over in-memory fakes, recording what it stored and wrote. */
+function setup() {
+  const updates: any[] = [];
+  const stored: any[] = [];
+  const prisma = {
+    precisionScan: {
+      findUnique: async ({ where }: any) => (where.id === 'scan-1' ? { id: 'scan-1', companyId: 'company-1' } : null),
+      update: async (args: any) => {
+        updates.push(args);
+        return args;
+      },
+    },
+  };
+  const storage = {
+    uploadBuffer: async (options: any) => {
+      stored.push(options);
+      return `s3://bucket/${options.key}`;
+    },
+    getPublicUrlFromUri: (uri: string) => uri.replace('s3://bucket/', 'https://files.example/'),
+  };
+  const sessions = {
+    findCompleted: (who: AuthenticatedUser, uploadId: string) =>
+      uploadId === 'up-usdz' && who.userId === 'user-1'
+        ? { fileUri: 's3://bucket/sessions/up-usdz/model.usdz', fileName: 'model.usdz', contentType: 'application/octet-stream', size: 1, sha256: '' }
+        : null,
+  };
+  const service = new PrecisionScanService(prisma as any, {} as any, {} as any, storage as any, sessions as any);
+  return { service, updates, stored };
+}
+
+test('models sent as upload sessions are linked next to the inline ones', async () => {
+  const { service, updates, stored } = setup();
+  const result = await service.uploadScanFiles(
+    'scan-1',
+    [{ fieldName: 'file_obj', fileName: 'model.obj', buffer: Buffer.from('v 0 0 0') }],
+    { actor, uploads: [{ format: 'usdz', uploadId: 'up-usdz' }] },
+  );
+
+  // The session's file is already in storage: only the OBJ is uploaded
+  assert.equal(stored.length, 1);
+  assert.equal(stored[0].key, 'precision-scans/scan-1/model.obj');
+  assert.deepEqual(updates[0].data, {
+    usdzUrl: 'https://files.example/sessions/up-usdz/model.usdz',
+    objUrl: 'https://files.example/precision-scans/scan-1/model.obj',
+  });
+  assert.deepEqual(result.uploadedFiles, ['model.obj', 'model.usdz']);
+});
+
+test('an unknown session or another company fails before anything is stored', async () => {
+  const { service, updates, stored } = setup();
+  const obj = [{ fieldName: 'file_obj', fileName: 'model.obj', buffer: Buffer.from('v 0 0 0') }];
+
+  await assert.rejects(
+    service.uploadScanFiles('scan-1', obj, { actor, uploads: [{ format: 'usdz', uploadId: 'up-gone' }] }),
+    BadRequestException,
+  );
+  await assert.rejects(
+    service.uploadScanFiles('scan-1', obj, {
+      actor: { ...actor, companyId: 'company-2' },
+      uploads: [{ format: 'usdz', uploadId: 'up-usdz' }],
+    }),
+    NotFoundException,
+  );
+  assert.equal(stored.length, 0);
+  assert.equal(updates.length, 0);
+});
@@ -10,6 +10,7 @@ import { PrismaService } from '../../infra/prisma/prisma.service';
 import { MeshJobService } from '../compute-mesh/mesh-job.service';
 import { PushService } from '../notifications/push.service';
 import { ObjectStorageService } from '../../infra/storage/object-storage.service';
+import { UploadSessionsService } from '../uploads/upload-sessions.service';
 import type { MeshJob } from '../compute-mesh/mesh-node.interface';
 import { AuthenticatedUser } from '../auth/jwt.strategy';
 import { GlobalRole } from '../auth/auth.guards';
@@ -36,6 +37,7 @@ export class PrecisionScanService implements OnModuleInit, OnModuleDestroy {
     private readonly meshJobs: MeshJobService,
     private readonly push: PushService,
     private readonly storage: ObjectStorageService,
+    private readonly uploadSessions: UploadSessionsService,
   ) {}
 
   onModuleInit() {
@@ -328,6 +330,12 @@ export class PrecisionScanService implements OnModuleInit, OnModuleDestroy {
   async uploadScanFiles(
     scanId: string,
     files: Array<{ fieldName: string; fileName: string; buffer: Buffer }>,
+    // Models too large for one request arrive as completed upload sessions
+    // (`upload_{format}` fields), already in storage
+    sessionUploads?: {
+      actor: AuthenticatedUser;
+      uploads: Array<{ format: string; uploadId: string }>;
+    },
   ) {
     const scan = await this.prisma.precisionScan.findUnique({
       where: { id: scanId },
@@ -335,6 +343,23 @@ export class PrecisionScanService implements OnModuleInit, OnModuleDestroy {
     if (!scan) throw new NotFoundException('Precision scan not found');
 
     const urlMap: Record<string, string> = {};
+    const uploadedFiles = files.map((f) => f.fileName);
+
+    // Resolve sessions first so a bad id fails before anything is stored
+    if (sessionUploads?.uploads.length) {
+      const { actor, uploads } = sessionUploads;
+      if (scan.companyId !== actor.companyId) {
+        throw new NotFoundException('Precision scan not found');
+      }
+      for (const { format, uploadId } of uploads) {
+        const upload = this.uploadSessions.findCompleted(actor, uploadId);
+        if (!upload) {
+          throw new BadRequestException(`Upload session for ${format} not found or not completed`);
+        }
+        urlMap[`${format}Url`] = this.storage.getPublicUrlFromUri(upload.fileUri);
+        uploadedFiles.push(upload.fileName);
+      }
+    }
 
     for (const file of files) {
       // Determine format from the field name (file_usdz, file_obj, etc.)
@@ -376,10 +401,10 @@ export class PrecisionScanService implements OnModuleInit, OnModuleDestroy {
     }
 
     this.logger.log(
-      `PrecisionScan ${scanId}: uploaded ${files.length} files — ${Object.keys(urlMap).join(', ')}`,
+      `PrecisionScan ${scanId}: uploaded ${uploadedFiles.length} files — ${Object.keys(urlMap).join(', ')}`,
     );
 
-    return { scanId, urls: urlMap, uploadedFiles: files.map((f) => f.fileName) };
+    return { scanId, urls: urlMap, uploadedFiles };
   }
 
   private mimeTypeForFormat(ext: string): string {
@@ -0,0 +1,78 @@
+import {
+  BadRequestException,
+  Body,
+  Controller,
+  Get,
+  Param,
+  Post,
+  Put,
+  Query,
+  Req,
+  UseGuards,
+} from "@nestjs/common";
+import type { FastifyRequest } from "fastify";
+import { JwtAuthGuard } from "../auth/auth.guards";
+import type { AuthenticatedUser } from "../auth/jwt.strategy";
+import { UploadSessionsService } from "./upload-sessions.service";
+
+/**
+ * Resumable upload sessions for the NexBRIDGE desktop app.
+ *
+ *   POST /upload-sessions                  {fileName, contentType, size, sha256, chunkSize}
+ *                                          → {uploadId, offset}
+ *   GET  /upload-sessions/:id              → {uploadId, offset}
+ *   PUT  /upload-sessions/:id?offset=n     raw chunk (application/octet-stream)
+ *                                          → {uploadId, offset}  (409 + {offset} if n is stale)
+ *   POST /upload-sessions/:id/complete     → {uploadId, size, sha256, fileUri}
+ *
+ * Kept off /uploads, where POST creates signed URLs and GET serves static
+ * files.
+ */
+@Controller("upload-sessions")
+@UseGuards(JwtAuthGuard)
+export class UploadSessionsController {
+  constructor(private readonly sessions: UploadSessionsService) {}
+
+  @Post()
+  async create(
+    @Req() req: any,
+    @Body()
+    body: {
+      fileName?: string;
+      contentType?: string;
+      size?: number;
+      sha256?: string;
+      chunkSize?: number;
+    },
+  ) {
+    const actor = req.user as AuthenticatedUser;
+    return this.sessions.create(actor, body);
+  }
+
+  @Get(":id")
+  async status(@Req() req: any, @Param("id") id: string) {
+    const actor = req.user as AuthenticatedUser;
+    return this.sessions.status(actor, id);
+  }
+
+  @Put(":id")
+  async putChunk(
+    @Req() req: FastifyRequest,
+    @Param("id") id: string,
+    @Query("offset") offset: string,
+  ) {
+    const actor = (req as any).user as AuthenticatedUser;
+    // The body is a raw Buffer (registered via addContentTypeParser in main.ts)
+    const body = (req as any).body;
+    if (!body || !Buffer.isBuffer(body)) {
+      throw new BadRequestException("Expected an application/octet-stream chunk");
+    }
+    return this.sessions.putChunk(actor, id, Number(offset), body);
+  }
+
+  @Post(":id/complete")
+  async complete(@Req() req: any, @Param("id") id: string) {
+    const actor = req.user as AuthenticatedUser;
+    return this.sessions.complete(actor, id);
+  }
+}
@@ -0,0 +1,35 @@
+import { test } from "node:test";
+import * as assert from "node:assert/strict";
+import { createHash } from "node:crypto";
+import { UploadSessionsService } from "./upload-sessions.service";
+import type { AuthenticatedUser } from "../auth/jwt.strategy";
+
+const owner = { userId: "user-1", companyId: "company-1" } as AuthenticatedUser;
+
+test("only the owner, in the same company, can use a completed upload", async () => {
+  const storage = { uploadBuffer: async (options: any) => `s3://bucket/${options.key}` };
+  const service = new UploadSessionsService(storage as any);
+  // Don't keep the test process alive
+  clearInterval((service as any).cleanupHandle);
+
+  const data = Buffer.from("<html><body>scope</body></html>");
+  const sha256 = createHash("sha256").update(data).digest("hex");
+  const { uploadId } = await service.create(owner, { fileName: "scope.html", size: data.length, sha256 });
+  await service.putChunk(owner, uploadId, 0, data);
+
+  // Not completed yet
+  assert.equal(service.findCompleted(owner, uploadId), null);
+
+  const completed = await service.complete(owner, uploadId);
+  assert.deepEqual(service.findCompleted(owner, uploadId), {
+    fileUri: completed.fileUri,
+    fileName: "scope.html",
+    contentType: "application/octet-stream",
+    size: data.length,
+    sha256,
+  });
+
+  assert.equal(service.findCompleted({ ...owner, userId: "user-2" }, uploadId), null);
+  assert.equal(service.findCompleted({ ...owner, companyId: "company-2" }, uploadId), null);
+  assert.equal(service.findCompleted(owner, "unknown"), null);
+});
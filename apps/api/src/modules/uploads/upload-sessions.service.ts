@@ -0,0 +1,232 @@
+import {
+  BadRequestException,
+  ConflictException,
+  Injectable,
+  Logger,
+  NotFoundException,
+  UnprocessableEntityException,
+} from "@nestjs/common";
+import { createHash, randomUUID } from "node:crypto";
+import * as fs from "node:fs";
+import * as os from "node:os";
+import * as path from "node:path";
+import type { AuthenticatedUser } from "../auth/jwt.strategy";
+import { ObjectStorageService } from "../../infra/storage/object-storage.service";
+
+/** Sessions untouched for this long are dropped (the desktop client assumes 72 h). */
+const SESSION_TTL_MS = 72 * 60 * 60_000;
+/** Largest chunk accepted in one PUT. */
+const MAX_CHUNK_BYTES = 32 * 1024 * 1024;
+/** Largest file accepted through a session. */
+const MAX_UPLOAD_BYTES = 2 * 1024 * 1024 * 1024;
+
+type UploadSession = {
+  id: string;
+  userId: string;
+  companyId: string;
+  fileName: string;
+  contentType: string;
+  size: number;
+  sha256: string;
+  /** Bytes received so far, in order */
+  offset: number;
+  partPath: string;
+  updatedAt: number;
+  /** Set once the upload is assembled, verified and stored */
+  fileUri?: string;
+};
+
+/**
+ * Resumable, chunked uploads for the NexBRIDGE desktop app.
+ *
+ * Large files (converted HTML, original documents, scan models) arrive in
+ * fixed-size chunks appended to a temp file. The client may ask for the
+ * acknowledged offset at any time and resumes from there after a dropped
+ * connection or a restart; a chunk sent at a stale offset is rejected with
+ * 409 and the server's offset. On completion the assembled file is checked
+ * against the declared SHA-256 and stored in object storage, and endpoints
+ * that consume it look it up by upload id.
+ *
+ * Session state is kept in memory, like upload proxy tokens: after an API
+ * restart GET returns 404 and the client starts a new session.
+ */
+@Injectable()
+export class UploadSessionsService {
+  private readonly logger = new Logger(UploadSessionsService.name);
+
+  private readonly sessions = new Map<string, UploadSession>();
+
+  private readonly dir = path.join(
+    process.env.NCC_UPLOAD_TMP_DIR || os.tmpdir(),
+    "ncc_uploads",
+    "upload_sessions",
+  );
+
+  // Drop expired sessions every 10 minutes
+  private readonly cleanupHandle = setInterval(() => {
+    const now = Date.now();
+    for (const session of this.sessions.values()) {
+      if (now - session.updatedAt > SESSION_TTL_MS) this.discard(session);
+    }
+  }, 10 * 60_000);
+
+  constructor(private readonly storage: ObjectStorageService) {}
+
+  async create(
+    actor: AuthenticatedUser,
+    body: { fileName?: string; contentType?: string; size?: number; sha256?: string },
+  ) {
+    const size = Number(body.size);
+    if (!Number.isSafeInteger(size) || size <= 0) {
+      throw new BadRequestException("size must be a positive integer");
+    }
+    if (size > MAX_UPLOAD_BYTES) {
+      throw new BadRequestException("File too large. Maximum size is 2 GB.");
+    }
+    const sha256 = (body.sha256 || "").toLowerCase();
+    if (!/^[0-9a-f]{64}$/.test(sha256)) {
+      throw new BadRequestException("sha256 must be a hex SHA-256 digest");
+    }
+
+    await fs.promises.mkdir(this.dir, { recursive: true });
+    const id = randomUUID();
+    const session: UploadSession = {
+      id,
+      userId: actor.userId,
+      companyId: actor.companyId,
+      fileName: body.fileName?.trim() || "upload",
+      contentType: body.contentType || "application/octet-stream",
+      size,
+      sha256,
+      offset: 0,
+      partPath: path.join(this.dir, `${id}.part`),
+      updatedAt: Date.now(),
+    };
+    await fs.promises.writeFile(session.partPath, Buffer.alloc(0));
+    this.sessions.set(id, session);
+
+    return { uploadId: id, offset: 0 };
+  }
+
+  status(actor: AuthenticatedUser, uploadId: string) {
+    const session = this.get(actor, uploadId);
+    return { uploadId: session.id, offset: session.offset };
+  }
+
+  /** Append `chunk` if it starts at the session's offset. */
+  async putChunk(actor: AuthenticatedUser, uploadId: string, offset: number, chunk: Buffer) {
+    const session = this.get(actor, uploadId);
+    if (session.fileUri) {
+      throw new ConflictException({ message: "Upload already completed", offset: session.offset });
+    }
+    if (!Number.isSafeInteger(offset) || offset !== session.offset) {
+      throw new ConflictException({
+        message: `Expected offset ${session.offset}`,
+        offset: session.offset,
+      });
+    }
+    if (chunk.length === 0 || chunk.length > MAX_CHUNK_BYTES) {
+      throw new BadRequestException(`Chunks must be 1 byte to ${MAX_CHUNK_BYTES} bytes`);
+    }
+    if (offset + chunk.length > session.size) {
+      throw new BadRequestException("Chunk runs past the declared size");
+    }
+
+    await fs.promises.appendFile(session.partPath, chunk);
+    session.offset += chunk.length;
+    session.updatedAt = Date.now();
+    return { uploadId: session.id, offset: session.offset };
+  }
+
+  /**
+   * Verify and store the assembled file. Completing twice returns the same
+   * result, so a client that lost the first response can ask again.
+   */
+  async complete(actor: AuthenticatedUser, uploadId: string) {
+    const session = this.get(actor, uploadId);
+    if (!session.fileUri) {
+      if (session.offset !== session.size) {
+        throw new ConflictException({
+          message: `Upload incomplete: ${session.offset} of ${session.size} bytes`,
+          offset: session.offset,
+        });
+      }
+
+      const buffer = await fs.promises.readFile(session.partPath);
+      const sha256 = createHash("sha256").update(buffer).digest("hex");
+      if (sha256 !== session.sha256) {
+        this.discard(session);
+        throw new UnprocessableEntityException(
+          `Hash mismatch: declared ${session.sha256}, received ${sha256}`,
+        );
+      }
+
+      const safeName = session.fileName.replace(/[^a-zA-Z0-9_.-]/g, "_");
+      const key = [
+        "user-uploads",
+        session.companyId,
+        session.userId,
+        "sessions",
+        session.id,
+        safeName,
+      ].join("/");
+      session.fileUri = await this.storage.uploadBuffer({
+        key,
+        buffer,
+        contentType: session.contentType,
+      });
+      session.updatedAt = Date.now();
+      await fs.promises.rm(session.partPath, { force: true });
+
+      this.logger.log(
+        `Upload session ${session.id}: ${safeName} (${Math.round(session.size / 1024)} KB) → ${session.fileUri}`,
+      );
+    }
+
+    return {
+      uploadId: session.id,
+      size: session.size,
+      sha256: session.sha256,
+      fileUri: session.fileUri,
+    };
+  }
+
+  /**
+   * A completed upload, for the endpoint that consumes it (upload-html
+   * `html_upload_id`, `/original` `uploadId`, precision scan `upload_{format}`).
+   * Returns null if the id is unknown, not the actor's (user and company), or
+   * not completed.
+   */
+  findCompleted(actor: AuthenticatedUser, uploadId: string) {
+    const session = this.sessions.get(uploadId);
+    if (
+      !session ||
+      session.userId !== actor.userId ||
+      session.companyId !== actor.companyId ||
+      !session.fileUri
+    ) {
+      return null;
+    }
+    return {
+      fileUri: session.fileUri,
+      fileName: session.fileName,
+      contentType: session.contentType,
+      size: session.size,
+      sha256: session.sha256,
+    };
+  }
+
+  private get(actor: AuthenticatedUser, uploadId: string): UploadSession {
+    const session = this.sessions.get(uploadId);
+    // Someone else's session looks the same as a missing one
+    if (!session || session.userId !== actor.userId) {
+      throw new NotFoundException("Upload session not found");
+    }
+    return session;
+  }
+
+  private discard(session: UploadSession) {
+    this.sessions.delete(session.id);
+    fs.promises.rm(session.partPath, { force: true }).catch(() => undefined);
+  }
+}
@@ -3,11 +3,13 @@ import { UploadsController } from "./uploads.controller";
 import { UploadProxyController } from "./upload-proxy.controller";
 import { UploadProxyService } from "./upload-proxy.service";
 import { FileProxyController } from "./file-proxy.controller";
+import { UploadSessionsController } from "./upload-sessions.controller";
+import { UploadSessionsService } from "./upload-sessions.service";
 
 @Global()
 @Module({
-  controllers: [UploadsController, UploadProxyController, FileProxyController],
-  providers: [UploadProxyService],
-  exports: [UploadProxyService],
+  controllers: [UploadsController, UploadProxyController, FileProxyController, UploadSessionsController],
+  providers: [UploadProxyService, UploadSessionsService],
+  exports: [UploadProxyService, UploadSessionsService],
 })
 export class UploadsModule {}
@@ -1,4 +1,4 @@
-import { Controller, Post, Get, Patch, Body, Query, Req, UseGuards, Param } from "@nestjs/common";
+import { Controller, Post, Get, Patch, Delete, Body, Query, Req, UseGuards, Param } from "@nestjs/common";
 import { JwtAuthGuard, GlobalRoles, GlobalRole } from "../auth/auth.guards";
 import type { AuthenticatedUser } from "../auth/jwt.strategy";
 import { PersonalContactsService } from "./personal-contacts.service";
@@ -15,6 +15,7 @@ export class PersonalContactsController {
     @Body()
     body: {
       contacts: Array<{
+        id?: string | null;
         displayName?: string | null;
         firstName?: string | null;
         lastName?: string | null;
@@ -41,6 +42,13 @@ export class PersonalContactsController {
     return this.contacts.updatePrimaryContact(actor, contactId, body.email, body.phone);
   }
 
+  @UseGuards(JwtAuthGuard)
+  @Delete(":contactId")
+  async deleteContact(@Req() req: any, @Param("contactId") contactId: string) {
+    const actor = req.user as AuthenticatedUser;
+    return this.contacts.deleteContact(actor, contactId);
+  }
+
   @UseGuards(JwtAuthGuard)
   @Get()
   async listContacts(
@@ -5,6 +5,7 @@ import { GlobalRole } from "../auth/auth.guards";
 import { PersonalContactSource, PersonalContactSubjectType, Prisma } from "@prisma/client";
 
 interface ImportContactInput {
+  id?: string | null;           // Existing contact to update (a client that keeps ids)
   displayName?: string | null;
   firstName?: string | null;
   lastName?: string | null;
@@ -61,6 +62,7 @@ export class PersonalContactsService {
         const allPhones = input.allPhones?.length ? input.allPhones : (phone ? [phone] : null);
 
         return {
+          id: input.id?.trim() || null,
           ownerUserId,
           displayName,
           firstName: input.firstName ?? null,
@@ -92,17 +94,24 @@ export class PersonalContactsService {
       // Perform per-contact upserts without wrapping the entire import in a
       // long-lived interactive transaction. This avoids hitting Prisma's
       // 5-second interactive transaction timeout when importing large CSVs.
-      for (const c of contacts) {
+      for (const { id, ...c } of contacts) {
         const orClauses: any[] = [];
         if (c.email) orClauses.push({ email: c.email });
         if (c.phone) orClauses.push({ phone: c.phone });
 
-        const existing = await this.prisma.personalContact.findFirst({
-          where: {
-            ownerUserId,
-            OR: orClauses,
-          },
-        });
+        // A known id wins, so a contact whose email and phone both changed
+        // updates its own row; otherwise (or if that row is gone) match by
+        // email/phone
+        const existing =
+          (id
+            ? await this.prisma.personalContact.findFirst({ where: { id, ownerUserId } })
+            : null) ??
+          (await this.prisma.personalContact.findFirst({
+            where: {
+              ownerUserId,
+              OR: orClauses,
+            },
+          }));
 
         if (existing) {
           // Merge allEmails/allPhones arrays if both exist
@@ -273,6 +282,35 @@ export class PersonalContactsService {
     };
   }
 
+  /**
+   * Delete one of the current user's contacts along with its links.
+   * Referrals made from it are kept and lose the reference. Deleting a
+   * contact that is already gone succeeds with `deleted: false`, so device
+   * sync can retry safely.
+   */
+  async deleteContact(actor: AuthenticatedUser, contactId: string) {
+    const ownerUserId = this.ensureUserId(actor);
+
+    const contact = await this.prisma.personalContact.findFirst({
+      where: { id: contactId, ownerUserId },
+      select: { id: true },
+    });
+    if (!contact) {
+      return { id: contactId, deleted: false };
+    }
+
+    await this.prisma.$transaction([
+      this.prisma.personalContactLink.deleteMany({ where: { personalContactId: contactId } }),
+      this.prisma.referral.updateMany({
+        where: { personalContactId: contactId },
+        data: { personalContactId: null },
+      }),
+      this.prisma.personalContact.delete({ where: { id: contactId } }),
+    ]);
+
+    return { id: contactId, deleted: true };
+  }
+
   async linkToSubject(
     actor: AuthenticatedUser,
     personalContactId: string,
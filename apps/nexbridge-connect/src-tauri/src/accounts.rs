@@ -0,0 +1,206 @@
+// ---------------------------------------------------------------------------
+// accounts — per-company, per-user partitions of the local indexes
+// ---------------------------------------------------------------------------
+// Everything keyed to a Nexus tenant (upload statuses, nexus_doc_ids, contact
+// groups, converted HTML) is kept separately for each company + user login:
+//
+//   {app_data}/accounts.json                          known accounts, active one
+//   {app_data}/accounts/{company_id}/{user_id}/documents.db
+//   {app_data}/accounts/{company_id}/{user_id}/contact_groups.db
+//   {app_data}/accounts/{company_id}/{user_id}/converted/
+//
+// Until someone signs in, the app runs on the unpartitioned files directly in
+// {app_data}, which is where every earlier version kept them. The first
+// account to sign in takes that data over: the databases are snapshotted into
+// its partition and the originals deleted, so nothing is left behind to show
+// up under a second account.
+//
+// The desktop app swaps partitions in place (see `switch_account` in app.rs);
+// the CLI opens whichever partition was active last.
+// ---------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+const REGISTRY_FILE: &str = "accounts.json";
+const ACCOUNTS_DIR: &str = "accounts";
+
+/// Identifies one partition: a user within a company.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountKey {
+    pub company_id: String,
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountInfo {
+    pub company_id: String,
+    pub user_id: String,
+    pub company_name: Option<String>,
+    pub last_active_at: String,
+}
+
+impl AccountInfo {
+    pub fn key(&self) -> AccountKey {
+        AccountKey {
+            company_id: self.company_id.clone(),
+            user_id: self.user_id.clone(),
+        }
+    }
+}
+
+/// Contents of accounts.json.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountRegistry {
+    #[serde(default)]
+    pub accounts: Vec<AccountInfo>,
+    pub active: Option<AccountKey>,
+    /// Set once the pre-partitioning data in {app_data} has been handed to
+    /// the first account (or there was none to hand over).
+    #[serde(default)]
+    pub legacy_migrated: bool,
+}
+
+impl AccountRegistry {
+    /// Record `key` as the active account, adding it if it's new.
+    pub fn activate(&mut self, key: &AccountKey, company_name: Option<String>) -> AccountInfo {
+        let now = chrono::Utc::now().to_rfc3339();
+        let info = match self.accounts.iter_mut().find(|a| a.key() == *key) {
+            Some(existing) => {
+                existing.last_active_at = now;
+                if company_name.is_some() {
+                    existing.company_name = company_name;
+                }
+                existing.clone()
+            }
+            None => {
+                let info = AccountInfo {
+                    company_id: key.company_id.clone(),
+                    user_id: key.user_id.clone(),
+                    company_name,
+                    last_active_at: now,
+                };
+                self.accounts.push(info.clone());
+                info
+            }
+        };
+        self.active = Some(key.clone());
+        info
+    }
+}
+
+/// Where one partition keeps its files.
+#[derive(Debug, Clone)]
+pub struct Partition {
+    pub dir: PathBuf,
+    pub documents_db: PathBuf,
+    pub contact_groups_db: PathBuf,
+    pub converted_dir: PathBuf,
+}
+
+impl Partition {
+    pub fn in_dir(dir: PathBuf) -> Self {
+        Self {
+            documents_db: dir.join("documents.db"),
+            contact_groups_db: dir.join("contact_groups.db"),
+            converted_dir: dir.join("converted"),
+            dir,
+        }
+    }
+
+    /// The unpartitioned layout used before accounts existed.
+    pub fn legacy(app_dir: &Path) -> Self {
+        Self::in_dir(app_dir.to_path_buf())
+    }
+
+    pub fn for_account(app_dir: &Path, key: &AccountKey) -> Self {
+        Self::in_dir(
+            app_dir
+                .join(ACCOUNTS_DIR)
+                .join(path_segment(&key.company_id))
+                .join(path_segment(&key.user_id)),
+        )
+    }
+
+    /// Whether either database exists here.
+    pub fn has_data(&self) -> bool {
+        self.documents_db.exists() || self.contact_groups_db.exists()
+    }
+}
+
+pub fn load_registry(app_dir: &Path) -> AccountRegistry {
+    let path = app_dir.join(REGISTRY_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+            eprintln!("[accounts] Ignoring unreadable {}: {}", path.display(), e);
+            AccountRegistry::default()
+        }),
+        Err(_) => AccountRegistry::default(),
+    }
+}
+
+/// Write accounts.json via a temp file so a crash can't leave it half-written.
+pub fn save_registry(app_dir: &Path, registry: &AccountRegistry) -> Result<(), String> {
+    let path = app_dir.join(REGISTRY_FILE);
+    let tmp = app_dir.join(format!("{}.tmp", REGISTRY_FILE));
+    let json = serde_json::to_string_pretty(registry).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The partition to open at startup: the last active account's, or the
+/// legacy files if nobody has signed in since partitioning was introduced.
+pub fn active_partition(app_dir: &Path) -> Partition {
+    let registry = load_registry(app_dir);
+    match registry.active {
+        Some(key) if registry.legacy_migrated => Partition::for_account(app_dir, &key),
+        _ => Partition::legacy(app_dir),
+    }
+}
+
+/// Move the legacy converted/ cache into `target` (if it has none yet).
+pub fn move_legacy_cache(app_dir: &Path, target: &Partition) -> Result<(), String> {
+    let legacy = Partition::legacy(app_dir).converted_dir;
+    if !legacy.exists() || target.converted_dir.exists() {
+        return Ok(());
+    }
+    std::fs::rename(&legacy, &target.converted_dir).map_err(|e| {
+        format!(
+            "Failed to move {} to {}: {}",
+            legacy.display(),
+            target.converted_dir.display(),
+            e
+        )
+    })
+}
+
+/// Delete the legacy databases once they've been copied into a partition.
+/// Call only after the connections to them have been closed.
+pub fn remove_legacy_databases(app_dir: &Path) {
+    let legacy = Partition::legacy(app_dir);
+    for db in [&legacy.documents_db, &legacy.contact_groups_db] {
+        for suffix in ["", "-journal", "-wal", "-shm"] {
+            let path = PathBuf::from(format!("{}{}", db.display(), suffix));
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    eprintln!("[accounts] Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Ids are used as directory names as-is when they're plain (uuid/cuid);
+/// anything else is hashed so it can't escape or collide on disk.
+fn path_segment(id: &str) -> String {
+    let plain = !id.is_empty()
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if plain {
+        id.to_string()
+    } else {
+        let digest = Sha256::digest(id.as_bytes());
+        format!("h-{}", &hex::encode(digest)[..32])
+    }
+}
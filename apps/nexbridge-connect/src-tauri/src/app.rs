@@ -0,0 +1,1569 @@
+// ---------------------------------------------------------------------------
+// app — the desktop app: Tauri state, commands, tray and window setup
+// ---------------------------------------------------------------------------
+// Only built with the "gui" feature (on by default). Everything it calls
+// into lives in the core modules declared in lib.rs, which the headless
+// `nexbridge` CLI uses as well.
+// ---------------------------------------------------------------------------
+
+use crate::{
+    accounts, autostart, bandwidth, categorize, chunked_upload, contact_duplicates, contact_normalizer,
+    contact_providers, contact_sync, contacts, converter, documents, encryption, index_transfer, input,
+    keep_alive, precision_scan, scheduler, settings, smart_groups, system_info, tray, vcard, video,
+};
+use crate::accounts::{AccountInfo, AccountKey, Partition};
+use crate::autostart::AutostartStatus;
+use crate::bandwidth::TransferStatus;
+use crate::cache::{CacheStats, ConversionCache, PurgeResult};
+use crate::categorize::CategoryProposal;
+use crate::contact_duplicates::MergeProposal;
+use crate::contact_normalizer::{ApplyResult, ContactNormalization, NormalizeOptions};
+use crate::contact_providers::{ContactProviderConfig, ContactProviderStatus};
+use crate::contact_sync::{ContactSyncSummary, PrimaryOverride};
+use crate::contacts::{Contact, ContactUpdate};
+use crate::contact_groups::{ContactGroupIndex, ContactGroup, ContactSyncState};
+use crate::encryption::{DataKey, EncryptionStatus, KeyLookup, KeySource, WipeResult};
+use crate::converter::{ConversionResult, OutputFormat};
+use crate::index_transfer::{ExportFormat, ExportResult, ImportResult, PathMapping};
+use crate::index::{
+    BulkOperation, CategoryRule, DocumentIndex, DocumentPage, DocumentQuery, DocumentStatus, DocumentStats,
+    IndexedDocument, MetadataFilter, StatusHistoryEntry, UndoResult,
+};
+use crate::nexus_api::{Credentials, NexusApi};
+use crate::scheduler::{SyncRun, SyncScheduler, SyncTrigger};
+use crate::settings::SyncSettings;
+use crate::smart_groups::{GroupMembers, SmartGroupPreview, SmartRules};
+use crate::uploader::{UploadQueue, UploadProgress, UploadRequest, UploadResult};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Install a global panic hook that logs panic info to a file in the app data dir.
+/// This is critical for diagnosing crashes in release builds.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // Build a panic log message
+        let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())).unwrap_or_else(|| "unknown".to_string());
+        let payload = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "<unknown panic payload>".to_string()
+        };
+        let thread = std::thread::current();
+        let thread_name = thread.name().unwrap_or("<unnamed>");
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        let msg = format!(
+            "[{}] PANIC on thread '{}' at {}:\n  {}\n\n",
+            timestamp, thread_name, location, payload
+        );
+
+        // Write to app data dir
+        if let Some(data_dir) = dirs_next::data_dir() {
+            let app_dir = data_dir.join("com.nexus.nexbridge-connect");
+            let _ = std::fs::create_dir_all(&app_dir);
+            let log_path = app_dir.join("panic.log");
+            use std::io::Write;
+            if let Ok(mut f) = std::fs::OpenOptions::new().create(true).append(true).open(&log_path) {
+                let _ = f.write_all(msg.as_bytes());
+            }
+        }
+
+        // Also print to stderr
+        eprintln!("{}", msg);
+
+        // Run the default hook (prints the standard panic message)
+        default_hook(info);
+    }));
+}
+
+/// SQLite path for the placeholder indexes used while local data is locked.
+const IN_MEMORY_DB: &str = ":memory:";
+
+pub struct AppState {
+    pub app_data_dir: PathBuf,
+    /// Signed-in account whose partition the indexes below point at; None
+    /// while running on the pre-partitioning files. Held while switching.
+    pub active_account: Mutex<Option<AccountKey>>,
+    /// Key for the encrypted indexes and cache; None while locked.
+    pub data_key: Mutex<Option<DataKey>>,
+    pub encryption_status: Mutex<EncryptionStatus>,
+    pub settings: Mutex<SyncSettings>,
+    pub document_index: DocumentIndex,
+    pub upload_queue: UploadQueue,
+    /// Nexus API client; the frontend sets its session after sign-in
+    pub nexus_api: NexusApi,
+    pub contact_groups: ContactGroupIndex,
+    pub conversion_cache: ConversionCache,
+    pub scheduler: SyncScheduler,
+}
+
+
+/// Contacts from every source: the native address book, imported vCards,
+/// vCard folders and CardDAV accounts (see contact_providers.rs).
+#[tauri::command]
+async fn get_contacts(state: State<'_, AppState>) -> Result<Vec<Contact>, String> {
+    contact_providers::load_contacts(&state).await
+}
+
+/// Proposed clean-ups (phone formats, state names, ZIP codes, name case)
+/// for the given contacts, default all of them, without changing anything.
+/// Only contacts with something to change or flag are returned.
+#[tauri::command]
+async fn preview_contact_normalization(
+    state: State<'_, AppState>,
+    contact_ids: Option<Vec<String>>,
+    options: Option<NormalizeOptions>,
+) -> Result<Vec<ContactNormalization>, String> {
+    let options = options.unwrap_or_default();
+    options.validate()?;
+    let contacts: Vec<Contact> = contact_providers::load_contacts(&state)
+        .await?
+        .into_iter()
+        .filter(|c| contact_ids.as_ref().is_none_or(|ids| ids.contains(&c.id)))
+        .collect();
+    let writable = writable_contact_ids(&state)?;
+    Ok(contact_normalizer::preview(&contacts, &options)
+        .into_iter()
+        .map(|mut n| {
+            n.writable = writable.contains(&n.contact_id);
+            n
+        })
+        .collect())
+}
+
+/// Write the previewed clean-ups for the given contacts back to the native
+/// address book. They are recomputed from the current values, so a contact
+/// edited since the preview gets what it needs now.
+#[tauri::command]
+async fn apply_contact_normalization(
+    state: State<'_, AppState>,
+    contact_ids: Vec<String>,
+    options: Option<NormalizeOptions>,
+) -> Result<ApplyResult, String> {
+    let options = options.unwrap_or_default();
+    options.validate()?;
+    let writable = writable_contact_ids(&state)?;
+    let (updates, read_only): (Vec<_>, Vec<_>) = contact_providers::load_contacts(&state)
+        .await?
+        .iter()
+        .filter(|c| contact_ids.contains(&c.id))
+        .map(|c| contact_normalizer::normalize(c, &options))
+        .filter(|n| !n.changes.is_empty())
+        .partition(|n| writable.contains(&n.contact_id));
+    let updates: Vec<ContactUpdate> = updates
+        .into_iter()
+        .map(|n| ContactUpdate { id: n.contact_id, changes: n.changes })
+        .collect();
+
+    let updated = if updates.is_empty() {
+        0
+    } else {
+        tauri::async_runtime::spawn_blocking(move || contacts::apply_contact_updates(&updates))
+            .await
+            .map_err(|e| e.to_string())??
+            .updated
+    };
+    Ok(ApplyResult { updated, read_only: read_only.len() as u32 })
+}
+
+/// Contacts the native helper can write to: none without a writable
+/// helper, and never CardDAV or vCard contacts.
+fn writable_contact_ids(state: &AppState) -> Result<std::collections::HashSet<String>, String> {
+    if !contacts::supports_write_back() {
+        return Ok(Default::default());
+    }
+    Ok(state.contact_groups
+        .get_provider_contacts(contact_providers::NATIVE_PROVIDER_ID)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| c.id)
+        .collect())
+}
+
+#[tauri::command]
+fn get_sync_settings(state: State<AppState>) -> SyncSettings {
+    state.settings.lock().unwrap().clone()
+}
+
+/// Apply `change` to the settings, then validate, persist and apply the
+/// result and emit `settings-changed`. Nothing changes if any step fails.
+pub(crate) fn change_settings(
+    app: &AppHandle,
+    state: &AppState,
+    change: impl FnOnce(&mut SyncSettings),
+) -> Result<SyncSettings, String> {
+    let mut current = state.settings.lock().unwrap();
+    let mut updated = current.clone();
+    change(&mut updated);
+    updated.validate()?;
+    if updated.launch_at_startup != current.launch_at_startup {
+        autostart::set_enabled(app, updated.launch_at_startup)?;
+    }
+    settings::save(&state.app_data_dir, &updated)?;
+    bandwidth::limiter().configure(&updated.transfer_limits)?;
+    state.conversion_cache.set_budget(updated.cache_budget_bytes);
+    *current = updated.clone();
+    drop(current);
+
+    state.scheduler.wake();
+    let _ = app.emit("settings-changed", &updated);
+    Ok(updated)
+}
+
+#[tauri::command]
+fn update_sync_settings(app: AppHandle, state: State<AppState>, mut settings: SyncSettings) -> Result<SyncSettings, String> {
+    // Contact sources carry keyring entries and cached contacts, so they only
+    // change through add/remove_contact_provider
+    change_settings(&app, &state, |current| {
+        settings.contact_providers = std::mem::take(&mut current.contact_providers);
+        *current = settings
+    })
+}
+
+#[tauri::command]
+fn set_auto_sync(app: AppHandle, state: State<AppState>, enabled: bool) -> Result<SyncSettings, String> {
+    change_settings(&app, &state, |settings| settings.auto_sync_enabled = enabled)
+}
+
+#[tauri::command]
+fn set_selected_contacts(app: AppHandle, state: State<AppState>, ids: Vec<String>) -> Result<(), String> {
+    change_settings(&app, &state, |settings| settings.selected_contact_ids = ids).map(|_| ())
+}
+
+#[tauri::command]
+fn record_sync(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    change_settings(&app, &state, |settings| {
+        settings.last_sync_at = Some(chrono::Utc::now().to_rfc3339())
+    })
+    .map(|_| ())
+}
+
+/// Push the given contacts (default: the saved selection) to Nexus, sending
+/// only what changed since the last sync. `primary_overrides` are the
+/// primary email/phone picked per contact in the review dialog; they are
+/// kept for later syncs.
+#[tauri::command]
+async fn sync_contacts(
+    state: State<'_, AppState>,
+    contact_ids: Option<Vec<String>>,
+    primary_overrides: Option<std::collections::HashMap<String, PrimaryOverride>>,
+) -> Result<ContactSyncSummary, String> {
+    for (contact_id, primary) in primary_overrides.unwrap_or_default() {
+        state.contact_groups
+            .set_primary_overrides(&contact_id, primary.email.as_deref(), primary.phone.as_deref())
+            .map_err(|e| e.to_string())?;
+    }
+    let selected = contact_ids.unwrap_or_else(|| state.settings.lock().unwrap().selected_contact_ids.clone());
+    let device = contact_providers::load_contacts(&state).await?;
+    contact_sync::sync_contacts(&state.nexus_api, &state.contact_groups, &device, &selected).await
+}
+
+/// Likely duplicates among the device contacts, as merge proposals.
+/// Contacts already merged into another are left out.
+#[tauri::command]
+async fn find_duplicate_contacts(state: State<'_, AppState>) -> Result<Vec<MergeProposal>, String> {
+    let merged: std::collections::HashSet<String> = state.contact_groups
+        .get_merges()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(merged_id, _)| merged_id)
+        .collect();
+    let contacts: Vec<Contact> = contact_providers::load_contacts(&state)
+        .await?
+        .into_iter()
+        .filter(|c| !merged.contains(&c.id))
+        .collect();
+    Ok(contact_duplicates::find_duplicates(&contacts))
+}
+
+/// Merge `merged_ids` into `kept_id`: groups and the sync selection move to
+/// the kept contact, and the duplicates are no longer synced (copies
+/// already in Nexus are deleted on the next sync). `primary_email` and
+/// `primary_phone` are the values picked for the kept contact.
+#[tauri::command]
+fn merge_contacts(
+    app: AppHandle,
+    state: State<AppState>,
+    kept_id: String,
+    merged_ids: Vec<String>,
+    primary_email: Option<String>,
+    primary_phone: Option<String>,
+) -> Result<u32, String> {
+    let merged = state.contact_groups
+        .merge_contacts(&kept_id, &merged_ids)
+        .map_err(|e| e.to_string())?;
+    if primary_email.is_some() || primary_phone.is_some() {
+        state.contact_groups
+            .set_primary_overrides(&kept_id, primary_email.as_deref(), primary_phone.as_deref())
+            .map_err(|e| e.to_string())?;
+    }
+
+    let selected = state.settings.lock().unwrap().selected_contact_ids.clone();
+    if selected.iter().any(|id| merged_ids.contains(id)) {
+        change_settings(&app, &state, |settings| {
+            let ids = &mut settings.selected_contact_ids;
+            ids.retain(|id| !merged_ids.contains(id));
+            if !ids.contains(&kept_id) {
+                ids.push(kept_id.clone());
+            }
+        })?;
+    }
+    Ok(merged)
+}
+
+/// Per-contact sync status and last error.
+#[tauri::command]
+fn get_contact_sync_status(state: State<AppState>) -> Result<Vec<ContactSyncState>, String> {
+    state.contact_groups
+        .get_sync_states()
+        .map_err(|e| e.to_string())
+}
+
+/// Import a .vcf file as a contact source; its contacts show up with the
+/// device contacts from then on. Returns how many it holds.
+#[tauri::command]
+fn import_vcard_file(state: State<AppState>, path: String) -> Result<usize, String> {
+    let (dest, count) = vcard::import_file(std::path::Path::new(&path), &state.app_data_dir.join(vcard::VCARD_DIR))?;
+    eprintln!("[vcard] Imported {} contact(s) into {}", count, dest.display());
+    Ok(count)
+}
+
+/// Write a group's contacts (or `contact_ids`) to one .vcf file, version
+/// "3.0" or "4.0" (default), with CATEGORIES from their groups. Returns
+/// how many contacts were written.
+#[tauri::command]
+async fn export_contacts_vcard(
+    state: State<'_, AppState>,
+    path: String,
+    group_id: Option<String>,
+    contact_ids: Option<Vec<String>>,
+    version: Option<String>,
+) -> Result<usize, String> {
+    let version = version.as_deref().map(vcard::VCardVersion::parse).transpose()?.unwrap_or(vcard::VCardVersion::V4);
+    let contacts = contact_providers::load_contacts(&state).await?;
+    let groups = state.contact_groups.list_groups().map_err(|e| e.to_string())?;
+    let members = smart_groups::group_members(&state.contact_groups, &contacts, None)?;
+    let ids: std::collections::HashSet<String> = match (group_id, contact_ids) {
+        (Some(group_id), _) => members
+            .iter()
+            .find(|m| m.group_id == group_id)
+            .map(|m| m.contact_ids.iter().cloned().collect())
+            .unwrap_or_default(),
+        (None, Some(ids)) => ids.into_iter().collect(),
+        (None, None) => return Err("Choose a group or contacts to export".to_string()),
+    };
+
+    let mut cards = Vec::new();
+    for contact in contacts.iter().filter(|c| ids.contains(&c.id)) {
+        let categories = groups
+            .iter()
+            .filter(|g| members.iter().any(|m| m.group_id == g.id && m.contact_ids.contains(&contact.id)))
+            .map(|g| g.name.clone())
+            .collect();
+        cards.push(vcard::VCard::from_contact(contact, categories));
+    }
+    std::fs::write(&path, vcard::write(&cards, version)).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    Ok(cards.len())
+}
+
+/// Configured CardDAV accounts and vCard folders, with how their last
+/// refresh went.
+#[tauri::command]
+fn list_contact_providers(state: State<AppState>) -> Result<Vec<ContactProviderStatus>, String> {
+    let configs = state.settings.lock().unwrap().contact_providers.clone();
+    let states = state.contact_groups.get_provider_sync_states().map_err(|e| e.to_string())?;
+    Ok(configs
+        .into_iter()
+        .map(|config| {
+            let sync = states.iter().find(|s| s.provider_id == config.id());
+            ContactProviderStatus {
+                contact_count: sync.map(|s| s.contact_count).unwrap_or(0),
+                last_synced_at: sync.and_then(|s| s.last_synced_at.clone()),
+                error: sync.and_then(|s| s.error.clone()),
+                config,
+            }
+        })
+        .collect())
+}
+
+/// Add a CardDAV account or vCard folder. It is fetched once first, so a
+/// wrong URL or password is reported here rather than on the next sync; a
+/// CardDAV password goes to the OS keyring. An empty id gets a new one.
+#[tauri::command]
+async fn add_contact_provider(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    mut config: ContactProviderConfig,
+    password: Option<String>,
+) -> Result<ContactProviderStatus, String> {
+    match &mut config {
+        ContactProviderConfig::Carddav { id, .. } | ContactProviderConfig::VcardDirectory { id, .. } => {
+            if id.is_empty() {
+                *id = uuid::Uuid::new_v4().to_string();
+            }
+        }
+    }
+    config.validate()?;
+    if state.settings.lock().unwrap().contact_providers.iter().any(|p| p.id() == config.id()) {
+        return Err(format!("Contact source '{}' already exists", config.id()));
+    }
+
+    let provider = contact_providers::provider_for(&config, password.as_deref())?;
+    contact_providers::refresh(&state, provider.as_ref()).await?;
+    let saved = match (&config, &password) {
+        (ContactProviderConfig::Carddav { id, .. }, Some(password)) => contact_providers::store_password(id, password),
+        _ => Ok(()),
+    }
+    .and_then(|_| change_settings(&app, &state, |settings| settings.contact_providers.push(config.clone())));
+    if let Err(e) = saved {
+        let _ = state.contact_groups.remove_provider(config.id());
+        return Err(e);
+    }
+
+    let sync = state.contact_groups
+        .get_provider_sync_state(config.id())
+        .map_err(|e| e.to_string())?;
+    Ok(ContactProviderStatus {
+        contact_count: sync.as_ref().map(|s| s.contact_count).unwrap_or(0),
+        last_synced_at: sync.and_then(|s| s.last_synced_at),
+        error: None,
+        config,
+    })
+}
+
+/// Remove a contact source, its cached contacts and stored password. Its
+/// contacts already in Nexus are deleted on the next sync, like any
+/// contact that disappears from the device.
+#[tauri::command]
+fn remove_contact_provider(app: AppHandle, state: State<AppState>, id: String) -> Result<(), String> {
+    let removed = state.settings.lock().unwrap().contact_providers.iter().find(|p| p.id() == id).cloned();
+    change_settings(&app, &state, |settings| settings.contact_providers.retain(|p| p.id() != id))?;
+    state.contact_groups.remove_provider(&id).map_err(|e| e.to_string())?;
+    if let Some(ContactProviderConfig::Carddav { .. }) = removed {
+        contact_providers::delete_password(&id);
+    }
+    Ok(())
+}
+
+/// Whether the app is actually registered to start at login, which can
+/// differ from the stored `launch_at_startup` (e.g. removed in the OS
+/// settings).
+#[tauri::command]
+fn get_launch_at_startup_status(app: AppHandle) -> Result<AutostartStatus, String> {
+    autostart::status(&app)
+}
+
+// ============ Sync Scheduler Commands ============
+
+/// Recent auto-sync runs, newest first.
+#[tauri::command]
+fn get_sync_history(state: State<AppState>, limit: Option<u32>) -> Vec<SyncRun> {
+    state.scheduler.history(limit.unwrap_or(20) as usize)
+}
+
+/// Run contact sync, folder rescans and pending uploads now, without
+/// waiting for the next scheduled run.
+#[tauri::command]
+async fn run_sync_now(app: AppHandle, state: State<'_, AppState>) -> Result<SyncRun, String> {
+    state.scheduler.sync(&app, SyncTrigger::Manual).await
+}
+
+// ============ Account Commands ============
+
+#[tauri::command]
+fn get_active_account(state: State<AppState>) -> Option<AccountInfo> {
+    let active = state.active_account.lock().unwrap().clone()?;
+    accounts::load_registry(&state.app_data_dir)
+        .accounts
+        .into_iter()
+        .find(|a| a.key() == active)
+}
+
+#[tauri::command]
+fn list_accounts(state: State<AppState>) -> Vec<AccountInfo> {
+    accounts::load_registry(&state.app_data_dir).accounts
+}
+
+/// Point the document index, contact groups and conversion cache at the
+/// partition for this company + user. Called after every sign-in; a no-op if
+/// the account is already active. The first account to sign in inherits the
+/// data from before accounts were partitioned. While local data is locked the
+/// account is only remembered, and opened by `unlock_local_data`.
+#[tauri::command]
+fn switch_account(
+    app: AppHandle,
+    state: State<AppState>,
+    company_id: String,
+    user_id: String,
+    company_name: Option<String>,
+) -> Result<AccountInfo, String> {
+    if company_id.trim().is_empty() || user_id.trim().is_empty() {
+        return Err("Company and user id are required".to_string());
+    }
+    let key = AccountKey { company_id, user_id };
+    let mut active = state.active_account.lock().unwrap();
+    let unlocked = state.data_key.lock().unwrap().is_some();
+
+    if unlocked && active.as_ref() != Some(&key) {
+        open_partition(&state, Some(&key))?;
+        // Anything queued belongs to the previous account
+        state.upload_queue.reset();
+    }
+
+    let mut registry = accounts::load_registry(&state.app_data_dir);
+    let info = registry.activate(&key, company_name);
+    accounts::save_registry(&state.app_data_dir, &registry)?;
+    let changed = active.as_ref() != Some(&key);
+    *active = Some(key);
+
+    if changed && unlocked {
+        let _ = app.emit("account-changed", &info);
+    }
+    Ok(info)
+}
+
+// ============ API Session Commands ============
+
+/// Hand the signed-in API session to the Rust side. Called after login and
+/// on restore; tokens the client refreshes itself come back through the
+/// `api-session-refreshed` event.
+#[tauri::command]
+fn set_api_session(
+    state: State<AppState>,
+    api_url: String,
+    access_token: String,
+    refresh_token: Option<String>,
+) -> Result<(), String> {
+    if api_url.trim().is_empty() || access_token.is_empty() {
+        return Err("API URL and access token are required".to_string());
+    }
+    state.nexus_api.set_session(&api_url, Credentials { access_token, refresh_token });
+    Ok(())
+}
+
+#[tauri::command]
+fn clear_api_session(state: State<AppState>) {
+    state.nexus_api.clear_session();
+}
+
+/// Reopen the indexes and cache on `account`'s partition (or the legacy
+/// files for None) under the current data key. The first account opened
+/// takes over the legacy data.
+fn open_partition(state: &AppState, account: Option<&AccountKey>) -> Result<(), String> {
+    let data_key = state.data_key.lock().unwrap().clone().ok_or("Local data is locked")?;
+    let mut registry = accounts::load_registry(&state.app_data_dir);
+    let legacy = Partition::legacy(&state.app_data_dir);
+    let partition = match account {
+        Some(account) => Partition::for_account(&state.app_data_dir, account),
+        None => legacy.clone(),
+    };
+    std::fs::create_dir_all(&partition.dir)
+        .map_err(|e| format!("Failed to create {}: {}", partition.dir.display(), e))?;
+
+    // Decided per database: a sign-in that failed half way through has
+    // already copied one of them, and the other must still follow it
+    let migrate_legacy = account.is_some() && !registry.legacy_migrated && legacy.has_data();
+    if migrate_legacy {
+        eprintln!("[accounts] Moving existing local data into {}", partition.dir.display());
+        if legacy.documents_db.exists() && !partition.documents_db.exists() {
+            migrate_database(&partition.documents_db, |dest| {
+                DocumentIndex::new(&legacy.documents_db, Some(&data_key))
+                    .and_then(|index| index.snapshot_to(dest, Some(&data_key)))
+            })
+            .map_err(|e| format!("Failed to migrate document index: {}", e))?;
+        }
+        if legacy.contact_groups_db.exists() && !partition.contact_groups_db.exists() {
+            migrate_database(&partition.contact_groups_db, |dest| {
+                ContactGroupIndex::new(&legacy.contact_groups_db, Some(&data_key))
+                    .and_then(|groups| groups.snapshot_to(dest, Some(&data_key)))
+            })
+            .map_err(|e| format!("Failed to migrate contact groups: {}", e))?;
+        }
+        accounts::move_legacy_cache(&state.app_data_dir, &partition)?;
+    }
+
+    state.document_index
+        .reopen(&partition.documents_db, Some(&data_key))
+        .map_err(|e| format!("Failed to open document index: {}", e))?;
+    state.contact_groups
+        .reopen(&partition.contact_groups_db, Some(&data_key))
+        .map_err(|e| format!("Failed to open contact groups: {}", e))?;
+    state.conversion_cache.set_key(Some(data_key));
+    state.conversion_cache.set_dir(partition.converted_dir.clone());
+    if let Err(e) = state.conversion_cache.prune(&state.document_index) {
+        eprintln!("[cache] Prune after opening {} failed: {}", partition.dir.display(), e);
+    }
+
+    if account.is_some() && !registry.legacy_migrated {
+        if migrate_legacy {
+            accounts::remove_legacy_databases(&state.app_data_dir);
+        }
+        registry.legacy_migrated = true;
+        accounts::save_registry(&state.app_data_dir, &registry)?;
+    }
+    Ok(())
+}
+
+/// Snapshot into a temporary file next to `dest` and rename it into place, so
+/// an interrupted copy never looks like a migrated database.
+fn migrate_database(
+    dest: &std::path::Path,
+    snapshot: impl FnOnce(&std::path::Path) -> Result<(), rusqlite::Error>,
+) -> Result<(), String> {
+    let partial = PathBuf::from(format!("{}.migrating", dest.display()));
+    let _ = std::fs::remove_file(&partial);
+    snapshot(&partial).map_err(|e| e.to_string())?;
+    std::fs::rename(&partial, dest).map_err(|e| e.to_string())
+}
+
+// ============ Local Data Commands ============
+
+#[tauri::command]
+fn get_encryption_status(state: State<AppState>) -> EncryptionStatus {
+    encryption_status(&state)
+}
+
+fn encryption_status(state: &AppState) -> EncryptionStatus {
+    let mut status = state.encryption_status.lock().unwrap().clone();
+    status.unlocked = state.data_key.lock().unwrap().is_some();
+    status
+}
+
+/// Derive the data key from the passphrase (choosing it on first use) and
+/// open the active account's data. Only needed where there is no OS
+/// credential store.
+#[tauri::command]
+fn unlock_local_data(state: State<AppState>, passphrase: String) -> Result<EncryptionStatus, String> {
+    let active = state.active_account.lock().unwrap();
+    if state.data_key.lock().unwrap().is_none() {
+        let key = encryption::unlock_with_passphrase(&state.app_data_dir, &passphrase)?;
+        *state.data_key.lock().unwrap() = Some(key);
+        if let Err(e) = open_partition(&state, active.as_ref()) {
+            *state.data_key.lock().unwrap() = None;
+            return Err(e);
+        }
+        let mut status = state.encryption_status.lock().unwrap();
+        status.key_source = Some(KeySource::Passphrase);
+        status.passphrase_set = true;
+        status.error = None;
+    }
+    Ok(encryption_status(&state))
+}
+
+/// Offboarding: close the local data, overwrite and delete everything in the
+/// app data directory and remove the data key. The app keeps running on empty
+/// in-memory indexes until it is restarted.
+#[tauri::command]
+fn wipe_local_data(state: State<AppState>) -> Result<WipeResult, String> {
+    let mut active = state.active_account.lock().unwrap();
+    eprintln!("[encryption] Wiping {}", state.app_data_dir.display());
+
+    let in_memory = PathBuf::from(IN_MEMORY_DB);
+    state.document_index.reopen(&in_memory, None).map_err(|e| e.to_string())?;
+    state.contact_groups.reopen(&in_memory, None).map_err(|e| e.to_string())?;
+    state.conversion_cache.set_key(None);
+    state.upload_queue.reset();
+    *state.data_key.lock().unwrap() = None;
+    *active = None;
+
+    for provider in &state.settings.lock().unwrap().contact_providers {
+        if let ContactProviderConfig::Carddav { id, .. } = provider {
+            contact_providers::delete_password(id);
+        }
+    }
+    let result = encryption::wipe_local_data(&state.app_data_dir);
+    *state.encryption_status.lock().unwrap() = EncryptionStatus {
+        unlocked: false,
+        key_source: None,
+        passphrase_set: false,
+        error: Some("Local data was wiped; restart the app".to_string()),
+    };
+    Ok(result)
+}
+
+// ============ Contact Group Commands ============
+
+#[tauri::command]
+fn create_contact_group(
+    state: State<AppState>,
+    name: String,
+    description: Option<String>,
+    color: Option<String>,
+    rules: Option<SmartRules>,
+) -> Result<ContactGroup, String> {
+    if let Some(rules) = &rules {
+        smart_groups::validate_rules(rules)?;
+    }
+    state.contact_groups
+        .create_group(&name, description.as_deref(), color.as_deref(), rules.as_ref())
+        .map_err(|e| e.to_string())
+}
+
+/// Make a group a smart group with these rules, or a static one again
+/// (`None`; its hand-added members stay).
+#[tauri::command]
+fn set_contact_group_rules(state: State<AppState>, group_id: String, rules: Option<SmartRules>) -> Result<(), String> {
+    if let Some(rules) = &rules {
+        smart_groups::validate_rules(rules)?;
+    }
+    state.contact_groups
+        .set_group_rules(&group_id, rules.as_ref())
+        .map_err(|e| e.to_string())
+}
+
+/// Members of every group against the contacts as last loaded, with smart
+/// groups evaluated now.
+#[tauri::command]
+async fn get_contact_group_members(app: AppHandle) -> Result<Vec<GroupMembers>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let contacts = contact_providers::cached_contacts(&state)?;
+        smart_groups::group_members(&state.contact_groups, &contacts, None)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Contacts the rules would match now, for the smart group editor.
+/// Ignored contacts are left out when the rules say so.
+#[tauri::command]
+async fn preview_smart_group(app: AppHandle, rules: SmartRules) -> Result<SmartGroupPreview, String> {
+    smart_groups::validate_rules(&rules)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let contacts = contact_providers::cached_contacts(&state)?;
+        let lists = smart_groups::GroupLists {
+            ignored: state.contact_groups.get_ignored_contacts().map_err(|e| e.to_string())?.into_iter().collect(),
+            ..Default::default()
+        };
+        Ok(smart_groups::preview(&rules, &contacts, &lists))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn update_contact_group(
+    state: State<AppState>,
+    id: String,
+    name: String,
+    description: Option<String>,
+    color: Option<String>,
+) -> Result<(), String> {
+    state.contact_groups
+        .update_group(&id, &name, description.as_deref(), color.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_contact_group(state: State<AppState>, id: String) -> Result<(), String> {
+    state.contact_groups
+        .delete_group(&id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn list_contact_groups(state: State<AppState>) -> Result<Vec<ContactGroup>, String> {
+    state.contact_groups
+        .list_groups()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn add_contacts_to_group(
+    state: State<AppState>,
+    contact_ids: Vec<String>,
+    group_id: String,
+) -> Result<u32, String> {
+    state.contact_groups
+        .add_contacts_to_group(&contact_ids, &group_id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn remove_contacts_from_group(
+    state: State<AppState>,
+    contact_ids: Vec<String>,
+    group_id: String,
+) -> Result<u32, String> {
+    state.contact_groups
+        .remove_contacts_from_group(&contact_ids, &group_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Members of one group; see get_contact_group_members.
+#[tauri::command]
+async fn get_contacts_in_group(app: AppHandle, group_id: String) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let contacts = contact_providers::cached_contacts(&state)?;
+        let members = smart_groups::group_members(&state.contact_groups, &contacts, Some(&group_id))?;
+        Ok(members.into_iter().next().map(|m| m.contact_ids).unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Groups the contact is in, smart groups included.
+#[tauri::command]
+async fn get_groups_for_contact(app: AppHandle, contact_id: String) -> Result<Vec<ContactGroup>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let contacts = contact_providers::cached_contacts(&state)?;
+        let members = smart_groups::group_members(&state.contact_groups, &contacts, None)?;
+        let groups = state.contact_groups.list_groups().map_err(|e| e.to_string())?;
+        Ok(groups
+            .into_iter()
+            .filter(|g| members.iter().any(|m| m.group_id == g.id && m.contact_ids.contains(&contact_id)))
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn ignore_contacts(state: State<AppState>, contact_ids: Vec<String>) -> Result<u32, String> {
+    state.contact_groups
+        .ignore_contacts(&contact_ids)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn unignore_contacts(state: State<AppState>, contact_ids: Vec<String>) -> Result<u32, String> {
+    state.contact_groups
+        .unignore_contacts(&contact_ids)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_ignored_contacts(state: State<AppState>) -> Result<Vec<String>, String> {
+    state.contact_groups
+        .get_ignored_contacts()
+        .map_err(|e| e.to_string())
+}
+
+// ============ Document Commands ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanFolderResult {
+    pub documents_found: u32,
+    pub documents_new: u32,
+    pub documents_updated: u32,
+}
+
+#[tauri::command]
+fn scan_folder(state: State<AppState>, path: String) -> Result<ScanFolderResult, String> {
+    let result = documents::scan_directory(&path, &state.document_index)?;
+    // Rescanned on each auto-sync run
+    if let Err(e) = state.document_index.record_scan_root(&path) {
+        eprintln!("[scan] Failed to record scan root {}: {}", path, e);
+    }
+    Ok(ScanFolderResult {
+        documents_found: result.documents_found,
+        documents_new: result.documents_new,
+        documents_updated: result.documents_updated,
+    })
+}
+
+#[tauri::command]
+fn get_indexed_documents(state: State<AppState>) -> Result<Vec<IndexedDocument>, String> {
+    state.document_index
+        .get_all_documents()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_documents_by_status(state: State<AppState>, status: String) -> Result<Vec<IndexedDocument>, String> {
+    let doc_status = DocumentStatus::parse(&status)?;
+    state.document_index
+        .get_documents_by_status(doc_status)
+        .map_err(|e| e.to_string())
+}
+
+/// One page of documents matching `query`, with total and facet counts.
+/// Prefer this over `get_indexed_documents` for large indexes.
+#[tauri::command]
+fn query_documents(state: State<AppState>, query: DocumentQuery) -> Result<DocumentPage, String> {
+    state.document_index
+        .query_documents(&query)
+        .map_err(|e| e.to_string())
+}
+
+/// Filter by embedded author and/or photo capture date.
+#[tauri::command]
+fn find_documents_by_metadata(state: State<AppState>, filter: MetadataFilter) -> Result<Vec<IndexedDocument>, String> {
+    state.document_index
+        .find_documents_by_metadata(&filter)
+        .map_err(|e| e.to_string())
+}
+
+/// Export the index (or the documents matching `query`) to `path`. The format
+/// is taken from `format` or, failing that, the file extension.
+#[tauri::command]
+fn export_index(
+    state: State<AppState>,
+    path: String,
+    format: Option<String>,
+    query: Option<DocumentQuery>,
+) -> Result<ExportResult, String> {
+    let path = std::path::PathBuf::from(path);
+    let format = match format {
+        Some(f) => ExportFormat::parse(&f)?,
+        None => ExportFormat::from_path(&path).ok_or("Unknown export file extension; choose a format")?,
+    };
+    let docs = state.document_index
+        .find_documents(&query.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+    index_transfer::export_documents(&docs, &path, format)
+}
+
+/// Merge an exported index back in, re-keying paths through `mappings`.
+#[tauri::command]
+fn import_index(
+    state: State<AppState>,
+    path: String,
+    format: Option<String>,
+    mappings: Vec<PathMapping>,
+) -> Result<ImportResult, String> {
+    let format = format.as_deref().map(ExportFormat::parse).transpose()?;
+    index_transfer::import_file(&state.document_index, std::path::Path::new(&path), format, &mappings)
+}
+
+/// A document's status timeline, oldest first.
+#[tauri::command]
+fn get_document_status_history(state: State<AppState>, id: String) -> Result<Vec<StatusHistoryEntry>, String> {
+    state.document_index
+        .get_status_history(&id)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_document_stats(state: State<AppState>) -> Result<DocumentStats, String> {
+    state.document_index
+        .get_stats()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn update_document_status(
+    state: State<AppState>,
+    id: String,
+    status: String,
+    error_message: Option<String>,
+    reason: Option<String>,
+) -> Result<(), String> {
+    let doc_status = DocumentStatus::parse(&status)?;
+    state.document_index
+        .update_status(&id, doc_status.clone(), error_message.as_deref(), "user", reason.as_deref())
+        .map_err(|e| e.to_string())?;
+    if doc_status == DocumentStatus::Ignore {
+        state.conversion_cache.remove(&state.document_index, &id)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn ignore_folder(
+    state: State<AppState>,
+    folder_path: String,
+) -> Result<BulkOperation, String> {
+    println!("[DEBUG] ignore_folder called with path: {}", folder_path);
+    let operation = state.document_index
+        .ignore_documents_in_folder(&folder_path, "user")
+        .map_err(|e| e.to_string())?;
+    // Drop cached HTML for everything that just became IGNORE
+    state.conversion_cache.prune(&state.document_index)?;
+    Ok(operation)
+}
+
+#[tauri::command]
+fn import_folder(
+    state: State<AppState>,
+    folder_path: String,
+) -> Result<BulkOperation, String> {
+    println!("[DEBUG] import_folder called with path: {}", folder_path);
+    state.document_index
+        .import_documents_in_folder(&folder_path, "user")
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn bulk_update_document_status(
+    state: State<AppState>,
+    ids: Vec<String>,
+    status: String,
+    category: Option<String>,
+) -> Result<u32, String> {
+    println!("[DEBUG] bulk_update_document_status called with ids: {:?}, status: {}", ids, status);
+    let doc_status = DocumentStatus::parse(&status)?;
+    println!("[DEBUG] Converted to DocumentStatus: {:?}, as_str: {}", doc_status, doc_status.as_str());
+    let operation = state.document_index
+        .bulk_set_status(&ids, doc_status.clone(), "user")
+        .map_err(|e| e.to_string())?;
+    if doc_status == DocumentStatus::Ignore {
+        state.conversion_cache.prune(&state.document_index)?;
+    }
+    let mut errors: Vec<String> = operation.rejected_ids
+        .iter()
+        .map(|id| format!("{}: not allowed to move to {}", id, doc_status.as_str()))
+        .collect();
+    // Optionally categorize in the same pass: "auto" runs the rules, anything
+    // else is set as-is. Documents the transition refused are left alone.
+    let accepted: Vec<String> = ids.into_iter().filter(|id| !operation.rejected_ids.contains(id)).collect();
+    match category.as_deref() {
+        Some("auto") => {
+            let docs = documents_by_ids(&state.document_index, &accepted)?;
+            categorize::categorize_documents(&state.document_index, &state.conversion_cache, &docs, true)?;
+        }
+        Some(category) => {
+            for id in &accepted {
+                if let Err(e) = state.document_index.set_category(id, Some(category)) {
+                    errors.push(format!("{}: {}", id, e));
+                }
+            }
+        }
+        None => {}
+    }
+    if !errors.is_empty() {
+        return Err(format!("Some updates failed: {}", errors.join(", ")));
+    }
+    Ok(operation.count)
+}
+
+/// Revert the last folder ignore/import or bulk status change.
+#[tauri::command]
+fn undo_last_bulk_operation(state: State<AppState>) -> Result<Option<UndoResult>, String> {
+    let result = state.document_index
+        .undo_last_bulk_operation("user")
+        .map_err(|e| e.to_string())?;
+    if result.is_some() {
+        // Undoing an import can put documents back to IGNORE
+        state.conversion_cache.prune(&state.document_index)?;
+    }
+    Ok(result)
+}
+
+// ============ Categorization Commands ============
+
+/// Documents by id, skipping ids that are no longer in the index.
+fn documents_by_ids(index: &DocumentIndex, ids: &[String]) -> Result<Vec<IndexedDocument>, String> {
+    let mut docs = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(doc) = index.get_document(id).map_err(|e| e.to_string())? {
+            docs.push(doc);
+        }
+    }
+    Ok(docs)
+}
+
+/// Documents to categorize: explicit ids, else a status, else everything not ignored.
+fn categorize_targets(
+    index: &DocumentIndex,
+    ids: Option<Vec<String>>,
+    status: Option<String>,
+) -> Result<Vec<IndexedDocument>, String> {
+    if let Some(ids) = ids {
+        return documents_by_ids(index, &ids);
+    }
+    let docs = match status {
+        Some(status) => index.get_documents_by_status(DocumentStatus::parse(&status)?),
+        None => index.get_all_documents(),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(docs.into_iter().filter(|d| d.status != DocumentStatus::Ignore).collect())
+}
+
+#[tauri::command]
+fn get_category_rules(state: State<AppState>) -> Result<Vec<CategoryRule>, String> {
+    state.document_index
+        .list_category_rules()
+        .map_err(|e| e.to_string())
+}
+
+/// Create (id 0) or update a rule. Returns the rule id.
+#[tauri::command]
+fn save_category_rule(state: State<AppState>, rule: CategoryRule) -> Result<i64, String> {
+    categorize::validate_rule(&rule)?;
+    state.document_index
+        .save_category_rule(&rule)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_category_rule(state: State<AppState>, id: i64) -> Result<(), String> {
+    state.document_index
+        .delete_category_rule(id)
+        .map_err(|e| e.to_string())
+}
+
+/// Dry run: the category each document would get and the rule that decided it.
+#[tauri::command]
+fn preview_categories(
+    state: State<AppState>,
+    ids: Option<Vec<String>>,
+    status: Option<String>,
+) -> Result<Vec<CategoryProposal>, String> {
+    let docs = categorize_targets(&state.document_index, ids, status)?;
+    categorize::categorize_documents(&state.document_index, &state.conversion_cache, &docs, false)
+}
+
+/// Run the rules and store the proposed categories.
+#[tauri::command]
+fn apply_categories(
+    state: State<AppState>,
+    ids: Option<Vec<String>>,
+    status: Option<String>,
+) -> Result<Vec<CategoryProposal>, String> {
+    let docs = categorize_targets(&state.document_index, ids, status)?;
+    categorize::categorize_documents(&state.document_index, &state.conversion_cache, &docs, true)
+}
+
+/// Manually set (or clear) a document's category.
+#[tauri::command]
+fn set_document_category(state: State<AppState>, id: String, category: Option<String>) -> Result<(), String> {
+    state.document_index
+        .set_category(&id, category.as_deref().filter(|c| !c.trim().is_empty()))
+        .map_err(|e| e.to_string())
+}
+
+// ============ Conversion Commands ============
+
+/// Convert a document. `format` adds a Markdown or plain-text rendering to the
+/// result; with `image_dir`, Markdown images are written there as files.
+#[tauri::command]
+fn convert_document(
+    file_path: String,
+    format: Option<OutputFormat>,
+    image_dir: Option<String>,
+) -> Result<ConversionResult, String> {
+    let mut result = converter::convert_to_html(&file_path)?;
+    converter::render_output(
+        &mut result,
+        format.unwrap_or_default(),
+        image_dir.as_deref().map(std::path::Path::new),
+    )?;
+    Ok(result)
+}
+
+#[tauri::command]
+fn get_supported_formats() -> Vec<&'static str> {
+    converter::supported_formats()
+}
+
+/// Convert a document, cache the HTML and mark it CONVERTED. Returns
+/// conversion metadata, rendered to `format` like `convert_document`.
+/// The HTML goes into the conversion cache (see cache.rs), tagged with the
+/// source hash and converter version it was produced from. A document that
+/// can't become CONVERTED (e.g. already UPLOADED) is refused before anything
+/// is cached.
+#[tauri::command]
+fn convert_and_cache(
+    state: State<AppState>,
+    document_id: String,
+    file_path: String,
+    format: Option<OutputFormat>,
+    image_dir: Option<String>,
+) -> Result<ConversionResult, String> {
+    state.document_index
+        .check_status_transition(&document_id, &DocumentStatus::Converted)
+        .map_err(|e| e.to_string())?;
+
+    let source_hash = documents::calculate_file_hash(std::path::Path::new(&file_path)).ok();
+    let mut result = converter::convert_to_html(&file_path)?;
+
+    state.conversion_cache
+        .store(&state.document_index, &document_id, source_hash, &result.html)?;
+
+    // Update status to CONVERTED
+    state.document_index
+        .update_status(&document_id, DocumentStatus::Converted, None, "converter", None)
+        .map_err(|e| e.to_string())?;
+
+    converter::render_output(
+        &mut result,
+        format.unwrap_or_default(),
+        image_dir.as_deref().map(std::path::Path::new),
+    )?;
+    Ok(result)
+}
+
+/// Read cached HTML for a previously converted document. Fails if the cache
+/// is stale (source changed, converter upgraded, document ignored).
+#[tauri::command]
+fn get_cached_conversion(
+    state: State<AppState>,
+    document_id: String,
+) -> Result<String, String> {
+    state.conversion_cache.load(&state.document_index, &document_id)
+}
+
+#[tauri::command]
+fn get_cache_stats(state: State<AppState>) -> Result<CacheStats, String> {
+    state.conversion_cache.stats(&state.document_index)
+}
+
+/// Prune stale entries and enforce the size budget, or clear everything with `all`.
+#[tauri::command]
+fn purge_cache(state: State<AppState>, all: Option<bool>) -> Result<PurgeResult, String> {
+    if all.unwrap_or(false) {
+        state.conversion_cache.purge_all(&state.document_index)
+    } else {
+        state.conversion_cache.prune(&state.document_index)
+    }
+}
+
+/// Save a new cache budget and evict down to it straight away.
+#[tauri::command]
+fn set_cache_budget(app: AppHandle, state: State<AppState>, max_bytes: u64) -> Result<PurgeResult, String> {
+    change_settings(&app, &state, |settings| settings.cache_budget_bytes = max_bytes)?;
+    state.conversion_cache.enforce_budget(&state.document_index)
+}
+
+// ============ Upload Commands ============
+
+#[tauri::command]
+async fn upload_document(
+    state: State<'_, AppState>,
+    document_id: String,
+    html_content: String,
+    title: String,
+    category: String,
+    original_format: String,
+    word_count: u32,
+    folder_name: String,
+    breadcrumb: Vec<String>,
+    include_original: Option<bool>,
+) -> Result<UploadResult, String> {
+    // Metadata and the source path come from the index rather than the frontend
+    let doc = state.document_index
+        .get_document(&document_id)
+        .ok()
+        .flatten();
+    let original_path = match (&doc, include_original.unwrap_or(false)) {
+        (Some(doc), true) => Some(doc.file_path.clone()),
+        _ => None,
+    };
+    let metadata = doc.map(|doc| doc.metadata).unwrap_or_default();
+
+    let request = UploadRequest {
+        document_id,
+        html_content,
+        title,
+        category,
+        original_format,
+        word_count,
+        folder_name,
+        breadcrumb,
+        metadata,
+        original_path,
+    };
+    
+    Ok(state.upload_queue.upload_document(&state.nexus_api, request).await)
+}
+
+#[tauri::command]
+fn get_upload_progress(state: State<AppState>) -> UploadProgress {
+    state.upload_queue.get_progress()
+}
+
+#[tauri::command]
+fn get_transfer_status() -> TransferStatus {
+    bandwidth::limiter().status()
+}
+
+#[tauri::command]
+fn pause_upload(state: State<AppState>) {
+    state.upload_queue.pause();
+}
+
+#[tauri::command]
+fn resume_upload(state: State<AppState>) {
+    state.upload_queue.resume();
+}
+
+#[tauri::command]
+fn reset_upload_queue(state: State<AppState>) {
+    state.upload_queue.reset();
+}
+
+#[tauri::command]
+fn set_upload_total(state: State<AppState>, total: u32) {
+    state.upload_queue.total.store(total, std::sync::atomic::Ordering::SeqCst);
+}
+
+#[tauri::command]
+fn open_file_native(path: String) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("cmd")
+            .args(["/C", "start", "", &path])
+            .spawn()
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    install_panic_hook();
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_http::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(autostart::plugin())
+        .setup(|app| {
+            // Prevent macOS App Nap from throttling the mesh node
+            keep_alive::disable_app_nap();
+
+            // Initialize document index in app data directory
+            let app_data_dir = app.path().app_data_dir()
+                .expect("Failed to get app data directory");
+            std::fs::create_dir_all(&app_data_dir).ok();
+            
+            // Indexes are encrypted and partitioned per account. They start
+            // out as in-memory placeholders and are opened on the last active
+            // account's partition once the data key is available.
+            let registry = accounts::load_registry(&app_data_dir);
+            let active_account = registry.active;
+            let in_memory = PathBuf::from(IN_MEMORY_DB);
+
+            let (data_key, encryption_status) = match encryption::load_key(&app_data_dir) {
+                Ok(KeyLookup::Ready(key, source)) => (
+                    Some(key),
+                    EncryptionStatus { unlocked: true, key_source: Some(source), passphrase_set: false, error: None },
+                ),
+                Ok(KeyLookup::NeedsPassphrase { passphrase_set }) => (
+                    None,
+                    EncryptionStatus { unlocked: false, key_source: Some(KeySource::Passphrase), passphrase_set, error: None },
+                ),
+                Err(e) => {
+                    eprintln!("[encryption] {}", e);
+                    (None, EncryptionStatus { unlocked: false, key_source: None, passphrase_set: false, error: Some(e) })
+                }
+            };
+            let unlocked = data_key.is_some();
+
+            let document_index = DocumentIndex::new(&in_memory, None)
+                .expect("Failed to initialize document index");
+            
+            let contact_groups = ContactGroupIndex::new(&in_memory, None)
+                .expect("Failed to initialize contact groups");
+
+            let conversion_cache = ConversionCache::new(accounts::active_partition(&app_data_dir).converted_dir, None);
+
+            // Saved settings; transfer limits and the cache budget apply from the start
+            let sync_settings = settings::load(&app_data_dir);
+            if let Err(e) = bandwidth::limiter().configure(&sync_settings.transfer_limits) {
+                eprintln!("[settings] {}", e);
+            }
+            conversion_cache.set_budget(sync_settings.cache_budget_bytes);
+            autostart::refresh(app.handle(), sync_settings.launch_at_startup);
+
+            let nexus_api = NexusApi::new(env!("CARGO_PKG_VERSION"));
+            let refresh_handle = app.handle().clone();
+            nexus_api.on_refresh(move |credentials| {
+                // The frontend stores the rotated tokens
+                let _ = refresh_handle.emit("api-session-refreshed", credentials);
+            });
+
+            let app_state = AppState {
+                app_data_dir: app_data_dir.clone(),
+                active_account: Mutex::new(active_account.clone()),
+                data_key: Mutex::new(data_key),
+                encryption_status: Mutex::new(encryption_status),
+                settings: Mutex::new(sync_settings),
+                document_index,
+                upload_queue: UploadQueue::with_session_file(Some(
+                    app_data_dir.join(chunked_upload::SESSIONS_FILE),
+                )),
+                nexus_api,
+                contact_groups,
+                conversion_cache,
+                scheduler: SyncScheduler::new(),
+            };
+
+            // Open the data (this also reconciles the converted-HTML cache)
+            if unlocked {
+                if let Err(e) = open_partition(&app_state, active_account.as_ref()) {
+                    eprintln!("[encryption] Failed to open local data: {}", e);
+                    *app_state.data_key.lock().unwrap() = None;
+                    app_state.encryption_status.lock().unwrap().error = Some(e);
+                }
+            }
+            
+            app.manage(app_state);
+
+            // Auto-sync runs in the background for the life of the app
+            tauri::async_runtime::spawn(scheduler::run(app.handle().clone()));
+            
+            // Create system tray
+            tray::create_tray(app.handle())?;
+            
+            // Handle window close - hide instead of quit
+            let window = app.get_webview_window("main").unwrap();
+            
+            // Resize window to 80% of screen height, centered
+            if let Some(monitor) = window.primary_monitor().ok().flatten() {
+                let screen_size = monitor.size();
+                let scale = monitor.scale_factor();
+                
+                // Calculate dimensions (768 width already set, 80% of screen height)
+                let width = 768.0;
+                let height = (screen_size.height as f64 / scale) * 0.8;
+                
+                let _ = window.set_size(tauri::Size::Logical(tauri::LogicalSize { width, height }));
+                let _ = window.center();
+            }
+            
+            // Show window after positioning, unless started at login: then
+            // the app stays in the tray until opened from there
+            if !autostart::launched_at_login() {
+                let _ = window.show();
+            }
+            
+            let window_clone = window.clone();
+            window.on_window_event(move |event| {
+                if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                    // Prevent the window from closing, just hide it
+                    api.prevent_close();
+                    let _ = window_clone.hide();
+                }
+            });
+            
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            // Account commands
+            get_active_account,
+            list_accounts,
+            switch_account,
+            // API session commands
+            set_api_session,
+            clear_api_session,
+            // Local data commands
+            get_encryption_status,
+            unlock_local_data,
+            wipe_local_data,
+            // Contact commands
+            get_contacts,
+            preview_contact_normalization,
+            apply_contact_normalization,
+            get_sync_settings,
+            update_sync_settings,
+            set_auto_sync,
+            set_selected_contacts,
+            record_sync,
+            sync_contacts,
+            get_contact_sync_status,
+            find_duplicate_contacts,
+            merge_contacts,
+            import_vcard_file,
+            export_contacts_vcard,
+            list_contact_providers,
+            add_contact_provider,
+            remove_contact_provider,
+            get_launch_at_startup_status,
+            // Sync scheduler commands
+            get_sync_history,
+            run_sync_now,
+            // Contact group commands
+            create_contact_group,
+            update_contact_group,
+            delete_contact_group,
+            list_contact_groups,
+            add_contacts_to_group,
+            remove_contacts_from_group,
+            get_contacts_in_group,
+            get_groups_for_contact,
+            get_contact_group_members,
+            set_contact_group_rules,
+            preview_smart_group,
+            ignore_contacts,
+            unignore_contacts,
+            get_ignored_contacts,
+            // Document commands
+            scan_folder,
+            get_indexed_documents,
+            get_documents_by_status,
+            find_documents_by_metadata,
+            query_documents,
+            export_index,
+            import_index,
+            get_document_stats,
+            update_document_status,
+            bulk_update_document_status,
+            get_document_status_history,
+            undo_last_bulk_operation,
+            // Conversion commands
+            convert_document,
+            get_supported_formats,
+            convert_and_cache,
+            get_cached_conversion,
+            get_cache_stats,
+            purge_cache,
+            set_cache_budget,
+            get_category_rules,
+            save_category_rule,
+            delete_category_rule,
+            preview_categories,
+            apply_categories,
+            set_document_category,
+            // Upload commands
+            upload_document,
+            get_upload_progress,
+            pause_upload,
+            resume_upload,
+            reset_upload_queue,
+            set_upload_total,
+            get_transfer_status,
+            // File commands
+            open_file_native,
+            ignore_folder,
+            import_folder,
+            // Video assessment commands
+            video::get_video_metadata,
+            video::extract_frames,
+            video::extract_burst_frames,
+            video::cleanup_frames,
+            // NexCAD precision scan commands
+            precision_scan::download_scan_images,
+            precision_scan::run_photogrammetry,
+            precision_scan::convert_model,
+            precision_scan::generate_sketchup,
+            precision_scan::analyze_mesh,
+            precision_scan::upload_scan_results,
+            precision_scan::cleanup_scan,
+            precision_scan::list_local_scans,
+            // System info (DCM)
+            system_info::get_system_info,
+            // Remote control input injection
+            input::inject_mouse_move,
+            input::inject_mouse_button,
+            input::inject_key
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
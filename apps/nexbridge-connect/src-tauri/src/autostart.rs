@@ -0,0 +1,77 @@
+// ---------------------------------------------------------------------------
+// autostart — start NexBRIDGE Connect at login
+// ---------------------------------------------------------------------------
+// Applies SyncSettings.launch_at_startup through tauri-plugin-autostart,
+// which registers the app with the OS:
+//
+//   macOS    ~/Library/LaunchAgents/{app}.plist
+//   Windows  HKCU\Software\Microsoft\Windows\CurrentVersion\Run
+//   Linux    ~/.config/autostart/{app}.desktop (the AppImage itself, when
+//            running from one)
+//
+// The registration runs the app with LAUNCHED_AT_LOGIN_ARG, which makes it
+// start hidden in the tray. The entry names the executable it was created
+// from, so `refresh` re-registers at every startup while the setting is on;
+// a moved or reinstalled app then fixes its own entry.
+// ---------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_autostart::ManagerExt;
+
+/// Passed by the login registration so the window stays hidden
+pub const LAUNCHED_AT_LOGIN_ARG: &str = "--autostart";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutostartStatus {
+    /// Registered to start at login
+    pub registered: bool,
+}
+
+/// The autostart plugin, registering the app with LAUNCHED_AT_LOGIN_ARG.
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    tauri_plugin_autostart::Builder::new()
+        .arg(LAUNCHED_AT_LOGIN_ARG)
+        .build()
+}
+
+/// Whether this process was started by the login registration.
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|arg| arg == LAUNCHED_AT_LOGIN_ARG)
+}
+
+/// Register or unregister the app for login start.
+pub fn set_enabled<R: Runtime>(app: &AppHandle<R>, enabled: bool) -> Result<(), String> {
+    let manager = app.autolaunch();
+    if enabled {
+        manager
+            .enable()
+            .map_err(|e| format!("Failed to register for login start: {}", e))
+    } else if manager.is_enabled().unwrap_or(true) {
+        // Removing an entry that isn't there fails on Windows
+        manager
+            .disable()
+            .map_err(|e| format!("Failed to remove the login start entry: {}", e))
+    } else {
+        Ok(())
+    }
+}
+
+/// Re-register the current executable if launch at startup is on. Called
+/// once at startup.
+pub fn refresh<R: Runtime>(app: &AppHandle<R>, enabled: bool) {
+    if !enabled {
+        return;
+    }
+    if let Err(e) = set_enabled(app, true) {
+        eprintln!("[autostart] {}", e);
+    }
+}
+
+pub fn status<R: Runtime>(app: &AppHandle<R>) -> Result<AutostartStatus, String> {
+    app.autolaunch()
+        .is_enabled()
+        .map(|registered| AutostartStatus { registered })
+        .map_err(|e| format!("Failed to read the login start entry: {}", e))
+}
@@ -0,0 +1,335 @@
+// ---------------------------------------------------------------------------
+// bandwidth — global rate limits and scheduling for background transfers
+// ---------------------------------------------------------------------------
+// Every reqwest transfer that moves real payload (document uploads, chunked
+// uploads, scan image downloads, scan result uploads) goes through the one
+// process-wide `limiter()`:
+//
+//   - upload and download caps, shared by all transfers in that direction,
+//     enforced by pacing the body in small slices;
+//   - transfer windows ("only 18:00–07:00"), outside of which new transfers
+//     wait instead of starting;
+//   - an automatic pause while the OS reports the connection as metered
+//     (see `system_info::read_metered`). Asking the OS can take a second, so
+//     the answer is cached and refreshed on a blocking thread; checks never
+//     wait for it.
+//
+// Limits come from `SyncSettings::transfer_limits` and are applied with
+// `configure` whenever the settings change.
+// ---------------------------------------------------------------------------
+
+use crate::system_info;
+use chrono::{Local, NaiveTime};
+use futures_util::stream::{self, Stream};
+use reqwest::Body;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// Bodies are paced in slices of this size, so low caps stay smooth.
+const SLICE_BYTES: usize = 16 * 1024;
+/// How often a waiting transfer re-checks its window / the network.
+const WAIT_POLL: Duration = Duration::from_secs(30);
+/// How long a metered check is trusted before asking the OS again.
+const METERED_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+/// A daily window in local time. `end` before `start` wraps past midnight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferWindow {
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"
+    pub end: String,
+}
+
+impl TransferWindow {
+    fn parse(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let parse = |s: &str| {
+            NaiveTime::parse_from_str(s, "%H:%M")
+                .map_err(|_| format!("Invalid time '{}' in transfer window (expected HH:MM)", s))
+        };
+        Ok((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    fn contains(&self, now: NaiveTime) -> bool {
+        match self.parse() {
+            Ok((start, end)) if start <= end => now >= start && now < end,
+            Ok((start, end)) => now >= start || now < end,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Transfer settings, stored as part of `SyncSettings`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferLimits {
+    /// Upload cap in KB/s; None = unlimited
+    #[serde(default)]
+    pub upload_limit_kbps: Option<u32>,
+    /// Download cap in KB/s; None = unlimited
+    #[serde(default)]
+    pub download_limit_kbps: Option<u32>,
+    /// Transfers only start inside one of these; empty = any time
+    #[serde(default)]
+    pub windows: Vec<TransferWindow>,
+    #[serde(default = "default_pause_on_metered")]
+    pub pause_on_metered: bool,
+}
+
+fn default_pause_on_metered() -> bool {
+    true
+}
+
+impl Default for TransferLimits {
+    fn default() -> Self {
+        Self {
+            upload_limit_kbps: None,
+            download_limit_kbps: None,
+            windows: Vec::new(),
+            pause_on_metered: default_pause_on_metered(),
+        }
+    }
+}
+
+impl TransferLimits {
+    pub fn validate(&self) -> Result<(), String> {
+        for window in &self.windows {
+            let (start, end) = window.parse()?;
+            if start == end {
+                return Err(format!(
+                    "Transfer window {}–{} is empty",
+                    window.start, window.end
+                ));
+            }
+        }
+        if self.upload_limit_kbps == Some(0) || self.download_limit_kbps == Some(0) {
+            return Err("Transfer limits must be at least 1 KB/s (leave empty for unlimited)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Paces one direction: each reservation starts when the previous one's
+/// bytes would have finished at the capped rate.
+struct Pacer {
+    bytes_per_sec: Option<u64>,
+    next_free: Instant,
+}
+
+impl Pacer {
+    fn new(limit_kbps: Option<u32>) -> Self {
+        Self {
+            bytes_per_sec: limit_kbps.map(|k| k as u64 * 1024),
+            next_free: Instant::now(),
+        }
+    }
+
+    /// When a transfer of `bytes` may start, or None if uncapped.
+    fn reserve(&mut self, bytes: u64) -> Option<Instant> {
+        let rate = self.bytes_per_sec?;
+        let start = self.next_free.max(Instant::now());
+        self.next_free = start + Duration::from_secs_f64(bytes as f64 / rate as f64);
+        Some(start)
+    }
+}
+
+/// Current state of the limiter, for the settings screen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferStatus {
+    pub limits: TransferLimits,
+    /// Why transfers would be held back right now, if they would
+    pub blocked_reason: Option<String>,
+    /// None when the OS doesn't report it
+    pub metered: Option<bool>,
+}
+
+pub struct TransferLimiter {
+    limits: RwLock<TransferLimits>,
+    upload: Mutex<Pacer>,
+    download: Mutex<Pacer>,
+    metered: Arc<MeteredCache>,
+    /// Set while a transfer is held back by `wait_until_allowed`
+    waiting: Mutex<Option<String>>,
+}
+
+/// The last answer from the OS and when it was read.
+#[derive(Default)]
+struct MeteredCache {
+    value: Mutex<Option<(Instant, Option<bool>)>>,
+    refreshing: AtomicBool,
+}
+
+/// The process-wide limiter.
+pub fn limiter() -> &'static TransferLimiter {
+    static LIMITER: OnceLock<TransferLimiter> = OnceLock::new();
+    LIMITER.get_or_init(|| TransferLimiter::new(TransferLimits::default()))
+}
+
+impl TransferLimiter {
+    fn new(limits: TransferLimits) -> Self {
+        Self {
+            upload: Mutex::new(Pacer::new(limits.upload_limit_kbps)),
+            download: Mutex::new(Pacer::new(limits.download_limit_kbps)),
+            limits: RwLock::new(limits),
+            metered: Arc::new(MeteredCache::default()),
+            waiting: Mutex::new(None),
+        }
+    }
+
+    pub fn configure(&self, limits: &TransferLimits) -> Result<(), String> {
+        limits.validate()?;
+        *self.upload.lock().unwrap() = Pacer::new(limits.upload_limit_kbps);
+        *self.download.lock().unwrap() = Pacer::new(limits.download_limit_kbps);
+        *self.limits.write().unwrap() = limits.clone();
+        if limits.pause_on_metered {
+            // Have an answer ready before the first transfer asks
+            self.refresh_metered();
+        }
+        Ok(())
+    }
+
+    pub fn limits(&self) -> TransferLimits {
+        self.limits.read().unwrap().clone()
+    }
+
+    /// Wait until `bytes` may be sent/received under the cap.
+    pub async fn acquire(&self, direction: Direction, bytes: u64) {
+        let pacer = match direction {
+            Direction::Upload => &self.upload,
+            Direction::Download => &self.download,
+        };
+        let start = pacer.lock().unwrap().reserve(bytes);
+        if let Some(start) = start {
+            tokio::time::sleep_until(start.into()).await;
+        }
+    }
+
+    /// Why new transfers are held back right now, if they are.
+    pub fn blocked_reason(&self) -> Option<String> {
+        let limits = self.limits();
+
+        if !limits.windows.is_empty() {
+            let now = Local::now().time();
+            if !limits.windows.iter().any(|w| w.contains(now)) {
+                let windows: Vec<String> = limits
+                    .windows
+                    .iter()
+                    .map(|w| format!("{}–{}", w.start, w.end))
+                    .collect();
+                return Some(format!("Waiting for transfer window ({})", windows.join(", ")));
+            }
+        }
+
+        if limits.pause_on_metered && self.is_metered() == Some(true) {
+            return Some("Paused on a metered connection".to_string());
+        }
+
+        None
+    }
+
+    /// Block until transfers are allowed, calling `report` with the reason
+    /// each time the wait is (re-)checked.
+    pub async fn wait_until_allowed(&self, report: &(dyn Fn(&str) + Send + Sync)) {
+        while let Some(reason) = self.blocked_reason() {
+            report(&reason);
+            *self.waiting.lock().unwrap() = Some(reason);
+            tokio::time::sleep(WAIT_POLL).await;
+        }
+        *self.waiting.lock().unwrap() = None;
+    }
+
+    /// The reason a transfer is currently waiting, for progress reporting.
+    pub fn waiting_reason(&self) -> Option<String> {
+        self.waiting.lock().unwrap().clone()
+    }
+
+    pub fn status(&self) -> TransferStatus {
+        TransferStatus {
+            limits: self.limits(),
+            blocked_reason: self.blocked_reason(),
+            metered: self.is_metered(),
+        }
+    }
+
+    /// The cached answer; None until the OS has been asked once. A stale one
+    /// is still returned while a refresh runs in the background.
+    fn is_metered(&self) -> Option<bool> {
+        let cached = *self.metered.value.lock().unwrap();
+        if !matches!(cached, Some((checked_at, _)) if checked_at.elapsed() < METERED_TTL) {
+            self.refresh_metered();
+        }
+        cached.and_then(|(_, value)| value)
+    }
+
+    /// Ask the OS again off the calling thread, unless a refresh is running.
+    fn refresh_metered(&self) {
+        if self.metered.refreshing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let cache = self.metered.clone();
+        let refresh = move || {
+            let value = system_info::read_metered();
+            *cache.value.lock().unwrap() = Some((Instant::now(), value));
+            cache.refreshing.store(false, Ordering::SeqCst);
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(refresh);
+            }
+            Err(_) => {
+                std::thread::spawn(refresh);
+            }
+        }
+    }
+}
+
+// ============ Throttled bodies ============
+
+// A streamed body has no length of its own; callers that know it set
+// Content-Length themselves so the request isn't sent chunked.
+
+/// Request body for an in-memory payload, paced by the upload cap.
+pub fn throttled_bytes(data: Vec<u8>) -> Body {
+    let len = data.len();
+    Body::wrap_stream(slices(len, move |offset, n| Ok(data[offset..offset + n].to_vec())))
+}
+
+/// Request body streamed from a file, paced by the upload cap.
+pub fn throttled_file(path: PathBuf, len: u64) -> Body {
+    let mut file: Option<std::fs::File> = None;
+    Body::wrap_stream(slices(len as usize, move |_, n| {
+        if file.is_none() {
+            file = Some(std::fs::File::open(&path)?);
+        }
+        let mut buf = vec![0u8; n];
+        file.as_mut().unwrap().read_exact(&mut buf)?;
+        Ok(buf)
+    }))
+}
+
+/// Yield `len` bytes in `SLICE_BYTES` pieces from `read(offset, n)`,
+/// waiting on the upload pacer before each one.
+fn slices<F>(len: usize, read: F) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Send + Sync + 'static
+where
+    F: FnMut(usize, usize) -> std::io::Result<Vec<u8>> + Send + Sync + 'static,
+{
+    stream::unfold((0usize, read), move |(offset, mut read)| async move {
+        if offset >= len {
+            return None;
+        }
+        let n = SLICE_BYTES.min(len - offset);
+        limiter().acquire(Direction::Upload, n as u64).await;
+        let item = read(offset, n);
+        let next = if item.is_ok() { offset + n } else { len };
+        Some((item, (next, read)))
+    })
+}
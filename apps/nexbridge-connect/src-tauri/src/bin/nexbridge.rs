@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(nexbridge_connect_lib::cli::run())
+}
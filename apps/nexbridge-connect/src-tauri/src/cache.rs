@@ -0,0 +1,506 @@
+// ---------------------------------------------------------------------------
+// Converted-HTML cache
+// ---------------------------------------------------------------------------
+// Converted documents are cached so bulk upload doesn't have to re-run the
+// converter. HTML is stored zstd-compressed and content-addressed by the
+// hash of the source file it was converted from:
+//
+//   converted/{key[0..2]}/{source_hash}-v{CONVERTER_VERSION}-{html_digest}.html.zst
+//
+// Documents share a blob only when both the source bytes and the converted
+// HTML are identical. The same SOP copied into every job folder under the
+// same file name is stored once; copies under different names get a blob
+// each, because the <title> comes from the file name and so the HTML
+// digest differs. Sources that could not be hashed are never shared.
+//
+// Each document has a row in documents.db (`conversion_cache`) pointing at
+// its blob and recording the source hash, converter version, uncompressed
+// size and when it was last read.
+//
+// An entry is stale — and is dropped on read or on prune — when:
+//   - the document was deleted or marked IGNORE
+//   - a rescan recorded a different source hash
+//   - CONVERTER_VERSION changed since it was written
+//   - the blob is missing or doesn't decompress to the recorded size
+//
+// The cache is kept under a byte budget (compressed bytes on disk; saved as
+// SyncSettings.cache_budget_bytes) by evicting least-recently-read blobs. A CONVERTED document whose HTML is
+// evicted goes back to IMPORT so it gets converted again before upload.
+//
+// Blobs are encrypted with the local data key (see encryption.rs) after
+// compression. Uncompressed converted/{document_id}.html files and
+// unencrypted blobs from older versions are migrated by `prune`, which runs
+// at startup.
+// ---------------------------------------------------------------------------
+
+use crate::converter::CONVERTER_VERSION;
+use crate::encryption::{self, DataKey};
+use crate::index::{CacheEntry, DocumentIndex, DocumentStatus, IndexedDocument};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Default size budget for converted/ (2 GB on disk).
+pub const DEFAULT_CACHE_BUDGET_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+const BLOB_EXTENSION: &str = "html.zst";
+const ZSTD_LEVEL: i32 = 9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub entries: u32,
+    pub blobs: u32,
+    /// Compressed bytes on disk
+    pub total_bytes: u64,
+    /// Sum of uncompressed HTML sizes across entries
+    pub logical_bytes: u64,
+    pub budget_bytes: u64,
+    pub stale_entries: u32,
+    pub converter_version: u32,
+    pub oldest_access: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PurgeResult {
+    pub removed_entries: u32,
+    pub freed_bytes: u64,
+    pub reset_to_import: u32,
+}
+
+impl PurgeResult {
+    fn add(&mut self, other: PurgeResult) {
+        self.removed_entries += other.removed_entries;
+        self.freed_bytes += other.freed_bytes;
+        self.reset_to_import += other.reset_to_import;
+    }
+}
+
+pub struct ConversionCache {
+    dir: RwLock<PathBuf>,
+    key: RwLock<Option<DataKey>>,
+    budget_bytes: AtomicU64,
+}
+
+impl ConversionCache {
+    /// Blobs are encrypted under `key`. Without one (local data locked) the
+    /// cache refuses to read, write or prune, since the index it would be
+    /// reconciled against is only a placeholder.
+    pub fn new(dir: PathBuf, key: Option<DataKey>) -> Self {
+        std::fs::create_dir_all(&dir).ok();
+        Self {
+            dir: RwLock::new(dir),
+            key: RwLock::new(key),
+            budget_bytes: AtomicU64::new(DEFAULT_CACHE_BUDGET_BYTES),
+        }
+    }
+
+    fn data_key(&self) -> Result<DataKey, String> {
+        self.key.read().unwrap().clone().ok_or_else(|| "Local data is locked".to_string())
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.dir.read().unwrap().clone()
+    }
+
+    /// Point the cache at another directory (another account's partition).
+    pub fn set_dir(&self, dir: PathBuf) {
+        *self.dir.write().unwrap() = dir;
+    }
+
+    pub fn set_key(&self, key: Option<DataKey>) {
+        *self.key.write().unwrap() = key;
+    }
+
+    pub fn budget(&self) -> u64 {
+        self.budget_bytes.load(Ordering::SeqCst)
+    }
+
+    pub fn set_budget(&self, bytes: u64) {
+        self.budget_bytes.store(bytes, Ordering::SeqCst);
+    }
+
+    fn blob_path(&self, blob_key: &str) -> PathBuf {
+        let fan_out = blob_key.get(..2).unwrap_or("00");
+        self.dir().join(fan_out).join(format!("{}.{}", blob_key, BLOB_EXTENSION))
+    }
+
+    /// Write converted HTML for a document and record its metadata, then
+    /// evict older entries if the cache is over budget. If another document
+    /// with the same source already produced this blob, it is reused. The
+    /// blob just stored is never evicted here, even if it alone exceeds the
+    /// budget; the next store or prune brings the cache back under it.
+    pub fn store(
+        &self,
+        index: &DocumentIndex,
+        document_id: &str,
+        source_hash: Option<String>,
+        html: &str,
+    ) -> Result<(), String> {
+        let data_key = self.data_key()?;
+        let key = blob_key(document_id, source_hash.as_deref(), html);
+        let path = self.blob_path(&key);
+
+        // Re-converting onto a different blob releases the old one
+        if let Some(previous) = index.get_cache_entry(document_id).map_err(|e| e.to_string())? {
+            if previous.blob_key != key {
+                index.remove_cache_entry(document_id).map_err(|e| e.to_string())?;
+                self.delete_blob_if_unreferenced(index, &previous.blob_key)?;
+            }
+        }
+
+        let stored_bytes = match std::fs::metadata(&path) {
+            Ok(m) if m.len() > 0 => m.len(),
+            _ => write_blob(&path, html, &data_key)?,
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        index
+            .record_cache_entry(&CacheEntry {
+                document_id: document_id.to_string(),
+                source_hash,
+                converter_version: CONVERTER_VERSION,
+                size_bytes: html.len() as i64,
+                blob_key: key.clone(),
+                stored_bytes: stored_bytes as i64,
+                cached_at: now.clone(),
+                last_accessed_at: now,
+            })
+            .map_err(|e| e.to_string())?;
+
+        self.evict_over_budget(index, Some(&key))?;
+        Ok(())
+    }
+
+    /// Read cached HTML, dropping the entry instead if it is stale or damaged.
+    pub fn load(&self, index: &DocumentIndex, document_id: &str) -> Result<String, String> {
+        let data_key = self.data_key()?;
+        let not_found = || format!("No cached conversion found for document {}", document_id);
+
+        let entry = index
+            .get_cache_entry(document_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(not_found)?;
+        let doc = index.get_document(document_id).map_err(|e| e.to_string())?;
+
+        if is_stale(&entry, doc.as_ref()) {
+            self.remove(index, document_id)?;
+            return Err(format!(
+                "Cached conversion for document {} is out of date; convert it again",
+                document_id
+            ));
+        }
+
+        let html = match read_blob(&self.blob_path(&entry.blob_key), &data_key) {
+            Ok(h) if h.len() as i64 == entry.size_bytes => h,
+            _ => {
+                // Corrupt or missing blob — invalidate every document sharing it
+                self.remove_blob(index, &entry.blob_key)?;
+                return Err(not_found());
+            }
+        };
+
+        index.touch_cache_entry(document_id).map_err(|e| e.to_string())?;
+        Ok(html)
+    }
+
+    /// Drop one document's entry; its blob is deleted once nothing else uses it.
+    pub fn remove(&self, index: &DocumentIndex, document_id: &str) -> Result<PurgeResult, String> {
+        let entry = index.get_cache_entry(document_id).map_err(|e| e.to_string())?;
+        index.remove_cache_entry(document_id).map_err(|e| e.to_string())?;
+
+        let mut result = PurgeResult::default();
+        if let Some(entry) = entry {
+            result.removed_entries = 1;
+            result.freed_bytes = self.delete_blob_if_unreferenced(index, &entry.blob_key)?;
+        }
+        if reset_if_converted(index, document_id)? {
+            result.reset_to_import = 1;
+        }
+        Ok(result)
+    }
+
+    /// Delete a blob and every entry that points at it.
+    fn remove_blob(&self, index: &DocumentIndex, blob_key: &str) -> Result<PurgeResult, String> {
+        let mut result = PurgeResult::default();
+        for entry in index.get_cache_entries_for_blob(blob_key).map_err(|e| e.to_string())? {
+            index.remove_cache_entry(&entry.document_id).map_err(|e| e.to_string())?;
+            result.removed_entries += 1;
+            if reset_if_converted(index, &entry.document_id)? {
+                result.reset_to_import += 1;
+            }
+        }
+        result.freed_bytes = self.delete_blob_if_unreferenced(index, blob_key)?;
+        Ok(result)
+    }
+
+    fn delete_blob_if_unreferenced(&self, index: &DocumentIndex, blob_key: &str) -> Result<u64, String> {
+        let still_used = !index
+            .get_cache_entries_for_blob(blob_key)
+            .map_err(|e| e.to_string())?
+            .is_empty();
+        if still_used {
+            return Ok(0);
+        }
+        let path = self.blob_path(blob_key);
+        let freed = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let _ = std::fs::remove_file(&path);
+        Ok(freed)
+    }
+
+    /// Drop stale entries, migrate legacy files, reconcile blobs on disk with
+    /// the metadata table and enforce the size budget. Safe to run at startup.
+    pub fn prune(&self, index: &DocumentIndex) -> Result<PurgeResult, String> {
+        let data_key = self.data_key()?;
+        let mut result = PurgeResult::default();
+
+        result.add(self.migrate_legacy_files(index)?);
+        self.encrypt_plaintext_blobs(&data_key);
+
+        for entry in index
+            .get_stale_cache_entries(CONVERTER_VERSION)
+            .map_err(|e| e.to_string())?
+        {
+            result.add(self.remove(index, &entry.document_id)?);
+        }
+
+        // Entries whose blob has disappeared
+        let blobs = index.list_cache_blobs().map_err(|e| e.to_string())?;
+        for blob in &blobs {
+            if !self.blob_path(&blob.blob_key).exists() {
+                result.add(self.remove_blob(index, &blob.blob_key)?);
+            }
+        }
+
+        // Blobs nothing points at (e.g. left behind by a crash mid-store)
+        let tracked: HashSet<&str> = blobs.iter().map(|b| b.blob_key.as_str()).collect();
+        for path in self.blob_files() {
+            let key = match blob_key_from_path(&path) {
+                Some(k) => k,
+                None => continue,
+            };
+            if !tracked.contains(key.as_str()) {
+                result.freed_bytes += std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+
+        result.add(self.enforce_budget(index)?);
+        Ok(result)
+    }
+
+    /// Move uncompressed converted/{document_id}.html files into the blob
+    /// layout. Files for deleted or ignored documents are discarded.
+    fn migrate_legacy_files(&self, index: &DocumentIndex) -> Result<PurgeResult, String> {
+        let mut result = PurgeResult::default();
+        let dir = match std::fs::read_dir(self.dir()) {
+            Ok(d) => d,
+            Err(_) => return Ok(result),
+        };
+
+        for file in dir.filter_map(|f| f.ok()) {
+            let path = file.path();
+            if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("html") {
+                continue;
+            }
+            let document_id = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+
+            let doc = index.get_document(&document_id).map_err(|e| e.to_string())?;
+            let html = std::fs::read_to_string(&path).ok();
+            match (doc, html) {
+                (Some(doc), Some(html)) if doc.status != DocumentStatus::Ignore => {
+                    // Trust the index hash: the file was written from this source
+                    self.store(index, &document_id, doc.file_hash, &html)?;
+                    let _ = std::fs::remove_file(&path);
+                }
+                _ => {
+                    result.removed_entries += 1;
+                    result.freed_bytes += file.metadata().map(|m| m.len()).unwrap_or(0);
+                    let _ = std::fs::remove_file(&path);
+                    if reset_if_converted(index, &document_id)? {
+                        result.reset_to_import += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Evict least-recently-read blobs until the cache fits the budget.
+    pub fn enforce_budget(&self, index: &DocumentIndex) -> Result<PurgeResult, String> {
+        self.evict_over_budget(index, None)
+    }
+
+    /// `enforce_budget`, sparing the blob `keep`.
+    fn evict_over_budget(&self, index: &DocumentIndex, keep: Option<&str>) -> Result<PurgeResult, String> {
+        let mut result = PurgeResult::default();
+        let budget = self.budget();
+        let blobs = index.list_cache_blobs().map_err(|e| e.to_string())?;
+        let mut total: u64 = blobs.iter().map(|b| b.stored_bytes.max(0) as u64).sum();
+
+        for blob in blobs {
+            if total <= budget {
+                break;
+            }
+            if keep == Some(blob.blob_key.as_str()) {
+                continue;
+            }
+            total = total.saturating_sub(blob.stored_bytes.max(0) as u64);
+            result.add(self.remove_blob(index, &blob.blob_key)?);
+        }
+
+        Ok(result)
+    }
+
+    /// Remove every cached conversion.
+    pub fn purge_all(&self, index: &DocumentIndex) -> Result<PurgeResult, String> {
+        self.data_key()?;
+        let mut result = PurgeResult::default();
+        for blob in index.list_cache_blobs().map_err(|e| e.to_string())? {
+            result.add(self.remove_blob(index, &blob.blob_key)?);
+        }
+        // Anything left on disk is untracked
+        for path in self.blob_files() {
+            result.freed_bytes += std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let _ = std::fs::remove_file(&path);
+        }
+        Ok(result)
+    }
+
+    pub fn stats(&self, index: &DocumentIndex) -> Result<CacheStats, String> {
+        let entries = index.list_cache_entries().map_err(|e| e.to_string())?;
+        let blobs = index.list_cache_blobs().map_err(|e| e.to_string())?;
+        let stale = index
+            .get_stale_cache_entries(CONVERTER_VERSION)
+            .map_err(|e| e.to_string())?;
+
+        Ok(CacheStats {
+            entries: entries.len() as u32,
+            blobs: blobs.len() as u32,
+            total_bytes: blobs.iter().map(|b| b.stored_bytes.max(0) as u64).sum(),
+            logical_bytes: entries.iter().map(|e| e.size_bytes.max(0) as u64).sum(),
+            budget_bytes: self.budget(),
+            stale_entries: stale.len() as u32,
+            converter_version: CONVERTER_VERSION,
+            oldest_access: entries.first().map(|e| e.last_accessed_at.clone()),
+        })
+    }
+
+    /// Encrypt blobs written before the cache was encrypted. A blob that fails
+    /// is left alone; if it's damaged, the next read drops it.
+    fn encrypt_plaintext_blobs(&self, key: &DataKey) {
+        for path in self.blob_files() {
+            let bytes = match std::fs::read(&path) {
+                Ok(b) if !encryption::is_encrypted_blob(&b) => b,
+                _ => continue,
+            };
+            let written = encryption::encrypt_bytes(key, &bytes).and_then(|encrypted| replace_file(&path, &encrypted));
+            if let Err(e) = written {
+                eprintln!("[cache] Failed to encrypt {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// All blob files under the fan-out directories.
+    fn blob_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let dirs = match std::fs::read_dir(self.dir()) {
+            Ok(d) => d,
+            Err(_) => return files,
+        };
+        for sub in dirs.filter_map(|d| d.ok()).filter(|d| d.path().is_dir()) {
+            if let Ok(entries) = std::fs::read_dir(sub.path()) {
+                files.extend(
+                    entries
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .filter(|p| blob_key_from_path(p).is_some()),
+                );
+            }
+        }
+        files
+    }
+}
+
+/// Blobs are keyed by source hash, converter version and a short digest of
+/// the HTML. Without a source hash (unreadable source) the document id keeps
+/// the entry private.
+fn blob_key(document_id: &str, source_hash: Option<&str>, html: &str) -> String {
+    let digest = hex::encode(&Sha256::digest(html.as_bytes())[..8]);
+    match source_hash {
+        Some(hash) => format!("{}-v{}-{}", hash, CONVERTER_VERSION, digest),
+        None => format!("doc-{}-v{}-{}", document_id, CONVERTER_VERSION, digest),
+    }
+}
+
+fn blob_key_from_path(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_suffix(&format!(".{}", BLOB_EXTENSION)).map(|k| k.to_string())
+}
+
+/// Compress and encrypt to a temp file, then rename into place so readers
+/// never see a partial blob. Returns the size on disk.
+fn write_blob(path: &Path, html: &str, key: &DataKey) -> Result<u64, String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+    }
+    let compressed = zstd::encode_all(html.as_bytes(), ZSTD_LEVEL)
+        .map_err(|e| format!("Failed to compress converted HTML: {}", e))?;
+    let bytes = encryption::encrypt_bytes(key, &compressed)?;
+
+    replace_file(path, &bytes).map_err(|e| format!("Failed to cache converted HTML: {}", e))?;
+    Ok(bytes.len() as u64)
+}
+
+fn replace_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension(format!("tmp-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        e.to_string()
+    })
+}
+
+/// Read a blob; unencrypted ones from before encryption are still readable.
+fn read_blob(path: &Path, key: &DataKey) -> Result<String, String> {
+    let raw = std::fs::read(path).map_err(|e| e.to_string())?;
+    let compressed = if encryption::is_encrypted_blob(&raw) {
+        encryption::decrypt_bytes(key, &raw)?
+    } else {
+        raw
+    };
+    let bytes = zstd::decode_all(compressed.as_slice()).map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+fn reset_if_converted(index: &DocumentIndex, document_id: &str) -> Result<bool, String> {
+    let was_converted = matches!(
+        index.get_document(document_id).map_err(|e| e.to_string())?,
+        Some(IndexedDocument { status: DocumentStatus::Converted, .. })
+    );
+    if was_converted {
+        index.reset_converted_to_import(document_id).map_err(|e| e.to_string())?;
+    }
+    Ok(was_converted)
+}
+
+fn is_stale(entry: &CacheEntry, doc: Option<&IndexedDocument>) -> bool {
+    let doc = match doc {
+        Some(d) => d,
+        None => return true,
+    };
+    if entry.converter_version != CONVERTER_VERSION || doc.status == DocumentStatus::Ignore {
+        return true;
+    }
+    match (&entry.source_hash, &doc.file_hash) {
+        (Some(cached), Some(current)) => cached != current,
+        _ => false,
+    }
+}
@@ -0,0 +1,631 @@
+// ---------------------------------------------------------------------------
+// carddav — CardDAV contact provider (RFC 6352)
+// ---------------------------------------------------------------------------
+// Works with Nextcloud, Fastmail, iCloud (with an app-specific password) and
+// other CardDAV servers, using Basic auth over HTTPS.
+//
+// Discovery: the configured URL may be an address book, an address book
+// home, a principal or just the server. The first of those found, in that
+// order, leads to the address books (trying /.well-known/carddav when the
+// URL itself doesn't answer). Every address book found is synced.
+//
+// Each address book is synced with REPORT sync-collection (RFC 6578): with
+// the previous sync token the server lists only what changed or was
+// removed. Servers without it (or that have expired the token) get a full
+// PROPFIND listing of etags instead, compared with the cached ones. Changed
+// cards are then fetched with REPORT addressbook-multiget.
+//
+// The provider's sync token is JSON mapping each address book URL to its
+// server sync token, so discovery runs once rather than on every fetch; it
+// runs again if an address book disappears.
+//
+// Redirects are followed here rather than by reqwest, which would turn a
+// redirected PROPFIND/REPORT into a GET.
+//
+// Responses are read with a small XML reader that matches elements by local
+// name. The DAV: and CardDAV names used here don't clash, so namespaces are
+// not resolved.
+// ---------------------------------------------------------------------------
+
+use crate::contact_providers::{ContactChanges, ContactProvider, ProviderContact, ProviderFuture};
+use crate::vcard;
+use reqwest::header::LOCATION;
+use reqwest::{Client, Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+/// Contact ids are "carddav:{provider}:{UID}"
+pub const CARDDAV_ID_PREFIX: &str = "carddav:";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const MULTIGET_BATCH_SIZE: usize = 50;
+const MAX_REDIRECTS: usize = 5;
+
+const PROPFIND_DISCOVERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:carddav">
+  <d:prop><d:resourcetype/><d:current-user-principal/><c:addressbook-home-set/></d:prop>
+</d:propfind>"#;
+
+const PROPFIND_ETAGS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop><d:resourcetype/><d:getetag/></d:prop>
+</d:propfind>"#;
+
+/// Why a sync-collection report didn't produce a delta.
+#[derive(Debug)]
+enum SyncError {
+    /// The server no longer accepts the token; start over without one
+    InvalidToken,
+    /// The server doesn't do sync-collection; compare etags instead
+    Unsupported,
+    /// The address book is gone
+    NotFound,
+    Other(String),
+}
+
+impl From<String> for SyncError {
+    fn from(e: String) -> Self {
+        Self::Other(e)
+    }
+}
+
+/// Members of an address book, from sync-collection or a PROPFIND.
+struct Listing {
+    /// href path → etag
+    changed: Vec<(String, Option<String>)>,
+    /// Removed hrefs (only from an incremental sync-collection)
+    removed: Vec<String>,
+    /// True when `changed` is only what changed since the token
+    incremental: bool,
+    sync_token: Option<String>,
+}
+
+pub struct CardDavClient {
+    client: Client,
+    url: Url,
+    username: String,
+    password: String,
+}
+
+impl CardDavClient {
+    pub fn new(url: &str, username: &str, password: &str) -> Result<Self, String> {
+        let url = Url::parse(url).map_err(|_| format!("Invalid CardDAV URL '{}'", url))?;
+        let client = Client::builder()
+            .user_agent(format!("NexBRIDGE-Connect/{}", env!("CARGO_PKG_VERSION")))
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            client,
+            url,
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Send a WebDAV request, following redirects with the same method.
+    /// Returns the final URL (hrefs are relative to it), status and body.
+    async fn request(&self, method: &str, url: &Url, depth: &str, body: &str) -> Result<(Url, StatusCode, String), String> {
+        let method = Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+        let mut url = url.clone();
+        for _ in 0..=MAX_REDIRECTS {
+            let response = self
+                .client
+                .request(method.clone(), url.clone())
+                .basic_auth(&self.username, Some(&self.password))
+                .header("Depth", depth)
+                .header("Content-Type", "application/xml; charset=utf-8")
+                .body(body.to_string())
+                .send()
+                .await
+                .map_err(|e| format!("CardDAV request to {} failed: {}", url, e))?;
+            let status = response.status();
+
+            if status.is_redirection() {
+                let location = response.headers().get(LOCATION).and_then(|l| l.to_str().ok());
+                if let Some(next) = location.and_then(|l| url.join(l).ok()) {
+                    // Never send the password over plain HTTP after HTTPS
+                    if url.scheme() == "https" && next.scheme() != "https" {
+                        return Err(format!("CardDAV server redirected to insecure {}", next));
+                    }
+                    url = next;
+                    continue;
+                }
+            }
+            if status == StatusCode::UNAUTHORIZED {
+                return Err("CardDAV sign-in failed; check the username and (app) password".to_string());
+            }
+            let body = response.text().await.map_err(|e| format!("CardDAV response from {} failed: {}", url, e))?;
+            return Ok((url, status, body));
+        }
+        Err(format!("Too many redirects from {}", self.url))
+    }
+
+    /// PROPFIND returning the parsed multistatus.
+    async fn propfind(&self, url: &Url, depth: &str, body: &str) -> Result<(Url, Multistatus), String> {
+        let (url, status, text) = self.request("PROPFIND", url, depth, body).await?;
+        if status != StatusCode::MULTI_STATUS {
+            return Err(format!("CardDAV server answered {} for {}", status.as_u16(), url));
+        }
+        Ok((url.clone(), Multistatus::parse(&text)?))
+    }
+
+    // ============ Discovery ============
+
+    /// Address book URLs for the configured URL.
+    pub async fn discover(&self) -> Result<Vec<Url>, String> {
+        let mut starts = vec![self.url.clone()];
+        if let Ok(well_known) = self.url.join("/.well-known/carddav") {
+            if well_known != self.url {
+                starts.push(well_known);
+            }
+        }
+
+        let mut last_error = None;
+        for start in starts {
+            match self.find_address_books(&start).await {
+                Ok(books) if !books.is_empty() => return Ok(books),
+                Ok(_) => {}
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| format!("No address books found at {}", self.url)))
+    }
+
+    async fn find_address_books(&self, start: &Url) -> Result<Vec<Url>, String> {
+        let (url, found) = self.propfind(start, "0", PROPFIND_DISCOVERY).await?;
+        let Some(this) = found.responses.first() else {
+            return Ok(vec![]);
+        };
+        if this.is_address_book() {
+            return Ok(vec![url]);
+        }
+
+        let home = match this.prop_href("addressbook-home-set") {
+            Some(home) => Some(home),
+            None => match this.prop_href("current-user-principal") {
+                Some(principal) => {
+                    let principal = url.join(&principal).map_err(|e| e.to_string())?;
+                    let (principal_url, found) = self.propfind(&principal, "0", PROPFIND_DISCOVERY).await?;
+                    found
+                        .responses
+                        .first()
+                        .and_then(|r| r.prop_href("addressbook-home-set"))
+                        .and_then(|home| principal_url.join(&home).ok())
+                        .map(|home| home.to_string())
+                }
+                None => None,
+            },
+        };
+        // Without a home set, the URL may be the home itself
+        let home = match home {
+            Some(home) => url.join(&home).map_err(|e| e.to_string())?,
+            None => url,
+        };
+
+        let (home, listing) = self.propfind(&home, "1", PROPFIND_DISCOVERY).await?;
+        Ok(listing
+            .responses
+            .iter()
+            .filter(|r| r.is_address_book())
+            .filter_map(|r| home.join(&r.href).ok())
+            .collect())
+    }
+
+    // ============ Listing ============
+
+    /// REPORT sync-collection: everything with no token, else what changed.
+    async fn sync_collection(&self, book: &Url, token: Option<&str>) -> Result<Listing, SyncError> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token>{}</d:sync-token>
+  <d:sync-level>1</d:sync-level>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#,
+            xml_escape(token.unwrap_or_default())
+        );
+        let (url, status, text) = self.request("REPORT", book, "0", &body).await?;
+        match status {
+            StatusCode::MULTI_STATUS => {}
+            StatusCode::NOT_FOUND | StatusCode::GONE => return Err(SyncError::NotFound),
+            StatusCode::FORBIDDEN | StatusCode::CONFLICT if token.is_some() && text.contains("valid-sync-token") => {
+                return Err(SyncError::InvalidToken)
+            }
+            s if s.is_client_error() || s == StatusCode::NOT_IMPLEMENTED => return Err(SyncError::Unsupported),
+            s => return Err(SyncError::Other(format!("CardDAV server answered {} for {}", s.as_u16(), url))),
+        }
+
+        let found = Multistatus::parse(&text)?;
+        let mut listing = Listing {
+            changed: vec![],
+            removed: vec![],
+            incremental: token.is_some(),
+            sync_token: found.sync_token.clone(),
+        };
+        for response in found.responses {
+            let Some(path) = member_path(&url, &response.href) else { continue };
+            if response.status.is_some_and(|s| s == 404) {
+                listing.removed.push(path);
+            } else if !response.is_collection() {
+                listing.changed.push((path, response.prop_text("getetag")));
+            }
+        }
+        Ok(listing)
+    }
+
+    /// Every card in an address book with its etag (PROPFIND Depth: 1).
+    async fn list_etags(&self, book: &Url) -> Result<Listing, SyncError> {
+        let (url, status, text) = self.request("PROPFIND", book, "1", PROPFIND_ETAGS).await?;
+        match status {
+            StatusCode::MULTI_STATUS => {}
+            StatusCode::NOT_FOUND | StatusCode::GONE => return Err(SyncError::NotFound),
+            s => return Err(SyncError::Other(format!("CardDAV server answered {} for {}", s.as_u16(), url))),
+        }
+        let found = Multistatus::parse(&text)?;
+        let changed = found
+            .responses
+            .iter()
+            .filter(|r| !r.is_collection())
+            .filter_map(|r| Some((member_path(&url, &r.href)?, r.prop_text("getetag"))))
+            .collect();
+        Ok(Listing { changed, removed: vec![], incremental: false, sync_token: None })
+    }
+
+    /// REPORT addressbook-multiget: (href path, etag, vCard) per card found.
+    async fn multiget(&self, book: &Url, hrefs: &[String]) -> Result<Vec<(String, Option<String>, String)>, String> {
+        let mut cards = Vec::new();
+        for batch in hrefs.chunks(MULTIGET_BATCH_SIZE) {
+            let body = format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<c:addressbook-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:carddav">
+  <d:prop><d:getetag/><c:address-data/></d:prop>
+{}</c:addressbook-multiget>"#,
+                batch.iter().map(|h| format!("  <d:href>{}</d:href>\n", xml_escape(h))).collect::<String>()
+            );
+            let (url, status, text) = self.request("REPORT", book, "0", &body).await?;
+            if status != StatusCode::MULTI_STATUS {
+                return Err(format!("CardDAV server answered {} for {}", status.as_u16(), url));
+            }
+            for response in Multistatus::parse(&text)?.responses {
+                let (Some(path), Some(data)) = (member_path(&url, &response.href), response.prop_text("address-data")) else {
+                    continue;
+                };
+                cards.push((path, response.prop_text("getetag"), data));
+            }
+        }
+        Ok(cards)
+    }
+}
+
+/// Absolute path of a member href; None for the address book itself.
+fn member_path(book: &Url, href: &str) -> Option<String> {
+    let url = book.join(href).ok()?;
+    (url.path().trim_end_matches('/') != book.path().trim_end_matches('/')).then(|| url.path().to_string())
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// ============ Provider ============
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProviderToken {
+    /// Address book URL → its sync token
+    books: BTreeMap<String, Option<String>>,
+}
+
+pub struct CardDavProvider {
+    id: String,
+    client: CardDavClient,
+}
+
+impl CardDavProvider {
+    pub fn new(id: &str, url: &str, username: &str, password: &str) -> Result<Self, String> {
+        Ok(Self {
+            id: id.to_string(),
+            client: CardDavClient::new(url, username, password)?,
+        })
+    }
+
+    async fn fetch_books(
+        &self,
+        books: &BTreeMap<String, Option<String>>,
+        known: &HashMap<String, Option<String>>,
+    ) -> Result<ContactChanges, SyncError> {
+        let mut changes = ContactChanges::default();
+        let mut token = ProviderToken::default();
+        let mut listed_paths = Vec::new();
+
+        for (book, book_token) in books {
+            let url = Url::parse(book).map_err(|e| e.to_string())?;
+            let listing = match self.client.sync_collection(&url, book_token.as_deref()).await {
+                Err(SyncError::InvalidToken) => match self.client.sync_collection(&url, None).await {
+                    Err(SyncError::Unsupported) => self.client.list_etags(&url).await,
+                    other => other,
+                },
+                Err(SyncError::Unsupported) => self.client.list_etags(&url).await,
+                other => other,
+            }?;
+
+            let book_path = url.path().to_string();
+            let listed: HashSet<&str> = listing.changed.iter().map(|(path, _)| path.as_str()).collect();
+            if listing.incremental {
+                changes.removed.extend(listing.removed.iter().filter(|p| known.contains_key(*p)).cloned());
+            } else {
+                changes.removed.extend(
+                    known
+                        .keys()
+                        .filter(|key| key.starts_with(&book_path) && !listed.contains(key.as_str()))
+                        .cloned(),
+                );
+            }
+            listed_paths.push(book_path);
+
+            let wanted: Vec<String> = listing
+                .changed
+                .iter()
+                .filter(|(path, etag)| etag.is_none() || known.get(path) != Some(etag))
+                .map(|(path, _)| path.clone())
+                .collect();
+            for (path, etag, data) in self.client.multiget(&url, &wanted).await? {
+                let Some(card) = vcard::parse(&data)?.into_iter().next() else { continue };
+                let mut contact = card.to_contact();
+                let uid = card.uid.clone().unwrap_or_else(|| hex::encode(&Sha256::digest(path.as_bytes())[..12]));
+                contact.id = format!("{}{}:{}", CARDDAV_ID_PREFIX, self.id, uid);
+                changes.upserts.push(ProviderContact { key: path, etag, contact });
+            }
+            token.books.insert(book.clone(), listing.sync_token);
+        }
+
+        // Cards from address books that are no longer synced
+        changes.removed.extend(
+            known
+                .keys()
+                .filter(|key| !listed_paths.iter().any(|book| key.starts_with(book.as_str())))
+                .cloned(),
+        );
+        changes.sync_token = serde_json::to_string(&token).ok();
+        Ok(changes)
+    }
+
+    async fn discover_books(&self) -> Result<BTreeMap<String, Option<String>>, String> {
+        Ok(self.client.discover().await?.into_iter().map(|url| (url.to_string(), None)).collect())
+    }
+}
+
+impl ContactProvider for CardDavProvider {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        sync_token: Option<&'a str>,
+        known: &'a HashMap<String, Option<String>>,
+    ) -> ProviderFuture<'a, ContactChanges> {
+        Box::pin(async move {
+            let saved = sync_token
+                .and_then(|t| serde_json::from_str::<ProviderToken>(t).ok())
+                .filter(|t| !t.books.is_empty());
+            let books = match saved {
+                Some(saved) => saved.books,
+                None => self.discover_books().await?,
+            };
+            match self.fetch_books(&books, known).await {
+                Ok(changes) => Ok(changes),
+                // An address book went away: find the current ones
+                Err(SyncError::NotFound) if sync_token.is_some() => {
+                    let books = self.discover_books().await?;
+                    self.fetch_books(&books, known).await.map_err(sync_error_message)
+                }
+                Err(e) => Err(sync_error_message(e)),
+            }
+        })
+    }
+}
+
+fn sync_error_message(e: SyncError) -> String {
+    match e {
+        SyncError::NotFound => "CardDAV address book not found".to_string(),
+        SyncError::InvalidToken | SyncError::Unsupported => "CardDAV server rejected the sync request".to_string(),
+        SyncError::Other(e) => e,
+    }
+}
+
+// ============ Multistatus ============
+
+struct Multistatus {
+    responses: Vec<DavResponse>,
+    sync_token: Option<String>,
+}
+
+struct DavResponse {
+    href: String,
+    /// Response-level status (removed members in sync-collection)
+    status: Option<u16>,
+    /// Properties from 2xx propstats
+    props: Vec<Element>,
+}
+
+impl Multistatus {
+    fn parse(body: &str) -> Result<Self, String> {
+        let root = parse_xml(body)?;
+        if root.name != "multistatus" {
+            return Err(format!("Expected a WebDAV multistatus, got <{}>", root.name));
+        }
+        let sync_token = root.child("sync-token").map(|t| t.text().to_string()).filter(|t| !t.is_empty());
+        let responses = root
+            .children
+            .into_iter()
+            .filter(|c| c.name == "response")
+            .filter_map(|response| {
+                let href = response.child("href")?.text().to_string();
+                let status = response.child("status").and_then(|s| parse_status(s.text()));
+                let props = response
+                    .children
+                    .into_iter()
+                    .filter(|c| c.name == "propstat")
+                    .filter(|ps| ps.child("status").and_then(|s| parse_status(s.text())).is_none_or(|s| (200..300).contains(&s)))
+                    .flat_map(|ps| ps.children.into_iter().filter(|c| c.name == "prop"))
+                    .flat_map(|prop| prop.children)
+                    .collect();
+                Some(DavResponse { href, status, props })
+            })
+            .collect();
+        Ok(Self { responses, sync_token })
+    }
+}
+
+impl DavResponse {
+    fn prop(&self, name: &str) -> Option<&Element> {
+        self.props.iter().find(|p| p.name == name)
+    }
+
+    fn prop_text(&self, name: &str) -> Option<String> {
+        self.prop(name).map(|p| p.text().to_string()).filter(|t| !t.is_empty())
+    }
+
+    /// The <href> inside a property (principal, home set).
+    fn prop_href(&self, name: &str) -> Option<String> {
+        self.prop(name)?.child("href").map(|h| h.text().to_string())
+    }
+
+    fn is_collection(&self) -> bool {
+        self.prop("resourcetype").is_some_and(|t| t.child("collection").is_some())
+            || self.href.ends_with('/')
+    }
+
+    fn is_address_book(&self) -> bool {
+        self.prop("resourcetype").is_some_and(|t| t.child("addressbook").is_some())
+    }
+}
+
+/// "HTTP/1.1 404 Not Found" → 404
+fn parse_status(line: &str) -> Option<u16> {
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+// ============ XML ============
+
+#[derive(Debug, Clone, Default)]
+struct Element {
+    /// Local name, without any namespace prefix
+    name: String,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn text(&self) -> &str {
+        self.text.trim()
+    }
+}
+
+/// Parse a document into its root element. Enough XML for DAV responses:
+/// elements, text, CDATA, entities; attributes are skipped.
+fn parse_xml(input: &str) -> Result<Element, String> {
+    let malformed = || "Malformed XML in CardDAV response".to_string();
+    let mut stack = vec![Element::default()];
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<?") {
+            rest = &after[after.find("?>").ok_or_else(malformed)? + 2..];
+        } else if let Some(after) = rest.strip_prefix("<!--") {
+            rest = &after[after.find("-->").ok_or_else(malformed)? + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").ok_or_else(malformed)?;
+            stack.last_mut().ok_or_else(malformed)?.text.push_str(&after[..end]);
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<!") {
+            rest = &after[after.find('>').ok_or_else(malformed)? + 1..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').ok_or_else(malformed)?;
+            let element = stack.pop().ok_or_else(malformed)?;
+            stack.last_mut().ok_or_else(malformed)?.children.push(element);
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let end = tag_end(after).ok_or_else(malformed)?;
+            let tag = &after[..end];
+            let self_closing = tag.ends_with('/');
+            let name = tag.trim_end_matches('/').split_whitespace().next().ok_or_else(malformed)?;
+            let element = Element {
+                name: name.rsplit(':').next().unwrap_or(name).to_string(),
+                ..Default::default()
+            };
+            if self_closing {
+                stack.last_mut().ok_or_else(malformed)?.children.push(element);
+            } else {
+                stack.push(element);
+            }
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            stack.last_mut().ok_or_else(malformed)?.text.push_str(&decode_entities(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(malformed());
+    }
+    stack.pop().and_then(|root| root.children.into_iter().next()).ok_or_else(malformed)
+}
+
+/// Index of the '>' closing a tag, skipping quoted attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('>', None) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let decoded = after.find(';').and_then(|end| {
+            let entity = &after[..end];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => entity.strip_prefix('#').and_then(|n| n.parse().ok()).and_then(char::from_u32),
+                },
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,194 @@
+// ---------------------------------------------------------------------------
+// categorize — rule-based Nexus categories for indexed documents
+// ---------------------------------------------------------------------------
+// Rules live in the index DB (category_rules). Each rule names one field of
+// the document — breadcrumb folders, file name, extension or the extracted
+// text of its cached conversion — and a list of keywords. Rules are tried in
+// priority order (highest first, ties by age) and the first rule with a
+// matching keyword decides the category.
+//
+// Keywords match case-insensitively on word boundaries, so "sop" matches
+// "Site SOP v2.pdf" but not "philosophy.pdf". Extension rules compare the
+// whole extension. Text rules only see documents that have been converted;
+// for the rest they simply don't match.
+// ---------------------------------------------------------------------------
+
+use crate::cache::ConversionCache;
+use crate::converter;
+use crate::index::{CategoryRule, DocumentIndex, IndexedDocument, RuleField};
+use serde::{Deserialize, Serialize};
+
+/// What the rules propose for one document (dry run or applied).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryProposal {
+    pub document_id: String,
+    pub file_name: String,
+    pub current_category: Option<String>,
+    pub proposed_category: Option<String>,
+    pub rule_id: Option<i64>,
+    pub rule_name: Option<String>,
+    /// The keyword that triggered the rule
+    pub matched_keyword: Option<String>,
+}
+
+/// Rules seeded into a new index.
+pub fn default_rules() -> Vec<CategoryRule> {
+    // (category, keywords for folders/file names, phrases for text, priority)
+    let seeds: &[(&str, &[&str], &[&str], i32)] = &[
+        ("safety", &["safety", "osha", "sds", "msds", "hazard", "ppe", "lockout", "loto"], &["safety data sheet", "personal protective equipment"], 60),
+        ("permit", &["permit", "permits"], &["permit number", "permit no"], 50),
+        ("contract", &["contract", "contracts", "agreement", "subcontract"], &["this agreement", "hereinafter"], 40),
+        ("invoice", &["invoice", "invoices", "receipt", "receipts"], &["invoice number", "amount due", "invoice no"], 40),
+        ("warranty", &["warranty", "warranties", "guarantee"], &["warranty period", "limited warranty"], 40),
+        ("sop", &["sop", "sops", "procedure", "procedures", "work instruction"], &["standard operating procedure"], 30),
+    ];
+
+    let keywords = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+    let mut rules = Vec::new();
+    for (category, words, phrases, priority) in seeds {
+        // File names are more specific than the folder they sit in, and both
+        // beat a phrase somewhere in the body text
+        rules.push(CategoryRule {
+            id: 0,
+            name: format!("{} (file name)", category),
+            category: category.to_string(),
+            field: RuleField::FileName,
+            keywords: keywords(words),
+            priority: priority + 200,
+            enabled: true,
+        });
+        rules.push(CategoryRule {
+            id: 0,
+            name: format!("{} (folder)", category),
+            category: category.to_string(),
+            field: RuleField::Breadcrumb,
+            keywords: keywords(words),
+            priority: priority + 100,
+            enabled: true,
+        });
+        rules.push(CategoryRule {
+            id: 0,
+            name: format!("{} (text)", category),
+            category: category.to_string(),
+            field: RuleField::Text,
+            keywords: keywords(phrases),
+            priority: *priority,
+            enabled: true,
+        });
+    }
+    rules
+}
+
+/// Validate a rule before it is saved.
+pub fn validate_rule(rule: &CategoryRule) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("Rule name is required".to_string());
+    }
+    if rule.category.trim().is_empty() {
+        return Err("Rule category is required".to_string());
+    }
+    if rule.keywords.iter().all(|k| k.trim().is_empty()) {
+        return Err("Rule needs at least one keyword".to_string());
+    }
+    Ok(())
+}
+
+/// The first enabled rule (in the given order) that matches, with the
+/// keyword that matched. `text` is the document's extracted text, if known.
+pub fn match_rule<'r>(
+    rules: &'r [CategoryRule],
+    doc: &IndexedDocument,
+    text: Option<&str>,
+) -> Option<(&'r CategoryRule, String)> {
+    let file_name = doc.file_name.to_lowercase();
+    let extension = doc.file_type.as_deref().unwrap_or("").to_lowercase();
+    // The last breadcrumb entry is the file itself
+    let folders: Vec<String> = doc
+        .breadcrumb
+        .iter()
+        .take(doc.breadcrumb.len().saturating_sub(1))
+        .map(|f| f.to_lowercase())
+        .collect();
+    let text = text.map(|t| t.to_lowercase());
+
+    for rule in rules.iter().filter(|r| r.enabled) {
+        for keyword in &rule.keywords {
+            let keyword = keyword.trim().to_lowercase();
+            if keyword.is_empty() {
+                continue;
+            }
+            let matched = match rule.field {
+                RuleField::FileName => contains_word(&file_name, &keyword),
+                RuleField::Breadcrumb => folders.iter().any(|f| contains_word(f, &keyword)),
+                RuleField::Extension => extension == keyword.trim_start_matches('.'),
+                RuleField::Text => text.as_deref().is_some_and(|t| contains_word(t, &keyword)),
+            };
+            if matched {
+                return Some((rule, keyword));
+            }
+        }
+    }
+    None
+}
+
+/// Run the rules over `docs`. With `apply`, documents whose proposed category
+/// differs from their current one are updated in the index.
+pub fn categorize_documents(
+    index: &DocumentIndex,
+    cache: &ConversionCache,
+    docs: &[IndexedDocument],
+    apply: bool,
+) -> Result<Vec<CategoryProposal>, String> {
+    let rules = index.list_category_rules().map_err(|e| e.to_string())?;
+    let needs_text = rules.iter().any(|r| r.enabled && r.field == RuleField::Text);
+
+    let mut proposals = Vec::with_capacity(docs.len());
+    for doc in docs {
+        let text = if needs_text {
+            cache
+                .load(index, &doc.id)
+                .ok()
+                .map(|html| converter::strip_html_tags(&html))
+        } else {
+            None
+        };
+
+        let matched = match_rule(&rules, doc, text.as_deref());
+        let proposal = CategoryProposal {
+            document_id: doc.id.clone(),
+            file_name: doc.file_name.clone(),
+            current_category: doc.category.clone(),
+            proposed_category: matched.as_ref().map(|(r, _)| r.category.clone()),
+            rule_id: matched.as_ref().map(|(r, _)| r.id),
+            rule_name: matched.as_ref().map(|(r, _)| r.name.clone()),
+            matched_keyword: matched.map(|(_, k)| k),
+        };
+
+        if apply {
+            if let Some(category) = &proposal.proposed_category {
+                if proposal.current_category.as_ref() != Some(category) {
+                    index
+                        .set_category(&doc.id, Some(category))
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        proposals.push(proposal);
+    }
+    Ok(proposals)
+}
+
+/// Case-insensitive (callers lowercase both sides) whole-word containment.
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+    let mut search = 0;
+    while let Some(pos) = haystack[search..].find(needle) {
+        let start = search + pos;
+        let end = start + needle.len();
+        if !is_word(haystack[..start].chars().next_back()) && !is_word(haystack[end..].chars().next()) {
+            return true;
+        }
+        search = start + haystack[start..].chars().next().map_or(1, |c| c.len_utf8());
+    }
+    false
+}
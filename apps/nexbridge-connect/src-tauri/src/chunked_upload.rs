@@ -0,0 +1,376 @@
+// ---------------------------------------------------------------------------
+// chunked_upload — resumable, chunked uploads for large payloads
+// ---------------------------------------------------------------------------
+// Large bodies (converted HTML, original files, scan models) are sent to the
+// Nexus upload-session API in fixed-size chunks instead of one request:
+//
+//   POST {api}/upload-sessions                  {fileName, contentType, size, sha256, chunkSize}
+//                                               → {uploadId, offset}
+//   GET  {api}/upload-sessions/{id}             → {uploadId, offset}
+//   PUT  {api}/upload-sessions/{id}?offset={n}  chunk bytes, Content-Range: bytes n-m/size
+//                                               → {offset}   (409 + {offset} if n is stale)
+//   POST {api}/upload-sessions/{id}/complete    → {uploadId, size, sha256}
+//
+// Served by UploadSessionsController in apps/api.
+//
+// The finished upload is then referenced by its uploadId from the endpoint
+// that consumes it (upload-html, /original, precision-scans upload). The
+// requests themselves are made through `NexusApi`.
+//
+// Each chunk is retried with backoff; before a retry the server is asked for
+// the offset it actually has, so a chunk whose ack was lost isn't resent.
+// Sessions are recorded in a small JSON file keyed by target + content hash,
+// so an upload interrupted by a disconnect or an app restart picks up at the
+// last acknowledged offset. The server's hash of the assembled upload must
+// match the local one before the upload counts as done.
+// ---------------------------------------------------------------------------
+
+use crate::bandwidth::limiter;
+use crate::documents::calculate_file_hash;
+use crate::nexus_api::{ApiError, ChunkAck, CreateUploadSession, NexusApi};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Default name of the session file, kept next to whatever it belongs to.
+pub const SESSIONS_FILE: &str = "upload_sessions.json";
+/// Bodies larger than this go through an upload session.
+pub const CHUNK_THRESHOLD: u64 = 16 * 1024 * 1024;
+const CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const MAX_ATTEMPTS: u32 = 5;
+/// Sessions older than this are assumed to have expired on the server.
+const SESSION_MAX_AGE_HOURS: i64 = 72;
+
+/// What to upload: a file streamed from disk, or a body already in memory.
+#[derive(Debug, Clone)]
+pub enum UploadSource {
+    File(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl UploadSource {
+    async fn size(&self) -> Result<u64, String> {
+        match self {
+            Self::File(path) => tokio::fs::metadata(path)
+                .await
+                .map(|m| m.len())
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+            Self::Bytes(bytes) => Ok(bytes.len() as u64),
+        }
+    }
+
+    async fn sha256(&self) -> Result<String, String> {
+        match self {
+            Self::File(path) => {
+                let hash_path = path.clone();
+                tokio::task::spawn_blocking(move || calculate_file_hash(&hash_path))
+                    .await
+                    .map_err(|e| format!("Hashing task failed: {}", e))?
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            }
+            Self::Bytes(bytes) => Ok(hex::encode(Sha256::digest(bytes))),
+        }
+    }
+
+    async fn read_chunk(&self, offset: u64, len: u64) -> Result<Vec<u8>, String> {
+        match self {
+            Self::File(path) => {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || -> std::io::Result<Vec<u8>> {
+                    let mut file = std::fs::File::open(&path)?;
+                    file.seek(SeekFrom::Start(offset))?;
+                    let mut buf = vec![0u8; len as usize];
+                    file.read_exact(&mut buf)?;
+                    Ok(buf)
+                })
+                .await
+                .map_err(|e| format!("Read task failed: {}", e))?
+                .map_err(|e| format!("Failed to read chunk at {}: {}", offset, e))
+            }
+            Self::Bytes(bytes) => Ok(bytes[offset as usize..(offset + len) as usize].to_vec()),
+        }
+    }
+}
+
+/// A finished, hash-verified upload session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletedUpload {
+    pub upload_id: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Offset the upload resumed from (0 for a fresh session)
+    pub resumed_from: u64,
+}
+
+/// One in-flight session as recorded on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Session {
+    upload_id: String,
+    size: u64,
+    offset: u64,
+    updated_at: String,
+}
+
+/// Outcome of a single chunk PUT.
+enum ChunkError {
+    /// Worth retrying (network error, timeout, 5xx, 429)
+    Transient(String),
+    /// The server is at a different offset than we sent
+    OffsetMismatch(u64),
+    /// The session is gone (404/410) — start a new one
+    Expired,
+    Fatal(String),
+}
+
+pub struct ChunkedUploader {
+    /// JSON file holding in-flight sessions; `None` keeps them in memory only
+    state_file: Option<PathBuf>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl ChunkedUploader {
+    pub fn new(state_file: Option<PathBuf>) -> Self {
+        let sessions = state_file
+            .as_deref()
+            .map(load_sessions)
+            .unwrap_or_default();
+        Self {
+            state_file,
+            sessions: Mutex::new(sessions),
+        }
+    }
+
+    /// Whether a body of this size should go through an upload session.
+    pub fn should_chunk(size: u64) -> bool {
+        size > CHUNK_THRESHOLD
+    }
+
+    /// Upload `source` in chunks, resuming a recorded session for the same
+    /// content if there is one. `progress` is called with (sent, total) bytes
+    /// after every acknowledged chunk.
+    pub async fn upload(
+        &self,
+        api: &NexusApi,
+        file_name: &str,
+        content_type: &str,
+        source: &UploadSource,
+        progress: &(dyn Fn(u64, u64) + Send + Sync),
+    ) -> Result<CompletedUpload, String> {
+        let api_url = api.base_url().ok_or_else(|| ApiError::NotConfigured.to_string())?;
+        let size = source.size().await?;
+        let sha256 = source.sha256().await?;
+        let key = format!("{} {} {}", api_url, file_name, sha256);
+
+        // Resume where the server says we left off, or start over
+        let mut resumed = None;
+        if let Some(session) = self.session(&key) {
+            match self.session_offset(api, &session.upload_id).await {
+                Ok(Some(offset)) if session.size == size => {
+                    eprintln!(
+                        "[chunked_upload] Resuming {} at {}/{} bytes",
+                        file_name, offset, size
+                    );
+                    resumed = Some((session.upload_id, offset));
+                }
+                Ok(_) => self.forget(&key),
+                Err(e) => return Err(e),
+            }
+        }
+        let (upload_id, mut offset) = match resumed {
+            Some(r) => r,
+            None => {
+                let created = api
+                    .create_upload_session(&CreateUploadSession {
+                        file_name: file_name.to_string(),
+                        content_type: content_type.to_string(),
+                        size,
+                        sha256: sha256.clone(),
+                        chunk_size: CHUNK_SIZE,
+                    })
+                    .await?;
+                let upload_id = created
+                    .upload_id
+                    .ok_or_else(|| "Server returned no upload id".to_string())?;
+                (upload_id, 0)
+            }
+        };
+        let resumed_from = offset;
+        self.record(&key, &upload_id, size, offset);
+        progress(offset, size);
+
+        while offset < size {
+            // Chunk boundaries are where a transfer window / metered pause applies
+            limiter().wait_until_allowed(&|_| {}).await;
+            let len = CHUNK_SIZE.min(size - offset);
+            let chunk = source.read_chunk(offset, len).await?;
+            offset = self
+                .send_chunk_with_retry(api, &key, &upload_id, offset, size, chunk)
+                .await?;
+            self.record(&key, &upload_id, size, offset);
+            progress(offset, size);
+        }
+
+        let completed = api.complete_upload_session(&upload_id).await;
+        // Whatever the outcome, this session can't be resumed any further
+        self.forget(&key);
+        let completed = completed?;
+
+        if let Some(server_size) = completed.size {
+            if server_size != size {
+                return Err(format!(
+                    "Upload of {} was incomplete: server has {} of {} bytes",
+                    file_name, server_size, size
+                ));
+            }
+        }
+        match completed.sha256 {
+            Some(server_hash) if server_hash.eq_ignore_ascii_case(&sha256) => Ok(CompletedUpload {
+                upload_id,
+                size,
+                sha256,
+                resumed_from,
+            }),
+            Some(server_hash) => Err(format!(
+                "Hash mismatch for {}: sent {}, server has {}",
+                file_name, sha256, server_hash
+            )),
+            None => Err(format!("Server did not report a hash for {}", file_name)),
+        }
+    }
+
+    async fn send_chunk_with_retry(
+        &self,
+        api: &NexusApi,
+        key: &str,
+        upload_id: &str,
+        offset: u64,
+        size: u64,
+        chunk: Vec<u8>,
+    ) -> Result<u64, String> {
+        let mut last_error = String::new();
+        for attempt in 1..=MAX_ATTEMPTS {
+            match send_chunk(api, upload_id, offset, size, &chunk).await {
+                Ok(new_offset) => return Ok(new_offset),
+                Err(ChunkError::OffsetMismatch(server_offset)) if server_offset != offset => {
+                    // Either the ack for this chunk was lost (server is ahead)
+                    // or an earlier chunk is missing; the caller re-reads from
+                    // wherever the server actually is.
+                    return Ok(server_offset);
+                }
+                Err(ChunkError::OffsetMismatch(_)) => {
+                    last_error = "Server rejected the chunk at its own offset".to_string();
+                }
+                Err(ChunkError::Expired) => {
+                    self.forget(key);
+                    return Err("Upload session expired on the server; retry the upload".to_string());
+                }
+                Err(ChunkError::Fatal(e)) => return Err(e),
+                Err(ChunkError::Transient(e)) => {
+                    eprintln!(
+                        "[chunked_upload] Chunk at {} failed (attempt {}/{}): {}",
+                        offset, attempt, MAX_ATTEMPTS, e
+                    );
+                    last_error = e;
+                }
+            }
+
+            if attempt < MAX_ATTEMPTS {
+                tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+                // The chunk may have landed even though we didn't see the ack
+                if let Ok(Some(server_offset)) = self.session_offset(api, upload_id).await {
+                    if server_offset != offset {
+                        return Ok(server_offset);
+                    }
+                }
+            }
+        }
+        Err(format!(
+            "Chunk at offset {} failed after {} attempts: {}",
+            offset, MAX_ATTEMPTS, last_error
+        ))
+    }
+
+    /// The server's acknowledged offset, or `None` if the session is gone.
+    async fn session_offset(&self, api: &NexusApi, upload_id: &str) -> Result<Option<u64>, String> {
+        Ok(api.upload_session(upload_id).await?.map(|info| info.offset))
+    }
+
+    // ============ Session bookkeeping ============
+
+    fn session(&self, key: &str) -> Option<Session> {
+        self.sessions.lock().unwrap().get(key).cloned()
+    }
+
+    fn record(&self, key: &str, upload_id: &str, size: u64, offset: u64) {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.insert(
+            key.to_string(),
+            Session {
+                upload_id: upload_id.to_string(),
+                size,
+                offset,
+                updated_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
+        self.save(&sessions);
+    }
+
+    fn forget(&self, key: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.remove(key).is_some() {
+            self.save(&sessions);
+        }
+    }
+
+    fn save(&self, sessions: &HashMap<String, Session>) {
+        let Some(path) = &self.state_file else { return };
+        let tmp = PathBuf::from(format!("{}.tmp", path.display()));
+        let result = serde_json::to_string_pretty(sessions)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&tmp, json).map_err(|e| e.to_string()))
+            .and_then(|_| std::fs::rename(&tmp, path).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            eprintln!("[chunked_upload] Failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// PUT one chunk, sorting failures into what the retry loop should do.
+async fn send_chunk(
+    api: &NexusApi,
+    upload_id: &str,
+    offset: u64,
+    size: u64,
+    chunk: &[u8],
+) -> Result<u64, ChunkError> {
+    match api.put_upload_chunk(upload_id, offset, size, chunk).await {
+        Ok(ChunkAck::Accepted(new_offset)) => Ok(new_offset),
+        Ok(ChunkAck::OffsetMismatch(server_offset)) => Err(ChunkError::OffsetMismatch(server_offset)),
+        Err(e) if e.is_gone() => Err(ChunkError::Expired),
+        Err(e @ ApiError::Decode(_)) => Err(ChunkError::Transient(e.to_string())),
+        Err(e) if e.is_transient() => Err(ChunkError::Transient(e.to_string())),
+        Err(e) => Err(ChunkError::Fatal(e.to_string())),
+    }
+}
+
+/// Read recorded sessions, dropping any too old to still exist server-side.
+fn load_sessions(path: &Path) -> HashMap<String, Session> {
+    let Ok(raw) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    let mut sessions: HashMap<String, Session> = serde_json::from_str(&raw).unwrap_or_else(|e| {
+        eprintln!("[chunked_upload] Ignoring unreadable {}: {}", path.display(), e);
+        HashMap::new()
+    });
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(SESSION_MAX_AGE_HOURS);
+    sessions.retain(|_, s| {
+        chrono::DateTime::parse_from_rfc3339(&s.updated_at)
+            .map(|t| t > cutoff)
+            .unwrap_or(false)
+    });
+    sessions
+}
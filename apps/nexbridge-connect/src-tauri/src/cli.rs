@@ -0,0 +1,608 @@
+// ---------------------------------------------------------------------------
+// nexbridge — headless CLI for bulk document import
+// ---------------------------------------------------------------------------
+// Runs the same scan → convert → upload pipeline as the Documents tab, but
+// without a webview, so it can be driven from cron on a Linux box that
+// mounts the server share. Build it without the desktop app's GUI
+// dependencies (Tauri, WebKit, glib):
+//
+//   cargo build --release --no-default-features --bin nexbridge
+//
+//   nexbridge scan <path>
+//   nexbridge status [--status IMPORT]
+//   nexbridge stats
+//   nexbridge convert [--id <doc-id>]... [--status IMPORT]
+//   nexbridge upload  [--id <doc-id>]... [--category local-upload] [--with-original]
+//   nexbridge categorize [--id <doc-id>]... [--status IMPORT] [--apply]
+//   nexbridge export  [--status UPLOADED] [--output index.csv] [--format csv]
+//   nexbridge import  <file> [--map /Volumes/Share=Z:\Share]...
+//
+// Every command prints one JSON document to stdout. Failures print
+// {"error": "..."} to stderr and exit with status 1; partial failures
+// (some documents failed to convert/upload) exit with status 2.
+//
+// The index lives in the same app data directory as the desktop app, so the
+// GUI and CLI see the same statuses — for the account last signed in to the
+// desktop app (see accounts.rs). Override with --data-dir.
+//
+// The index and cache are encrypted (see encryption.rs). Where there is no OS
+// credential store, the passphrase comes from NEXBRIDGE_PASSPHRASE.
+//
+// API credentials come from NEXBRIDGE_API_URL / NEXBRIDGE_TOKEN (and
+// NEXBRIDGE_REFRESH_TOKEN), falling back to {data_dir}/cli.json
+// ({"api_url": "...", "token": "...", "refresh_token": "..."}) or --config.
+// With a refresh token an expired access token is renewed automatically; if
+// the tokens came from the config file, the renewed ones are written back.
+// ---------------------------------------------------------------------------
+
+use crate::accounts;
+use crate::cache::ConversionCache;
+use crate::categorize;
+use crate::chunked_upload;
+use crate::converter;
+use crate::documents;
+use crate::encryption::{self, DataKey, KeyLookup};
+use crate::index::{DocumentIndex, DocumentStatus, IndexedDocument};
+use crate::index_transfer::{self, ExportFormat, PathMapping};
+use crate::nexus_api::{Credentials, NexusApi};
+use crate::uploader::{UploadQueue, UploadRequest};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const APP_IDENTIFIER: &str = "com.nexus.nexbridge-connect";
+
+const USAGE: &str = "Usage: nexbridge [--data-dir <dir>] [--config <file>] <command> [options]
+
+Commands:
+  scan <path>                       Scan a folder into the document index
+  status [--status <STATUS>]        List indexed documents (optionally by status)
+  stats                             Show document counts by status
+  convert [--id <id>]... [--status <STATUS>]
+                                    Convert documents to HTML and cache them (default: IMPORT)
+  upload [--id <id>]... [--category <category>] [--with-original]
+                                    Upload converted documents to Nexus (default: CONVERTED);
+                                    without --category, each document's own category is used;
+                                    --with-original also uploads each source file
+  categorize [--id <id>]... [--status <STATUS>] [--apply]
+                                    Show the category rules' proposals; --apply stores them
+  export [--status <STATUS>] [--output <file>] [--format <csv|jsonl|xlsx|json>]
+                                    Export the document index (format from the file
+                                    extension; JSON when printing to stdout)
+  import <file> [--format <FORMAT>] [--map <FROM>=<TO>]...
+                                    Merge an exported index, rewriting path prefixes
+
+Environment:
+  NEXBRIDGE_API_URL, NEXBRIDGE_TOKEN  API credentials for upload
+  NEXBRIDGE_REFRESH_TOKEN             Renews NEXBRIDGE_TOKEN when it expires
+  NEXBRIDGE_PASSPHRASE                Local data passphrase (Linux without a keyring)";
+
+/// Credentials for the upload command, read from `cli.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CliConfig {
+    api_url: Option<String>,
+    token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+    /// File the tokens were read from, to store refreshed ones in
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+/// Parsed global options plus the remaining command arguments.
+struct Invocation {
+    data_dir: PathBuf,
+    config_path: Option<PathBuf>,
+    command: String,
+    args: Vec<String>,
+}
+
+/// Per-document outcome for convert/upload, reported in the JSON output.
+#[derive(Debug, Clone, Serialize)]
+struct ItemResult {
+    id: String,
+    file_path: String,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nexus_doc_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BatchResult {
+    total: u32,
+    succeeded: u32,
+    failed: u32,
+    items: Vec<ItemResult>,
+}
+
+impl BatchResult {
+    fn from_items(items: Vec<ItemResult>) -> Self {
+        let succeeded = items.iter().filter(|i| i.success).count() as u32;
+        Self {
+            total: items.len() as u32,
+            succeeded,
+            failed: items.len() as u32 - succeeded,
+            items,
+        }
+    }
+}
+
+/// Entry point for the `nexbridge` binary. Returns the process exit code.
+pub fn run() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let invocation = match parse_invocation(args) {
+        Ok(inv) => inv,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 1;
+        }
+    };
+
+    match dispatch(invocation) {
+        Ok((output, exit_code)) => {
+            println!("{}", serde_json::to_string_pretty(&output).unwrap_or_default());
+            exit_code
+        }
+        Err(e) => {
+            eprintln!("{}", serde_json::json!({ "error": e }));
+            1
+        }
+    }
+}
+
+fn parse_invocation(args: Vec<String>) -> Result<Invocation, String> {
+    let mut data_dir: Option<PathBuf> = None;
+    let mut config_path: Option<PathBuf> = None;
+    let mut iter = args.into_iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--data-dir" => {
+                data_dir = Some(PathBuf::from(iter.next().ok_or("--data-dir requires a value")?));
+            }
+            "--config" => {
+                config_path = Some(PathBuf::from(iter.next().ok_or("--config requires a value")?));
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+            _ => {
+                let data_dir = match data_dir {
+                    Some(d) => d,
+                    None => default_data_dir()?,
+                };
+                return Ok(Invocation {
+                    data_dir,
+                    config_path,
+                    command: arg,
+                    args: iter.collect(),
+                });
+            }
+        }
+    }
+
+    Err("No command given".to_string())
+}
+
+/// Same directory Tauri resolves as `app_data_dir()` for our bundle identifier.
+fn default_data_dir() -> Result<PathBuf, String> {
+    dirs_next::data_dir()
+        .map(|d| d.join(APP_IDENTIFIER))
+        .ok_or_else(|| "Could not determine app data directory; pass --data-dir".to_string())
+}
+
+/// `--flag value` pairs in order, then positional arguments.
+type ParsedArgs = (Vec<(String, String)>, Vec<String>);
+
+/// Collect `--flag value` pairs for a command. Repeated flags are kept in order.
+fn parse_flags(args: &[String], allowed: &[&str]) -> Result<ParsedArgs, String> {
+    let mut flags = Vec::new();
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        if let Some(name) = arg.strip_prefix("--") {
+            if !allowed.contains(&name) {
+                return Err(format!("Unknown option: {}", arg));
+            }
+            let value = iter.next().ok_or_else(|| format!("{} requires a value", arg))?;
+            flags.push((name.to_string(), value.clone()));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    Ok((flags, positional))
+}
+
+/// Remove a value-less `--name` switch from the arguments.
+fn take_switch(args: &[String], name: &str) -> (bool, Vec<String>) {
+    let switch = format!("--{}", name);
+    let rest: Vec<String> = args.iter().filter(|a| **a != switch).cloned().collect();
+    (rest.len() != args.len(), rest)
+}
+
+fn flag_values(flags: &[(String, String)], name: &str) -> Vec<String> {
+    flags.iter().filter(|(k, _)| k == name).map(|(_, v)| v.clone()).collect()
+}
+
+fn flag_value(flags: &[(String, String)], name: &str) -> Option<String> {
+    flags.iter().rev().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+}
+
+/// The local data key, looked up once per run (passphrase derivation is slow).
+fn data_key(data_dir: &Path) -> Result<DataKey, String> {
+    static KEY: OnceLock<DataKey> = OnceLock::new();
+    if let Some(key) = KEY.get() {
+        return Ok(key.clone());
+    }
+    let key = match encryption::load_key(data_dir)? {
+        KeyLookup::Ready(key, _) => key,
+        KeyLookup::NeedsPassphrase { .. } => {
+            let passphrase = std::env::var(encryption::PASSPHRASE_ENV)
+                .map_err(|_| format!("Local data is passphrase-protected; set {}", encryption::PASSPHRASE_ENV))?;
+            encryption::unlock_with_passphrase(data_dir, &passphrase)?
+        }
+    };
+    Ok(KEY.get_or_init(|| key).clone())
+}
+
+/// Opens the partition of the account last active in the desktop app.
+fn open_index(data_dir: &Path) -> Result<DocumentIndex, String> {
+    let partition = accounts::active_partition(data_dir);
+    std::fs::create_dir_all(&partition.dir)
+        .map_err(|e| format!("Failed to create data directory {}: {}", partition.dir.display(), e))?;
+    let key = data_key(data_dir)?;
+    DocumentIndex::new(&partition.documents_db, Some(&key))
+        .map_err(|e| format!("Failed to open document index: {}", e))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+/// Open the converted-HTML cache, reconciling it with the index the same way
+/// the desktop app does at startup.
+fn open_cache(index: &DocumentIndex, data_dir: &Path) -> Result<ConversionCache, String> {
+    let cache = ConversionCache::new(accounts::active_partition(data_dir).converted_dir, Some(data_key(data_dir)?));
+    cache.prune(index)?;
+    Ok(cache)
+}
+
+fn dispatch(inv: Invocation) -> Result<(serde_json::Value, i32), String> {
+    match inv.command.as_str() {
+        "scan" => {
+            let (_, positional) = parse_flags(&inv.args, &[])?;
+            let path = positional.first().ok_or("scan requires a folder path")?;
+            let index = open_index(&inv.data_dir)?;
+            let result = documents::scan_directory(path, &index)?;
+            Ok((
+                serde_json::json!({
+                    "path": path,
+                    "documents_found": result.documents_found,
+                    "documents_new": result.documents_new,
+                    "documents_updated": result.documents_updated,
+                }),
+                0,
+            ))
+        }
+        "status" => {
+            let (flags, _) = parse_flags(&inv.args, &["status"])?;
+            let index = open_index(&inv.data_dir)?;
+            let docs = select_documents(&index, &[], flag_value(&flags, "status").as_deref(), None)?;
+            Ok((to_json(&docs)?, 0))
+        }
+        "stats" => {
+            let index = open_index(&inv.data_dir)?;
+            let stats = index.get_stats().map_err(|e| e.to_string())?;
+            Ok((to_json(&stats)?, 0))
+        }
+        "convert" => {
+            let (flags, _) = parse_flags(&inv.args, &["id", "status"])?;
+            let index = open_index(&inv.data_dir)?;
+            let docs = select_documents(
+                &index,
+                &flag_values(&flags, "id"),
+                flag_value(&flags, "status").as_deref(),
+                Some(DocumentStatus::Import),
+            )?;
+            let batch = convert_documents(&index, &inv.data_dir, &docs)?;
+            let code = if batch.failed > 0 { 2 } else { 0 };
+            Ok((to_json(&batch)?, code))
+        }
+        "upload" => {
+            let (with_original, args) = take_switch(&inv.args, "with-original");
+            let (flags, _) = parse_flags(&args, &["id", "category"])?;
+            let config = load_config(&inv.data_dir, inv.config_path.as_deref())?;
+            let api = api_client(config)?;
+            let category = flag_value(&flags, "category");
+
+            let index = open_index(&inv.data_dir)?;
+            let docs = select_documents(
+                &index,
+                &flag_values(&flags, "id"),
+                None,
+                Some(DocumentStatus::Converted),
+            )?;
+            let batch = upload_documents(
+                &index,
+                &inv.data_dir,
+                &docs,
+                &api,
+                category.as_deref(),
+                with_original,
+            )?;
+            let code = if batch.failed > 0 { 2 } else { 0 };
+            Ok((to_json(&batch)?, code))
+        }
+        "categorize" => {
+            let (apply, args) = take_switch(&inv.args, "apply");
+            let (flags, _) = parse_flags(&args, &["id", "status"])?;
+            let index = open_index(&inv.data_dir)?;
+            let docs = select_documents(&index, &flag_values(&flags, "id"), flag_value(&flags, "status").as_deref(), None)?;
+            let docs: Vec<IndexedDocument> = docs
+                .into_iter()
+                .filter(|d| d.status != DocumentStatus::Ignore)
+                .collect();
+            let cache = open_cache(&index, &inv.data_dir)?;
+            let proposals = categorize::categorize_documents(&index, &cache, &docs, apply)?;
+            Ok((to_json(&proposals)?, 0))
+        }
+        "export" => {
+            let (flags, _) = parse_flags(&inv.args, &["status", "output", "format"])?;
+            let index = open_index(&inv.data_dir)?;
+            let docs = select_documents(&index, &[], flag_value(&flags, "status").as_deref(), None)?;
+            let format = flag_value(&flags, "format").map(|f| ExportFormat::parse(&f)).transpose()?;
+            match flag_value(&flags, "output") {
+                Some(output) => {
+                    let path = PathBuf::from(&output);
+                    let format = format
+                        .or_else(|| ExportFormat::from_path(&path))
+                        .unwrap_or(ExportFormat::Json);
+                    let result = index_transfer::export_documents(&docs, &path, format)?;
+                    Ok((to_json(&result)?, 0))
+                }
+                None if format.is_none_or(|f| f == ExportFormat::Json) => Ok((to_json(&docs)?, 0)),
+                None => Err("--format other than json requires --output".to_string()),
+            }
+        }
+        "import" => {
+            let (flags, positional) = parse_flags(&inv.args, &["format", "map"])?;
+            let path = positional.first().ok_or("import requires an exported file")?;
+            let format = flag_value(&flags, "format").map(|f| ExportFormat::parse(&f)).transpose()?;
+            let mappings = flag_values(&flags, "map")
+                .iter()
+                .map(|m| PathMapping::parse(m))
+                .collect::<Result<Vec<_>, _>>()?;
+            let index = open_index(&inv.data_dir)?;
+            let result = index_transfer::import_file(&index, Path::new(path), format, &mappings)?;
+            let code = if result.errors.is_empty() { 0 } else { 2 };
+            Ok((to_json(&result)?, code))
+        }
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+/// Resolve the documents a command operates on: explicit ids win, then an
+/// explicit status, then the command's default status (or everything).
+fn select_documents(
+    index: &DocumentIndex,
+    ids: &[String],
+    status: Option<&str>,
+    default_status: Option<DocumentStatus>,
+) -> Result<Vec<IndexedDocument>, String> {
+    if !ids.is_empty() {
+        return ids
+            .iter()
+            .map(|id| {
+                index
+                    .get_document(id)
+                    .map_err(|e| e.to_string())?
+                    .ok_or_else(|| format!("Document not found: {}", id))
+            })
+            .collect();
+    }
+
+    let status = match status {
+        Some(s) => Some(DocumentStatus::parse(s)?),
+        None => default_status,
+    };
+
+    match status {
+        Some(s) => index.get_documents_by_status(s).map_err(|e| e.to_string()),
+        None => index.get_all_documents().map_err(|e| e.to_string()),
+    }
+}
+
+fn convert_documents(index: &DocumentIndex, data_dir: &Path, docs: &[IndexedDocument]) -> Result<BatchResult, String> {
+    let cache = open_cache(index, data_dir)?;
+
+    let items = docs
+        .iter()
+        .map(|doc| {
+            // Refuse before caching anything the status change would then reject
+            let outcome = index
+                .check_status_transition(&doc.id, &DocumentStatus::Converted)
+                .map_err(|e| e.to_string())
+                .and_then(|()| {
+                    let source_hash = documents::calculate_file_hash(Path::new(&doc.file_path)).ok();
+                    let result = converter::convert_to_html(&doc.file_path)?;
+                    cache.store(index, &doc.id, source_hash, &result.html)
+                })
+                .and_then(|()| {
+                    index
+                        .update_status(&doc.id, DocumentStatus::Converted, None, "cli", None)
+                        .map_err(|e| e.to_string())
+                });
+
+            match outcome {
+                Ok(()) => {
+                    ItemResult {
+                        id: doc.id.clone(),
+                        file_path: doc.file_path.clone(),
+                        success: true,
+                        nexus_doc_id: None,
+                        error: None,
+                        original_sha256: None,
+                        original_error: None,
+                    }
+                }
+                Err(e) => {
+                    let _ = index.update_status(&doc.id, DocumentStatus::Failed, Some(&e), "cli", None);
+                    ItemResult {
+                        id: doc.id.clone(),
+                        file_path: doc.file_path.clone(),
+                        success: false,
+                        nexus_doc_id: None,
+                        error: Some(e),
+                        original_sha256: None,
+                        original_error: None,
+                    }
+                }
+            }
+        })
+        .collect();
+
+    Ok(BatchResult::from_items(items))
+}
+
+fn upload_documents(
+    index: &DocumentIndex,
+    data_dir: &Path,
+    docs: &[IndexedDocument],
+    api: &NexusApi,
+    category: Option<&str>,
+    with_original: bool,
+) -> Result<BatchResult, String> {
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| format!("Failed to start async runtime: {}", e))?;
+    let queue = UploadQueue::with_session_file(Some(data_dir.join(chunked_upload::SESSIONS_FILE)));
+    queue.total.store(docs.len() as u32, std::sync::atomic::Ordering::SeqCst);
+
+    let cache = open_cache(index, data_dir)?;
+    let mut items = Vec::with_capacity(docs.len());
+
+    for doc in docs {
+        let html = match cache.load(index, &doc.id) {
+            Ok(h) => h,
+            Err(e) => {
+                items.push(ItemResult {
+                    id: doc.id.clone(),
+                    file_path: doc.file_path.clone(),
+                    success: false,
+                    nexus_doc_id: None,
+                    error: Some(e),
+                    original_sha256: None,
+                    original_error: None,
+                });
+                continue;
+            }
+        };
+
+        let category = category
+            .or(doc.category.as_deref())
+            .unwrap_or("local-upload");
+        let mut request = UploadRequest::for_document(doc, html, category);
+        if with_original {
+            request.original_path = Some(doc.file_path.clone());
+        }
+        let result = runtime.block_on(queue.upload_document(api, request));
+
+        if result.success {
+            let _ = match &result.nexus_doc_id {
+                Some(nexus_id) => index.mark_uploaded(&doc.id, nexus_id, "cli"),
+                None => index.update_status(&doc.id, DocumentStatus::Uploaded, None, "cli", None),
+            };
+        } else {
+            let msg = result.error.clone().unwrap_or_else(|| "Upload failed".to_string());
+            let _ = index.update_status(&doc.id, DocumentStatus::Failed, Some(&msg), "cli", None);
+        }
+
+        items.push(ItemResult {
+            id: doc.id.clone(),
+            file_path: doc.file_path.clone(),
+            success: result.success,
+            nexus_doc_id: result.nexus_doc_id,
+            error: result.error,
+            original_sha256: result.original_sha256,
+            original_error: result.original_error,
+        });
+    }
+
+    Ok(BatchResult::from_items(items))
+}
+
+/// API client for the configured credentials. Refreshed tokens are saved
+/// back to the config file they came from.
+fn api_client(config: CliConfig) -> Result<NexusApi, String> {
+    let api_url = config.api_url.clone().ok_or("No API URL configured (set NEXBRIDGE_API_URL)")?;
+    let token = config.token.clone().ok_or("No API token configured (set NEXBRIDGE_TOKEN)")?;
+    let api = NexusApi::with_session(
+        env!("CARGO_PKG_VERSION"),
+        &api_url,
+        Credentials {
+            access_token: token,
+            refresh_token: config.refresh_token.clone(),
+        },
+    );
+
+    if let Some(path) = config.path.clone() {
+        api.on_refresh(move |credentials| {
+            let updated = CliConfig {
+                token: Some(credentials.access_token.clone()),
+                refresh_token: credentials.refresh_token.clone(),
+                ..config.clone()
+            };
+            let result = serde_json::to_string_pretty(&updated)
+                .map_err(|e| e.to_string())
+                .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({ "warning": format!("Failed to save refreshed token to {}: {}", path.display(), e) })
+                );
+            }
+        });
+    }
+    Ok(api)
+}
+
+/// Load API credentials. Environment variables take precedence over the config file.
+fn load_config(data_dir: &Path, explicit: Option<&Path>) -> Result<CliConfig, String> {
+    let path = explicit
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| data_dir.join("cli.json"));
+
+    let mut config = if path.exists() {
+        let raw = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str::<CliConfig>(&raw)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?
+    } else if explicit.is_some() {
+        return Err(format!("Config file not found: {}", path.display()));
+    } else {
+        CliConfig::default()
+    };
+
+    if let Ok(url) = std::env::var("NEXBRIDGE_API_URL") {
+        config.api_url = Some(url);
+    }
+    if let Ok(token) = std::env::var("NEXBRIDGE_TOKEN") {
+        config.token = Some(token);
+        config.refresh_token = std::env::var("NEXBRIDGE_REFRESH_TOKEN").ok();
+    } else if path.exists() {
+        config.path = Some(path);
+    }
+    config.api_url = config.api_url.map(|u| u.trim_end_matches('/').to_string());
+
+    Ok(config)
+}
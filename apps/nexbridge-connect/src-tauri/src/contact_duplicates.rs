@@ -0,0 +1,470 @@
+// ---------------------------------------------------------------------------
+// contact_duplicates — find likely duplicate device contacts
+// ---------------------------------------------------------------------------
+// Pairs of contacts are scored on:
+//
+//   phone    same number once normalized to E.164          0.9
+//   email    same address (case and +tags ignored)         0.9
+//   name     Jaro-Winkler similarity of the full names     0.6
+//            (+0.25 with the same company, -0.2 with a different one)
+//   company  same first name, and the rest of one name is part of the
+//            other's company ("Mike Plumbing" / Mike Johnson at
+//            Johnson Plumbing)                             0.6
+//
+// Signals combine as 1 - Π(1 - s). Pairs scoring at least MATCH_THRESHOLD
+// are joined into clusters (union-find), so A~B and B~C makes one proposal.
+// Only contacts sharing a phone, email, name token or company are compared.
+//
+// Each cluster becomes a `MergeProposal`: the most complete contact is kept,
+// and every field lists the candidate values with the one picked. Applying
+// a merge is handled by ContactGroupIndex::merge_contacts and contact_sync.
+// ---------------------------------------------------------------------------
+
+use crate::contacts::Contact;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Country code assumed for numbers written without one
+const DEFAULT_COUNTRY_CODE: &str = "1";
+const MATCH_THRESHOLD: f64 = 0.6;
+const NAME_SIMILARITY: f64 = 0.93;
+/// Words that say nothing about who a company is
+const COMPANY_NOISE: &[&str] = &[
+    "inc", "llc", "ltd", "co", "corp", "corporation", "company", "the", "and", "of", "services", "group",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeProposal {
+    /// Stable for the same set of contacts
+    pub id: String,
+    /// The contact to keep
+    pub primary_id: String,
+    /// Every contact in the cluster, primary first
+    pub contact_ids: Vec<String>,
+    /// Score of the weakest link that joined the cluster (0-1)
+    pub confidence: f64,
+    pub reasons: Vec<String>,
+    pub fields: Vec<FieldResolution>,
+    /// The primary contact with the picked values and all emails/phones
+    pub merged: Contact,
+}
+
+/// How one field of the merged contact was chosen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldResolution {
+    /// Contact field name, e.g. "email", "company"
+    pub field: String,
+    pub value: Option<String>,
+    /// Contact the value was taken from
+    pub source_id: Option<String>,
+    /// Every distinct value in the cluster
+    pub candidates: Vec<FieldCandidate>,
+    /// More than one distinct value to choose from
+    pub conflict: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldCandidate {
+    pub contact_id: String,
+    pub value: String,
+}
+
+/// Group likely duplicates in `contacts`, most confident first.
+pub fn find_duplicates(contacts: &[Contact]) -> Vec<MergeProposal> {
+    let keys: Vec<MatchKeys> = contacts.iter().map(MatchKeys::new).collect();
+
+    // Only compare contacts that share something
+    let mut blocks: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, k) in keys.iter().enumerate() {
+        let block_keys = k.phones.iter().map(|p| format!("p:{}", p))
+            .chain(k.emails.iter().map(|e| format!("e:{}", e)))
+            .chain(k.name_tokens.iter().map(|t| format!("n:{}", t)))
+            .chain(k.company.iter().map(|c| format!("c:{}", c)));
+        for key in block_keys.collect::<HashSet<_>>() {
+            blocks.entry(key).or_default().push(i);
+        }
+    }
+
+    let mut compared = HashSet::new();
+    let mut clusters = UnionFind::new(contacts.len());
+    let mut reasons: HashMap<(usize, usize), Vec<String>> = HashMap::new();
+    for members in blocks.values().filter(|m| m.len() > 1) {
+        for (n, &a) in members.iter().enumerate() {
+            for &b in &members[n + 1..] {
+                let pair = (a.min(b), a.max(b));
+                if !compared.insert(pair) {
+                    continue;
+                }
+                let (score, why) = score_pair(&keys[pair.0], &keys[pair.1]);
+                if score >= MATCH_THRESHOLD {
+                    clusters.union(pair.0, pair.1, score);
+                    reasons.insert(pair, why);
+                }
+            }
+        }
+    }
+
+    // Collect clusters of two or more
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..contacts.len() {
+        groups.entry(clusters.find(i)).or_default().push(i);
+    }
+    let mut proposals: Vec<MergeProposal> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| {
+            let mut why: Vec<String> = reasons
+                .iter()
+                .filter(|((a, _), _)| clusters.find(*a) == root)
+                .flat_map(|(_, r)| r.iter().cloned())
+                .collect();
+            why.sort();
+            why.dedup();
+            build_proposal(contacts, &members, clusters.weakest[root], why)
+        })
+        .collect();
+
+    proposals.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.primary_id.cmp(&b.primary_id))
+    });
+    proposals
+}
+
+// ============ Normalization ============
+
+/// E.164 form of a phone number ("+15551234567"), or None if it is too
+/// short to compare reliably. Numbers without a country code are taken to
+/// be in DEFAULT_COUNTRY_CODE.
+pub(crate) fn normalize_phone(raw: &str) -> Option<String> {
+    let trimmed = raw.trim();
+    // Drop extensions ("x123", "ext. 4")
+    let number = trimmed
+        .split(['x', 'X', ';', ','])
+        .next()
+        .unwrap_or(trimmed);
+    let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+
+    let international = if number.trim_start().starts_with('+') {
+        digits
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        rest.to_string()
+    } else if digits.len() == 10 {
+        format!("{}{}", DEFAULT_COUNTRY_CODE, digits)
+    } else if digits.len() == 11 && digits.starts_with(DEFAULT_COUNTRY_CODE) {
+        digits
+    } else {
+        return None;
+    };
+    (8..=15).contains(&international.len()).then(|| format!("+{}", international))
+}
+
+/// Lowercased, with any "+tag" dropped from the local part.
+pub(crate) fn normalize_email(raw: &str) -> Option<String> {
+    let email = raw.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    if local.is_empty() || !domain.contains('.') {
+        return None;
+    }
+    let local = local.split('+').next().unwrap_or(local);
+    Some(format!("{}@{}", local, domain))
+}
+
+fn name_tokens(value: &str) -> Vec<String> {
+    value
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn full_name(contact: &Contact) -> String {
+    let parts = [contact.first_name.as_deref(), contact.last_name.as_deref()];
+    let from_parts = parts.iter().flatten().copied().collect::<Vec<_>>().join(" ");
+    match contact.display_name.as_deref() {
+        Some(display) if !display.trim().is_empty() => display.to_string(),
+        _ => from_parts,
+    }
+}
+
+/// What a contact is compared on, normalized once.
+struct MatchKeys {
+    phones: HashSet<String>,
+    emails: HashSet<String>,
+    /// Full name tokens, lowercased
+    name_tokens: Vec<String>,
+    /// Name tokens sorted and joined, so "Johnson Mike" matches "Mike Johnson"
+    sorted_name: String,
+    company: Option<String>,
+    company_tokens: HashSet<String>,
+}
+
+impl MatchKeys {
+    fn new(contact: &Contact) -> Self {
+        let phones = contact
+            .all_phones
+            .iter()
+            .chain(contact.phone.iter())
+            .filter_map(|p| normalize_phone(p))
+            .collect();
+        let emails = contact
+            .all_emails
+            .iter()
+            .chain(contact.email.iter())
+            .filter_map(|e| normalize_email(e))
+            .collect();
+        let name_tokens = name_tokens(&full_name(contact));
+        let mut sorted = name_tokens.clone();
+        sorted.sort();
+        let company_tokens: HashSet<String> = contact
+            .company
+            .as_deref()
+            .map(name_tokens_of_company)
+            .unwrap_or_default();
+        let company = (!company_tokens.is_empty()).then(|| {
+            let mut tokens: Vec<&str> = company_tokens.iter().map(String::as_str).collect();
+            tokens.sort();
+            tokens.join(" ")
+        });
+
+        Self {
+            phones,
+            emails,
+            name_tokens,
+            sorted_name: sorted.join(" "),
+            company,
+            company_tokens,
+        }
+    }
+}
+
+fn name_tokens_of_company(company: &str) -> HashSet<String> {
+    name_tokens(company)
+        .into_iter()
+        .filter(|t| !COMPANY_NOISE.contains(&t.as_str()))
+        .collect()
+}
+
+// ============ Scoring ============
+
+fn score_pair(a: &MatchKeys, b: &MatchKeys) -> (f64, Vec<String>) {
+    let mut signals = Vec::new();
+    let mut reasons = Vec::new();
+
+    if let Some(phone) = a.phones.intersection(&b.phones).min() {
+        signals.push(0.9);
+        reasons.push(format!("Same phone {}", phone));
+    }
+    if let Some(email) = a.emails.intersection(&b.emails).min() {
+        signals.push(0.9);
+        reasons.push(format!("Same email {}", email));
+    }
+
+    let same_company = a.company.is_some() && a.company == b.company;
+    let different_company = a.company.is_some() && b.company.is_some() && !same_company;
+    if !a.sorted_name.is_empty() && !b.sorted_name.is_empty() {
+        let similarity = jaro_winkler(&a.name_tokens.join(" "), &b.name_tokens.join(" "))
+            .max(jaro_winkler(&a.sorted_name, &b.sorted_name));
+        if similarity >= NAME_SIMILARITY {
+            let mut score: f64 = 0.6;
+            if same_company {
+                score += 0.25;
+                reasons.push("Similar names at the same company".to_string());
+            } else if different_company {
+                score -= 0.2;
+            } else {
+                reasons.push("Similar names".to_string());
+            }
+            signals.push(score);
+        }
+    }
+
+    if name_matches_company(a, b) || name_matches_company(b, a) {
+        signals.push(0.6);
+        reasons.push("Name matches the other's company".to_string());
+    }
+
+    let score = 1.0 - signals.iter().fold(1.0, |rest, s| rest * (1.0 - s));
+    (score, reasons)
+}
+
+/// Same first name, and the rest of `a`'s name appears in `b`'s company.
+fn name_matches_company(a: &MatchKeys, b: &MatchKeys) -> bool {
+    let (Some(first_a), Some(first_b)) = (a.name_tokens.first(), b.name_tokens.first()) else {
+        return false;
+    };
+    first_a == first_b
+        && a.name_tokens.len() > 1
+        && a.name_tokens[1..]
+            .iter()
+            .any(|t| t.len() > 2 && b.company_tokens.contains(t))
+}
+
+/// Jaro-Winkler similarity (0-1) of two strings.
+fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return if a.len() == b.len() { 1.0 } else { 0.0 };
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0usize;
+    for i in 0..a.len() {
+        let lo = i.saturating_sub(window);
+        let hi = (i + window + 1).min(b.len());
+        for j in lo..hi {
+            if !b_matched[j] && a[i] == b[j] {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let a_seq = a.iter().zip(&a_matched).filter(|(_, m)| **m).map(|(c, _)| c);
+    let b_seq = b.iter().zip(&b_matched).filter(|(_, m)| **m).map(|(c, _)| c);
+    let transpositions = a_seq.zip(b_seq).filter(|(x, y)| x != y).count() / 2;
+
+    let m = matches as f64;
+    let jaro = (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0;
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+    /// Per root: lowest pair score that joined the cluster
+    weakest: Vec<f64>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+            weakest: vec![1.0; n],
+        }
+    }
+
+    fn find(&self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize, score: f64) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        let weakest = self.weakest[ra].min(self.weakest[rb]).min(score);
+        let root = ra.min(rb);
+        self.parent[ra.max(rb)] = root;
+        self.weakest[root] = weakest;
+    }
+}
+
+// ============ Proposals ============
+
+fn completeness(contact: &Contact) -> usize {
+    let scalars = [
+        &contact.display_name, &contact.first_name, &contact.last_name, &contact.email, &contact.phone,
+        &contact.street, &contact.city, &contact.state, &contact.zip, &contact.country, &contact.company,
+        &contact.job_title,
+    ];
+    scalars.iter().filter(|v| v.as_deref().is_some_and(|s| !s.trim().is_empty())).count()
+        + contact.all_emails.len()
+        + contact.all_phones.len()
+}
+
+fn build_proposal(contacts: &[Contact], members: &[usize], confidence: f64, reasons: Vec<String>) -> MergeProposal {
+    let mut cluster: Vec<&Contact> = members.iter().map(|&i| &contacts[i]).collect();
+    // Most complete first; ties by id so the pick is stable
+    cluster.sort_by(|a, b| completeness(b).cmp(&completeness(a)).then_with(|| a.id.cmp(&b.id)));
+    let primary = cluster[0];
+
+    let mut merged = primary.clone();
+    let mut fields = Vec::new();
+    {
+        let mut resolve = |field: &str, get: fn(&Contact) -> &Option<String>, set: &mut Option<String>| {
+            let mut candidates: Vec<FieldCandidate> = Vec::new();
+            for contact in &cluster {
+                if let Some(value) = get(contact).as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                    if !candidates.iter().any(|c| c.value.eq_ignore_ascii_case(value)) {
+                        candidates.push(FieldCandidate {
+                            contact_id: contact.id.clone(),
+                            value: value.to_string(),
+                        });
+                    }
+                }
+            }
+            // The primary's own value wins; otherwise the first one found
+            let picked = candidates.first().cloned();
+            *set = picked.as_ref().map(|c| c.value.clone());
+            fields.push(FieldResolution {
+                field: field.to_string(),
+                value: picked.as_ref().map(|c| c.value.clone()),
+                source_id: picked.map(|c| c.contact_id),
+                conflict: candidates.len() > 1,
+                candidates,
+            });
+        };
+        resolve("display_name", |c| &c.display_name, &mut merged.display_name);
+        resolve("first_name", |c| &c.first_name, &mut merged.first_name);
+        resolve("last_name", |c| &c.last_name, &mut merged.last_name);
+        resolve("email", |c| &c.email, &mut merged.email);
+        resolve("phone", |c| &c.phone, &mut merged.phone);
+        resolve("street", |c| &c.street, &mut merged.street);
+        resolve("city", |c| &c.city, &mut merged.city);
+        resolve("state", |c| &c.state, &mut merged.state);
+        resolve("zip", |c| &c.zip, &mut merged.zip);
+        resolve("country", |c| &c.country, &mut merged.country);
+        resolve("company", |c| &c.company, &mut merged.company);
+        resolve("job_title", |c| &c.job_title, &mut merged.job_title);
+    }
+    // Plus-tagged addresses still deliver separately, so only case is ignored
+    merged.all_emails = union_by(&cluster, |c| &c.all_emails, |e| Some(e.trim().to_lowercase()));
+    merged.all_phones = union_by(&cluster, |c| &c.all_phones, normalize_phone);
+
+    let contact_ids: Vec<String> = cluster.iter().map(|c| c.id.clone()).collect();
+    let mut sorted_ids = contact_ids.clone();
+    sorted_ids.sort();
+    let id = hex::encode(&Sha256::digest(sorted_ids.join("\n").as_bytes())[..8]);
+
+    MergeProposal {
+        id,
+        primary_id: primary.id.clone(),
+        contact_ids,
+        confidence: (confidence * 100.0).round() / 100.0,
+        reasons,
+        fields,
+        merged,
+    }
+}
+
+/// All values across the cluster, skipping ones equal once normalized.
+fn union_by(
+    cluster: &[&Contact],
+    get: fn(&Contact) -> &Vec<String>,
+    normalize: fn(&str) -> Option<String>,
+) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut values = Vec::new();
+    for contact in cluster {
+        for value in get(contact) {
+            let key = normalize(value).unwrap_or_else(|| value.trim().to_lowercase());
+            if seen.insert(key) {
+                values.push(value.clone());
+            }
+        }
+    }
+    values
+}
@@ -1,6 +1,12 @@
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use crate::contact_providers::ContactChanges;
+use crate::contacts::Contact;
+use crate::encryption::{self, DataKey};
+use crate::index::add_column_if_missing;
+use crate::smart_groups::SmartRules;
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Mutex;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +16,65 @@ pub struct ContactGroup {
     pub description: Option<String>,
     pub color: Option<String>,
     pub created_at: String,
+    /// Set for a smart group (see smart_groups.rs)
+    #[serde(default)]
+    pub rules: Option<SmartRules>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactSyncStatus {
+    /// Not sent yet
+    Pending,
+    Synced,
+    /// The last attempt failed; retried on the next run
+    Failed,
+}
+
+impl ContactSyncStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContactSyncStatus::Pending => "pending",
+            ContactSyncStatus::Synced => "synced",
+            ContactSyncStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "pending" => ContactSyncStatus::Pending,
+            "synced" => ContactSyncStatus::Synced,
+            _ => ContactSyncStatus::Failed,
+        }
+    }
+}
+
+/// Where a device contact stands with Nexus (see contact_sync.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactSyncState {
+    pub contact_id: String,
+    /// Id of the contact in Nexus, once created
+    pub nexus_id: Option<String>,
+    /// Fingerprint of what was last sent successfully
+    pub fingerprint: Option<String>,
+    pub status: ContactSyncStatus,
+    pub error: Option<String>,
+    /// Primary email/phone chosen in the review dialog, if not the device's
+    pub primary_email: Option<String>,
+    pub primary_phone: Option<String>,
+    pub last_synced_at: Option<String>,
+    pub updated_at: String,
+}
+
+/// How a contact provider's last refresh went (see contact_providers.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderSyncState {
+    pub provider_id: String,
+    pub sync_token: Option<String>,
+    pub last_synced_at: Option<String>,
+    /// Error from the last attempt; cleared by the next success
+    pub error: Option<String>,
+    pub contact_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,8 +89,32 @@ pub struct ContactGroupIndex {
 }
 
 impl ContactGroupIndex {
-    pub fn new(db_path: &PathBuf) -> Result<Self, rusqlite::Error> {
-        let conn = Connection::open(db_path)?;
+    /// Open (creating if needed) the database, encrypted under `key`. Only
+    /// the locked-app placeholder is opened without one.
+    pub fn new(db_path: &Path, key: Option<&DataKey>) -> Result<Self, rusqlite::Error> {
+        Ok(Self {
+            conn: Mutex::new(Self::open(db_path, key)?),
+        })
+    }
+
+    /// Switch to another database file (another account's partition). The
+    /// new file is opened and migrated before the old connection is closed,
+    /// so a failure leaves the current one in place.
+    pub fn reopen(&self, db_path: &Path, key: Option<&DataKey>) -> Result<(), rusqlite::Error> {
+        let conn = Self::open(db_path, key)?;
+        *self.conn.lock().unwrap() = conn;
+        Ok(())
+    }
+
+    /// Write a consistent copy of the open database to `dest`, which must not
+    /// exist yet. `key` must be the one the database was opened with.
+    pub fn snapshot_to(&self, dest: &Path, key: Option<&DataKey>) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        encryption::snapshot_database(&conn, dest, key)
+    }
+
+    fn open(db_path: &Path, key: Option<&DataKey>) -> Result<Connection, rusqlite::Error> {
+        let conn = encryption::open_database(db_path, key)?;
         
         // Create groups table
         conn.execute(
@@ -51,6 +140,20 @@ impl ContactGroupIndex {
             [],
         )?;
 
+        // Smart group rules, as JSON
+        add_column_if_missing(&conn, "contact_groups", "rules", "TEXT")?;
+
+        // Contacts removed by hand from a smart group they match
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS contact_group_exclusions (
+                contact_id TEXT NOT NULL,
+                group_id TEXT NOT NULL,
+                excluded_at TEXT NOT NULL,
+                PRIMARY KEY (contact_id, group_id)
+            )",
+            [],
+        )?;
+
         // Create indexes
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_group_members_contact ON contact_group_members(contact_id)",
@@ -70,21 +173,72 @@ impl ContactGroupIndex {
             [],
         )?;
 
-        Ok(Self {
-            conn: Mutex::new(conn),
-        })
+        // Duplicates merged into another contact (see contact_duplicates.rs)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS contact_merges (
+                merged_id TEXT PRIMARY KEY,
+                kept_id TEXT NOT NULL,
+                merged_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Per-contact state of the sync to Nexus
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS contact_sync_state (
+                contact_id TEXT PRIMARY KEY,
+                nexus_id TEXT,
+                fingerprint TEXT,
+                status TEXT NOT NULL,
+                error TEXT,
+                primary_email TEXT,
+                primary_phone TEXT,
+                last_synced_at TEXT,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Contacts as last fetched from each contact provider
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_contacts (
+                provider_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                etag TEXT,
+                contact TEXT NOT NULL,
+                PRIMARY KEY (provider_id, key)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_sync_state (
+                provider_id TEXT PRIMARY KEY,
+                sync_token TEXT,
+                last_synced_at TEXT,
+                error TEXT
+            )",
+            [],
+        )?;
+
+        Ok(conn)
     }
 
     // ============ Group CRUD ============
 
-    pub fn create_group(&self, name: &str, description: Option<&str>, color: Option<&str>) -> Result<ContactGroup, rusqlite::Error> {
+    pub fn create_group(
+        &self,
+        name: &str,
+        description: Option<&str>,
+        color: Option<&str>,
+        rules: Option<&SmartRules>,
+    ) -> Result<ContactGroup, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
 
         conn.execute(
-            "INSERT INTO contact_groups (id, name, description, color, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![id, name, description, color, now],
+            "INSERT INTO contact_groups (id, name, description, color, created_at, rules) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, name, description, color, now, rules_json(rules)],
         )?;
 
         Ok(ContactGroup {
@@ -93,6 +247,7 @@ impl ContactGroupIndex {
             description: description.map(|s| s.to_string()),
             color: color.map(|s| s.to_string()),
             created_at: now,
+            rules: rules.cloned(),
         })
     }
 
@@ -105,9 +260,24 @@ impl ContactGroupIndex {
         Ok(())
     }
 
+    /// Turn a group into a smart group (`Some`) or back into a static one.
+    pub fn set_group_rules(&self, id: &str, rules: Option<&SmartRules>) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE contact_groups SET rules = ?1 WHERE id = ?2",
+            params![rules_json(rules), id],
+        )?;
+        if rules.is_none() {
+            conn.execute("DELETE FROM contact_group_exclusions WHERE group_id = ?1", [id])?;
+        }
+        Ok(())
+    }
+
     pub fn delete_group(&self, id: &str) -> Result<(), rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
-        // Members are deleted via CASCADE
+        // foreign_keys is off, so the CASCADE on members doesn't fire
+        conn.execute("DELETE FROM contact_group_members WHERE group_id = ?1", [id])?;
+        conn.execute("DELETE FROM contact_group_exclusions WHERE group_id = ?1", [id])?;
         conn.execute("DELETE FROM contact_groups WHERE id = ?1", [id])?;
         Ok(())
     }
@@ -115,7 +285,7 @@ impl ContactGroupIndex {
     pub fn list_groups(&self) -> Result<Vec<ContactGroup>, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, color, created_at FROM contact_groups ORDER BY name"
+            "SELECT id, name, description, color, created_at, rules FROM contact_groups ORDER BY name"
         )?;
 
         let groups = stmt.query_map([], |row| {
@@ -125,6 +295,7 @@ impl ContactGroupIndex {
                 description: row.get(2)?,
                 color: row.get(3)?,
                 created_at: row.get(4)?,
+                rules: parse_rules(row.get(5)?),
             })
         })?;
 
@@ -134,7 +305,7 @@ impl ContactGroupIndex {
     pub fn get_group(&self, id: &str) -> Result<Option<ContactGroup>, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, name, description, color, created_at FROM contact_groups WHERE id = ?1"
+            "SELECT id, name, description, color, created_at, rules FROM contact_groups WHERE id = ?1"
         )?;
 
         let mut rows = stmt.query([id])?;
@@ -145,6 +316,7 @@ impl ContactGroupIndex {
                 description: row.get(2)?,
                 color: row.get(3)?,
                 created_at: row.get(4)?,
+                rules: parse_rules(row.get(5)?),
             }))
         } else {
             Ok(None)
@@ -159,6 +331,10 @@ impl ContactGroupIndex {
         let mut added = 0;
 
         for contact_id in contact_ids {
+            conn.execute(
+                "DELETE FROM contact_group_exclusions WHERE contact_id = ?1 AND group_id = ?2",
+                params![contact_id, group_id],
+            )?;
             let result = conn.execute(
                 "INSERT OR IGNORE INTO contact_group_members (contact_id, group_id, added_at) VALUES (?1, ?2, ?3)",
                 params![contact_id, group_id, now],
@@ -171,54 +347,61 @@ impl ContactGroupIndex {
         Ok(added)
     }
 
+    /// Remove contacts from a group. For a smart group they are also
+    /// excluded, so its rules don't bring them back.
     pub fn remove_contacts_from_group(&self, contact_ids: &[String], group_id: &str) -> Result<u32, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        let smart: bool = conn.query_row(
+            "SELECT rules IS NOT NULL FROM contact_groups WHERE id = ?1",
+            [group_id],
+            |row| row.get(0),
+        ).unwrap_or(false);
         let mut removed = 0;
 
         for contact_id in contact_ids {
-            let result = conn.execute(
+            let mut n = conn.execute(
                 "DELETE FROM contact_group_members WHERE contact_id = ?1 AND group_id = ?2",
                 params![contact_id, group_id],
-            );
-            if let Ok(n) = result {
-                removed += n as u32;
+            ).unwrap_or(0);
+            if smart {
+                n += conn.execute(
+                    "INSERT OR IGNORE INTO contact_group_exclusions (contact_id, group_id, excluded_at) VALUES (?1, ?2, ?3)",
+                    params![contact_id, group_id, now],
+                )?;
+            }
+            if n > 0 {
+                removed += 1;
             }
         }
 
         Ok(removed)
     }
 
-    pub fn get_contacts_in_group(&self, group_id: &str) -> Result<Vec<String>, rusqlite::Error> {
+    /// Contacts added by hand, per group.
+    pub fn get_group_members(&self) -> Result<HashMap<String, Vec<String>>, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT contact_id FROM contact_group_members WHERE group_id = ?1"
-        )?;
-
-        let ids = stmt.query_map([group_id], |row| row.get(0))?;
-        ids.collect()
+        let mut stmt = conn.prepare("SELECT group_id, contact_id FROM contact_group_members ORDER BY rowid")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut members: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (group_id, contact_id) = row?;
+            members.entry(group_id).or_default().push(contact_id);
+        }
+        Ok(members)
     }
 
-    pub fn get_groups_for_contact(&self, contact_id: &str) -> Result<Vec<ContactGroup>, rusqlite::Error> {
+    /// Contacts removed by hand from smart groups, per group.
+    pub fn get_group_exclusions(&self) -> Result<HashMap<String, Vec<String>>, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT g.id, g.name, g.description, g.color, g.created_at 
-             FROM contact_groups g
-             INNER JOIN contact_group_members m ON g.id = m.group_id
-             WHERE m.contact_id = ?1
-             ORDER BY g.name"
-        )?;
-
-        let groups = stmt.query_map([contact_id], |row| {
-            Ok(ContactGroup {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                color: row.get(3)?,
-                created_at: row.get(4)?,
-            })
-        })?;
-
-        groups.collect()
+        let mut stmt = conn.prepare("SELECT group_id, contact_id FROM contact_group_exclusions")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut excluded: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            let (group_id, contact_id) = row?;
+            excluded.entry(group_id).or_default().push(contact_id);
+        }
+        Ok(excluded)
     }
 
     pub fn get_group_member_counts(&self) -> Result<Vec<(String, u32)>, rusqlite::Error> {
@@ -287,4 +470,263 @@ impl ContactGroupIndex {
         )?;
         Ok(count > 0)
     }
+
+    // ============ Merges ============
+
+    /// Merge duplicates into `kept_id`: it joins every group they were in,
+    /// they leave their groups, and they are recorded as merged so sync
+    /// leaves them out. Earlier merges into them move to `kept_id`.
+    /// Returns how many contacts were merged.
+    pub fn merge_contacts(&self, kept_id: &str, merged_ids: &[String]) -> Result<u32, rusqlite::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut merged = 0;
+
+        for merged_id in merged_ids.iter().filter(|id| id.as_str() != kept_id) {
+            tx.execute(
+                "INSERT OR IGNORE INTO contact_group_members (contact_id, group_id, added_at)
+                 SELECT ?1, group_id, ?3 FROM contact_group_members WHERE contact_id = ?2",
+                params![kept_id, merged_id, now],
+            )?;
+            tx.execute("DELETE FROM contact_group_members WHERE contact_id = ?1", [merged_id])?;
+            tx.execute("DELETE FROM contact_group_exclusions WHERE contact_id = ?1", [merged_id])?;
+            tx.execute(
+                "UPDATE contact_merges SET kept_id = ?1 WHERE kept_id = ?2",
+                params![kept_id, merged_id],
+            )?;
+            merged += tx.execute(
+                "INSERT OR REPLACE INTO contact_merges (merged_id, kept_id, merged_at) VALUES (?1, ?2, ?3)",
+                params![merged_id, kept_id, now],
+            )? as u32;
+        }
+        // Merging a contact back into one merged into it undoes that merge
+        tx.execute("DELETE FROM contact_merges WHERE merged_id = ?1", [kept_id])?;
+
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    /// Merged contact id → the id it was merged into.
+    pub fn get_merges(&self) -> Result<Vec<(String, String)>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT merged_id, kept_id FROM contact_merges")?;
+        let merges = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        merges.collect()
+    }
+
+    // ============ Sync State ============
+
+    pub fn get_sync_states(&self) -> Result<Vec<ContactSyncState>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT contact_id, nexus_id, fingerprint, status, error, primary_email, primary_phone,
+                    last_synced_at, updated_at
+             FROM contact_sync_state ORDER BY contact_id",
+        )?;
+
+        let states = stmt.query_map([], |row| {
+            let status: String = row.get(3)?;
+            Ok(ContactSyncState {
+                contact_id: row.get(0)?,
+                nexus_id: row.get(1)?,
+                fingerprint: row.get(2)?,
+                status: ContactSyncStatus::parse(&status),
+                error: row.get(4)?,
+                primary_email: row.get(5)?,
+                primary_phone: row.get(6)?,
+                last_synced_at: row.get(7)?,
+                updated_at: row.get(8)?,
+            })
+        })?;
+
+        states.collect()
+    }
+
+    /// Record a successful create/update. A missing `nexus_id` keeps the
+    /// one already stored.
+    pub fn record_contact_synced(
+        &self,
+        contact_id: &str,
+        nexus_id: Option<&str>,
+        fingerprint: &str,
+    ) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO contact_sync_state (contact_id, nexus_id, fingerprint, status, error, last_synced_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?5)
+             ON CONFLICT(contact_id) DO UPDATE SET
+                nexus_id = COALESCE(excluded.nexus_id, nexus_id),
+                fingerprint = excluded.fingerprint,
+                status = excluded.status,
+                error = NULL,
+                last_synced_at = excluded.last_synced_at,
+                updated_at = excluded.updated_at",
+            params![contact_id, nexus_id, fingerprint, ContactSyncStatus::Synced.as_str(), now],
+        )?;
+        Ok(())
+    }
+
+    /// Record a failed attempt; the last good fingerprint is kept so the
+    /// contact is sent again next time.
+    pub fn record_contact_sync_failed(&self, contact_id: &str, error: &str) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO contact_sync_state (contact_id, status, error, updated_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(contact_id) DO UPDATE SET
+                status = excluded.status,
+                error = excluded.error,
+                updated_at = excluded.updated_at",
+            params![contact_id, ContactSyncStatus::Failed.as_str(), error, now],
+        )?;
+        Ok(())
+    }
+
+    /// Remember the primary email/phone picked for a contact; sent on every
+    /// sync until changed.
+    pub fn set_primary_overrides(
+        &self,
+        contact_id: &str,
+        primary_email: Option<&str>,
+        primary_phone: Option<&str>,
+    ) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO contact_sync_state (contact_id, status, primary_email, primary_phone, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(contact_id) DO UPDATE SET
+                primary_email = excluded.primary_email,
+                primary_phone = excluded.primary_phone,
+                updated_at = excluded.updated_at",
+            params![contact_id, ContactSyncStatus::Pending.as_str(), primary_email, primary_phone, now],
+        )?;
+        Ok(())
+    }
+
+    /// Forget a contact once it has been deleted from Nexus.
+    pub fn remove_sync_state(&self, contact_id: &str) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM contact_sync_state WHERE contact_id = ?1", [contact_id])?;
+        Ok(())
+    }
+
+    // ============ Contact Providers ============
+
+    /// A provider's cached contacts, in the order they were first fetched.
+    pub fn get_provider_contacts(&self, provider_id: &str) -> Result<Vec<Contact>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT contact FROM provider_contacts WHERE provider_id = ?1 ORDER BY rowid"
+        )?;
+        let contacts = stmt.query_map([provider_id], |row| {
+            let json: String = row.get(0)?;
+            serde_json::from_str(&json).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+            })
+        })?;
+        contacts.collect()
+    }
+
+    /// Cached key → etag for a provider.
+    pub fn get_provider_etags(&self, provider_id: &str) -> Result<HashMap<String, Option<String>>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, etag FROM provider_contacts WHERE provider_id = ?1")?;
+        let etags = stmt.query_map([provider_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        etags.collect()
+    }
+
+    pub fn get_provider_sync_state(&self, provider_id: &str) -> Result<Option<ProviderSyncState>, rusqlite::Error> {
+        Ok(self
+            .get_provider_sync_states()?
+            .into_iter()
+            .find(|s| s.provider_id == provider_id))
+    }
+
+    pub fn get_provider_sync_states(&self) -> Result<Vec<ProviderSyncState>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT s.provider_id, s.sync_token, s.last_synced_at, s.error,
+                    (SELECT COUNT(*) FROM provider_contacts c WHERE c.provider_id = s.provider_id)
+             FROM provider_sync_state s ORDER BY s.provider_id",
+        )?;
+        let states = stmt.query_map([], |row| {
+            Ok(ProviderSyncState {
+                provider_id: row.get(0)?,
+                sync_token: row.get(1)?,
+                last_synced_at: row.get(2)?,
+                error: row.get(3)?,
+                contact_count: row.get(4)?,
+            })
+        })?;
+        states.collect()
+    }
+
+    /// Apply one fetch's changes and store its sync token, all or nothing.
+    /// Contacts that come back unchanged aren't rewritten.
+    pub fn apply_provider_changes(&self, provider_id: &str, changes: &ContactChanges) -> Result<(), rusqlite::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        for key in &changes.removed {
+            tx.execute(
+                "DELETE FROM provider_contacts WHERE provider_id = ?1 AND key = ?2",
+                params![provider_id, key],
+            )?;
+        }
+        for upsert in &changes.upserts {
+            let json = serde_json::to_string(&upsert.contact)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            tx.execute(
+                "INSERT INTO provider_contacts (provider_id, key, etag, contact) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(provider_id, key) DO UPDATE SET etag = excluded.etag, contact = excluded.contact
+                 WHERE etag IS NOT excluded.etag OR contact != excluded.contact",
+                params![provider_id, upsert.key, upsert.etag, json],
+            )?;
+        }
+        tx.execute(
+            "INSERT INTO provider_sync_state (provider_id, sync_token, last_synced_at, error)
+             VALUES (?1, ?2, ?3, NULL)
+             ON CONFLICT(provider_id) DO UPDATE SET
+                sync_token = excluded.sync_token,
+                last_synced_at = excluded.last_synced_at,
+                error = NULL",
+            params![provider_id, changes.sync_token, now],
+        )?;
+
+        tx.commit()
+    }
+
+    /// Record a failed refresh; the cached contacts and token are kept.
+    pub fn record_provider_error(&self, provider_id: &str, error: &str) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO provider_sync_state (provider_id, error) VALUES (?1, ?2)
+             ON CONFLICT(provider_id) DO UPDATE SET error = excluded.error",
+            params![provider_id, error],
+        )?;
+        Ok(())
+    }
+
+    /// Drop a removed provider's cached contacts and state.
+    pub fn remove_provider(&self, provider_id: &str) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM provider_contacts WHERE provider_id = ?1", [provider_id])?;
+        conn.execute("DELETE FROM provider_sync_state WHERE provider_id = ?1", [provider_id])?;
+        Ok(())
+    }
+}
+
+fn rules_json(rules: Option<&SmartRules>) -> Option<String> {
+    rules.and_then(|r| serde_json::to_string(r).ok())
+}
+
+/// Rules that no longer parse leave the group static rather than failing
+/// the whole listing.
+fn parse_rules(json: Option<String>) -> Option<SmartRules> {
+    json.and_then(|j| serde_json::from_str(&j).ok())
 }
@@ -0,0 +1,538 @@
+// ---------------------------------------------------------------------------
+// contact_normalizer — clean up contact fields, on every platform
+// ---------------------------------------------------------------------------
+// Rules, field by field:
+//
+//   phones   E.164 ("+15551234567"); numbers without a country code are in
+//            the default region, extensions are kept as " x123"
+//   emails   trimmed and lowercased
+//   names    whitespace collapsed; title-cased when typed all in one case
+//            ("o'BRIEN" is left alone, "JOHN O'BRIEN" becomes "John O'Brien")
+//   state    US states and Canadian provinces abbreviated ("California" ->
+//            "CA") or spelled out, per `StateStyle`
+//   zip      US "12345" / "12345-6789", Canadian "K1A 0B1"
+//
+// The address country comes from the country field, else from the state,
+// else the default region. Values a rule can't fix are left as they are
+// and reported as issues.
+//
+// `normalize` only computes the result; the preview/apply commands in
+// lib.rs show it and write it back through the native helper
+// (`contacts::apply_contact_updates`) for contacts that come from it.
+// ---------------------------------------------------------------------------
+
+use crate::contacts::Contact;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_REGION: &str = "US";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeOptions {
+    /// ISO 3166 code of the region numbers without a country code are in
+    #[serde(default = "default_region")]
+    pub default_region: String,
+    #[serde(default)]
+    pub state_style: StateStyle,
+}
+
+fn default_region() -> String {
+    DEFAULT_REGION.to_string()
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            default_region: default_region(),
+            state_style: StateStyle::default(),
+        }
+    }
+}
+
+impl NormalizeOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        phone_region(&self.default_region)
+            .map(|_| ())
+            .ok_or_else(|| format!("Unsupported region '{}'", self.default_region))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateStyle {
+    /// "CA", "ON"
+    #[default]
+    Abbreviation,
+    /// "California", "Ontario"
+    Name,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactField {
+    FirstName,
+    LastName,
+    DisplayName,
+    Email,
+    Phone,
+    State,
+    Zip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: ContactField,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldIssue {
+    pub field: ContactField,
+    pub value: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactNormalization {
+    pub contact_id: String,
+    /// For display in the preview
+    pub name: String,
+    pub changes: Vec<FieldChange>,
+    pub issues: Vec<FieldIssue>,
+    /// The contact with every change applied
+    pub normalized: Contact,
+    /// Whether the changes can be written back to where the contact came
+    /// from (set by the caller; false here)
+    pub writable: bool,
+}
+
+impl ContactNormalization {
+    pub fn is_clean(&self) -> bool {
+        self.changes.is_empty() && self.issues.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplyResult {
+    /// Contacts written back
+    pub updated: u32,
+    /// Contacts with changes that can't be written back: from a CardDAV
+    /// account or vCard file, or no writable native address book
+    pub read_only: u32,
+}
+
+/// The contacts with something to change or flag.
+pub fn preview(contacts: &[Contact], options: &NormalizeOptions) -> Vec<ContactNormalization> {
+    contacts
+        .iter()
+        .map(|c| normalize(c, options))
+        .filter(|n| !n.is_clean())
+        .collect()
+}
+
+pub fn normalize(contact: &Contact, options: &NormalizeOptions) -> ContactNormalization {
+    let mut diff = Diff::default();
+    let mut normalized = contact.clone();
+
+    // Names. A display name made of the first and last name follows them;
+    // any other (a company, a nickname) is left alone.
+    normalized.first_name = diff.fix_opt(ContactField::FirstName, &contact.first_name, |v| Ok(normalize_name(v)));
+    normalized.last_name = diff.fix_opt(ContactField::LastName, &contact.last_name, |v| Ok(normalize_name(v)));
+    let composed = full_name(&contact.first_name, &contact.last_name);
+    if !composed.is_empty() {
+        let renamed = full_name(&normalized.first_name, &normalized.last_name);
+        normalized.display_name = diff.fix_opt(ContactField::DisplayName, &contact.display_name, |v| {
+            Ok(if collapse_whitespace(v).to_lowercase() == composed.to_lowercase() {
+                renamed.clone()
+            } else {
+                v.to_string()
+            })
+        });
+    }
+
+    // Emails and phones; the primary value is usually also in the list, so
+    // a change is only reported once
+    normalized.all_emails = contact.all_emails.iter().map(|e| diff.fix(ContactField::Email, e, normalize_email)).collect();
+    normalized.email = diff.fix_opt(ContactField::Email, &contact.email, normalize_email);
+    let region = options.default_region.as_str();
+    normalized.all_phones = contact
+        .all_phones
+        .iter()
+        .map(|p| diff.fix(ContactField::Phone, p, |v| format_phone(v, region)))
+        .collect();
+    normalized.phone = diff.fix_opt(ContactField::Phone, &contact.phone, |v| format_phone(v, region));
+
+    // Address
+    let country = address_country(contact, options);
+    normalized.state = diff.fix_opt(ContactField::State, &contact.state, |v| {
+        normalize_state(v, country, options.state_style)
+    });
+    normalized.zip = diff.fix_opt(ContactField::Zip, &contact.zip, |v| normalize_postal_code(v, country));
+
+    let name = [normalized.display_name.clone(), Some(full_name(&normalized.first_name, &normalized.last_name))]
+        .into_iter()
+        .flatten()
+        .chain(normalized.email.clone())
+        .find(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| contact.id.clone());
+    ContactNormalization {
+        contact_id: contact.id.clone(),
+        name,
+        changes: diff.changes,
+        issues: diff.issues,
+        normalized,
+        writable: false,
+    }
+}
+
+/// Changes and issues found so far for one contact.
+#[derive(Default)]
+struct Diff {
+    changes: Vec<FieldChange>,
+    issues: Vec<FieldIssue>,
+}
+
+impl Diff {
+    /// `value` run through `rule`; the original when the rule fails.
+    /// Blank values are left alone.
+    fn fix(&mut self, field: ContactField, value: &str, rule: impl FnOnce(&str) -> Result<String, String>) -> String {
+        if value.trim().is_empty() {
+            return value.to_string();
+        }
+        match rule(value) {
+            Ok(after) => {
+                let change = FieldChange { field, before: value.to_string(), after: after.clone() };
+                if after != value && !self.changes.contains(&change) {
+                    self.changes.push(change);
+                }
+                after
+            }
+            Err(message) => {
+                let issue = FieldIssue { field, value: value.to_string(), message };
+                if !self.issues.contains(&issue) {
+                    self.issues.push(issue);
+                }
+                value.to_string()
+            }
+        }
+    }
+
+    fn fix_opt(
+        &mut self,
+        field: ContactField,
+        value: &Option<String>,
+        rule: impl FnOnce(&str) -> Result<String, String>,
+    ) -> Option<String> {
+        value.as_deref().map(|v| self.fix(field, v, rule))
+    }
+}
+
+// ============ Names ============
+
+/// Lowercase words that stay lowercase inside a name ("Ludwig van Beethoven")
+const NAME_PARTICLES: &[&str] = &[
+    "van", "von", "der", "den", "de", "del", "della", "da", "di", "du", "dos", "das", "la", "le", "y", "bin", "al",
+];
+const ROMAN_NUMERALS: &[&str] = &["ii", "iii", "iv", "vi", "vii", "viii"];
+
+pub fn normalize_name(value: &str) -> String {
+    let collapsed = collapse_whitespace(value);
+    let cased: Vec<char> = collapsed.chars().filter(|c| c.is_lowercase() || c.is_uppercase()).collect();
+    let one_case = cased.iter().all(|c| c.is_lowercase()) || cased.iter().all(|c| c.is_uppercase());
+    if cased.is_empty() || !one_case {
+        return collapsed;
+    }
+
+    let words: Vec<String> = collapsed.split(' ').map(str::to_lowercase).collect();
+    let last = words.len() - 1;
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if i < last && NAME_PARTICLES.contains(&word.as_str()) {
+                word.clone()
+            } else if i > 0 && ROMAN_NUMERALS.contains(&word.as_str()) {
+                word.to_uppercase()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Uppercase the first letter and each letter after - ' ’ or ., plus the
+/// letter after a "Mc" prefix.
+fn capitalize(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut upper_next = true;
+    for c in word.chars() {
+        if upper_next && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            out.push(c);
+        }
+        if matches!(c, '-' | '\'' | '’' | '.') {
+            upper_next = true;
+        }
+    }
+    match out.strip_prefix("Mc") {
+        Some(rest) if rest.chars().count() > 1 && rest.starts_with(|c: char| c.is_lowercase()) => {
+            let mut chars = rest.chars();
+            let first: String = chars.next().into_iter().flat_map(char::to_uppercase).collect();
+            format!("Mc{}{}", first, chars.as_str())
+        }
+        _ => out,
+    }
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn full_name(first: &Option<String>, last: &Option<String>) -> String {
+    [first, last]
+        .into_iter()
+        .flatten()
+        .map(|v| collapse_whitespace(v))
+        .filter(|v| !v.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ============ Emails ============
+
+pub fn normalize_email(value: &str) -> Result<String, String> {
+    let email = value.trim().to_lowercase();
+    let email = email.strip_prefix("mailto:").unwrap_or(&email);
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.contains(char::is_whitespace)
+        }
+        None => false,
+    };
+    if valid {
+        Ok(email.to_string())
+    } else {
+        Err("Not a valid email address".to_string())
+    }
+}
+
+// ============ Phones ============
+
+struct PhoneRegion {
+    region: &'static str,
+    calling_code: &'static str,
+    /// Digits in a national number, without the trunk prefix
+    national_len: (usize, usize),
+    /// Whether national numbers are dialled with a leading 0
+    trunk_zero: bool,
+}
+
+const PHONE_REGIONS: &[PhoneRegion] = &[
+    PhoneRegion { region: "US", calling_code: "1", national_len: (10, 10), trunk_zero: false },
+    PhoneRegion { region: "CA", calling_code: "1", national_len: (10, 10), trunk_zero: false },
+    PhoneRegion { region: "MX", calling_code: "52", national_len: (10, 10), trunk_zero: false },
+    PhoneRegion { region: "GB", calling_code: "44", national_len: (9, 10), trunk_zero: true },
+    PhoneRegion { region: "IE", calling_code: "353", national_len: (7, 9), trunk_zero: true },
+    PhoneRegion { region: "FR", calling_code: "33", national_len: (9, 9), trunk_zero: true },
+    PhoneRegion { region: "DE", calling_code: "49", national_len: (6, 11), trunk_zero: true },
+    PhoneRegion { region: "NL", calling_code: "31", national_len: (9, 9), trunk_zero: true },
+    // Italian numbers keep their leading 0
+    PhoneRegion { region: "IT", calling_code: "39", national_len: (6, 11), trunk_zero: false },
+    PhoneRegion { region: "ES", calling_code: "34", national_len: (9, 9), trunk_zero: false },
+    PhoneRegion { region: "AU", calling_code: "61", national_len: (9, 9), trunk_zero: true },
+    PhoneRegion { region: "NZ", calling_code: "64", national_len: (8, 10), trunk_zero: true },
+    PhoneRegion { region: "IN", calling_code: "91", national_len: (10, 10), trunk_zero: true },
+];
+
+fn phone_region(region: &str) -> Option<&'static PhoneRegion> {
+    PHONE_REGIONS.iter().find(|r| r.region.eq_ignore_ascii_case(region))
+}
+
+/// E.164 form of a phone number, plus " x{ext}" when it has an extension.
+pub fn format_phone(value: &str, region: &str) -> Result<String, String> {
+    let region = phone_region(region).ok_or_else(|| format!("Unsupported region '{}'", region))?;
+    let lower = value.trim().to_lowercase();
+    let (number, extension) = split_extension(&lower);
+    if number.chars().any(char::is_alphabetic) {
+        return Err("Phone number contains letters".to_string());
+    }
+    if !number.chars().all(|c| c.is_ascii_digit() || c.is_whitespace() || "()-./+".contains(c)) {
+        return Err("Not a valid phone number".to_string());
+    }
+    let international_format = number.trim_start().starts_with('+');
+    // "+44 (0)20 ..." shows the trunk prefix dropped when dialling from abroad
+    let number = if international_format { number.replace("(0)", "") } else { number.to_string() };
+    let digits: String = number.chars().filter(|c| c.is_ascii_digit()).collect();
+
+    let international = if international_format {
+        digits
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        rest.to_string()
+    } else if let (Some(rest), "1") = (digits.strip_prefix("011"), region.calling_code) {
+        rest.to_string()
+    } else {
+        let mut national = digits.as_str();
+        if region.calling_code == "1" && national.len() == 11 {
+            national = national.strip_prefix('1').unwrap_or(national);
+        } else if region.trunk_zero {
+            national = national.strip_prefix('0').unwrap_or(national);
+        }
+        let (min, max) = region.national_len;
+        if !(min..=max).contains(&national.len()) {
+            return Err(format!("Not a valid {} phone number", region.region));
+        }
+        format!("{}{}", region.calling_code, national)
+    };
+
+    if !(8..=15).contains(&international.len()) {
+        return Err("Not a valid phone number".to_string());
+    }
+    // North American area codes never start with 0 or 1
+    if international.len() == 11 && international.starts_with('1') && !matches!(international.as_bytes()[1], b'2'..=b'9') {
+        return Err("Not a valid area code".to_string());
+    }
+    Ok(match extension {
+        Some(ext) => format!("+{} x{}", international, ext),
+        None => format!("+{}", international),
+    })
+}
+
+/// The number and its extension ("x12", "ext. 12", "#12", ";12").
+fn split_extension(value: &str) -> (&str, Option<String>) {
+    let marker = [value.find("ext"), value.find(['x', '#', ';', ','])].into_iter().flatten().min();
+    match marker {
+        Some(at) => {
+            let ext: String = value[at..].chars().filter(|c| c.is_ascii_digit()).collect();
+            (&value[..at], (!ext.is_empty()).then_some(ext))
+        }
+        None => (value, None),
+    }
+}
+
+// ============ Addresses ============
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Country {
+    Us,
+    Ca,
+    Other,
+}
+
+/// (code, name, country) for US states, DC and territories, and Canadian
+/// provinces and territories
+const STATES: &[(&str, &str, Country)] = &[
+    ("AL", "Alabama", Country::Us), ("AK", "Alaska", Country::Us), ("AZ", "Arizona", Country::Us),
+    ("AR", "Arkansas", Country::Us), ("CA", "California", Country::Us), ("CO", "Colorado", Country::Us),
+    ("CT", "Connecticut", Country::Us), ("DE", "Delaware", Country::Us), ("FL", "Florida", Country::Us),
+    ("GA", "Georgia", Country::Us), ("HI", "Hawaii", Country::Us), ("ID", "Idaho", Country::Us),
+    ("IL", "Illinois", Country::Us), ("IN", "Indiana", Country::Us), ("IA", "Iowa", Country::Us),
+    ("KS", "Kansas", Country::Us), ("KY", "Kentucky", Country::Us), ("LA", "Louisiana", Country::Us),
+    ("ME", "Maine", Country::Us), ("MD", "Maryland", Country::Us), ("MA", "Massachusetts", Country::Us),
+    ("MI", "Michigan", Country::Us), ("MN", "Minnesota", Country::Us), ("MS", "Mississippi", Country::Us),
+    ("MO", "Missouri", Country::Us), ("MT", "Montana", Country::Us), ("NE", "Nebraska", Country::Us),
+    ("NV", "Nevada", Country::Us), ("NH", "New Hampshire", Country::Us), ("NJ", "New Jersey", Country::Us),
+    ("NM", "New Mexico", Country::Us), ("NY", "New York", Country::Us), ("NC", "North Carolina", Country::Us),
+    ("ND", "North Dakota", Country::Us), ("OH", "Ohio", Country::Us), ("OK", "Oklahoma", Country::Us),
+    ("OR", "Oregon", Country::Us), ("PA", "Pennsylvania", Country::Us), ("RI", "Rhode Island", Country::Us),
+    ("SC", "South Carolina", Country::Us), ("SD", "South Dakota", Country::Us), ("TN", "Tennessee", Country::Us),
+    ("TX", "Texas", Country::Us), ("UT", "Utah", Country::Us), ("VT", "Vermont", Country::Us),
+    ("VA", "Virginia", Country::Us), ("WA", "Washington", Country::Us), ("WV", "West Virginia", Country::Us),
+    ("WI", "Wisconsin", Country::Us), ("WY", "Wyoming", Country::Us),
+    ("DC", "District of Columbia", Country::Us), ("PR", "Puerto Rico", Country::Us), ("GU", "Guam", Country::Us),
+    ("VI", "Virgin Islands", Country::Us), ("AS", "American Samoa", Country::Us),
+    ("MP", "Northern Mariana Islands", Country::Us),
+    ("AB", "Alberta", Country::Ca), ("BC", "British Columbia", Country::Ca), ("MB", "Manitoba", Country::Ca),
+    ("NB", "New Brunswick", Country::Ca), ("NL", "Newfoundland and Labrador", Country::Ca),
+    ("NS", "Nova Scotia", Country::Ca), ("ON", "Ontario", Country::Ca), ("PE", "Prince Edward Island", Country::Ca),
+    ("QC", "Quebec", Country::Ca), ("SK", "Saskatchewan", Country::Ca), ("NT", "Northwest Territories", Country::Ca),
+    ("NU", "Nunavut", Country::Ca), ("YT", "Yukon", Country::Ca),
+];
+
+/// Other spellings seen in address books, lowercased without punctuation
+const STATE_ALIASES: &[(&str, &str)] = &[
+    ("washington dc", "DC"), ("newfoundland", "NL"), ("pei", "PE"), ("québec", "QC"),
+    ("yukon territory", "YT"), ("nwt", "NT"), ("us virgin islands", "VI"),
+];
+
+/// Two-letter code of a US state or Canadian province, however written.
+pub fn state_code(value: &str) -> Option<&'static str> {
+    lookup_state(value).map(|(code, _, _)| *code)
+}
+
+fn lookup_state(value: &str) -> Option<&'static (&'static str, &'static str, Country)> {
+    let key = collapse_whitespace(&value.replace(['.', ','], "")).to_lowercase();
+    let code = STATE_ALIASES.iter().find(|(alias, _)| *alias == key).map(|(_, code)| *code);
+    STATES.iter().find(|(c, name, _)| {
+        code == Some(*c) || c.eq_ignore_ascii_case(&key) || name.to_lowercase() == key
+    })
+}
+
+fn address_country(contact: &Contact, options: &NormalizeOptions) -> Country {
+    if let Some(country) = contact.country.as_deref().filter(|c| !c.trim().is_empty()) {
+        let key = collapse_whitespace(&country.replace('.', "")).to_lowercase();
+        return match key.as_str() {
+            "us" | "usa" | "united states" | "united states of america" => Country::Us,
+            "ca" | "can" | "canada" => Country::Ca,
+            _ => Country::Other,
+        };
+    }
+    match contact.state.as_deref().filter(|s| !s.trim().is_empty()) {
+        Some(state) => lookup_state(state).map(|(_, _, country)| *country).unwrap_or(Country::Other),
+        None => match options.default_region.to_uppercase().as_str() {
+            "US" => Country::Us,
+            "CA" => Country::Ca,
+            _ => Country::Other,
+        },
+    }
+}
+
+fn normalize_state(value: &str, country: Country, style: StateStyle) -> Result<String, String> {
+    match (lookup_state(value), country) {
+        (Some((code, name, state_country)), _) if *state_country == country => Ok(match style {
+            StateStyle::Abbreviation => code.to_string(),
+            StateStyle::Name => name.to_string(),
+        }),
+        (_, Country::Us) => Err("Not a US state".to_string()),
+        (_, Country::Ca) => Err("Not a Canadian province".to_string()),
+        (_, Country::Other) => Ok(value.to_string()),
+    }
+}
+
+fn normalize_postal_code(value: &str, country: Country) -> Result<String, String> {
+    let compact: String = value.chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+    match country {
+        Country::Us => {
+            if !compact.chars().all(|c| c.is_ascii_digit()) {
+                return Err("Not a valid ZIP code".to_string());
+            }
+            match compact.len() {
+                // Leading zero lost to a spreadsheet ("2134" for Boston)
+                4 => Ok(format!("0{}", compact)),
+                5 => Ok(compact),
+                9 => Ok(format!("{}-{}", &compact[..5], &compact[5..])),
+                _ => Err("Not a valid ZIP code".to_string()),
+            }
+        }
+        Country::Ca => {
+            let upper = compact.to_uppercase();
+            let shape = upper
+                .chars()
+                .enumerate()
+                .all(|(i, c)| if i % 2 == 0 { c.is_ascii_alphabetic() } else { c.is_ascii_digit() });
+            if upper.len() == 6 && shape {
+                Ok(format!("{} {}", &upper[..3], &upper[3..]))
+            } else {
+                Err("Not a valid postal code".to_string())
+            }
+        }
+        Country::Other => Ok(value.to_string()),
+    }
+}
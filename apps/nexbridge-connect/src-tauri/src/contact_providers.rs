@@ -0,0 +1,417 @@
+// ---------------------------------------------------------------------------
+// contact_providers — where device contacts come from
+// ---------------------------------------------------------------------------
+// Every source of contacts is a `ContactProvider`:
+//
+//   native           the macOS/Windows helpers (`contacts::get_system_contacts`)
+//   imported-vcards  .vcf files imported with `import_vcard_file`
+//   vcard_directory  a folder of .vcf files (Evolution/GNOME exports, a
+//                    synced folder), configured in SyncSettings
+//   carddav          a CardDAV account (Nextcloud, Fastmail, iCloud with an
+//                    app password; see carddav.rs), configured in
+//                    SyncSettings with its password in the OS keyring
+//
+// A provider reports changes against what was fetched last time: it gets
+// the sync token it returned before and the keys/etags cached for it, and
+// returns upserts, removed keys and a new token. The cache lives in the
+// contact groups database (`provider_contacts`), so a source that fails —
+// server down, folder unmounted — keeps serving its last good contacts, and
+// the error is shown next to it in the settings.
+//
+// Contact ids stay as they were for native contacts; vCard contacts are
+// "vcard:{UID}" and CardDAV contacts "carddav:{provider}:{UID}". When two
+// providers return the same id the first one wins.
+// ---------------------------------------------------------------------------
+
+use crate::carddav::CardDavProvider;
+use crate::contacts::{self, Contact};
+use crate::vcard;
+#[cfg(feature = "gui")]
+use crate::app::AppState;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+pub const NATIVE_PROVIDER_ID: &str = "native";
+pub const IMPORTED_VCARDS_PROVIDER_ID: &str = "imported-vcards";
+const KEYRING_SERVICE: &str = "com.nexus.nexbridge-connect";
+
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + 'a>>;
+
+/// One contact as a provider knows it. `key` identifies it within the
+/// provider (a CardDAV href, a file and position); `etag` changes whenever
+/// the contact does, where the source can tell.
+#[derive(Debug, Clone)]
+pub struct ProviderContact {
+    pub key: String,
+    pub etag: Option<String>,
+    pub contact: Contact,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ContactChanges {
+    /// New or changed since the last fetch
+    pub upserts: Vec<ProviderContact>,
+    /// Keys of cached contacts that are gone
+    pub removed: Vec<String>,
+    /// Passed back on the next fetch
+    pub sync_token: Option<String>,
+}
+
+pub trait ContactProvider: Send + Sync {
+    fn id(&self) -> &str;
+
+    /// Changes since the fetch that returned `sync_token`. `known` maps the
+    /// keys cached for this provider to their etags (None without one); with
+    /// no token everything is listed and anything not in it is removed.
+    fn fetch<'a>(
+        &'a self,
+        sync_token: Option<&'a str>,
+        known: &'a HashMap<String, Option<String>>,
+    ) -> ProviderFuture<'a, ContactChanges>;
+}
+
+// ============ Configuration ============
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ContactProviderConfig {
+    Carddav {
+        id: String,
+        name: String,
+        /// Server, principal or address book URL
+        url: String,
+        username: String,
+    },
+    VcardDirectory {
+        id: String,
+        name: String,
+        path: String,
+    },
+}
+
+impl ContactProviderConfig {
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Carddav { id, .. } | Self::VcardDirectory { id, .. } => id,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Carddav { name, .. } | Self::VcardDirectory { name, .. } => name,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let id = self.id();
+        if id.trim().is_empty() || id == NATIVE_PROVIDER_ID || id == IMPORTED_VCARDS_PROVIDER_ID {
+            return Err(format!("Invalid contact source id '{}'", id));
+        }
+        if self.name().trim().is_empty() {
+            return Err("Contact sources need a name".to_string());
+        }
+        match self {
+            Self::Carddav { url, username, .. } => {
+                let parsed = reqwest::Url::parse(url).map_err(|_| format!("Invalid CardDAV URL '{}'", url))?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err(format!("CardDAV URL must start with https:// ('{}')", url));
+                }
+                if username.trim().is_empty() {
+                    return Err("CardDAV accounts need a username".to_string());
+                }
+            }
+            Self::VcardDirectory { path, .. } => {
+                if !Path::new(path).is_absolute() {
+                    return Err(format!("vCard folder must be an absolute path ('{}')", path));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn provider(&self) -> Result<Box<dyn ContactProvider>, String> {
+        match self {
+            Self::Carddav { id, url, username, .. } => {
+                let password = load_password(id)?;
+                Ok(Box::new(CardDavProvider::new(id, url, username, &password)?))
+            }
+            Self::VcardDirectory { id, path, .. } => Ok(Box::new(VCardDirectoryProvider::new(id, path))),
+        }
+    }
+}
+
+/// A configured source and how its last refresh went.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactProviderStatus {
+    #[serde(flatten)]
+    pub config: ContactProviderConfig,
+    pub contact_count: u32,
+    pub last_synced_at: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Keyring entry holding a CardDAV account's password.
+fn keyring_entry(provider_id: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, &format!("carddav:{}", provider_id))
+        .map_err(|e| format!("No credential store available for the CardDAV password: {}", e))
+}
+
+pub fn store_password(provider_id: &str, password: &str) -> Result<(), String> {
+    keyring_entry(provider_id)?
+        .set_password(password)
+        .map_err(|e| format!("Failed to store the CardDAV password: {}", e))
+}
+
+fn load_password(provider_id: &str) -> Result<String, String> {
+    keyring_entry(provider_id)?.get_password().map_err(|e| match e {
+        keyring::Error::NoEntry => "CardDAV password missing; remove and re-add the account".to_string(),
+        e => format!("Failed to read the CardDAV password: {}", e),
+    })
+}
+
+pub fn delete_password(provider_id: &str) {
+    let result = keyring_entry(provider_id).and_then(|entry| match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    });
+    if let Err(e) = result {
+        eprintln!("[contact_providers] Could not remove password for {}: {}", provider_id, e);
+    }
+}
+
+/// A provider for `config` that can be tried before it is saved.
+pub fn provider_for(config: &ContactProviderConfig, password: Option<&str>) -> Result<Box<dyn ContactProvider>, String> {
+    match (config, password) {
+        (ContactProviderConfig::Carddav { id, url, username, .. }, Some(password)) => {
+            Ok(Box::new(CardDavProvider::new(id, url, username, password)?))
+        }
+        _ => config.provider(),
+    }
+}
+
+// ============ Loading ============
+// For the desktop app, against its settings and contact groups database.
+
+/// All contacts: native first, then imported vCards, then the configured
+/// sources in order. Each provider is refreshed first; one that fails
+/// serves its cached contacts. Errs only when nothing could be loaded.
+#[cfg(feature = "gui")]
+pub(crate) async fn load_contacts(state: &AppState) -> Result<Vec<Contact>, String> {
+    let configs = state.settings.lock().unwrap().contact_providers.clone();
+    let mut providers: Vec<Box<dyn ContactProvider>> = vec![
+        Box::new(NativeProvider),
+        Box::new(VCardDirectoryProvider::new(
+            IMPORTED_VCARDS_PROVIDER_ID,
+            state.app_data_dir.join(vcard::VCARD_DIR),
+        )),
+    ];
+    let mut first_error = None;
+    for config in &configs {
+        match config.provider() {
+            Ok(provider) => providers.push(provider),
+            Err(e) => {
+                record_failure(state, config.id(), &e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    for provider in &providers {
+        if let Err(e) = refresh(state, provider.as_ref()).await {
+            record_failure(state, provider.id(), &e);
+            first_error.get_or_insert(e);
+        }
+    }
+
+    let contacts = cached_contacts(state)?;
+    match first_error {
+        Some(e) if contacts.is_empty() => Err(e),
+        _ => Ok(contacts),
+    }
+}
+
+/// All contacts as last fetched, in the same order as `load_contacts`,
+/// without refreshing any source.
+#[cfg(feature = "gui")]
+pub(crate) fn cached_contacts(state: &AppState) -> Result<Vec<Contact>, String> {
+    let configs = state.settings.lock().unwrap().contact_providers.clone();
+    let provider_ids = [NATIVE_PROVIDER_ID, IMPORTED_VCARDS_PROVIDER_ID]
+        .into_iter()
+        .chain(configs.iter().map(|c| c.id()));
+
+    let mut seen = HashSet::new();
+    let mut contacts = Vec::new();
+    for provider_id in provider_ids {
+        let cached = state.contact_groups
+            .get_provider_contacts(provider_id)
+            .map_err(|e| e.to_string())?;
+        contacts.extend(cached.into_iter().filter(|c| seen.insert(c.id.clone())));
+    }
+    Ok(contacts)
+}
+
+/// Fetch a provider's changes and apply them to its cache.
+#[cfg(feature = "gui")]
+pub(crate) async fn refresh(state: &AppState, provider: &dyn ContactProvider) -> Result<(), String> {
+    let groups = &state.contact_groups;
+    let sync_token = groups
+        .get_provider_sync_state(provider.id())
+        .map_err(|e| e.to_string())?
+        .and_then(|s| s.sync_token);
+    let known = groups.get_provider_etags(provider.id()).map_err(|e| e.to_string())?;
+    let changes = provider.fetch(sync_token.as_deref(), &known).await?;
+    groups
+        .apply_provider_changes(provider.id(), &changes)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "gui")]
+fn record_failure(state: &AppState, provider_id: &str, error: &str) {
+    eprintln!("[contact_providers] {}: {}", provider_id, error);
+    if let Err(e) = state.contact_groups.record_provider_error(provider_id, error) {
+        eprintln!("[contact_providers] Failed to record error: {}", e);
+    }
+}
+
+/// Keys in `known` that aren't in `listed`.
+pub fn removed_keys(known: &HashMap<String, Option<String>>, listed: &HashSet<String>) -> Vec<String> {
+    known.keys().filter(|k| !listed.contains(*k)).cloned().collect()
+}
+
+// ============ Native helpers ============
+
+/// The macOS/Windows helpers, which list everything on each fetch.
+pub struct NativeProvider;
+
+impl ContactProvider for NativeProvider {
+    fn id(&self) -> &str {
+        NATIVE_PROVIDER_ID
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        _sync_token: Option<&'a str>,
+        known: &'a HashMap<String, Option<String>>,
+    ) -> ProviderFuture<'a, ContactChanges> {
+        Box::pin(async move {
+            let listed = tokio::task::spawn_blocking(contacts::get_system_contacts)
+                .await
+                .map_err(|e| e.to_string())??;
+            let keys: HashSet<String> = listed.iter().map(|c| c.id.clone()).collect();
+            Ok(ContactChanges {
+                removed: removed_keys(known, &keys),
+                upserts: listed
+                    .into_iter()
+                    .map(|contact| ProviderContact { key: contact.id.clone(), etag: None, contact })
+                    .collect(),
+                sync_token: None,
+            })
+        })
+    }
+}
+
+// ============ vCard folders ============
+
+/// Every .vcf file under a folder (recursively). Only files whose size or
+/// modification time changed are re-read; the sync token is a hash of the
+/// whole listing, so an untouched folder costs one directory walk.
+pub struct VCardDirectoryProvider {
+    id: String,
+    dir: PathBuf,
+}
+
+impl VCardDirectoryProvider {
+    pub fn new(id: &str, dir: impl Into<PathBuf>) -> Self {
+        Self { id: id.to_string(), dir: dir.into() }
+    }
+
+    /// (path relative to the folder, etag) for each .vcf file, sorted.
+    fn list_files(&self) -> Vec<(String, String)> {
+        let mut files: Vec<(String, String)> = walkdir::WalkDir::new(&self.dir)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("vcf")))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                let modified = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_nanos())
+                    .unwrap_or_default();
+                let relative = e.path().strip_prefix(&self.dir).ok()?.to_string_lossy().replace('\\', "/");
+                Some((relative, format!("{}-{}", modified, meta.len())))
+            })
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+impl ContactProvider for VCardDirectoryProvider {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        sync_token: Option<&'a str>,
+        known: &'a HashMap<String, Option<String>>,
+    ) -> ProviderFuture<'a, ContactChanges> {
+        Box::pin(async move {
+            let files = self.list_files();
+            let listing: String = files.iter().map(|(path, etag)| format!("{}\n{}\n", path, etag)).collect();
+            let token = hex::encode(Sha256::digest(listing.as_bytes()));
+            if sync_token == Some(token.as_str()) {
+                return Ok(ContactChanges { sync_token: Some(token), ..Default::default() });
+            }
+
+            // Cached keys are "{file}#{n}"
+            let mut known_by_file: HashMap<&str, Vec<(&String, Option<&str>)>> = HashMap::new();
+            for (key, etag) in known {
+                let file = key.rsplit_once('#').map(|(file, _)| file).unwrap_or(key);
+                known_by_file.entry(file).or_default().push((key, etag.as_deref()));
+            }
+
+            let mut changes = ContactChanges::default();
+            let mut listed = HashSet::new();
+            for (file, etag) in &files {
+                let cached = known_by_file.get(file.as_str()).map(Vec::as_slice).unwrap_or_default();
+                if !cached.is_empty() && cached.iter().all(|(_, e)| *e == Some(etag.as_str())) {
+                    listed.extend(cached.iter().map(|(key, _)| (*key).clone()));
+                    continue;
+                }
+                let cards = std::fs::read(self.dir.join(file))
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| vcard::parse_bytes(&bytes));
+                match cards {
+                    Ok(cards) => {
+                        for (n, card) in cards.iter().enumerate() {
+                            let key = format!("{}#{}", file, n);
+                            listed.insert(key.clone());
+                            changes.upserts.push(ProviderContact {
+                                key,
+                                etag: Some(etag.clone()),
+                                contact: card.to_contact(),
+                            });
+                        }
+                    }
+                    // Likely half-written; keep what was read before
+                    Err(e) => {
+                        eprintln!("[contact_providers] Skipping {}: {}", file, e);
+                        listed.extend(cached.iter().map(|(key, _)| (*key).clone()));
+                    }
+                }
+            }
+            changes.removed = removed_keys(known, &listed);
+            changes.sync_token = Some(token);
+            Ok(changes)
+        })
+    }
+}
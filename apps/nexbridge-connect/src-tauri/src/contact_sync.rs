@@ -0,0 +1,291 @@
+// ---------------------------------------------------------------------------
+// contact_sync — push selected device contacts to Nexus
+// ---------------------------------------------------------------------------
+// Each run compares the device contacts with what was last sent, using the
+// per-contact state in the contact groups database (`contact_sync_state`):
+//
+//   create  selected, not ignored, and not in Nexus yet
+//   update  selected, in Nexus, and its fingerprint changed (or the last
+//           attempt failed)
+//   delete  in Nexus, but removed from the device, ignored, or merged
+//           into another contact
+//
+// A contact that others were merged into also carries their emails and
+// phones, so Nexus gets one copy with everything.
+//
+// The fingerprint is a SHA-256 of the exact payload sent for the contact,
+// so only changes to fields Nexus stores cause an update. Creates and
+// updates go to POST /personal-contacts/import in batches; deletions to
+// DELETE /personal-contacts/{id}. Updates carry the contact's Nexus id, so
+// Nexus updates that record even when both email and phone changed. Each
+// contact's outcome and error are recorded in its state row; a batch whose
+// response doesn't carry one id per contact is recorded as failed, since the
+// ids can't be matched up.
+//
+// Nexus only stores contacts with an email or a phone; selected contacts
+// with neither are skipped rather than sent.
+//
+// Deselecting a contact only stops syncing it; the copy in Nexus stays.
+// Deletions are also held back when the device returns no contacts at all,
+// which is far more likely a permissions problem than an empty address book.
+// ---------------------------------------------------------------------------
+
+use crate::carddav::CARDDAV_ID_PREFIX;
+use crate::contact_groups::{ContactGroupIndex, ContactSyncState, ContactSyncStatus};
+use crate::contacts::Contact;
+use crate::nexus_api::{ApiError, ImportContact, NexusApi};
+use crate::vcard::VCARD_ID_PREFIX;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+const IMPORT_BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactSyncSummary {
+    pub created: u32,
+    pub updated: u32,
+    pub deleted: u32,
+    pub unchanged: u32,
+    pub failed: u32,
+    /// Selected contacts left out because they are ignored
+    pub ignored: u32,
+    /// Selected contacts left out because they were merged into another
+    pub merged: u32,
+    /// Selected contacts left out because they have no email or phone
+    pub skipped: u32,
+}
+
+/// Primary email/phone picked in the review dialog.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrimaryOverride {
+    pub email: Option<String>,
+    pub phone: Option<String>,
+}
+
+struct Change {
+    contact_id: String,
+    payload: ImportContact,
+    fingerprint: String,
+    is_update: bool,
+}
+
+/// Sync the `selected` contacts from `device` and delete synced contacts
+/// that are gone, ignored or merged. Per-contact failures are recorded and counted;
+/// an error is returned only when nothing could be sent at all.
+pub async fn sync_contacts(
+    api: &NexusApi,
+    groups: &ContactGroupIndex,
+    device: &[Contact],
+    selected: &[String],
+) -> Result<ContactSyncSummary, String> {
+    let ignored: HashSet<String> = groups.get_ignored_contacts().map_err(|e| e.to_string())?.into_iter().collect();
+    let states: HashMap<String, ContactSyncState> = groups
+        .get_sync_states()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|state| (state.contact_id.clone(), state))
+        .collect();
+    let merges: HashMap<String, String> = groups.get_merges().map_err(|e| e.to_string())?.into_iter().collect();
+    let selected: HashSet<&str> = selected.iter().map(String::as_str).collect();
+    let mut summary = ContactSyncSummary::default();
+
+    // Duplicates still on the device, by the contact they were merged into
+    let mut duplicates: HashMap<&str, Vec<&Contact>> = HashMap::new();
+    for contact in device {
+        if let Some(kept_id) = merges.get(&contact.id) {
+            duplicates.entry(kept_id.as_str()).or_default().push(contact);
+        }
+    }
+
+    // Work out what changed since the last run
+    let mut changes = Vec::new();
+    for contact in device.iter().filter(|c| selected.contains(c.id.as_str())) {
+        if ignored.contains(&contact.id) {
+            summary.ignored += 1;
+            continue;
+        }
+        if merges.contains_key(&contact.id) {
+            summary.merged += 1;
+            continue;
+        }
+        let state = states.get(&contact.id);
+        let merged = duplicates.get(contact.id.as_str()).map(Vec::as_slice).unwrap_or_default();
+        let mut payload = import_payload(contact, merged, state);
+        if is_blank(&payload.email) && is_blank(&payload.phone) {
+            summary.skipped += 1;
+            continue;
+        }
+        // The id isn't part of the fingerprint: it doesn't change what Nexus stores
+        let fingerprint = fingerprint(&payload);
+        payload.id = state.and_then(|s| s.nexus_id.clone());
+        match state {
+            Some(s) if s.status == ContactSyncStatus::Synced && s.fingerprint.as_deref() == Some(fingerprint.as_str()) => {
+                summary.unchanged += 1;
+            }
+            _ => changes.push(Change {
+                contact_id: contact.id.clone(),
+                payload,
+                fingerprint,
+                is_update: state.is_some_and(|s| s.nexus_id.is_some()),
+            }),
+        }
+    }
+
+    let on_device: HashSet<&str> = device.iter().map(|c| c.id.as_str()).collect();
+    let deletions: Vec<&ContactSyncState> = if device.is_empty() {
+        vec![]
+    } else {
+        states
+            .values()
+            .filter(|s| s.nexus_id.is_some())
+            .filter(|s| {
+                ignored.contains(&s.contact_id)
+                    || merges.contains_key(&s.contact_id)
+                    || !on_device.contains(s.contact_id.as_str())
+            })
+            .collect()
+    };
+
+    // Creates and updates
+    for batch in changes.chunks(IMPORT_BATCH_SIZE) {
+        let payload: Vec<ImportContact> = batch.iter().map(|c| c.payload.clone()).collect();
+        match api.import_contacts(&payload).await {
+            Ok(result) if result.contacts.len() != batch.len() => {
+                // Ids come back in request order, so without a full list
+                // there is no telling which contact got which; retried next run
+                let e = ApiError::Decode(format!(
+                    "Nexus returned {} ids for {} contacts",
+                    result.contacts.len(),
+                    batch.len()
+                ));
+                fail_all(groups, batch.iter().map(|c| c.contact_id.as_str()), &e, &mut summary)?;
+            }
+            Ok(result) => {
+                for (change, nexus) in batch.iter().zip(result.contacts) {
+                    groups
+                        .record_contact_synced(&change.contact_id, Some(&nexus.id), &change.fingerprint)
+                        .map_err(|e| e.to_string())?;
+                    if change.is_update {
+                        summary.updated += 1;
+                    } else {
+                        summary.created += 1;
+                    }
+                }
+            }
+            Err(e) => {
+                fail_all(groups, batch.iter().map(|c| c.contact_id.as_str()), &e, &mut summary)?;
+                if is_fatal(&e) {
+                    return Err(e.to_string());
+                }
+            }
+        }
+    }
+
+    // Deletions
+    for state in deletions {
+        let Some(nexus_id) = &state.nexus_id else { continue };
+        match api.delete_contact(nexus_id).await {
+            Ok(()) => {
+                groups.remove_sync_state(&state.contact_id).map_err(|e| e.to_string())?;
+                summary.deleted += 1;
+            }
+            Err(e) => {
+                fail_all(groups, [state.contact_id.as_str()], &e, &mut summary)?;
+                if is_fatal(&e) {
+                    return Err(e.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// The same payload the Contacts tab sends, plus the emails and phones of
+/// contacts merged into this one, with any saved primary email/phone that
+/// is still among them.
+fn import_payload(contact: &Contact, merged: &[&Contact], state: Option<&ContactSyncState>) -> ImportContact {
+    let mut all_emails = contact.all_emails.clone();
+    let mut all_phones = contact.all_phones.clone();
+    for duplicate in merged {
+        for email in &duplicate.all_emails {
+            if !all_emails.iter().any(|e| e.eq_ignore_ascii_case(email)) {
+                all_emails.push(email.clone());
+            }
+        }
+        for phone in &duplicate.all_phones {
+            if !all_phones.contains(phone) {
+                all_phones.push(phone.clone());
+            }
+        }
+    }
+
+    let primary_email = state
+        .and_then(|s| s.primary_email.clone())
+        .filter(|email| all_emails.contains(email));
+    let primary_phone = state
+        .and_then(|s| s.primary_phone.clone())
+        .filter(|phone| all_phones.contains(phone));
+
+    ImportContact {
+        id: None,
+        display_name: contact.display_name.clone(),
+        first_name: contact.first_name.clone(),
+        last_name: contact.last_name.clone(),
+        email: primary_email.or_else(|| contact.email.clone()),
+        phone: primary_phone.or_else(|| contact.phone.clone()),
+        all_emails,
+        all_phones,
+        source: contact_source(&contact.id).to_string(),
+    }
+}
+
+/// Nexus drops contacts whose primary email and phone are both empty.
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|v| v.trim().is_empty())
+}
+
+fn fingerprint(payload: &ImportContact) -> String {
+    let json = serde_json::to_vec(payload).unwrap_or_default();
+    hex::encode(Sha256::digest(&json))
+}
+
+/// The `source` Nexus records for a contact, from the provider it came
+/// from: the macOS/Windows address book for native contacts, UPLOAD for
+/// vCard files and CardDAV servers (and native contacts anywhere else).
+pub fn contact_source(contact_id: &str) -> &'static str {
+    if contact_id.starts_with(VCARD_ID_PREFIX) || contact_id.starts_with(CARDDAV_ID_PREFIX) {
+        "UPLOAD"
+    } else if cfg!(target_os = "macos") {
+        "MACOS"
+    } else if cfg!(target_os = "windows") {
+        "WINDOWS"
+    } else {
+        "UPLOAD"
+    }
+}
+
+/// Errors that will fail every other request in the run too.
+fn is_fatal(e: &ApiError) -> bool {
+    matches!(
+        e,
+        ApiError::NotConfigured | ApiError::Unauthorized | ApiError::License { .. } | ApiError::UpdateRequired { .. }
+    )
+}
+
+fn fail_all<'a>(
+    groups: &ContactGroupIndex,
+    contact_ids: impl IntoIterator<Item = &'a str>,
+    error: &ApiError,
+    summary: &mut ContactSyncSummary,
+) -> Result<(), String> {
+    let message = error.to_string();
+    for contact_id in contact_ids {
+        groups
+            .record_contact_sync_failed(contact_id, &message)
+            .map_err(|e| e.to_string())?;
+        summary.failed += 1;
+    }
+    Ok(())
+}
@@ -1,5 +1,8 @@
+use crate::contact_normalizer::FieldChange;
 use serde::{Deserialize, Serialize};
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 use std::process::Command;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +29,7 @@ pub struct Contact {
 
 /// Contact structure from native helpers (Swift/PowerShell)
 /// Uses camelCase to match JSON output from helpers
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct NativeContact {
@@ -194,14 +198,15 @@ pub fn get_system_contacts() -> Result<Vec<Contact>, String> {
     }
 }
 
-/// Fallback for other platforms (Linux, etc.)
+/// No native address book elsewhere (Linux, etc.); contacts come from the
+/// CardDAV and vCard providers instead (see contact_providers.rs)
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 pub fn get_system_contacts() -> Result<Vec<Contact>, String> {
-    eprintln!("[contacts] Platform not supported, using demo contacts");
-    Ok(get_demo_contacts())
+    Ok(vec![])
 }
 
 /// Parse JSON from native helper into Contact structs
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 fn parse_native_contacts(json: &str) -> Result<Vec<Contact>, String> {
     match serde_json::from_str::<Vec<NativeContact>>(json) {
         Ok(native_contacts) => {
@@ -233,6 +238,7 @@ fn parse_native_contacts(json: &str) -> Result<Vec<Contact>, String> {
     }
 }
 
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 fn get_demo_contacts() -> Vec<Contact> {
     vec![
         Contact {
@@ -255,74 +261,98 @@ fn get_demo_contacts() -> Vec<Contact> {
     ]
 }
 
-/// Response from the normalize operation
-#[derive(Debug, Deserialize)]
+/// Normalized values for one native contact, as sent to the helper's
+/// `apply` action
+#[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct NormalizeResponse {
-    updated: u32,
-    total: u32,
+pub struct ContactUpdate {
+    pub id: String,
+    pub changes: Vec<FieldChange>,
 }
 
-/// Result struct for normalize operation (matches lib.rs NormalizeResult)
-#[derive(Debug, Clone, Serialize)]
+/// Response from the helper's `apply` action
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NormalizeResult {
     pub updated: u32,
     pub total: u32,
 }
 
-/// Normalize state values in Apple Contacts (macOS only)
+/// Whether `apply_contact_updates` can write to the native address book.
+/// The Windows helper only reads.
+pub fn supports_write_back() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        get_macos_helper_path().is_some()
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        false
+    }
+}
+
+/// Write normalized values back to Apple Contacts (macOS only). The helper
+/// only replaces values still equal to `before`, so edits made since the
+/// preview are kept.
 #[cfg(target_os = "macos")]
-pub fn normalize_contacts() -> Result<NormalizeResult, String> {
+pub fn apply_contact_updates(updates: &[ContactUpdate]) -> Result<NormalizeResult, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
     let helper_path = match get_macos_helper_path() {
         Some(p) => p,
         None => {
             return Err("macOS contacts helper not found".to_string());
         }
     };
-    
-    eprintln!("[contacts] Normalizing states using helper at: {:?}", helper_path);
-    
+
+    eprintln!("[contacts] Writing {} normalized contacts using helper at: {:?}", updates.len(), helper_path);
+
+    let input = serde_json::to_vec(updates).map_err(|e| e.to_string())?;
     let output = Command::new(&helper_path)
-        .arg("normalize")
-        .output();
-    
+        .arg("apply")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(&input)?;
+            }
+            child.wait_with_output()
+        });
+
     match output {
         Ok(result) => {
+            let stderr = String::from_utf8_lossy(&result.stderr);
             if !result.status.success() {
-                let stderr = String::from_utf8_lossy(&result.stderr);
-                eprintln!("[contacts] Normalize stderr: {}", stderr);
-                
+                eprintln!("[contacts] Apply stderr: {}", stderr);
+
                 if stderr.contains("denied") || stderr.contains("authorized") {
                     return Err("Contacts access denied. Please grant permission in System Settings > Privacy & Security > Contacts.".to_string());
                 }
-                
-                return Err(format!("Failed to normalize contacts: {}", stderr));
+
+                return Err(format!("Failed to update contacts: {}", stderr));
             }
-            
-            let stdout = String::from_utf8_lossy(&result.stdout);
-            eprintln!("[contacts] Normalize output: {}", stdout);
-            
-            // Parse the JSON response
-            match serde_json::from_str::<NormalizeResponse>(&stdout) {
-                Ok(resp) => Ok(NormalizeResult {
-                    updated: resp.updated,
-                    total: resp.total,
-                }),
-                Err(e) => {
-                    eprintln!("[contacts] Failed to parse normalize response: {}", e);
-                    Err(format!("Failed to parse normalize response: {}", e))
-                }
+            if !stderr.is_empty() {
+                eprintln!("[contacts] Apply stderr: {}", stderr);
             }
+
+            let stdout = String::from_utf8_lossy(&result.stdout);
+            eprintln!("[contacts] Apply output: {}", stdout);
+            serde_json::from_str::<NormalizeResult>(&stdout).map_err(|e| {
+                eprintln!("[contacts] Failed to parse apply response: {}", e);
+                format!("Failed to parse apply response: {}", e)
+            })
         }
         Err(e) => {
-            eprintln!("[contacts] Failed to run normalize: {}", e);
-            Err(format!("Failed to run normalize: {}", e))
+            eprintln!("[contacts] Failed to run apply: {}", e);
+            Err(format!("Failed to run apply: {}", e))
         }
     }
 }
 
-/// Normalize contacts - stub for non-macOS platforms
+/// No writable native address book on other platforms
 #[cfg(not(target_os = "macos"))]
-pub fn normalize_contacts() -> Result<NormalizeResult, String> {
-    Err("Contact normalization is only supported on macOS".to_string())
+pub fn apply_contact_updates(_updates: &[ContactUpdate]) -> Result<NormalizeResult, String> {
+    Err("Writing contacts back is only supported on macOS".to_string())
 }
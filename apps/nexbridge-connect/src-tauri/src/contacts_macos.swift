@@ -154,101 +154,98 @@ func fetchContacts() -> [ContactInfo] {
     return contacts
 }
 
-// Normalize states in Apple Contacts (writes back to contacts database)
-func normalizeContactStates() -> (updated: Int, total: Int) {
+// Normalized values from the app (contact_normalizer.rs)
+struct FieldChange: Codable {
+    let field: String
+    let before: String
+    let after: String
+}
+
+struct ContactUpdate: Codable {
+    let id: String
+    let changes: [FieldChange]
+}
+
+// Write normalized values back to Apple Contacts. A value is only replaced
+// while it still equals `before`, so edits made since the preview are kept.
+func applyContactUpdates(_ updates: [ContactUpdate]) -> (updated: Int, total: Int) {
     let store = CNContactStore()
     var updated = 0
-    var total = 0
-    
-    // Fetch all keys needed for modification
+
     let keys: [CNKeyDescriptor] = [
         CNContactIdentifierKey as CNKeyDescriptor,
         CNContactGivenNameKey as CNKeyDescriptor,
         CNContactFamilyNameKey as CNKeyDescriptor,
-        CNContactMiddleNameKey as CNKeyDescriptor,
-        CNContactNamePrefixKey as CNKeyDescriptor,
-        CNContactNameSuffixKey as CNKeyDescriptor,
-        CNContactNicknameKey as CNKeyDescriptor,
-        CNContactOrganizationNameKey as CNKeyDescriptor,
-        CNContactDepartmentNameKey as CNKeyDescriptor,
-        CNContactJobTitleKey as CNKeyDescriptor,
         CNContactPhoneNumbersKey as CNKeyDescriptor,
         CNContactEmailAddressesKey as CNKeyDescriptor,
         CNContactPostalAddressesKey as CNKeyDescriptor,
-        CNContactUrlAddressesKey as CNKeyDescriptor,
-        CNContactSocialProfilesKey as CNKeyDescriptor,
-        CNContactInstantMessageAddressesKey as CNKeyDescriptor,
-        CNContactBirthdayKey as CNKeyDescriptor,
-        CNContactNonGregorianBirthdayKey as CNKeyDescriptor,
-        CNContactDatesKey as CNKeyDescriptor,
-        CNContactNoteKey as CNKeyDescriptor,
-        CNContactImageDataKey as CNKeyDescriptor,
-        CNContactThumbnailImageDataKey as CNKeyDescriptor,
-        CNContactImageDataAvailableKey as CNKeyDescriptor,
-        CNContactRelationsKey as CNKeyDescriptor,
-        CNContactTypeKey as CNKeyDescriptor,
     ]
-    
-    let request = CNContactFetchRequest(keysToFetch: keys)
-    
-    do {
-        try store.enumerateContacts(with: request) { contact, _ in
-            total += 1
-            
-            // Check if any address needs normalization
-            var needsUpdate = false
-            var updatedAddresses: [(CNLabeledValue<CNPostalAddress>, CNMutablePostalAddress)] = []
-            
-            for labeledAddress in contact.postalAddresses {
-                let address = labeledAddress.value
-                if !address.state.isEmpty {
-                    let normalized = normalizeState(address.state)
-                    if normalized != address.state {
-                        needsUpdate = true
-                        let mutableAddress = CNMutablePostalAddress()
-                        mutableAddress.street = address.street
-                        mutableAddress.city = address.city
-                        mutableAddress.state = normalized ?? address.state
-                        mutableAddress.postalCode = address.postalCode
-                        mutableAddress.country = address.country
-                        updatedAddresses.append((labeledAddress, mutableAddress))
-                    }
+
+    for update in updates {
+        guard let contact = try? store.unifiedContact(withIdentifier: update.id, keysToFetch: keys),
+              let mutableContact = contact.mutableCopy() as? CNMutableContact else {
+            print("Contact not found: \(update.id)", to: &standardError)
+            continue
+        }
+
+        var changed = false
+        for change in update.changes {
+            switch change.field {
+            case "first_name" where mutableContact.givenName == change.before:
+                mutableContact.givenName = change.after
+                changed = true
+            case "last_name" where mutableContact.familyName == change.before:
+                mutableContact.familyName = change.after
+                changed = true
+            case "phone":
+                mutableContact.phoneNumbers = mutableContact.phoneNumbers.map { labeled in
+                    guard labeled.value.stringValue == change.before else { return labeled }
+                    changed = true
+                    return labeled.settingValue(CNPhoneNumber(stringValue: change.after))
                 }
-            }
-            
-            if needsUpdate {
-                let mutableContact = contact.mutableCopy() as! CNMutableContact
-                
-                // Replace addresses with normalized versions
-                var newAddresses: [CNLabeledValue<CNPostalAddress>] = []
-                for labeledAddress in contact.postalAddresses {
-                    if let update = updatedAddresses.first(where: { $0.0 == labeledAddress }) {
-                        newAddresses.append(CNLabeledValue(
-                            label: labeledAddress.label,
-                            value: update.1
-                        ))
+            case "email":
+                mutableContact.emailAddresses = mutableContact.emailAddresses.map { labeled in
+                    guard labeled.value as String == change.before else { return labeled }
+                    changed = true
+                    return labeled.settingValue(change.after as NSString)
+                }
+            case "state", "zip":
+                mutableContact.postalAddresses = mutableContact.postalAddresses.map { labeled in
+                    let address = labeled.value
+                    // fetchContacts reports states already abbreviated
+                    let matches = change.field == "state"
+                        ? address.state == change.before || normalizeState(address.state) == change.before
+                        : address.postalCode == change.before
+                    guard matches, let mutableAddress = address.mutableCopy() as? CNMutablePostalAddress else {
+                        return labeled
+                    }
+                    if change.field == "state" {
+                        mutableAddress.state = change.after
                     } else {
-                        newAddresses.append(labeledAddress)
+                        mutableAddress.postalCode = change.after
                     }
+                    changed = true
+                    return labeled.settingValue(mutableAddress)
                 }
-                mutableContact.postalAddresses = newAddresses
-                
-                let saveRequest = CNSaveRequest()
-                saveRequest.update(mutableContact)
-                
-                do {
-                    try store.execute(saveRequest)
-                    updated += 1
-                } catch {
-                    print("Failed to update contact: \(error)", to: &standardError)
-                }
+            default:
+                // display_name is built from the names by fetchContacts
+                break
+            }
+        }
+
+        if changed {
+            let saveRequest = CNSaveRequest()
+            saveRequest.update(mutableContact)
+            do {
+                try store.execute(saveRequest)
+                updated += 1
+            } catch {
+                print("Failed to update contact: \(error)", to: &standardError)
             }
         }
-    } catch {
-        print("Error enumerating contacts: \(error)", to: &standardError)
     }
-    
-    return (updated, total)
+
+    return (updated, updates.count)
 }
 
 var standardError = FileHandle.standardError
@@ -276,11 +273,16 @@ func main() {
     } else if args.count > 1 && args[1] == "request" {
         let granted = requestContactsAccess()
         print(granted ? "granted" : "denied")
-    } else if args.count > 1 && args[1] == "normalize" {
-        let result = normalizeContactStates()
+    } else if args.count > 1 && args[1] == "apply" {
+        let input = FileHandle.standardInput.readDataToEndOfFile()
+        guard let updates = try? JSONDecoder().decode([ContactUpdate].self, from: input) else {
+            print("Invalid apply input", to: &standardError)
+            exit(1)
+        }
+        let result = applyContactUpdates(updates)
         print("{\"updated\": \(result.updated), \"total\": \(result.total)}")
     } else {
-        print("Usage: contacts_helper [fetch|request|normalize]")
+        print("Usage: contacts_helper [fetch|request|apply]")
     }
 }
 
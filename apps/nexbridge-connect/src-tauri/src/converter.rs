@@ -1,3 +1,4 @@
+use crate::render;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use pulldown_cmark::{html, Options, Parser};
 use serde::{Deserialize, Serialize};
@@ -6,6 +7,9 @@ use std::fs;
 use std::io::Read;
 use std::path::Path;
 
+/// Bump whenever converter output changes so cached HTML is regenerated.
+pub const CONVERTER_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversionResult {
     pub html: String,
@@ -15,6 +19,62 @@ pub struct ConversionResult {
     pub image_count: u32,
     pub original_format: String,
     pub error: Option<String>,
+    /// Requested output format. `html` is always populated; Markdown and
+    /// plain-text renderings go in `content`.
+    #[serde(default)]
+    pub format: OutputFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Image files written next to Markdown output (when not inlined)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub image_files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Html,
+    Markdown,
+    Text,
+}
+
+/// Fill in `content` for the requested format. For Markdown, embedded images
+/// are written to `image_dir` as sibling files when given, otherwise kept as
+/// data URIs.
+pub fn render_output(
+    result: &mut ConversionResult,
+    format: OutputFormat,
+    image_dir: Option<&Path>,
+) -> Result<(), String> {
+    result.format = format;
+    match format {
+        OutputFormat::Html => {
+            result.content = None;
+        }
+        OutputFormat::Markdown => {
+            let stem = sanitize_file_stem(&result.title);
+            let images = match image_dir {
+                Some(dir) => render::ImageOutput::Files { dir, stem: &stem },
+                None => render::ImageOutput::Inline,
+            };
+            let rendered = render::html_to_markdown(&result.html, &images)?;
+            result.content = Some(rendered.content);
+            result.image_files = rendered.image_files;
+        }
+        OutputFormat::Text => {
+            result.content = Some(render::html_to_text(&result.html));
+        }
+    }
+    Ok(())
+}
+
+fn sanitize_file_stem(title: &str) -> String {
+    let stem: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if stem.is_empty() { "document".to_string() } else { stem }
 }
 
 /// Convert a document to HTML with embedded base64 images.
@@ -113,11 +173,14 @@ fn convert_doc_textutil(path: &Path, title: &str, extension: &str) -> Result<Con
         image_count,
         original_format: extension.to_string(),
         error: None,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: Vec::new(),
     })
 }
 
 /// Strip HTML tags for plain-text word counting.
-fn strip_html_tags(html: &str) -> String {
+pub(crate) fn strip_html_tags(html: &str) -> String {
     let mut result = String::with_capacity(html.len());
     let mut in_tag = false;
     for c in html.chars() {
@@ -303,6 +366,9 @@ fn convert_docx(path: &Path, title: &str) -> Result<ConversionResult, String> {
         image_count,
         original_format: "docx".to_string(),
         error: None,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: Vec::new(),
     })
 }
 
@@ -331,7 +397,7 @@ fn extract_pdf_images(path: &Path) -> (Vec<String>, u32) {
         Err(_) => return (data_uris, 0),
     };
 
-    for (_obj_ref, obj) in &doc.objects {
+    for obj in doc.objects.values() {
         // Direct enum matching — avoids method calls that could have edge-case panics
         let stream = match obj {
             lopdf::Object::Stream(ref s) => s,
@@ -456,15 +522,13 @@ fn convert_pdf(path: &Path, title: &str) -> Result<ConversionResult, String> {
     let extracted_count = images.len() as u32;
     let total_images = extracted_count + unextracted_count;
 
-    // Only fail if we got absolutely nothing
-    if !has_text && !has_images {
-        if !warnings.is_empty() {
-            return Err(format!(
-                "PDF conversion failed — no content could be extracted: {}",
-                warnings.join("; ")
-            ));
-        }
-        // Truly empty PDF — we'll still return a minimal shell
+    // Only fail if we got absolutely nothing. A truly empty PDF (no
+    // warnings either) still returns a minimal shell.
+    if !has_text && !has_images && !warnings.is_empty() {
+        return Err(format!(
+            "PDF conversion failed — no content could be extracted: {}",
+            warnings.join("; ")
+        ));
     }
 
     let word_count = text.split_whitespace().count() as u32;
@@ -539,6 +603,9 @@ fn convert_pdf(path: &Path, title: &str) -> Result<ConversionResult, String> {
         image_count: total_images,
         original_format: "pdf".to_string(),
         error: error_info,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: Vec::new(),
     })
 }
 
@@ -581,6 +648,9 @@ fn convert_markdown(path: &Path, title: &str) -> Result<ConversionResult, String
         image_count,
         original_format: "markdown".to_string(),
         error: None,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: Vec::new(),
     })
 }
 
@@ -610,6 +680,9 @@ fn convert_text(path: &Path, title: &str) -> Result<ConversionResult, String> {
         image_count: 0,
         original_format: "text".to_string(),
         error: None,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: Vec::new(),
     })
 }
 
@@ -631,6 +704,9 @@ fn convert_html_passthrough(path: &Path, title: &str) -> Result<ConversionResult
         image_count,
         original_format: "html".to_string(),
         error: None,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: Vec::new(),
     })
 }
 
@@ -671,6 +747,9 @@ fn convert_image(path: &Path, title: &str, extension: &str) -> Result<Conversion
         image_count: 1,
         original_format: extension.to_string(),
         error: None,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: Vec::new(),
     })
 }
 
@@ -803,11 +882,9 @@ fn extract_text_lopdf_fallback(path: &Path) -> String {
                             }
                         }
                     }
-                    "Td" | "TD" | "T*" => {
-                        // Text positioning — insert newline
-                        if !text.ends_with('\n') {
-                            text.push('\n');
-                        }
+                    // Text positioning — insert newline
+                    "Td" | "TD" | "T*" if !text.ends_with('\n') => {
+                        text.push('\n');
                     }
                     _ => {}
                 }
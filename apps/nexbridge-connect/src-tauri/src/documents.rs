@@ -1,4 +1,5 @@
 use crate::index::{DocumentIndex, DocumentStatus, IndexedDocument};
+use crate::metadata;
 use sha2::{Sha256, Digest};
 use std::fs::{self, File};
 use std::io::Read;
@@ -93,6 +94,15 @@ pub fn scan_directory(
         let file_hash = calculate_file_hash(path).ok();
 
         let file_path_str = path.to_string_lossy().to_string();
+
+        // Embedded author/dates/EXIF for filtering and the upload payload.
+        // An unchanged file keeps what was extracted last time.
+        let indexed = index.get_document_by_path(&file_path_str).ok().flatten();
+        let doc_metadata = match indexed {
+            Some(existing) if file_hash.is_some() && existing.file_hash == file_hash => existing.metadata,
+            _ => metadata::extract_metadata(path),
+        };
+
         let file_name = path.file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("unknown")
@@ -112,6 +122,8 @@ pub fn scan_directory(
             updated_at: now.clone(),
             uploaded_at: None,
             nexus_doc_id: None,
+            category: None,
+            metadata: doc_metadata,
         };
 
         match index.upsert_document(&doc) {
@@ -143,7 +155,7 @@ fn build_breadcrumb(root: &Path, file_path: &Path, root_name: &str) -> Vec<Strin
 }
 
 /// Calculate SHA-256 hash of a file
-fn calculate_file_hash(path: &Path) -> Result<String, std::io::Error> {
+pub(crate) fn calculate_file_hash(path: &Path) -> Result<String, std::io::Error> {
     let mut file = File::open(path)?;
     let mut hasher = Sha256::new();
     let mut buffer = [0u8; 8192];
@@ -0,0 +1,445 @@
+// ---------------------------------------------------------------------------
+// encryption — local data at rest
+// ---------------------------------------------------------------------------
+// documents.db and contact_groups.db are SQLCipher databases, and converted
+// HTML blobs are AES-256-GCM encrypted, all under one random 256-bit key per
+// OS user. Where the key lives:
+//
+//   - macOS Keychain, Windows Credential Manager, or the Linux Secret Service
+//     (GNOME Keyring / KWallet) — via the `keyring` crate
+//   - Linux without a Secret Service: nowhere. The key is derived from a
+//     passphrase with Argon2id each launch; the salt and a check value (to
+//     tell a wrong passphrase from a damaged file) are in
+//     {app_data}/data_key.json. Once a passphrase is set it stays in use.
+//
+// Until a passphrase is entered the app runs "locked" on empty in-memory
+// indexes (see `unlock_local_data` in app.rs). The CLI reads the passphrase
+// from NEXBRIDGE_PASSPHRASE.
+//
+// Plaintext databases and blobs from earlier versions are encrypted in place
+// the first time they are opened with a key.
+//
+// `wipe_local_data` overwrites and deletes everything under {app_data} and
+// removes the key, for offboarding. With the key gone, anything the overwrite
+// missed (SSD remapping, backups) is unreadable anyway.
+// ---------------------------------------------------------------------------
+
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::{Aes256Gcm, Nonce};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+const KEYRING_SERVICE: &str = "com.nexus.nexbridge-connect";
+const KEYRING_USER: &str = "local-data-key";
+const PASSPHRASE_FILE: &str = "data_key.json";
+const PASSPHRASE_CHECK: &[u8] = b"nexbridge-local-data";
+pub const PASSPHRASE_ENV: &str = "NEXBRIDGE_PASSPHRASE";
+const MIN_PASSPHRASE_LEN: usize = 10;
+
+/// Prefix of an encrypted blob: magic, then a 12-byte nonce, then ciphertext.
+const BLOB_MAGIC: &[u8; 4] = b"NBE1";
+const NONCE_LEN: usize = 12;
+
+/// The 256-bit data key. Zeroed when dropped.
+#[derive(Clone)]
+pub struct DataKey([u8; 32]);
+
+impl Drop for DataKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl DataKey {
+    fn generate() -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        DataKey(key)
+    }
+
+    fn from_hex(s: &str) -> Result<Self, String> {
+        let mut bytes = hex::decode(s.trim()).map_err(|_| "Stored data key is not valid hex".to_string())?;
+        if bytes.len() != 32 {
+            bytes.zeroize();
+            return Err("Stored data key has the wrong length".to_string());
+        }
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&bytes);
+        bytes.zeroize();
+        Ok(DataKey(key))
+    }
+
+    /// SQLCipher raw-key literal, skipping its own key derivation.
+    fn sqlcipher_literal(&self) -> String {
+        format!("\"x'{}'\"", hex::encode(self.0))
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new_from_slice(&self.0).expect("32-byte key")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeySource {
+    Keychain,
+    Passphrase,
+}
+
+/// Result of looking for the data key at startup.
+pub enum KeyLookup {
+    Ready(DataKey, KeySource),
+    /// No OS credential store; `unlock_with_passphrase` must be called.
+    /// `passphrase_set` is false on first use (the passphrase will be chosen).
+    NeedsPassphrase { passphrase_set: bool },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    pub unlocked: bool,
+    pub key_source: Option<KeySource>,
+    pub passphrase_set: bool,
+    /// Why the key couldn't be loaded, if it couldn't
+    pub error: Option<String>,
+}
+
+/// {app_data}/data_key.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PassphraseFile {
+    version: u32,
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    /// PASSPHRASE_CHECK encrypted under the derived key
+    check: String,
+}
+
+// ============ Key management ============
+
+/// Find (or on first run create) the data key.
+pub fn load_key(app_dir: &Path) -> Result<KeyLookup, String> {
+    if passphrase_path(app_dir).exists() {
+        return Ok(KeyLookup::NeedsPassphrase { passphrase_set: true });
+    }
+
+    let entry = match keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+        Ok(e) => e,
+        Err(e) => return no_keychain(e),
+    };
+    match entry.get_password() {
+        Ok(hex_key) => Ok(KeyLookup::Ready(DataKey::from_hex(&hex_key)?, KeySource::Keychain)),
+        Err(keyring::Error::NoEntry) => {
+            let key = DataKey::generate();
+            match entry.set_password(&hex::encode(key.0)) {
+                Ok(()) => {
+                    eprintln!("[encryption] Created data key in the OS credential store");
+                    Ok(KeyLookup::Ready(key, KeySource::Keychain))
+                }
+                Err(e) => no_keychain(e),
+            }
+        }
+        Err(e) => no_keychain(e),
+    }
+}
+
+/// Linux desktops without a Secret Service fall back to a passphrase; on
+/// macOS and Windows the credential store is always there, so a failure is
+/// an error rather than a reason to weaken the setup.
+fn no_keychain(e: keyring::Error) -> Result<KeyLookup, String> {
+    if cfg!(target_os = "linux") {
+        eprintln!("[encryption] No OS credential store ({}); using a passphrase", e);
+        Ok(KeyLookup::NeedsPassphrase { passphrase_set: false })
+    } else {
+        Err(format!("Could not access the OS credential store: {}", e))
+    }
+}
+
+/// Derive the key from `passphrase`. The first call chooses the passphrase;
+/// later calls verify it.
+pub fn unlock_with_passphrase(app_dir: &Path, passphrase: &str) -> Result<DataKey, String> {
+    let path = passphrase_path(app_dir);
+    if path.exists() {
+        let raw = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: PassphraseFile = serde_json::from_str(&raw).map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+        let key = derive_key(passphrase, &file)?;
+        let check = hex::decode(&file.check).map_err(|_| format!("Invalid {}", path.display()))?;
+        match decrypt_bytes(&key, &check) {
+            Ok(plain) if plain == PASSPHRASE_CHECK => Ok(key),
+            _ => Err("Incorrect passphrase".to_string()),
+        }
+    } else {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(format!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+        }
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let mut file = PassphraseFile {
+            version: 1,
+            salt: hex::encode(salt),
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+            check: String::new(),
+        };
+        let key = derive_key(passphrase, &file)?;
+        file.check = hex::encode(encrypt_bytes(&key, PASSPHRASE_CHECK)?);
+        let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(app_dir).ok();
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(key)
+    }
+}
+
+fn derive_key(passphrase: &str, file: &PassphraseFile) -> Result<DataKey, String> {
+    let salt = hex::decode(&file.salt).map_err(|_| "Invalid passphrase salt".to_string())?;
+    let params = argon2::Params::new(file.memory_kib, file.iterations, file.parallelism, Some(32))
+        .map_err(|e| e.to_string())?;
+    let argon = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    let mut key = [0u8; 32];
+    argon
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(DataKey(key))
+}
+
+fn passphrase_path(app_dir: &Path) -> PathBuf {
+    app_dir.join(PASSPHRASE_FILE)
+}
+
+// ============ Databases ============
+
+/// Open a database with `key`, encrypting it first if it is still plaintext.
+/// Without a key the database is opened as-is (in-memory placeholders while
+/// locked).
+pub fn open_database(db_path: &Path, key: Option<&DataKey>) -> Result<Connection, rusqlite::Error> {
+    let key = match key {
+        Some(k) => k,
+        None => return Connection::open(db_path),
+    };
+
+    if is_plaintext_database(db_path) {
+        encrypt_plaintext_database(db_path, key)?;
+    }
+    let conn = Connection::open(db_path)?;
+    apply_key(&conn, key)?;
+    // Fails with NotADatabase if the key is wrong
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))?;
+    Ok(conn)
+}
+
+fn apply_key(conn: &Connection, key: &DataKey) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(&format!("PRAGMA key = {};", key.sqlcipher_literal()))
+}
+
+/// A plaintext SQLite file starts with this header; SQLCipher files don't.
+fn is_plaintext_database(db_path: &Path) -> bool {
+    use std::io::Read;
+    let mut header = [0u8; 16];
+    std::fs::File::open(db_path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map(|_| &header == b"SQLite format 3\0")
+        .unwrap_or(false)
+}
+
+/// Re-write a plaintext database encrypted, then replace the original. The
+/// plaintext (with any -wal/-journal it left behind) is moved aside and only
+/// scrubbed once the encrypted copy is in place, so a failure part-way leaves
+/// a readable database behind.
+fn encrypt_plaintext_database(db_path: &Path, key: &DataKey) -> Result<(), rusqlite::Error> {
+    eprintln!("[encryption] Encrypting {}", db_path.display());
+    let tmp = db_path.with_extension(format!("encrypting-{}", uuid::Uuid::new_v4()));
+    if let Err(e) = export_encrypted(db_path, &tmp, key) {
+        for file in database_files(&tmp) {
+            let _ = std::fs::remove_file(file);
+        }
+        return Err(e);
+    }
+
+    let aside = db_path.with_extension(format!("plaintext-{}", uuid::Uuid::new_v4()));
+    let mut moved: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (from, to) in database_files(db_path).into_iter().zip(database_files(&aside)) {
+        if !from.exists() {
+            continue;
+        }
+        if let Err(e) = std::fs::rename(&from, &to) {
+            restore_moved(&moved);
+            let _ = std::fs::remove_file(&tmp);
+            return Err(io_error(&format!("Failed to move {} aside", from.display()), e));
+        }
+        moved.push((from, to));
+    }
+    if let Err(e) = std::fs::rename(&tmp, db_path) {
+        restore_moved(&moved);
+        let _ = std::fs::remove_file(&tmp);
+        return Err(io_error(&format!("Failed to replace {}", db_path.display()), e));
+    }
+
+    for (_, plaintext) in &moved {
+        if let Err(e) = overwrite_file(plaintext).and_then(|_| std::fs::remove_file(plaintext)) {
+            eprintln!("[encryption] Failed to scrub {}: {}", plaintext.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// A file operation around the database failed; reported as SQLite's own I/O
+/// error so callers see it as one.
+fn io_error(context: &str, e: std::io::Error) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_IOERR),
+        Some(format!("{}: {}", context, e)),
+    )
+}
+
+/// Export `db_path` into a new encrypted file at `dest` and check it opens
+/// with `key`.
+fn export_encrypted(db_path: &Path, dest: &Path, key: &DataKey) -> Result<(), rusqlite::Error> {
+    {
+        let conn = Connection::open(db_path)?;
+        conn.execute(
+            &format!("ATTACH DATABASE ?1 AS encrypted KEY {}", key.sqlcipher_literal()),
+            [dest.to_string_lossy()],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+        conn.execute_batch("DETACH DATABASE encrypted;")?;
+    }
+    let conn = Connection::open(dest)?;
+    apply_key(&conn, key)?;
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))?;
+    Ok(())
+}
+
+/// A database file followed by the journal files SQLite may leave next to it.
+fn database_files(db_path: &Path) -> Vec<PathBuf> {
+    ["", "-journal", "-wal", "-shm"]
+        .iter()
+        .map(|suffix| PathBuf::from(format!("{}{}", db_path.display(), suffix)))
+        .collect()
+}
+
+fn restore_moved(moved: &[(PathBuf, PathBuf)]) {
+    for (from, to) in moved {
+        if let Err(e) = std::fs::rename(to, from) {
+            eprintln!("[encryption] Failed to restore {}: {}", from.display(), e);
+        }
+    }
+}
+
+/// Copy the open database to `dest` (which must not exist), under the same
+/// key as the source.
+pub fn snapshot_database(conn: &Connection, dest: &Path, key: Option<&DataKey>) -> Result<(), rusqlite::Error> {
+    match key {
+        Some(key) => {
+            conn.execute(
+                &format!("ATTACH DATABASE ?1 AS snapshot KEY {}", key.sqlcipher_literal()),
+                [dest.to_string_lossy()],
+            )?;
+            conn.query_row("SELECT sqlcipher_export('snapshot')", [], |_| Ok(()))?;
+            conn.execute_batch("DETACH DATABASE snapshot;")
+        }
+        None => conn.execute("VACUUM INTO ?1", [dest.to_string_lossy()]).map(|_| ()),
+    }
+}
+
+// ============ Blobs ============
+
+pub fn is_encrypted_blob(bytes: &[u8]) -> bool {
+    bytes.len() >= BLOB_MAGIC.len() + NONCE_LEN && bytes.starts_with(BLOB_MAGIC)
+}
+
+/// Encrypt with a fresh random nonce: magic + nonce + ciphertext.
+pub fn encrypt_bytes(key: &DataKey, plain: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = key
+        .cipher()
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut out = Vec::with_capacity(BLOB_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(BLOB_MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+pub fn decrypt_bytes(key: &DataKey, data: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_encrypted_blob(data) {
+        return Err("Not an encrypted blob".to_string());
+    }
+    let (nonce, ciphertext) = data[BLOB_MAGIC.len()..].split_at(NONCE_LEN);
+    key.cipher()
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed (wrong key or damaged data)".to_string())
+}
+
+// ============ Wipe ============
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WipeResult {
+    pub files_removed: u32,
+    pub bytes_overwritten: u64,
+    pub key_removed: bool,
+    pub errors: Vec<String>,
+}
+
+/// Overwrite and delete everything under `app_dir`, then delete the data key
+/// from the OS credential store. Callers must close the databases first.
+pub fn wipe_local_data(app_dir: &Path) -> WipeResult {
+    let mut result = WipeResult::default();
+    let had_passphrase = passphrase_path(app_dir).exists();
+
+    for entry in walkdir::WalkDir::new(app_dir)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if path == app_dir {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            if let Err(e) = std::fs::remove_dir(path) {
+                result.errors.push(format!("{}: {}", path.display(), e));
+            }
+            continue;
+        }
+        match overwrite_file(path).and_then(|bytes| std::fs::remove_file(path).map(|_| bytes)) {
+            Ok(bytes) => {
+                result.files_removed += 1;
+                result.bytes_overwritten += bytes;
+            }
+            Err(e) => result.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    match keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).and_then(|e| e.delete_credential()) {
+        Ok(()) => result.key_removed = true,
+        // Passphrase setups have no stored key; the salt file is gone
+        Err(keyring::Error::NoEntry) => result.key_removed = had_passphrase,
+        Err(_) if had_passphrase => result.key_removed = true,
+        Err(e) => result.errors.push(format!("Failed to delete data key: {}", e)),
+    }
+
+    result
+}
+
+/// Overwrite a file's contents with zeros and flush it to disk.
+fn overwrite_file(path: &Path) -> std::io::Result<u64> {
+    use std::io::Write;
+    let len = std::fs::metadata(path)?.len();
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    let zeros = vec![0u8; 64 * 1024];
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..n])?;
+        remaining -= n as u64;
+    }
+    file.sync_all()?;
+    Ok(len)
+}
@@ -1,6 +1,11 @@
 use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use crate::encryption::{self, DataKey};
+use crate::metadata::DocumentMetadata;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as CURSOR_ENCODING, Engine};
+use rusqlite::types::Value as SqlValue;
+use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::Mutex;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -26,16 +31,91 @@ impl DocumentStatus {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
-        match s {
-            "IMPORT" => DocumentStatus::Import,
-            "IGNORE" => DocumentStatus::Ignore,
-            "CONVERTED" => DocumentStatus::Converted,
-            "UPLOADED" => DocumentStatus::Uploaded,
-            "FAILED" => DocumentStatus::Failed,
-            _ => DocumentStatus::Pending,
+    /// Parse a status name (case-insensitive). Unknown names are an error
+    /// rather than silently becoming PENDING.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_uppercase().as_str() {
+            "PENDING" => Ok(DocumentStatus::Pending),
+            "IMPORT" => Ok(DocumentStatus::Import),
+            "IGNORE" => Ok(DocumentStatus::Ignore),
+            "CONVERTED" => Ok(DocumentStatus::Converted),
+            "UPLOADED" => Ok(DocumentStatus::Uploaded),
+            "FAILED" => Ok(DocumentStatus::Failed),
+            _ => Err(format!("Unknown document status: {}", s)),
         }
     }
+
+    /// Statuses a document may move to from this one.
+    ///
+    /// UPLOADED is terminal: the document is linked to `nexus_doc_id` and
+    /// moving it anywhere else would orphan that link. IGNORE can only be
+    /// lifted back to PENDING or IMPORT. Conversion and upload can start from
+    /// any other working status (the preview pane converts on the fly).
+    pub fn allowed_transitions(&self) -> &'static [DocumentStatus] {
+        use DocumentStatus::*;
+        match self {
+            Pending => &[Import, Ignore, Converted, Failed, Uploaded],
+            Import => &[Pending, Ignore, Converted, Failed, Uploaded],
+            Ignore => &[Pending, Import],
+            Converted => &[Pending, Import, Ignore, Failed, Uploaded],
+            Failed => &[Pending, Import, Ignore, Converted, Uploaded],
+            Uploaded => &[],
+        }
+    }
+
+    /// Staying in the same status (e.g. to update the error message) is
+    /// always allowed.
+    pub fn can_transition_to(&self, to: &DocumentStatus) -> bool {
+        self == to || self.allowed_transitions().contains(to)
+    }
+}
+
+/// Why a status change was refused.
+#[derive(Debug)]
+pub enum StatusError {
+    NotFound(String),
+    InvalidTransition {
+        id: String,
+        from: DocumentStatus,
+        to: DocumentStatus,
+    },
+    Db(rusqlite::Error),
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusError::NotFound(id) => write!(f, "Document not found: {}", id),
+            StatusError::InvalidTransition { id, from, to } => write!(
+                f,
+                "Document {} cannot move from {} to {}",
+                id,
+                from.as_str(),
+                to.as_str()
+            ),
+            StatusError::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StatusError {
+    fn from(e: rusqlite::Error) -> Self {
+        StatusError::Db(e)
+    }
+}
+
+/// One row of a document's status timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusHistoryEntry {
+    pub id: i64,
+    pub document_id: String,
+    /// None for the entry recorded when the document was first indexed
+    pub from_status: Option<DocumentStatus>,
+    pub to_status: DocumentStatus,
+    /// Who made the change: "user", "cli", "converter", "cache", ...
+    pub actor: String,
+    pub reason: Option<String>,
+    pub created_at: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +133,321 @@ pub struct IndexedDocument {
     pub updated_at: String,
     pub uploaded_at: Option<String>,
     pub nexus_doc_id: Option<String>,
+    /// Nexus category, set by the categorization rules or by hand
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub metadata: DocumentMetadata,
+}
+
+const DOCUMENT_COLUMNS: &str = "id, file_path, file_name, file_type, file_size, file_hash, breadcrumb, status, error_message, scanned_at, updated_at, uploaded_at, nexus_doc_id, \
+     title, author, subject, keywords, doc_created_at, doc_modified_at, page_count, language, captured_at, camera_make, camera_model, gps_latitude, gps_longitude, \
+     category";
+
+/// Embedded-metadata columns on `documents`, added after the table first
+/// shipped (see metadata.rs).
+const METADATA_COLUMNS: &[(&str, &str)] = &[
+    ("title", "TEXT"),
+    ("author", "TEXT"),
+    ("subject", "TEXT"),
+    ("keywords", "TEXT"),
+    ("doc_created_at", "TEXT"),
+    ("doc_modified_at", "TEXT"),
+    ("page_count", "INTEGER"),
+    ("language", "TEXT"),
+    ("captured_at", "TEXT"),
+    ("camera_make", "TEXT"),
+    ("camera_model", "TEXT"),
+    ("gps_latitude", "REAL"),
+    ("gps_longitude", "REAL"),
+];
+
+/// Filter for `find_documents_by_metadata`. Unset fields don't constrain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataFilter {
+    /// Case-insensitive substring match on the author
+    pub author: Option<String>,
+    /// Inclusive lower bound on photo capture time ("YYYY-MM-DD" or a full timestamp)
+    pub captured_from: Option<String>,
+    /// Inclusive upper bound; a bare date covers that whole day
+    pub captured_to: Option<String>,
+}
+
+/// Sort order for `query_documents`. Ties are broken by id so the keyset
+/// cursor is stable.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentSortKey {
+    #[default]
+    FilePath,
+    FileName,
+    FileSize,
+    FileType,
+    Status,
+    ScannedAt,
+    UpdatedAt,
+    UploadedAt,
+}
+
+impl DocumentSortKey {
+    /// SQL expression sorted on. Nullable columns are coalesced so keyset
+    /// comparisons never see NULL.
+    fn expression(&self) -> &'static str {
+        match self {
+            DocumentSortKey::FilePath => "file_path",
+            DocumentSortKey::FileName => "file_name COLLATE NOCASE",
+            DocumentSortKey::FileSize => "COALESCE(file_size, 0)",
+            DocumentSortKey::FileType => "COALESCE(file_type, '')",
+            DocumentSortKey::Status => "status",
+            DocumentSortKey::ScannedAt => "COALESCE(scanned_at, '')",
+            DocumentSortKey::UpdatedAt => "COALESCE(updated_at, '')",
+            DocumentSortKey::UploadedAt => "COALESCE(uploaded_at, '')",
+        }
+    }
+}
+
+/// Which timestamp a `query_documents` date range applies to.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentDateField {
+    ScannedAt,
+    #[default]
+    UpdatedAt,
+    UploadedAt,
+    /// Embedded authoring date (see metadata.rs)
+    CreatedAt,
+    /// Photo capture time
+    CapturedAt,
+}
+
+impl DocumentDateField {
+    fn column(&self) -> &'static str {
+        match self {
+            DocumentDateField::ScannedAt => "scanned_at",
+            DocumentDateField::UpdatedAt => "updated_at",
+            DocumentDateField::UploadedAt => "uploaded_at",
+            DocumentDateField::CreatedAt => "doc_created_at",
+            DocumentDateField::CapturedAt => "captured_at",
+        }
+    }
+}
+
+/// Filters, sort and page for `query_documents`. Empty/unset filters don't
+/// constrain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentQuery {
+    pub statuses: Vec<DocumentStatus>,
+    /// Extensions without the dot, e.g. ["pdf", "docx"]
+    pub file_types: Vec<String>,
+    /// Only documents under this folder (matched on path components)
+    pub path_prefix: Option<String>,
+    /// Exact folder name appearing anywhere in the breadcrumb
+    pub breadcrumb_segment: Option<String>,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    pub date_field: DocumentDateField,
+    /// Inclusive bounds ("YYYY-MM-DD" or a full timestamp)
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    /// Case-insensitive substring match on the file name
+    pub name_contains: Option<String>,
+    pub sort: DocumentSortKey,
+    pub descending: bool,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    /// Page size; defaults to 100, capped at 1000
+    pub limit: Option<u32>,
+}
+
+/// One page of `query_documents` results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentPage {
+    pub documents: Vec<IndexedDocument>,
+    /// Pass back as `cursor` for the next page; None on the last page
+    pub next_cursor: Option<String>,
+    /// Documents matching all filters
+    pub total: u32,
+    pub facets: DocumentFacets,
+}
+
+/// Counts per status and per file type. Each facet applies every filter
+/// except its own, so the UI can show what picking another value would give.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocumentFacets {
+    pub by_status: BTreeMap<String, u32>,
+    pub by_type: BTreeMap<String, u32>,
+}
+
+/// How many documents `import_documents` inserted vs. updated.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportCounts {
+    pub inserted: u32,
+    pub updated: u32,
+}
+
+/// Result of a logged bulk status change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkOperation {
+    /// None when nothing matched (and nothing was logged)
+    pub operation_id: Option<i64>,
+    pub kind: String,
+    /// Folder path, or a summary for multi-select changes
+    pub target: String,
+    pub count: u32,
+    pub affected_ids: Vec<String>,
+    /// Documents the status state machine wouldn't move (e.g. UPLOADED)
+    pub rejected_ids: Vec<String>,
+}
+
+/// What `undo_last_bulk_operation` put back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoResult {
+    pub operation_id: i64,
+    pub kind: String,
+    pub target: String,
+    pub restored_ids: Vec<String>,
+    /// Documents whose status had changed again since the operation
+    pub skipped: u32,
+}
+
+/// Position after the last row of a page: the sort value and id.
+#[derive(Debug, Serialize, Deserialize)]
+struct QueryCursor {
+    sort: DocumentSortKey,
+    descending: bool,
+    value: serde_json::Value,
+    id: String,
+}
+
+/// Read a status column, failing the row on a value we don't know.
+fn parse_status_column(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<DocumentStatus> {
+    let value: String = row.get(idx)?;
+    DocumentStatus::parse(&value).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, e.into())
+    })
+}
+
+/// Number of columns in `DOCUMENT_COLUMNS`.
+const DOCUMENT_COLUMN_COUNT: usize = 27;
+
+/// Map a row selected with `DOCUMENT_COLUMNS` to an `IndexedDocument`.
+fn document_from_row(row: &rusqlite::Row) -> rusqlite::Result<IndexedDocument> {
+    let breadcrumb_json: String = row.get(6)?;
+    let breadcrumb: Vec<String> = serde_json::from_str(&breadcrumb_json).unwrap_or_default();
+    let status = parse_status_column(row, 7)?;
+
+    Ok(IndexedDocument {
+        id: row.get(0)?,
+        file_path: row.get(1)?,
+        file_name: row.get(2)?,
+        file_type: row.get(3)?,
+        file_size: row.get(4)?,
+        file_hash: row.get(5)?,
+        breadcrumb,
+        status,
+        error_message: row.get(8)?,
+        scanned_at: row.get(9)?,
+        updated_at: row.get(10)?,
+        uploaded_at: row.get(11)?,
+        nexus_doc_id: row.get(12)?,
+        metadata: DocumentMetadata {
+            title: row.get(13)?,
+            author: row.get(14)?,
+            subject: row.get(15)?,
+            keywords: row.get(16)?,
+            created_at: row.get(17)?,
+            modified_at: row.get(18)?,
+            page_count: row.get(19)?,
+            language: row.get(20)?,
+            captured_at: row.get(21)?,
+            camera_make: row.get(22)?,
+            camera_model: row.get(23)?,
+            gps_latitude: row.get(24)?,
+            gps_longitude: row.get(25)?,
+        },
+        category: row.get(26)?,
+    })
+}
+
+/// Which part of a document a categorization rule looks at.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleField {
+    /// Any folder in the breadcrumb
+    Breadcrumb,
+    FileName,
+    /// Exact extension match, without the dot
+    Extension,
+    /// Extracted text of the cached conversion
+    Text,
+}
+
+impl RuleField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleField::Breadcrumb => "breadcrumb",
+            RuleField::FileName => "file_name",
+            RuleField::Extension => "extension",
+            RuleField::Text => "text",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "breadcrumb" => Some(RuleField::Breadcrumb),
+            "file_name" => Some(RuleField::FileName),
+            "extension" => Some(RuleField::Extension),
+            "text" => Some(RuleField::Text),
+            _ => None,
+        }
+    }
+}
+
+/// A categorization rule: if any keyword matches `field`, the document gets
+/// `category`. Higher priority rules are tried first (see categorize.rs).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryRule {
+    /// 0 for a rule that hasn't been saved yet
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    pub category: String,
+    pub field: RuleField,
+    pub keywords: Vec<String>,
+    pub priority: i32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Bookkeeping for one cached conversion in `converted/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub document_id: String,
+    pub source_hash: Option<String>,
+    pub converter_version: u32,
+    /// Uncompressed HTML length, checked on read
+    pub size_bytes: i64,
+    /// Content-addressed blob holding the compressed HTML (shared by
+    /// documents with identical sources)
+    pub blob_key: String,
+    /// Compressed size on disk
+    pub stored_bytes: i64,
+    pub cached_at: String,
+    pub last_accessed_at: String,
+}
+
+/// One compressed blob in `converted/`, with how many documents share it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheBlob {
+    pub blob_key: String,
+    pub stored_bytes: i64,
+    pub ref_count: u32,
+    pub last_accessed_at: String,
 }
 
 pub struct DocumentIndex {
@@ -60,8 +455,32 @@ pub struct DocumentIndex {
 }
 
 impl DocumentIndex {
-    pub fn new(db_path: &PathBuf) -> Result<Self, rusqlite::Error> {
-        let conn = Connection::open(db_path)?;
+    /// Open (creating if needed) the database, encrypted under `key`. Only
+    /// the locked-app placeholder is opened without one.
+    pub fn new(db_path: &Path, key: Option<&DataKey>) -> Result<Self, rusqlite::Error> {
+        Ok(Self {
+            conn: Mutex::new(Self::open(db_path, key)?),
+        })
+    }
+
+    /// Switch to another database file (another account's partition). The
+    /// new file is opened and migrated before the old connection is closed,
+    /// so a failure leaves the current one in place.
+    pub fn reopen(&self, db_path: &Path, key: Option<&DataKey>) -> Result<(), rusqlite::Error> {
+        let conn = Self::open(db_path, key)?;
+        *self.conn.lock().unwrap() = conn;
+        Ok(())
+    }
+
+    /// Write a consistent copy of the open database to `dest`, which must not
+    /// exist yet. `key` must be the one the database was opened with.
+    pub fn snapshot_to(&self, dest: &Path, key: Option<&DataKey>) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        encryption::snapshot_database(&conn, dest, key)
+    }
+
+    fn open(db_path: &Path, key: Option<&DataKey>) -> Result<Connection, rusqlite::Error> {
+        let conn = encryption::open_database(db_path, key)?;
         
         // Create tables if they don't exist
         conn.execute(
@@ -93,25 +512,181 @@ impl DocumentIndex {
             [],
         )?;
 
-        Ok(Self {
-            conn: Mutex::new(conn),
-        })
+        for (column, decl) in METADATA_COLUMNS {
+            add_column_if_missing(&conn, "documents", column, decl)?;
+        }
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_author ON documents(author COLLATE NOCASE)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_captured_at ON documents(captured_at)",
+            [],
+        )?;
+
+        add_column_if_missing(&conn, "documents", "category", "TEXT")?;
+
+        // Audit trail of every status change
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS status_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                document_id TEXT NOT NULL,
+                from_status TEXT,
+                to_status TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                reason TEXT,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_status_history_doc ON status_history(document_id, id)",
+            [],
+        )?;
+
+        // Undo log for bulk status changes (folder ignore/import, multi-select)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS bulk_operations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                target TEXT NOT NULL,
+                new_status TEXT NOT NULL,
+                document_count INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                undone_at TEXT
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS bulk_operation_items (
+                operation_id INTEGER NOT NULL,
+                document_id TEXT NOT NULL,
+                previous_status TEXT NOT NULL,
+                previous_updated_at TEXT,
+                PRIMARY KEY (operation_id, document_id)
+            )",
+            [],
+        )?;
+
+        // Sort keys and facets for query_documents
+        for (name, expr) in [
+            ("idx_file_type", "file_type"),
+            ("idx_file_name", "file_name COLLATE NOCASE"),
+            ("idx_file_size", "file_size"),
+            ("idx_scanned_at", "scanned_at"),
+            ("idx_updated_at", "updated_at"),
+            ("idx_status_type", "status, file_type"),
+        ] {
+            conn.execute(
+                &format!("CREATE INDEX IF NOT EXISTS {} ON documents({})", name, expr),
+                [],
+            )?;
+        }
+
+        // Categorization rules, seeded with the defaults the first time
+        let rules_table_exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'category_rules'",
+            [],
+            |row| row.get(0),
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS category_rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                category TEXT NOT NULL,
+                field TEXT NOT NULL,
+                keywords TEXT NOT NULL,
+                priority INTEGER NOT NULL DEFAULT 0,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        if !rules_table_exists {
+            for rule in crate::categorize::default_rules() {
+                insert_category_rule(&conn, &rule)?;
+            }
+        }
+
+        // Metadata for converted HTML cached on disk (see cache.rs)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS conversion_cache (
+                document_id TEXT PRIMARY KEY,
+                source_hash TEXT,
+                converter_version INTEGER NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                cached_at TEXT NOT NULL,
+                last_accessed_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Columns added after the cache table first shipped
+        add_column_if_missing(&conn, "conversion_cache", "blob_key", "TEXT NOT NULL DEFAULT ''")?;
+        add_column_if_missing(&conn, "conversion_cache", "stored_bytes", "INTEGER NOT NULL DEFAULT 0")?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_cache_accessed ON conversion_cache(last_accessed_at)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_cache_blob ON conversion_cache(blob_key)",
+            [],
+        )?;
+
+        // Folders the user has scanned, rescanned by the auto-sync scheduler
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS scan_roots (
+                path TEXT PRIMARY KEY,
+                last_scanned_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(conn)
     }
 
     pub fn upsert_document(&self, doc: &IndexedDocument) -> Result<(), rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         let breadcrumb_json = serde_json::to_string(&doc.breadcrumb).unwrap_or_default();
+        let meta = &doc.metadata;
+        let is_new: bool = conn.query_row(
+            "SELECT NOT EXISTS (SELECT 1 FROM documents WHERE file_path = ?1)",
+            [&doc.file_path],
+            |row| row.get(0),
+        )?;
         
         conn.execute(
-            "INSERT INTO documents (id, file_path, file_name, file_type, file_size, file_hash, breadcrumb, status, error_message, scanned_at, updated_at, uploaded_at, nexus_doc_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
-             ON CONFLICT(file_path) DO UPDATE SET
-                file_name = excluded.file_name,
-                file_type = excluded.file_type,
-                file_size = excluded.file_size,
-                file_hash = excluded.file_hash,
-                breadcrumb = excluded.breadcrumb,
-                updated_at = excluded.updated_at",
+            &format!(
+                "INSERT INTO documents ({})
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27)
+                 ON CONFLICT(file_path) DO UPDATE SET
+                    file_name = excluded.file_name,
+                    file_type = excluded.file_type,
+                    file_size = excluded.file_size,
+                    file_hash = excluded.file_hash,
+                    breadcrumb = excluded.breadcrumb,
+                    updated_at = excluded.updated_at,
+                    title = excluded.title,
+                    author = excluded.author,
+                    subject = excluded.subject,
+                    keywords = excluded.keywords,
+                    doc_created_at = excluded.doc_created_at,
+                    doc_modified_at = excluded.doc_modified_at,
+                    page_count = excluded.page_count,
+                    language = excluded.language,
+                    captured_at = excluded.captured_at,
+                    camera_make = excluded.camera_make,
+                    camera_model = excluded.camera_model,
+                    gps_latitude = excluded.gps_latitude,
+                    gps_longitude = excluded.gps_longitude",
+                DOCUMENT_COLUMNS
+            ),
             params![
                 doc.id,
                 doc.file_path,
@@ -126,97 +701,409 @@ impl DocumentIndex {
                 doc.updated_at,
                 doc.uploaded_at,
                 doc.nexus_doc_id,
+                meta.title,
+                meta.author,
+                meta.subject,
+                meta.keywords,
+                meta.created_at,
+                meta.modified_at,
+                meta.page_count,
+                meta.language,
+                meta.captured_at,
+                meta.camera_make,
+                meta.camera_model,
+                meta.gps_latitude,
+                meta.gps_longitude,
+                doc.category,
             ],
         )?;
+
+        if is_new {
+            record_transition(&conn, &doc.id, None, &doc.status, "scanner", None, &doc.scanned_at)?;
+        }
         Ok(())
     }
 
-    pub fn update_status(&self, id: &str, status: DocumentStatus, error_message: Option<&str>) -> Result<(), rusqlite::Error> {
-        let conn = self.conn.lock().unwrap();
+    /// Move a document to `status` if the state machine allows it, recording
+    /// the change in `status_history`.
+    pub fn update_status(
+        &self,
+        id: &str,
+        status: DocumentStatus,
+        error_message: Option<&str>,
+        actor: &str,
+        reason: Option<&str>,
+    ) -> Result<(), StatusError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
         let now = chrono::Utc::now().to_rfc3339();
-        
-        conn.execute(
+
+        let from = current_status(&tx, id)?;
+        check_transition(id, &from, &status)?;
+        tx.execute(
             "UPDATE documents SET status = ?1, error_message = ?2, updated_at = ?3 WHERE id = ?4",
             params![status.as_str(), error_message, now, id],
         )?;
+        record_transition(&tx, id, Some(&from), &status, actor, reason.or(error_message), &now)?;
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn mark_uploaded(&self, id: &str, nexus_doc_id: &str) -> Result<(), rusqlite::Error> {
+    /// Whether `update_status(id, to, ..)` would currently be allowed, for
+    /// callers with side effects to do before the status change.
+    pub fn check_status_transition(&self, id: &str, to: &DocumentStatus) -> Result<(), StatusError> {
         let conn = self.conn.lock().unwrap();
+        let from = current_status(&conn, id)?;
+        check_transition(id, &from, to)
+    }
+
+    pub fn mark_uploaded(&self, id: &str, nexus_doc_id: &str, actor: &str) -> Result<(), StatusError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
         let now = chrono::Utc::now().to_rfc3339();
-        
-        conn.execute(
+
+        let from = current_status(&tx, id)?;
+        check_transition(id, &from, &DocumentStatus::Uploaded)?;
+        tx.execute(
             "UPDATE documents SET status = 'UPLOADED', nexus_doc_id = ?1, uploaded_at = ?2, updated_at = ?2 WHERE id = ?3",
             params![nexus_doc_id, now, id],
         )?;
+        let reason = format!("Nexus document {}", nexus_doc_id);
+        record_transition(&tx, id, Some(&from), &DocumentStatus::Uploaded, actor, Some(&reason), &now)?;
+        tx.commit()?;
         Ok(())
     }
 
-    pub fn get_all_documents(&self) -> Result<Vec<IndexedDocument>, rusqlite::Error> {
+    /// A document's status changes, oldest first.
+    pub fn get_status_history(&self, document_id: &str) -> Result<Vec<StatusHistoryEntry>, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, file_path, file_name, file_type, file_size, file_hash, breadcrumb, status, error_message, scanned_at, updated_at, uploaded_at, nexus_doc_id
-             FROM documents ORDER BY file_path"
+            "SELECT id, document_id, from_status, to_status, actor, reason, created_at
+             FROM status_history WHERE document_id = ?1 ORDER BY id",
         )?;
 
-        let docs = stmt.query_map([], |row| {
-            let breadcrumb_json: String = row.get(6)?;
-            let breadcrumb: Vec<String> = serde_json::from_str(&breadcrumb_json).unwrap_or_default();
-            let status_str: String = row.get(7)?;
-            
-            Ok(IndexedDocument {
+        let entries = stmt.query_map([document_id], |row| {
+            let from_status: Option<String> = row.get(2)?;
+            Ok(StatusHistoryEntry {
                 id: row.get(0)?,
-                file_path: row.get(1)?,
-                file_name: row.get(2)?,
-                file_type: row.get(3)?,
-                file_size: row.get(4)?,
-                file_hash: row.get(5)?,
-                breadcrumb,
-                status: DocumentStatus::from_str(&status_str),
-                error_message: row.get(8)?,
-                scanned_at: row.get(9)?,
-                updated_at: row.get(10)?,
-                uploaded_at: row.get(11)?,
-                nexus_doc_id: row.get(12)?,
+                document_id: row.get(1)?,
+                from_status: match from_status {
+                    Some(_) => Some(parse_status_column(row, 2)?),
+                    None => None,
+                },
+                to_status: parse_status_column(row, 3)?,
+                actor: row.get(4)?,
+                reason: row.get(5)?,
+                created_at: row.get(6)?,
             })
         })?;
+        entries.collect()
+    }
+
+    pub fn get_all_documents(&self) -> Result<Vec<IndexedDocument>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM documents ORDER BY file_path",
+            DOCUMENT_COLUMNS
+        ))?;
 
+        let docs = stmt.query_map([], document_from_row)?;
         docs.collect()
     }
 
     pub fn get_documents_by_status(&self, status: DocumentStatus) -> Result<Vec<IndexedDocument>, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, file_path, file_name, file_type, file_size, file_hash, breadcrumb, status, error_message, scanned_at, updated_at, uploaded_at, nexus_doc_id
-             FROM documents WHERE status = ?1 ORDER BY file_path"
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM documents WHERE status = ?1 ORDER BY file_path",
+            DOCUMENT_COLUMNS
+        ))?;
 
-        let docs = stmt.query_map([status.as_str()], |row| {
-            let breadcrumb_json: String = row.get(6)?;
-            let breadcrumb: Vec<String> = serde_json::from_str(&breadcrumb_json).unwrap_or_default();
-            let status_str: String = row.get(7)?;
-            
-            Ok(IndexedDocument {
-                id: row.get(0)?,
-                file_path: row.get(1)?,
-                file_name: row.get(2)?,
-                file_type: row.get(3)?,
-                file_size: row.get(4)?,
-                file_hash: row.get(5)?,
-                breadcrumb,
-                status: DocumentStatus::from_str(&status_str),
-                error_message: row.get(8)?,
-                scanned_at: row.get(9)?,
-                updated_at: row.get(10)?,
-                uploaded_at: row.get(11)?,
-                nexus_doc_id: row.get(12)?,
+        let docs = stmt.query_map([status.as_str()], document_from_row)?;
+        docs.collect()
+    }
+
+    pub fn get_document(&self, id: &str) -> Result<Option<IndexedDocument>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM documents WHERE id = ?1",
+            DOCUMENT_COLUMNS
+        ))?;
+
+        let mut rows = stmt.query_map([id], document_from_row)?;
+        rows.next().transpose()
+    }
+
+    pub fn get_document_by_path(&self, file_path: &str) -> Result<Option<IndexedDocument>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM documents WHERE file_path = ?1",
+            DOCUMENT_COLUMNS
+        ))?;
+
+        let mut rows = stmt.query_map([file_path], document_from_row)?;
+        rows.next().transpose()
+    }
+
+    /// Documents matching an author / photo-date filter, newest capture first.
+    pub fn find_documents_by_metadata(&self, filter: &MetadataFilter) -> Result<Vec<IndexedDocument>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut clauses: Vec<String> = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+
+        if let Some(author) = filter.author.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+            clauses.push("author LIKE ? ESCAPE '\\'".to_string());
+            values.push(format!("%{}%", escape_like(author)));
+        }
+        if let Some(from) = &filter.captured_from {
+            clauses.push("captured_at >= ?".to_string());
+            values.push(from.clone());
+        }
+        if let Some(to) = &filter.captured_to {
+            let (op, bound) = date_upper_bound(to);
+            clauses.push(format!("captured_at {} ?", op));
+            values.push(bound);
+        }
+
+        let where_clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM documents {} ORDER BY captured_at DESC, file_name",
+            DOCUMENT_COLUMNS, where_clause
+        ))?;
+
+        let docs = stmt.query_map(rusqlite::params_from_iter(values.iter()), document_from_row)?;
+        docs.collect()
+    }
+
+    /// Filtered, sorted, keyset-paginated documents with total and facet counts.
+    pub fn query_documents(&self, query: &DocumentQuery) -> Result<DocumentPage, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+        let sort_expr = query.sort.expression();
+        let (order, keyset_op) = if query.descending { ("DESC", "<") } else { ("ASC", ">") };
+
+        let (mut clauses, mut values) = query_filter(query, None);
+
+        if let Some(cursor) = &query.cursor {
+            let cursor = decode_cursor(cursor)
+                .filter(|c| c.sort == query.sort && c.descending == query.descending)
+                .ok_or_else(|| rusqlite::Error::InvalidParameterName("Invalid or stale cursor".to_string()))?;
+            clauses.push(format!(
+                "({expr} {op} ? OR ({expr} = ? AND id {op} ?))",
+                expr = sort_expr,
+                op = keyset_op
+            ));
+            let value = json_to_sql(&cursor.value);
+            values.push(value.clone());
+            values.push(value);
+            values.push(SqlValue::Text(cursor.id));
+        }
+
+        let sql = format!(
+            "SELECT {}, {} AS sort_value FROM documents {} ORDER BY {} {}, id {} LIMIT {}",
+            DOCUMENT_COLUMNS,
+            sort_expr,
+            where_sql(&clauses),
+            sort_expr,
+            order,
+            order,
+            limit + 1
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows: Vec<(IndexedDocument, SqlValue)> = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), |row| {
+                Ok((document_from_row(row)?, row.get(DOCUMENT_COLUMN_COUNT)?))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        let next_cursor = if rows.len() > limit as usize {
+            rows.truncate(limit as usize);
+            rows.last().map(|(doc, value)| {
+                encode_cursor(&QueryCursor {
+                    sort: query.sort,
+                    descending: query.descending,
+                    value: sql_to_json(value),
+                    id: doc.id.clone(),
+                })
             })
-        })?;
+        } else {
+            None
+        };
+
+        let (clauses, values) = query_filter(query, None);
+        let total: u32 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM documents {}", where_sql(&clauses)),
+            rusqlite::params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut facets = DocumentFacets::default();
+        for (facet, column) in [(QueryFacet::Status, "status"), (QueryFacet::FileType, "COALESCE(file_type, '')")] {
+            let (clauses, values) = query_filter(query, Some(facet));
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {col}, COUNT(*) FROM documents {} GROUP BY {col}",
+                where_sql(&clauses),
+                col = column
+            ))?;
+            let counts = stmt.query_map(rusqlite::params_from_iter(values.iter()), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?))
+            })?;
+            let target = match facet {
+                QueryFacet::Status => &mut facets.by_status,
+                QueryFacet::FileType => &mut facets.by_type,
+            };
+            for count in counts {
+                let (key, n) = count?;
+                target.insert(key, n);
+            }
+        }
+
+        Ok(DocumentPage {
+            documents: rows.into_iter().map(|(doc, _)| doc).collect(),
+            next_cursor,
+            total,
+            facets,
+        })
+    }
+
+    /// Every document matching the query's filters, in its sort order.
+    /// Cursor and limit are ignored (used for exports).
+    pub fn find_documents(&self, query: &DocumentQuery) -> Result<Vec<IndexedDocument>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let (clauses, values) = query_filter(query, None);
+        let order = if query.descending { "DESC" } else { "ASC" };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM documents {} ORDER BY {} {}, id {}",
+            DOCUMENT_COLUMNS,
+            where_sql(&clauses),
+            query.sort.expression(),
+            order,
+            order
+        ))?;
 
+        let docs = stmt.query_map(rusqlite::params_from_iter(values.iter()), document_from_row)?;
         docs.collect()
     }
 
+    /// Merge documents from an exported index, matched by file path. Existing
+    /// documents take the exported status, category and Nexus link; new ones
+    /// are inserted whole. Runs in one transaction and is recorded in the
+    /// status history as `actor`.
+    ///
+    /// Restoring exported state bypasses the transition rules, with one
+    /// exception: CONVERTED only makes sense with cached HTML on this machine,
+    /// so without it the document comes in as IMPORT.
+    pub fn import_documents(
+        &self,
+        docs: &[IndexedDocument],
+        actor: &str,
+        reason: &str,
+    ) -> Result<ImportCounts, rusqlite::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut counts = ImportCounts::default();
+
+        for doc in docs {
+            let existing: Option<(String, String)> = tx
+                .query_row(
+                    "SELECT id, status FROM documents WHERE file_path = ?1",
+                    [&doc.file_path],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map(Some)
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    e => Err(e),
+                })?;
+
+            let id = match &existing {
+                Some((id, _)) => id.clone(),
+                None => {
+                    let id_taken: bool = tx.query_row(
+                        "SELECT EXISTS (SELECT 1 FROM documents WHERE id = ?1)",
+                        [&doc.id],
+                        |row| row.get(0),
+                    )?;
+                    if id_taken { uuid::Uuid::new_v4().to_string() } else { doc.id.clone() }
+                }
+            };
+
+            let has_cache: bool = tx.query_row(
+                "SELECT EXISTS (SELECT 1 FROM conversion_cache WHERE document_id = ?1)",
+                [&id],
+                |row| row.get(0),
+            )?;
+            let status = if doc.status == DocumentStatus::Converted && !has_cache {
+                DocumentStatus::Import
+            } else {
+                doc.status.clone()
+            };
+
+            match existing {
+                Some((_, previous)) => {
+                    tx.execute(
+                        "UPDATE documents SET status = ?1, error_message = ?2, category = ?3, nexus_doc_id = ?4,
+                            uploaded_at = ?5, updated_at = ?6
+                         WHERE id = ?7",
+                        params![status.as_str(), doc.error_message, doc.category, doc.nexus_doc_id, doc.uploaded_at, now, id],
+                    )?;
+                    let from = DocumentStatus::parse(&previous).ok();
+                    record_transition(&tx, &id, from.as_ref(), &status, actor, Some(reason), &now)?;
+                    counts.updated += 1;
+                }
+                None => {
+                    let breadcrumb_json = serde_json::to_string(&doc.breadcrumb).unwrap_or_default();
+                    let meta = &doc.metadata;
+                    tx.execute(
+                        &format!(
+                            "INSERT INTO documents ({})
+                             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27)",
+                            DOCUMENT_COLUMNS
+                        ),
+                        params![
+                            id,
+                            doc.file_path,
+                            doc.file_name,
+                            doc.file_type,
+                            doc.file_size,
+                            doc.file_hash,
+                            breadcrumb_json,
+                            status.as_str(),
+                            doc.error_message,
+                            doc.scanned_at,
+                            now,
+                            doc.uploaded_at,
+                            doc.nexus_doc_id,
+                            meta.title,
+                            meta.author,
+                            meta.subject,
+                            meta.keywords,
+                            meta.created_at,
+                            meta.modified_at,
+                            meta.page_count,
+                            meta.language,
+                            meta.captured_at,
+                            meta.camera_make,
+                            meta.camera_model,
+                            meta.gps_latitude,
+                            meta.gps_longitude,
+                            doc.category,
+                        ],
+                    )?;
+                    record_transition(&tx, &id, None, &status, actor, Some(reason), &now)?;
+                    counts.inserted += 1;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(counts)
+    }
+
     pub fn get_stats(&self) -> Result<DocumentStats, rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         
@@ -245,29 +1132,676 @@ impl DocumentIndex {
         Ok(())
     }
 
-    pub fn ignore_documents_in_folder(&self, folder_path: &str) -> Result<u32, rusqlite::Error> {
+    /// Set every document under `folder_path` to IGNORE (where the state
+    /// machine allows it), logged as an undoable bulk operation.
+    pub fn ignore_documents_in_folder(&self, folder_path: &str, actor: &str) -> Result<BulkOperation, rusqlite::Error> {
+        self.set_folder_status(folder_path, DocumentStatus::Ignore, "ignore_folder", &["IGNORE"], actor)
+    }
+
+    /// Set every document under `folder_path` to IMPORT, leaving IGNORE
+    /// alone, logged as an undoable bulk operation.
+    pub fn import_documents_in_folder(&self, folder_path: &str, actor: &str) -> Result<BulkOperation, rusqlite::Error> {
+        self.set_folder_status(folder_path, DocumentStatus::Import, "import_folder", &["IGNORE", "IMPORT"], actor)
+    }
+
+    fn set_folder_status(
+        &self,
+        folder_path: &str,
+        status: DocumentStatus,
+        kind: &str,
+        skip_statuses: &[&str],
+        actor: &str,
+    ) -> Result<BulkOperation, rusqlite::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        // Match on path-component boundaries ("/Jobs/Smith" must not touch
+        // "/Jobs/Smithfield"); range bounds also keep % and _ literal
+        let (low, high) = path_prefix_range(folder_path);
+        let skip = skip_statuses.iter().map(|s| format!("'{}'", s)).collect::<Vec<_>>().join(", ");
+        let targets = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT id, status, updated_at FROM documents
+                 WHERE file_path >= ?1 AND file_path < ?2 AND status NOT IN ({})",
+                skip
+            ))?;
+            let rows = stmt.query_map(params![low, high], bulk_target_from_row)?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let operation = apply_bulk_status(&tx, kind, folder_path, status, &targets, actor)?;
+        tx.commit()?;
+        Ok(operation)
+    }
+
+    /// Set the status of the given documents in one transaction, logged as an
+    /// undoable bulk operation. Unknown ids are skipped; documents the state
+    /// machine won't move are reported in `rejected_ids`.
+    pub fn bulk_set_status(&self, ids: &[String], status: DocumentStatus, actor: &str) -> Result<BulkOperation, rusqlite::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut targets = Vec::with_capacity(ids.len());
+        {
+            let mut stmt = tx.prepare("SELECT id, status, updated_at FROM documents WHERE id = ?1")?;
+            for id in ids {
+                if let Some(target) = stmt.query_map([id], bulk_target_from_row)?.next().transpose()? {
+                    targets.push(target);
+                }
+            }
+        }
+
+        let target = format!("{} documents", targets.len());
+        let operation = apply_bulk_status(&tx, "bulk_status", &target, status, &targets, actor)?;
+        tx.commit()?;
+        Ok(operation)
+    }
+
+    /// Revert the most recent bulk operation that hasn't been undone yet.
+    /// Restored documents get back their previous status and `updated_at`.
+    /// Documents whose status changed again since then are left alone, and a
+    /// CONVERTED document whose cached HTML is gone comes back as IMPORT.
+    /// Restoring a previous status bypasses the transition rules (it's where
+    /// the document legitimately was), but is still recorded in the history.
+    pub fn undo_last_bulk_operation(&self, actor: &str) -> Result<Option<UndoResult>, rusqlite::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let operation: Option<(i64, String, String, String)> = tx
+            .query_row(
+                "SELECT id, kind, target, new_status FROM bulk_operations
+                 WHERE undone_at IS NULL ORDER BY id DESC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+        let (operation_id, kind, target, new_status) = match operation {
+            Some(op) => op,
+            None => return Ok(None),
+        };
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let items = {
+            let mut stmt = tx.prepare(
+                "SELECT document_id, previous_status, previous_updated_at FROM bulk_operation_items WHERE operation_id = ?1",
+            )?;
+            let rows = stmt.query_map([operation_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let from = DocumentStatus::parse(&new_status).ok();
+        let reason = format!("Undo of {} #{}", kind, operation_id);
+        let mut restored_ids = Vec::new();
+        let mut skipped = 0u32;
+        for (document_id, previous_status, previous_updated_at) in items {
+            let changed = tx.execute(
+                "UPDATE documents SET
+                    status = CASE
+                        WHEN ?1 = 'CONVERTED' AND NOT EXISTS (
+                            SELECT 1 FROM conversion_cache WHERE document_id = documents.id
+                        ) THEN 'IMPORT'
+                        ELSE ?1
+                    END,
+                    updated_at = COALESCE(?2, ?3)
+                 WHERE id = ?4 AND status = ?5",
+                params![previous_status, previous_updated_at, now, document_id, new_status],
+            )?;
+            if changed > 0 {
+                let restored: String = tx.query_row(
+                    "SELECT status FROM documents WHERE id = ?1",
+                    [&document_id],
+                    |row| row.get(0),
+                )?;
+                if let Ok(to) = DocumentStatus::parse(&restored) {
+                    record_transition(&tx, &document_id, from.as_ref(), &to, actor, Some(&reason), &now)?;
+                }
+                restored_ids.push(document_id);
+            } else {
+                skipped += 1;
+            }
+        }
+
+        tx.execute(
+            "UPDATE bulk_operations SET undone_at = ?1 WHERE id = ?2",
+            params![now, operation_id],
+        )?;
+        tx.commit()?;
+
+        Ok(Some(UndoResult {
+            operation_id,
+            kind,
+            target,
+            restored_ids,
+            skipped,
+        }))
+    }
+
+    pub fn set_category(&self, id: &str, category: Option<&str>) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        conn.execute(
+            "UPDATE documents SET category = ?1, updated_at = ?2 WHERE id = ?3",
+            params![category, now, id],
+        )?;
+        Ok(())
+    }
+
+    // ============ Category Rules ============
+
+    /// All rules in evaluation order: priority descending, then oldest first.
+    pub fn list_category_rules(&self) -> Result<Vec<CategoryRule>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, category, field, keywords, priority, enabled
+             FROM category_rules ORDER BY priority DESC, id ASC",
+        )?;
+
+        let rules = stmt.query_map([], |row| {
+            let field: String = row.get(3)?;
+            let keywords: String = row.get(4)?;
+            Ok(CategoryRule {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                category: row.get(2)?,
+                field: RuleField::parse(&field).unwrap_or(RuleField::FileName),
+                keywords: serde_json::from_str(&keywords).unwrap_or_default(),
+                priority: row.get(5)?,
+                enabled: row.get(6)?,
+            })
+        })?;
+        rules.collect()
+    }
+
+    /// Insert a rule (id 0) or update an existing one. Returns the rule id.
+    pub fn save_category_rule(&self, rule: &CategoryRule) -> Result<i64, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        if rule.id == 0 {
+            return insert_category_rule(&conn, rule);
+        }
+
+        let keywords = serde_json::to_string(&rule.keywords).unwrap_or_default();
+        let changed = conn.execute(
+            "UPDATE category_rules SET name = ?1, category = ?2, field = ?3, keywords = ?4, priority = ?5, enabled = ?6
+             WHERE id = ?7",
+            params![rule.name, rule.category, rule.field.as_str(), keywords, rule.priority, rule.enabled, rule.id],
+        )?;
+        if changed == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(rule.id)
+    }
+
+    pub fn delete_category_rule(&self, id: i64) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM category_rules WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// Remember a scanned folder, or bump its scan time.
+    pub fn record_scan_root(&self, path: &str) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO scan_roots (path, last_scanned_at) VALUES (?1, ?2)
+             ON CONFLICT(path) DO UPDATE SET last_scanned_at = excluded.last_scanned_at",
+            params![path, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_scan_roots(&self) -> Result<Vec<String>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path FROM scan_roots ORDER BY path")?;
+        let roots = stmt.query_map([], |row| row.get(0))?;
+        roots.collect()
+    }
+
+    // ============ Conversion Cache Metadata ============
+
+    pub fn record_cache_entry(&self, entry: &CacheEntry) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO conversion_cache (document_id, source_hash, converter_version, size_bytes, blob_key, stored_bytes, cached_at, last_accessed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(document_id) DO UPDATE SET
+                source_hash = excluded.source_hash,
+                converter_version = excluded.converter_version,
+                size_bytes = excluded.size_bytes,
+                blob_key = excluded.blob_key,
+                stored_bytes = excluded.stored_bytes,
+                cached_at = excluded.cached_at,
+                last_accessed_at = excluded.last_accessed_at",
+            params![
+                entry.document_id,
+                entry.source_hash,
+                entry.converter_version,
+                entry.size_bytes,
+                entry.blob_key,
+                entry.stored_bytes,
+                entry.cached_at,
+                entry.last_accessed_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_cache_entry(&self, document_id: &str) -> Result<Option<CacheEntry>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT document_id, source_hash, converter_version, size_bytes, blob_key, stored_bytes, cached_at, last_accessed_at
+             FROM conversion_cache WHERE document_id = ?1"
+        )?;
+
+        let mut rows = stmt.query_map([document_id], cache_entry_from_row)?;
+        rows.next().transpose()
+    }
+
+    pub fn touch_cache_entry(&self, document_id: &str) -> Result<(), rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         let now = chrono::Utc::now().to_rfc3339();
-        // Use LIKE to match all files in folder and subfolders
-        let pattern = format!("{}%", folder_path);
-        let updated = conn.execute(
-            "UPDATE documents SET status = 'IGNORE', updated_at = ?1 WHERE file_path LIKE ?2 AND status != 'UPLOADED'",
-            params![now, pattern],
+        conn.execute(
+            "UPDATE conversion_cache SET last_accessed_at = ?1 WHERE document_id = ?2",
+            params![now, document_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_cache_entry(&self, document_id: &str) -> Result<(), rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM conversion_cache WHERE document_id = ?1", [document_id])?;
+        Ok(())
+    }
+
+    /// All cache entries, least recently used first.
+    pub fn list_cache_entries(&self) -> Result<Vec<CacheEntry>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT document_id, source_hash, converter_version, size_bytes, blob_key, stored_bytes, cached_at, last_accessed_at
+             FROM conversion_cache ORDER BY last_accessed_at ASC"
+        )?;
+
+        let entries = stmt.query_map([], cache_entry_from_row)?;
+        entries.collect()
+    }
+
+    /// Cache entries that no longer match their document: the document was
+    /// deleted or ignored, its source hash changed on rescan, or it was
+    /// produced by an older converter.
+    pub fn get_stale_cache_entries(&self, converter_version: u32) -> Result<Vec<CacheEntry>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT c.document_id, c.source_hash, c.converter_version, c.size_bytes, c.blob_key, c.stored_bytes, c.cached_at, c.last_accessed_at
+             FROM conversion_cache c
+             LEFT JOIN documents d ON d.id = c.document_id
+             WHERE d.id IS NULL
+                OR d.status = 'IGNORE'
+                OR c.converter_version != ?1
+                OR (d.file_hash IS NOT NULL AND c.source_hash IS NOT NULL AND d.file_hash != c.source_hash)"
+        )?;
+
+        let entries = stmt.query_map([converter_version], cache_entry_from_row)?;
+        entries.collect()
+    }
+
+    pub fn get_cache_entries_for_blob(&self, blob_key: &str) -> Result<Vec<CacheEntry>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT document_id, source_hash, converter_version, size_bytes, blob_key, stored_bytes, cached_at, last_accessed_at
+             FROM conversion_cache WHERE blob_key = ?1"
+        )?;
+
+        let entries = stmt.query_map([blob_key], cache_entry_from_row)?;
+        entries.collect()
+    }
+
+    /// Distinct blobs, least recently read first (a blob is as recent as the
+    /// most recent read of any document sharing it).
+    pub fn list_cache_blobs(&self) -> Result<Vec<CacheBlob>, rusqlite::Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT blob_key, MAX(stored_bytes), COUNT(*), MAX(last_accessed_at) AS last_access
+             FROM conversion_cache GROUP BY blob_key ORDER BY last_access ASC"
         )?;
-        Ok(updated as u32)
+
+        let blobs = stmt.query_map([], |row| {
+            Ok(CacheBlob {
+                blob_key: row.get(0)?,
+                stored_bytes: row.get(1)?,
+                ref_count: row.get(2)?,
+                last_accessed_at: row.get(3)?,
+            })
+        })?;
+        blobs.collect()
     }
 
-    pub fn import_documents_in_folder(&self, folder_path: &str) -> Result<u32, rusqlite::Error> {
+    /// Documents whose cached HTML is gone must be converted again before upload.
+    pub fn reset_converted_to_import(&self, document_id: &str) -> Result<(), rusqlite::Error> {
         let conn = self.conn.lock().unwrap();
         let now = chrono::Utc::now().to_rfc3339();
-        // Use LIKE to match all files in folder and subfolders
-        let pattern = format!("{}%", folder_path);
-        let updated = conn.execute(
-            "UPDATE documents SET status = 'IMPORT', updated_at = ?1 WHERE file_path LIKE ?2 AND status NOT IN ('UPLOADED', 'IGNORE')",
-            params![now, pattern],
+        let changed = conn.execute(
+            "UPDATE documents SET status = 'IMPORT', updated_at = ?1 WHERE id = ?2 AND status = 'CONVERTED'",
+            params![now, document_id],
         )?;
-        Ok(updated as u32)
+        if changed > 0 {
+            record_transition(
+                &conn,
+                document_id,
+                Some(&DocumentStatus::Converted),
+                &DocumentStatus::Import,
+                "cache",
+                Some("Cached conversion missing or stale"),
+                &now,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn cache_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<CacheEntry> {
+    Ok(CacheEntry {
+        document_id: row.get(0)?,
+        source_hash: row.get(1)?,
+        converter_version: row.get(2)?,
+        size_bytes: row.get(3)?,
+        blob_key: row.get(4)?,
+        stored_bytes: row.get(5)?,
+        cached_at: row.get(6)?,
+        last_accessed_at: row.get(7)?,
+    })
+}
+
+/// Insert a new rule and return its id. `rule.id` is ignored.
+fn insert_category_rule(conn: &Connection, rule: &CategoryRule) -> Result<i64, rusqlite::Error> {
+    let keywords = serde_json::to_string(&rule.keywords).unwrap_or_default();
+    conn.execute(
+        "INSERT INTO category_rules (name, category, field, keywords, priority, enabled, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            rule.name,
+            rule.category,
+            rule.field.as_str(),
+            keywords,
+            rule.priority,
+            rule.enabled,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// A document about to be changed by a bulk operation: id, status, updated_at.
+type BulkTarget = (String, String, Option<String>);
+
+fn bulk_target_from_row(row: &rusqlite::Row) -> rusqlite::Result<BulkTarget> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+}
+
+/// Update `targets` to `status` and record their previous statuses so the
+/// change can be undone. Empty operations aren't logged.
+fn apply_bulk_status(
+    tx: &rusqlite::Transaction,
+    kind: &str,
+    target: &str,
+    status: DocumentStatus,
+    targets: &[BulkTarget],
+    actor: &str,
+) -> Result<BulkOperation, rusqlite::Error> {
+    // Leave out documents already there and ones the state machine won't move
+    let mut rejected_ids = Vec::new();
+    let targets: Vec<&BulkTarget> = targets
+        .iter()
+        .filter(|(id, from, _)| match DocumentStatus::parse(from) {
+            Ok(from) if from == status => false,
+            Ok(from) if from.can_transition_to(&status) => true,
+            _ => {
+                rejected_ids.push(id.clone());
+                false
+            }
+        })
+        .collect();
+
+    if targets.is_empty() {
+        return Ok(BulkOperation {
+            operation_id: None,
+            kind: kind.to_string(),
+            target: target.to_string(),
+            count: 0,
+            affected_ids: Vec::new(),
+            rejected_ids,
+        });
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    tx.execute(
+        "INSERT INTO bulk_operations (kind, target, new_status, document_count, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![kind, target, status.as_str(), targets.len() as i64, now],
+    )?;
+    let operation_id = tx.last_insert_rowid();
+
+    let mut update = tx.prepare("UPDATE documents SET status = ?1, updated_at = ?2 WHERE id = ?3")?;
+    let mut log = tx.prepare(
+        "INSERT OR REPLACE INTO bulk_operation_items (operation_id, document_id, previous_status, previous_updated_at)
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    let reason = format!("{}: {}", kind, target);
+    for (id, previous_status, previous_updated_at) in &targets {
+        update.execute(params![status.as_str(), now, id])?;
+        log.execute(params![operation_id, id, previous_status, previous_updated_at])?;
+        let from = DocumentStatus::parse(previous_status).ok();
+        record_transition(tx, id, from.as_ref(), &status, actor, Some(&reason), &now)?;
+    }
+
+    Ok(BulkOperation {
+        operation_id: Some(operation_id),
+        kind: kind.to_string(),
+        target: target.to_string(),
+        count: targets.len() as u32,
+        affected_ids: targets.iter().map(|(id, _, _)| id.clone()).collect(),
+        rejected_ids,
+    })
+}
+
+fn current_status(conn: &Connection, id: &str) -> Result<DocumentStatus, StatusError> {
+    let status: Option<String> = conn
+        .query_row("SELECT status FROM documents WHERE id = ?1", [id], |row| row.get(0))
+        .map(Some)
+        .or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(None),
+            e => Err(e),
+        })?;
+    match status {
+        Some(s) => DocumentStatus::parse(&s).map_err(|e| {
+            StatusError::Db(rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into()))
+        }),
+        None => Err(StatusError::NotFound(id.to_string())),
+    }
+}
+
+fn check_transition(id: &str, from: &DocumentStatus, to: &DocumentStatus) -> Result<(), StatusError> {
+    if from.can_transition_to(to) {
+        Ok(())
+    } else {
+        Err(StatusError::InvalidTransition {
+            id: id.to_string(),
+            from: from.clone(),
+            to: to.clone(),
+        })
+    }
+}
+
+/// Append to `status_history`. Staying in the same status isn't a transition
+/// and isn't recorded.
+fn record_transition(
+    conn: &Connection,
+    document_id: &str,
+    from: Option<&DocumentStatus>,
+    to: &DocumentStatus,
+    actor: &str,
+    reason: Option<&str>,
+    at: &str,
+) -> Result<(), rusqlite::Error> {
+    if from == Some(to) {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO status_history (document_id, from_status, to_status, actor, reason, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![document_id, from.map(|f| f.as_str()), to.as_str(), actor, reason, at],
+    )?;
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum QueryFacet {
+    Status,
+    FileType,
+}
+
+/// WHERE clauses and their `?` values for a document query. `skip` leaves out
+/// one facet's own filter when counting that facet.
+fn query_filter(query: &DocumentQuery, skip: Option<QueryFacet>) -> (Vec<String>, Vec<SqlValue>) {
+    let mut clauses = Vec::new();
+    let mut values = Vec::new();
+    let placeholders = |n: usize| vec!["?"; n].join(", ");
+
+    if !query.statuses.is_empty() && skip != Some(QueryFacet::Status) {
+        clauses.push(format!("status IN ({})", placeholders(query.statuses.len())));
+        values.extend(query.statuses.iter().map(|s| SqlValue::Text(s.as_str().to_string())));
+    }
+    if !query.file_types.is_empty() && skip != Some(QueryFacet::FileType) {
+        clauses.push(format!("file_type IN ({})", placeholders(query.file_types.len())));
+        values.extend(
+            query
+                .file_types
+                .iter()
+                .map(|t| SqlValue::Text(t.trim_start_matches('.').to_lowercase())),
+        );
+    }
+    if let Some(prefix) = query.path_prefix.as_deref().filter(|p| !p.trim().is_empty()) {
+        let (low, high) = path_prefix_range(prefix);
+        clauses.push("file_path >= ? AND file_path < ?".to_string());
+        values.push(SqlValue::Text(low));
+        values.push(SqlValue::Text(high));
+    }
+    if let Some(segment) = query.breadcrumb_segment.as_deref().filter(|s| !s.is_empty()) {
+        clauses.push("EXISTS (SELECT 1 FROM json_each(documents.breadcrumb) WHERE json_each.value = ?)".to_string());
+        values.push(SqlValue::Text(segment.to_string()));
+    }
+    if let Some(min) = query.min_size {
+        clauses.push("file_size >= ?".to_string());
+        values.push(SqlValue::Integer(min));
+    }
+    if let Some(max) = query.max_size {
+        clauses.push("file_size <= ?".to_string());
+        values.push(SqlValue::Integer(max));
+    }
+    let date_column = query.date_field.column();
+    if let Some(from) = &query.date_from {
+        clauses.push(format!("{} >= ?", date_column));
+        values.push(SqlValue::Text(from.clone()));
+    }
+    if let Some(to) = &query.date_to {
+        let (op, bound) = date_upper_bound(to);
+        clauses.push(format!("{} {} ?", date_column, op));
+        values.push(SqlValue::Text(bound));
+    }
+    if let Some(name) = query.name_contains.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        clauses.push("file_name LIKE ? ESCAPE '\\'".to_string());
+        values.push(SqlValue::Text(format!("%{}%", escape_like(name))));
+    }
+
+    (clauses, values)
+}
+
+fn where_sql(clauses: &[String]) -> String {
+    if clauses.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", clauses.join(" AND "))
+    }
+}
+
+/// `[low, high)` bounds selecting every path strictly inside `folder`, on
+/// path-component boundaries: "/Jobs/Smith" covers "/Jobs/Smith/a.pdf" but
+/// not "/Jobs/Smithfield/a.pdf". Range bounds (rather than LIKE) need no
+/// escaping and can use the file_path index.
+fn path_prefix_range(folder: &str) -> (String, String) {
+    let separator = if folder.contains('\\') && !folder.contains('/') { '\\' } else { '/' };
+    let trimmed = folder.trim_end_matches(['/', '\\']);
+    let low = format!("{}{}", trimmed, separator);
+    // The character right after the separator sorts after every child path
+    let high = format!("{}{}", trimmed, char::from_u32(separator as u32 + 1).unwrap_or(separator));
+    (low, high)
+}
+
+fn encode_cursor(cursor: &QueryCursor) -> String {
+    CURSOR_ENCODING.encode(serde_json::to_vec(cursor).unwrap_or_default())
+}
+
+fn decode_cursor(cursor: &str) -> Option<QueryCursor> {
+    let bytes = CURSOR_ENCODING.decode(cursor).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn sql_to_json(value: &SqlValue) -> serde_json::Value {
+    match value {
+        SqlValue::Integer(i) => serde_json::Value::from(*i),
+        SqlValue::Real(f) => serde_json::Value::from(*f),
+        SqlValue::Text(s) => serde_json::Value::from(s.clone()),
+        SqlValue::Null | SqlValue::Blob(_) => serde_json::Value::Null,
+    }
+}
+
+fn json_to_sql(value: &serde_json::Value) -> SqlValue {
+    match value {
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(SqlValue::Integer)
+            .unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or(0.0))),
+        serde_json::Value::String(s) => SqlValue::Text(s.clone()),
+        _ => SqlValue::Null,
+    }
+}
+
+/// Upper bound for an inclusive date filter: a bare "YYYY-MM-DD" means
+/// "through the end of that day" (`< next day`), anything else is `<= to`.
+fn date_upper_bound(to: &str) -> (&'static str, String) {
+    match chrono::NaiveDate::parse_from_str(to, "%Y-%m-%d") {
+        Ok(day) => ("<", (day + chrono::Duration::days(1)).format("%Y-%m-%d").to_string()),
+        Err(_) => ("<=", to.to_string()),
+    }
+}
+
+/// Escape `%`, `_` and backslash for a `LIKE ... ESCAPE '\'` pattern.
+fn escape_like(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Add a column to an existing table if an older schema lacks it.
+pub(crate) fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|c| c.ok())
+        .any(|c| c == column);
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
     }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
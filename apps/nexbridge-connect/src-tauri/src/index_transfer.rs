@@ -0,0 +1,479 @@
+// ---------------------------------------------------------------------------
+// index_transfer — export/import of the document index
+// ---------------------------------------------------------------------------
+// Exports documents.db (or a filtered subset) for reporting and for moving a
+// PM's decisions to a new machine:
+//
+//   - CSV and XLSX: one row per document, a fixed set of columns (see
+//     EXPORT_COLUMNS), breadcrumb joined with " > "
+//   - JSON Lines: one full IndexedDocument per line, including all metadata
+//   - JSON: a single array, as the CLI has always written
+//
+// Import reads any of these back (XLSX re-saved by Excel included) and
+// re-keys each path through a prefix mapping such as
+// "/Volumes/Share" → "Z:\Share", so statuses survive a mount or drive-letter
+// change. Documents are matched to the local index by their (mapped) path.
+// ---------------------------------------------------------------------------
+
+use crate::index::{DocumentIndex, DocumentStatus, IndexedDocument};
+use crate::metadata::DocumentMetadata;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Xlsx,
+    Json,
+}
+
+impl ExportFormat {
+    /// Guess the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" | "ndjson" => Some(ExportFormat::Jsonl),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" | "ndjson" => Ok(ExportFormat::Jsonl),
+            "xlsx" => Ok(ExportFormat::Xlsx),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(format!("Unknown export format: {}", s)),
+        }
+    }
+}
+
+/// Rewrites paths starting with `from` to start with `to` instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathMapping {
+    pub from: String,
+    pub to: String,
+}
+
+impl PathMapping {
+    /// Parse "FROM=TO" (CLI form).
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (from, to) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Path mapping must look like FROM=TO: {}", s))?;
+        if from.is_empty() {
+            return Err(format!("Path mapping has an empty source prefix: {}", s));
+        }
+        Ok(PathMapping {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportResult {
+    pub path: String,
+    pub format: ExportFormat,
+    pub documents: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportResult {
+    /// Rows read from the file
+    pub read: u32,
+    pub inserted: u32,
+    pub updated: u32,
+    /// Rows whose path matched a mapping
+    pub remapped: u32,
+    /// Rows that couldn't be read, with the reason
+    pub errors: Vec<String>,
+}
+
+/// Columns written to CSV/XLSX, in order. Import matches them by header
+/// name, so reordered or extra columns are fine.
+const EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "file_path",
+    "file_name",
+    "file_type",
+    "file_size",
+    "file_hash",
+    "breadcrumb",
+    "status",
+    "error_message",
+    "category",
+    "nexus_doc_id",
+    "uploaded_at",
+    "scanned_at",
+    "updated_at",
+    "title",
+    "author",
+    "captured_at",
+];
+
+const BREADCRUMB_SEPARATOR: &str = " > ";
+
+// ============ Export ============
+
+pub fn export_documents(docs: &[IndexedDocument], path: &Path, format: ExportFormat) -> Result<ExportResult, String> {
+    let written = match format {
+        ExportFormat::Csv => write_csv(docs, path),
+        ExportFormat::Jsonl => write_jsonl(docs, path),
+        ExportFormat::Xlsx => write_xlsx(docs, path),
+        ExportFormat::Json => serde_json::to_string_pretty(docs)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string())),
+    };
+    written.map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(ExportResult {
+        path: path.to_string_lossy().to_string(),
+        format,
+        documents: docs.len(),
+    })
+}
+
+fn export_row(doc: &IndexedDocument) -> Vec<String> {
+    let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+    vec![
+        doc.id.clone(),
+        doc.file_path.clone(),
+        doc.file_name.clone(),
+        opt(&doc.file_type),
+        doc.file_size.to_string(),
+        opt(&doc.file_hash),
+        doc.breadcrumb.join(BREADCRUMB_SEPARATOR),
+        doc.status.as_str().to_string(),
+        opt(&doc.error_message),
+        opt(&doc.category),
+        opt(&doc.nexus_doc_id),
+        opt(&doc.uploaded_at),
+        doc.scanned_at.clone(),
+        doc.updated_at.clone(),
+        opt(&doc.metadata.title),
+        opt(&doc.metadata.author),
+        opt(&doc.metadata.captured_at),
+    ]
+}
+
+fn write_csv(docs: &[IndexedDocument], path: &Path) -> Result<(), String> {
+    let mut writer = csv::Writer::from_path(path).map_err(|e| e.to_string())?;
+    writer.write_record(EXPORT_COLUMNS).map_err(|e| e.to_string())?;
+    for doc in docs {
+        writer.write_record(export_row(doc)).map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+fn write_jsonl(docs: &[IndexedDocument], path: &Path) -> Result<(), String> {
+    let mut out = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+    for doc in docs {
+        serde_json::to_writer(&mut out, doc).map_err(|e| e.to_string())?;
+        out.write_all(b"\n").map_err(|e| e.to_string())?;
+    }
+    out.flush().map_err(|e| e.to_string())
+}
+
+/// Minimal single-sheet workbook with inline strings; file_size is written as
+/// a number so it sorts and sums in Excel.
+fn write_xlsx(docs: &[IndexedDocument], path: &Path) -> Result<(), String> {
+    let size_column = EXPORT_COLUMNS.iter().position(|c| *c == "file_size");
+    let mut sheet = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\
+         <sheetViews><sheetView workbookViewId=\"0\"><pane ySplit=\"1\" topLeftCell=\"A2\" activePane=\"bottomLeft\" state=\"frozen\"/></sheetView></sheetViews>\
+         <sheetData>",
+    );
+
+    let header: Vec<String> = EXPORT_COLUMNS.iter().map(|c| c.to_string()).collect();
+    let rows = std::iter::once(header).chain(docs.iter().map(export_row));
+    for (r, row) in rows.enumerate() {
+        sheet.push_str(&format!("<row r=\"{}\">", r + 1));
+        for (c, value) in row.iter().enumerate() {
+            let cell_ref = format!("{}{}", column_letters(c), r + 1);
+            if r > 0 && Some(c) == size_column {
+                sheet.push_str(&format!("<c r=\"{}\"><v>{}</v></c>", cell_ref, value));
+            } else if !value.is_empty() {
+                sheet.push_str(&format!(
+                    "<c r=\"{}\" t=\"inlineStr\"><is><t xml:space=\"preserve\">{}</t></is></c>",
+                    cell_ref,
+                    xml_escape(value)
+                ));
+            }
+        }
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+
+    let parts: [(&str, &str); 4] = [
+        (
+            "[Content_Types].xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/xl/workbook.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml\"/>\
+             <Override PartName=\"/xl/worksheets/sheet1.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml\"/>\
+             </Types>",
+        ),
+        (
+            "_rels/.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"xl/workbook.xml\"/>\
+             </Relationships>",
+        ),
+        (
+            "xl/workbook.xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" \
+             xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
+             <sheets><sheet name=\"Documents\" sheetId=\"1\" r:id=\"rId1\"/></sheets></workbook>",
+        ),
+        (
+            "xl/_rels/workbook.xml.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet\" Target=\"worksheets/sheet1.xml\"/>\
+             </Relationships>",
+        ),
+    ];
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for (name, content) in parts.iter().copied().chain(std::iter::once(("xl/worksheets/sheet1.xml", sheet.as_str()))) {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(content.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 0 → A, 25 → Z, 26 → AA, ...
+fn column_letters(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push((b'A' + (index % 26) as u8) as char);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Escape for XML text, dropping control characters XML 1.0 can't carry.
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ============ Import ============
+
+/// Read an exported file, remap its paths and merge it into the index.
+pub fn import_file(
+    index: &DocumentIndex,
+    path: &Path,
+    format: Option<ExportFormat>,
+    mappings: &[PathMapping],
+) -> Result<ImportResult, String> {
+    let format = format
+        .or_else(|| ExportFormat::from_path(path))
+        .ok_or_else(|| format!("Can't tell the format of {}; pass one explicitly", path.display()))?;
+
+    let mut result = ImportResult::default();
+    let rows = match format {
+        ExportFormat::Csv => read_csv(path)?,
+        ExportFormat::Xlsx => read_xlsx(path)?,
+        ExportFormat::Jsonl => read_jsonl(path)?,
+        ExportFormat::Json => {
+            let raw = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let docs: Vec<IndexedDocument> = serde_json::from_str(&raw).map_err(|e| format!("Invalid JSON export: {}", e))?;
+            docs.into_iter().map(Ok).collect()
+        }
+    };
+
+    // Rows are numbered from 1, after the header row in CSV/XLSX
+    let first_row = match format {
+        ExportFormat::Csv | ExportFormat::Xlsx => 2,
+        ExportFormat::Jsonl | ExportFormat::Json => 1,
+    };
+    let mut docs = Vec::with_capacity(rows.len());
+    for (n, row) in rows.into_iter().enumerate() {
+        result.read += 1;
+        match row {
+            Ok(mut doc) => {
+                if let Some(mapped) = remap_path(&doc.file_path, mappings) {
+                    doc.file_path = mapped;
+                    result.remapped += 1;
+                }
+                docs.push(doc);
+            }
+            Err(e) => result.errors.push(format!("Row {}: {}", n + first_row, e)),
+        }
+    }
+
+    let reason = format!("Imported from {}", path.display());
+    let counts = index
+        .import_documents(&docs, "import", &reason)
+        .map_err(|e| format!("Import failed: {}", e))?;
+    result.inserted = counts.inserted;
+    result.updated = counts.updated;
+    Ok(result)
+}
+
+/// Apply the longest matching prefix mapping, on path-component boundaries.
+/// The rest of the path takes the separator style of the target prefix.
+pub fn remap_path(path: &str, mappings: &[PathMapping]) -> Option<String> {
+    let mapping = mappings
+        .iter()
+        .filter(|m| {
+            let from = m.from.trim_end_matches(['/', '\\']);
+            path == from || path.strip_prefix(from).is_some_and(|rest| rest.starts_with(['/', '\\']))
+        })
+        .max_by_key(|m| m.from.trim_end_matches(['/', '\\']).len())?;
+
+    let from = mapping.from.trim_end_matches(['/', '\\']);
+    let to = mapping.to.trim_end_matches(['/', '\\']);
+    let rest = &path[from.len()..];
+    let windows_target = to.contains('\\') || (to.len() == 2 && to.ends_with(':'));
+    let rest = if windows_target { rest.replace('/', "\\") } else { rest.replace('\\', "/") };
+    Some(format!("{}{}", to, rest))
+}
+
+fn read_jsonl(path: &Path) -> Result<Vec<Result<IndexedDocument, String>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut rows = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        rows.push(serde_json::from_str::<IndexedDocument>(&line).map_err(|e| e.to_string()));
+    }
+    Ok(rows)
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Result<IndexedDocument, String>>, String> {
+    let mut reader = csv::Reader::from_path(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+
+    Ok(reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| e.to_string())?;
+            document_from_columns(&headers, &record.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        })
+        .collect())
+}
+
+fn read_xlsx(path: &Path) -> Result<Vec<Result<IndexedDocument, String>>, String> {
+    use calamine::{open_workbook_auto, Data, Reader};
+
+    let mut workbook = open_workbook_auto(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or("Workbook has no sheets")?
+        .map_err(|e| e.to_string())?;
+
+    let cell_text = |cell: &Data| -> String {
+        match cell {
+            Data::Empty => String::new(),
+            Data::String(s) | Data::DateTimeIso(s) | Data::DurationIso(s) => s.clone(),
+            Data::Int(i) => i.to_string(),
+            // Excel stores every number as a float
+            Data::Float(f) if f.fract() == 0.0 && f.abs() < 1e15 => (*f as i64).to_string(),
+            other => other.to_string(),
+        }
+    };
+
+    let mut rows = range.rows();
+    let headers: Vec<String> = match rows.next() {
+        Some(header) => header.iter().map(|c| cell_text(c).trim().to_lowercase()).collect(),
+        None => return Ok(Vec::new()),
+    };
+
+    Ok(rows
+        .filter(|row| row.iter().any(|c| !matches!(c, Data::Empty)))
+        .map(|row| document_from_columns(&headers, &row.iter().map(cell_text).collect::<Vec<_>>()))
+        .collect())
+}
+
+/// Build a document from a CSV/XLSX row using the header names.
+fn document_from_columns(headers: &[String], values: &[String]) -> Result<IndexedDocument, String> {
+    let get = |name: &str| -> Option<String> {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .and_then(|i| values.get(i))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    let file_path = get("file_path").ok_or("Missing file_path")?;
+    let status = match get("status") {
+        Some(s) => DocumentStatus::parse(&s)?,
+        None => DocumentStatus::Pending,
+    };
+    let file_size = match get("file_size") {
+        Some(s) => s.parse::<i64>().map_err(|_| format!("Invalid file_size: {}", s))?,
+        None => 0,
+    };
+    let file_name = get("file_name").unwrap_or_else(|| {
+        file_path
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(&file_path)
+            .to_string()
+    });
+    let now = chrono::Utc::now().to_rfc3339();
+
+    Ok(IndexedDocument {
+        id: get("id").unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        file_name,
+        file_type: get("file_type"),
+        file_size,
+        file_hash: get("file_hash"),
+        breadcrumb: get("breadcrumb")
+            .map(|b| b.split(BREADCRUMB_SEPARATOR).map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        status,
+        error_message: get("error_message"),
+        scanned_at: get("scanned_at").unwrap_or_else(|| now.clone()),
+        updated_at: get("updated_at").unwrap_or(now),
+        uploaded_at: get("uploaded_at"),
+        nexus_doc_id: get("nexus_doc_id"),
+        category: get("category"),
+        metadata: DocumentMetadata {
+            title: get("title"),
+            author: get("author"),
+            captured_at: get("captured_at"),
+            ..Default::default()
+        },
+        file_path,
+    })
+}
@@ -1,4 +1,4 @@
-use rusqlite::{Connection, Result as SqliteResult};
+use rusqlite::{Connection, OpenFlags};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
@@ -135,7 +135,7 @@ struct RawMailContact {
 
 /// Get the path to Mail.app's Envelope Index
 fn get_mail_db_path() -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
+    let home = dirs_next::home_dir()?;
     
     // Try V10 first (macOS Ventura+), then V9, V8, etc.
     for version in &["V10", "V9", "V8", "V7"] {
@@ -231,7 +231,8 @@ pub fn analyze_mail() -> Result<MailAnalysisResult, String> {
     
     eprintln!("[mail_index] Opening Mail database at: {:?}", db_path);
     
-    let conn = Connection::open(&db_path)
+    // Read-only: Mail.app owns this database
+    let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
         .map_err(|e| format!("Failed to open Mail database: {}. You may need to grant Full Disk Access.", e))?;
     
     // Query to get contacts with their email counts and recent subjects
@@ -326,10 +327,20 @@ pub fn analyze_mail() -> Result<MailAnalysisResult, String> {
     })
 }
 
+/// Construction score of every sender Mail.app has, by lowercased email
+/// address (for smart group rules; see smart_groups.rs)
+pub fn construction_scores() -> Result<HashMap<String, u32>, String> {
+    Ok(analyze_mail()?
+        .contacts
+        .into_iter()
+        .map(|c| (c.email.to_lowercase(), c.construction_score))
+        .collect())
+}
+
 /// Get construction score for a specific email address
 pub fn get_contact_score(email: &str) -> Option<u32> {
     let db_path = get_mail_db_path()?;
-    let conn = Connection::open(&db_path).ok()?;
+    let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY).ok()?;
     
     let query = r#"
         SELECT 
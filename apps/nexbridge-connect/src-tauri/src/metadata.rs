@@ -0,0 +1,403 @@
+// ---------------------------------------------------------------------------
+// metadata — embedded document metadata for the index
+// ---------------------------------------------------------------------------
+// Pulled at scan time and stored alongside each IndexedDocument:
+//
+//   - DOCX/XLSX/PPTX: docProps/core.xml (+ app.xml for page/slide count,
+//     word/styles.xml for the default language)
+//   - ODT/ODS/ODP: meta.xml
+//   - PDF: the trailer Info dictionary, then XMP from the catalog's
+//     Metadata stream for anything Info didn't have; page count; /Lang
+//   - Images: EXIF capture time, camera make/model, GPS position
+//
+// Everything is best effort — a file we can't read simply has no metadata.
+// Document dates are ISO-8601, normalized to UTC when the source carries a
+// time zone. Photo capture times keep the camera's wall-clock time (with its
+// offset when OffsetTimeOriginal is present) so a "photo date" filter matches
+// the day the picture was actually taken.
+// ---------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    /// When the document was authored (not the file's mtime)
+    pub created_at: Option<String>,
+    pub modified_at: Option<String>,
+    pub page_count: Option<i64>,
+    /// BCP 47 tag as recorded in the file, e.g. "en-US"
+    pub language: Option<String>,
+    /// Photo capture time (EXIF DateTimeOriginal)
+    pub captured_at: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub gps_latitude: Option<f64>,
+    pub gps_longitude: Option<f64>,
+}
+
+impl DocumentMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Extract embedded metadata for a file. Never fails; unreadable or
+/// unsupported files yield empty metadata. Guarded with catch_unwind like the
+/// converter, since lopdf and zip can panic on malformed input.
+pub fn extract_metadata(path: &Path) -> DocumentMetadata {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        match extension.as_str() {
+            "docx" | "xlsx" | "pptx" => extract_ooxml(path),
+            "odt" | "ods" | "odp" => extract_odf(path),
+            "pdf" => extract_pdf(path),
+            "jpg" | "jpeg" | "png" | "tiff" | "tif" | "webp" => extract_exif(path),
+            _ => None,
+        }
+    }));
+
+    match result {
+        Ok(meta) => meta.unwrap_or_default(),
+        Err(_) => {
+            eprintln!("[metadata] Extractor crashed on {}", path.display());
+            DocumentMetadata::default()
+        }
+    }
+}
+
+// ============ Office documents ============
+
+fn read_zip_entry(archive: &mut zip::ZipArchive<File>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut xml = String::new();
+    entry.read_to_string(&mut xml).ok()?;
+    Some(xml)
+}
+
+fn extract_ooxml(path: &Path) -> Option<DocumentMetadata> {
+    let mut archive = zip::ZipArchive::new(File::open(path).ok()?).ok()?;
+    let mut meta = DocumentMetadata::default();
+
+    if let Some(core) = read_zip_entry(&mut archive, "docProps/core.xml") {
+        meta.title = xml_element_text(&core, "dc:title");
+        meta.author = xml_element_text(&core, "dc:creator");
+        meta.subject = xml_element_text(&core, "dc:subject");
+        meta.keywords = xml_element_text(&core, "cp:keywords");
+        meta.created_at = xml_element_text(&core, "dcterms:created").map(|d| normalize_iso_date(&d));
+        meta.modified_at = xml_element_text(&core, "dcterms:modified").map(|d| normalize_iso_date(&d));
+        meta.language = xml_element_text(&core, "dc:language");
+    }
+
+    if let Some(app) = read_zip_entry(&mut archive, "docProps/app.xml") {
+        meta.page_count = xml_element_text(&app, "Pages")
+            .or_else(|| xml_element_text(&app, "Slides"))
+            .and_then(|n| n.parse().ok());
+    }
+
+    // Word rarely fills dc:language; the default run language lives in styles
+    if meta.language.is_none() {
+        if let Some(styles) = read_zip_entry(&mut archive, "word/styles.xml") {
+            meta.language = xml_tag_attr(&styles, "w:lang", "w:val");
+        }
+    }
+
+    Some(meta)
+}
+
+fn extract_odf(path: &Path) -> Option<DocumentMetadata> {
+    let mut archive = zip::ZipArchive::new(File::open(path).ok()?).ok()?;
+    let xml = read_zip_entry(&mut archive, "meta.xml")?;
+
+    Some(DocumentMetadata {
+        title: xml_element_text(&xml, "dc:title"),
+        author: xml_element_text(&xml, "meta:initial-creator")
+            .or_else(|| xml_element_text(&xml, "dc:creator")),
+        subject: xml_element_text(&xml, "dc:subject"),
+        keywords: xml_element_text(&xml, "meta:keyword"),
+        created_at: xml_element_text(&xml, "meta:creation-date").map(|d| normalize_iso_date(&d)),
+        modified_at: xml_element_text(&xml, "dc:date").map(|d| normalize_iso_date(&d)),
+        page_count: xml_tag_attr(&xml, "meta:document-statistic", "meta:page-count")
+            .and_then(|n| n.parse().ok()),
+        language: xml_element_text(&xml, "dc:language"),
+        ..Default::default()
+    })
+}
+
+// ============ PDF ============
+
+fn extract_pdf(path: &Path) -> Option<DocumentMetadata> {
+    let doc = lopdf::Document::load(path).ok()?;
+    let mut meta = DocumentMetadata {
+        page_count: Some(doc.get_pages().len() as i64),
+        ..Default::default()
+    };
+
+    let info = doc
+        .trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|obj| doc.dereference(obj).ok())
+        .and_then(|(_, obj)| obj.as_dict().ok());
+    if let Some(info) = info {
+        let text = |key: &[u8]| -> Option<String> {
+            let (_, obj) = doc.dereference(info.get(key).ok()?).ok()?;
+            match obj {
+                lopdf::Object::String(bytes, _) => non_empty(decode_pdf_text(bytes)),
+                _ => None,
+            }
+        };
+        meta.title = text(b"Title");
+        meta.author = text(b"Author");
+        meta.subject = text(b"Subject");
+        meta.keywords = text(b"Keywords");
+        meta.created_at = text(b"CreationDate").and_then(|d| parse_pdf_date(&d));
+        meta.modified_at = text(b"ModDate").and_then(|d| parse_pdf_date(&d));
+    }
+
+    if let Ok(catalog) = doc.catalog() {
+        if let Ok(lopdf::Object::String(bytes, _)) = catalog.get_deref(b"Lang", &doc) {
+            meta.language = non_empty(decode_pdf_text(bytes));
+        }
+
+        // Newer producers (and PDF 2.0) put the authoritative copy in XMP
+        if let Ok(lopdf::Object::Stream(stream)) = catalog.get_deref(b"Metadata", &doc) {
+            let bytes = stream
+                .decompressed_content()
+                .unwrap_or_else(|_| stream.content.clone());
+            let xmp = String::from_utf8_lossy(&bytes);
+            let field = |tag: &str| xml_element_text(&xmp, tag).or_else(|| xml_attr(&xmp, tag));
+
+            meta.title = meta.title.or_else(|| field("dc:title"));
+            meta.author = meta.author.or_else(|| field("dc:creator"));
+            meta.subject = meta.subject.or_else(|| field("dc:description"));
+            meta.keywords = meta.keywords.or_else(|| field("pdf:Keywords"));
+            meta.created_at = meta
+                .created_at
+                .or_else(|| field("xmp:CreateDate").map(|d| normalize_iso_date(&d)));
+            meta.modified_at = meta
+                .modified_at
+                .or_else(|| field("xmp:ModifyDate").map(|d| normalize_iso_date(&d)));
+            meta.language = meta.language.or_else(|| field("dc:language"));
+        }
+    }
+
+    Some(meta)
+}
+
+/// PDF text strings are UTF-16BE with a BOM, or PDFDocEncoding (treated as
+/// Latin-1, which matches it for everything but a few punctuation marks).
+fn decode_pdf_text(bytes: &[u8]) -> String {
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        String::from_utf8_lossy(&bytes[3..]).into_owned()
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+/// Parse a PDF date ("D:YYYYMMDDHHmmSSOHH'mm'", everything after the year
+/// optional).
+fn parse_pdf_date(raw: &str) -> Option<String> {
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+    let s = raw.trim();
+    let s = s.strip_prefix("D:").unwrap_or(s);
+    let digits: String = s.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    let part = |start: usize, len: usize, default: u32| -> u32 {
+        digits
+            .get(start..start + len)
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(default)
+    };
+    let naive = NaiveDate::from_ymd_opt(part(0, 4, 0) as i32, part(4, 2, 1), part(6, 2, 1))?
+        .and_hms_opt(part(8, 2, 0), part(10, 2, 0), part(12, 2, 0))?;
+
+    let tz: Vec<char> = s[digits.len()..].chars().filter(|c| *c != '\'').collect();
+    let offset_secs = match tz.first() {
+        Some('Z') => Some(0),
+        Some(sign @ ('+' | '-')) => {
+            let tz_digits: String = tz[1..].iter().take_while(|c| c.is_ascii_digit()).collect();
+            let hours: i32 = tz_digits.get(0..2)?.parse().ok()?;
+            let minutes: i32 = tz_digits.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+            let secs = hours * 3600 + minutes * 60;
+            Some(if *sign == '-' { -secs } else { secs })
+        }
+        _ => None,
+    };
+
+    Some(match offset_secs {
+        Some(secs) => FixedOffset::east_opt(secs)?
+            .from_local_datetime(&naive)
+            .single()?
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339(),
+        None => naive.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    })
+}
+
+// ============ Images ============
+
+fn extract_exif(path: &Path) -> Option<DocumentMetadata> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let exif = exif::Reader::new().read_from_container(&mut reader).ok()?;
+
+    let ascii = |tag: exif::Tag| -> Option<String> {
+        match exif.get_field(tag, exif::In::PRIMARY).map(|f| &f.value) {
+            Some(exif::Value::Ascii(parts)) => parts
+                .first()
+                .and_then(|p| non_empty(String::from_utf8_lossy(p).trim().to_string())),
+            _ => None,
+        }
+    };
+
+    let captured_at = ascii(exif::Tag::DateTimeOriginal)
+        .or_else(|| ascii(exif::Tag::DateTime))
+        .and_then(|raw| {
+            let dt = exif::DateTime::from_ascii(raw.as_bytes()).ok()?;
+            let local = format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+            );
+            Some(match ascii(exif::Tag::OffsetTimeOriginal) {
+                Some(offset) => chrono::DateTime::parse_from_rfc3339(&format!("{}{}", local, offset))
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or(local),
+                None => local,
+            })
+        });
+
+    let coordinate = |value_tag: exif::Tag, ref_tag: exif::Tag, negative_ref: &str| -> Option<f64> {
+        let field = exif.get_field(value_tag, exif::In::PRIMARY)?;
+        let degrees = match &field.value {
+            exif::Value::Rational(parts) if parts.len() >= 3 => {
+                parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0
+            }
+            _ => return None,
+        };
+        if !degrees.is_finite() {
+            return None;
+        }
+        let negative = ascii(ref_tag).is_some_and(|r| r.eq_ignore_ascii_case(negative_ref));
+        Some(if negative { -degrees } else { degrees })
+    };
+
+    Some(DocumentMetadata {
+        title: ascii(exif::Tag::ImageDescription),
+        author: ascii(exif::Tag::Artist),
+        captured_at,
+        camera_make: ascii(exif::Tag::Make),
+        camera_model: ascii(exif::Tag::Model),
+        gps_latitude: coordinate(exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, "S"),
+        gps_longitude: coordinate(exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef, "W"),
+        ..Default::default()
+    })
+}
+
+// ============ XML helpers ============
+
+/// Text content of the first `<tag ...>...</tag>` element. Nested markup
+/// (XMP's rdf:Seq/rdf:Alt lists) is flattened, list items joined with "; ".
+fn xml_element_text(xml: &str, tag: &str) -> Option<String> {
+    let open = find_open_tag(xml, tag)?;
+    let after_open = &xml[open..];
+    let tag_end = after_open.find('>')?;
+    if after_open[..tag_end].ends_with('/') {
+        return None;
+    }
+    let body = &after_open[tag_end + 1..];
+    let body = &body[..body.find(&format!("</{}>", tag))?];
+
+    let mut parts = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        let next_tag = rest.find('<').unwrap_or(rest.len());
+        let text = rest[..next_tag].trim();
+        if !text.is_empty() {
+            parts.push(crate::render::decode_entities(text));
+        }
+        rest = &rest[next_tag..];
+        match rest.find('>') {
+            Some(end) if !rest.is_empty() => rest = &rest[end + 1..],
+            _ => break,
+        }
+    }
+    non_empty(parts.join("; "))
+}
+
+/// Value of `attribute` on the first `<tag ...>` element.
+fn xml_tag_attr(xml: &str, tag: &str, attribute: &str) -> Option<String> {
+    let open = find_open_tag(xml, tag)?;
+    let element = &xml[open..];
+    xml_attr(&element[..element.find('>')?], attribute)
+}
+
+/// First `name="value"` attribute anywhere in the fragment (XMP also allows
+/// properties written as attributes of rdf:Description).
+fn xml_attr(xml: &str, name: &str) -> Option<String> {
+    let mut search = 0;
+    while let Some(pos) = xml[search..].find(name) {
+        let start = search + pos;
+        let preceded_by_space = start > 0 && xml.as_bytes()[start - 1].is_ascii_whitespace();
+        let after = xml[start + name.len()..].trim_start();
+        if preceded_by_space {
+            if let Some(value) = after.strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+                let inner = &value[1..];
+                return non_empty(crate::render::decode_entities(&inner[..inner.find(quote)?]));
+            }
+        }
+        search = start + name.len();
+    }
+    None
+}
+
+fn find_open_tag(xml: &str, tag: &str) -> Option<usize> {
+    let needle = format!("<{}", tag);
+    let mut search = 0;
+    while let Some(pos) = xml[search..].find(&needle) {
+        let start = search + pos;
+        match xml[start + needle.len()..].chars().next() {
+            Some(c) if c == '>' || c == '/' || c.is_whitespace() => return Some(start),
+            _ => search = start + needle.len(),
+        }
+    }
+    None
+}
+
+/// W3CDTF / XMP dates: normalize zoned values to UTC, keep the rest as-is.
+fn normalize_iso_date(raw: &str) -> String {
+    let raw = raw.trim();
+    chrono::DateTime::parse_from_rfc3339(raw)
+        .map(|t| t.with_timezone(&chrono::Utc).to_rfc3339())
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+fn non_empty(s: String) -> Option<String> {
+    let trimmed = s.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
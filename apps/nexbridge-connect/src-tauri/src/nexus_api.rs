@@ -0,0 +1,695 @@
+// ---------------------------------------------------------------------------
+// nexus_api — typed client for the Nexus REST API
+// ---------------------------------------------------------------------------
+// Everything the Rust side sends to Nexus goes through `NexusApi`:
+//
+//   - the base URL and tokens live here (set from the frontend after login
+//     via `set_api_session`, or from cli.json for the CLI), so callers pass
+//     typed requests instead of URL strings and bearer tokens;
+//   - every request carries User-Agent, X-App-Platform and X-App-Version,
+//     matching `platformHeaders()` in src/lib/api.ts;
+//   - a 401 triggers one token refresh (POST /auth/refresh) and one retry;
+//     rotated tokens are passed to the `on_refresh` callback so they can be
+//     stored;
+//   - failures come back as `ApiError`, with 402/426 decoded the same way
+//     the frontend does.
+//
+// Endpoints:
+//   POST /auth/refresh
+//   POST /document-import/upload-html
+//   POST /document-import/documents/{id}/original
+//   POST /upload-sessions, GET|PUT /upload-sessions/{id}, POST /upload-sessions/{id}/complete
+//   POST /precision-scans/{id}/upload
+//   POST /personal-contacts/import, DELETE /personal-contacts/{id}
+//   GET  API-relative paths (scan image downloads)
+//
+// Request bodies are built per attempt (see `send`) so a retried request
+// gets a fresh, throttled body stream.
+// ---------------------------------------------------------------------------
+
+use crate::bandwidth::{throttled_bytes, throttled_file};
+use crate::metadata::DocumentMetadata;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
+
+const PLATFORM: &str = "nexbridge";
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(120);
+
+// ============ Errors ============
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// No API session has been set (not signed in)
+    NotConfigured,
+    /// The request never got a response (DNS, TLS, connection reset, timeout)
+    Network(String),
+    /// Still 401 after refreshing, or there was no refresh token to use
+    Unauthorized,
+    /// 402 — license lapsed or locked
+    License {
+        code: String,
+        message: String,
+        export_only: bool,
+    },
+    /// 426 — this app version is no longer accepted
+    UpdateRequired {
+        message: String,
+        min_version: Option<String>,
+        download_url: Option<String>,
+    },
+    /// Any other non-success status
+    Http { status: u16, message: String },
+    /// A success response whose body wasn't what we expected
+    Decode(String),
+}
+
+impl ApiError {
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::Unauthorized => Some(401),
+            Self::License { .. } => Some(402),
+            Self::UpdateRequired { .. } => Some(426),
+            Self::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Worth retrying as-is (network trouble, timeouts, 429, 5xx).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Network(_) => true,
+            Self::Http { status, .. } => *status == 408 || *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// The resource no longer exists (404/410).
+    pub fn is_gone(&self) -> bool {
+        matches!(self.status(), Some(404) | Some(410))
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotConfigured => write!(f, "Not signed in to Nexus"),
+            Self::Network(e) => write!(f, "Request failed: {}", e),
+            Self::Unauthorized => write!(f, "Session expired"),
+            Self::License { message, .. } => write!(f, "{}", message),
+            Self::UpdateRequired { message, .. } => write!(f, "{}", message),
+            Self::Http { status, message } => write!(f, "HTTP {}: {}", status, message),
+            Self::Decode(e) => write!(f, "Unexpected response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<ApiError> for String {
+    fn from(e: ApiError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Network(e.to_string())
+    }
+}
+
+// ============ Session ============
+
+/// Tokens from /auth/login or /auth/refresh.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Session {
+    base_url: String,
+    credentials: Credentials,
+}
+
+type RefreshCallback = Box<dyn Fn(&Credentials) + Send + Sync>;
+
+pub struct NexusApi {
+    client: Client,
+    app_version: String,
+    session: RwLock<Option<Session>>,
+    /// Held while refreshing so concurrent 401s trigger a single refresh
+    refresh_lock: tokio::sync::Mutex<()>,
+    on_refresh: RwLock<Option<RefreshCallback>>,
+}
+
+impl NexusApi {
+    /// A client with no session yet; requests fail with `NotConfigured`
+    /// until `set_session` is called.
+    pub fn new(app_version: &str) -> Self {
+        let user_agent = format!(
+            "NexBRIDGE-Connect/{} ({}; {})",
+            app_version,
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        Self {
+            client: Client::builder()
+                .user_agent(user_agent)
+                .build()
+                .unwrap_or_else(|_| Client::new()),
+            app_version: app_version.to_string(),
+            session: RwLock::new(None),
+            refresh_lock: tokio::sync::Mutex::new(()),
+            on_refresh: RwLock::new(None),
+        }
+    }
+
+    pub fn with_session(app_version: &str, base_url: &str, credentials: Credentials) -> Self {
+        let api = Self::new(app_version);
+        api.set_session(base_url, credentials);
+        api
+    }
+
+    pub fn set_session(&self, base_url: &str, credentials: Credentials) {
+        *self.session.write().unwrap() = Some(Session {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            credentials,
+        });
+    }
+
+    pub fn clear_session(&self) {
+        *self.session.write().unwrap() = None;
+    }
+
+    pub fn base_url(&self) -> Option<String> {
+        self.session.read().unwrap().as_ref().map(|s| s.base_url.clone())
+    }
+
+    /// Called with the new tokens after every successful refresh.
+    pub fn on_refresh(&self, callback: impl Fn(&Credentials) + Send + Sync + 'static) {
+        *self.on_refresh.write().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Absolute URL for an API-relative path.
+    pub fn url(&self, path: &str) -> Result<String, ApiError> {
+        let base = self.base_url().ok_or(ApiError::NotConfigured)?;
+        Ok(format!("{}/{}", base, path.trim_start_matches('/')))
+    }
+
+    // ============ Request pipeline ============
+
+    /// Send the request `build` makes for `url`, with auth and platform
+    /// headers. On 401 the token is refreshed once and `build` is called
+    /// again for the retry. Returns the response whatever its status
+    /// (other than 401); see `send` for the checked version.
+    pub async fn send_raw<F>(&self, path: &str, build: F) -> Result<Response, ApiError>
+    where
+        F: Fn(&Client, &str) -> RequestBuilder,
+    {
+        let url = self.url(path)?;
+        let token = self.access_token()?;
+        let response = self.decorate(build(&self.client, &url), &token).send().await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        self.refresh(&token).await?;
+        let token = self.access_token()?;
+        let retry = self.decorate(build(&self.client, &url), &token).send().await?;
+        if retry.status() == StatusCode::UNAUTHORIZED {
+            return Err(ApiError::Unauthorized);
+        }
+        Ok(retry)
+    }
+
+    /// `send_raw`, turning any non-success status into an `ApiError`.
+    pub async fn send<F>(&self, path: &str, build: F) -> Result<Response, ApiError>
+    where
+        F: Fn(&Client, &str) -> RequestBuilder,
+    {
+        let response = self.send_raw(path, build).await?;
+        check(response).await
+    }
+
+    async fn send_json<T, F>(&self, path: &str, build: F) -> Result<T, ApiError>
+    where
+        T: DeserializeOwned,
+        F: Fn(&Client, &str) -> RequestBuilder,
+    {
+        let response = self.send(path, build).await?;
+        response.json::<T>().await.map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    fn decorate(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        request
+            .bearer_auth(token)
+            .header("X-App-Platform", PLATFORM)
+            .header("X-App-Version", &self.app_version)
+    }
+
+    fn access_token(&self) -> Result<String, ApiError> {
+        self.session
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.credentials.access_token.clone())
+            .ok_or(ApiError::NotConfigured)
+    }
+
+    /// Exchange the refresh token for new tokens, unless another request
+    /// already did so since `stale_token` was sent.
+    async fn refresh(&self, stale_token: &str) -> Result<(), ApiError> {
+        let _guard = self.refresh_lock.lock().await;
+
+        let session = self.session.read().unwrap().clone().ok_or(ApiError::NotConfigured)?;
+        if session.credentials.access_token != stale_token {
+            return Ok(());
+        }
+        let refresh_token = session.credentials.refresh_token.ok_or(ApiError::Unauthorized)?;
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RefreshRequest<'a> {
+            refresh_token: &'a str,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RefreshResponse {
+            access_token: String,
+            refresh_token: Option<String>,
+        }
+
+        let response = self
+            .client
+            .post(format!("{}/auth/refresh", session.base_url))
+            .header("X-App-Platform", PLATFORM)
+            .header("X-App-Version", &self.app_version)
+            .json(&RefreshRequest {
+                refresh_token: &refresh_token,
+            })
+            .send()
+            .await?;
+        match response.status() {
+            s if s.is_success() => {}
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                return Err(ApiError::Unauthorized)
+            }
+            _ => return Err(check(response).await.unwrap_err()),
+        }
+        let body: RefreshResponse = response
+            .json()
+            .await
+            .map_err(|e| ApiError::Decode(e.to_string()))?;
+
+        let credentials = Credentials {
+            access_token: body.access_token,
+            // Servers that don't rotate refresh tokens omit it
+            refresh_token: body.refresh_token.or(Some(refresh_token)),
+        };
+        self.set_session(&session.base_url, credentials.clone());
+        eprintln!("[nexus_api] Access token refreshed");
+        if let Some(callback) = self.on_refresh.read().unwrap().as_ref() {
+            callback(&credentials);
+        }
+        Ok(())
+    }
+
+    // ============ Document import ============
+
+    pub async fn upload_html(&self, request: &UploadHtmlRequest) -> Result<UploadHtmlResponse, ApiError> {
+        let body = serde_json::to_vec(request).map_err(|e| ApiError::Decode(e.to_string()))?;
+        self.send_json("document-import/upload-html", |client, url| {
+            client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .header("Content-Length", body.len())
+                .body(throttled_bytes(body.clone()))
+        })
+        .await
+    }
+
+    /// Attach the original source file to an uploaded document.
+    pub async fn upload_original(&self, nexus_doc_id: &str, original: &OriginalFile) -> Result<(), ApiError> {
+        let path = format!("document-import/documents/{}/original", nexus_doc_id);
+        self.send(&path, |client, url| {
+            let form = Form::new()
+                .text("sha256", original.sha256.clone())
+                .text("size", original.size.to_string());
+            client.post(url).multipart(original.content.attach(form, "file", "uploadId"))
+        })
+        .await
+        .map(|_| ())
+    }
+
+    // ============ Upload sessions ============
+
+    pub async fn create_upload_session(&self, request: &CreateUploadSession) -> Result<UploadSessionInfo, ApiError> {
+        self.send_json("upload-sessions", |client, url| client.post(url).json(request)).await
+    }
+
+    /// The session's acknowledged offset, or None if it no longer exists.
+    pub async fn upload_session(&self, upload_id: &str) -> Result<Option<UploadSessionInfo>, ApiError> {
+        let response = self
+            .send_raw(&format!("upload-sessions/{}", upload_id), |client, url| client.get(url))
+            .await?;
+        match response.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::FORBIDDEN => Ok(None),
+            _ => check(response)
+                .await?
+                .json()
+                .await
+                .map(Some)
+                .map_err(|e| ApiError::Decode(e.to_string())),
+        }
+    }
+
+    /// PUT one chunk at `offset`. A 409 means the server is at a different
+    /// offset; that comes back as `ChunkAck::OffsetMismatch`.
+    pub async fn put_upload_chunk(
+        &self,
+        upload_id: &str,
+        offset: u64,
+        total: u64,
+        chunk: &[u8],
+    ) -> Result<ChunkAck, ApiError> {
+        let end = offset + chunk.len() as u64 - 1;
+        let response = self
+            .send_raw(&format!("upload-sessions/{}", upload_id), |client, url| {
+                client
+                    .put(url)
+                    .query(&[("offset", offset)])
+                    .header("Content-Type", "application/octet-stream")
+                    .header("Content-Range", format!("bytes {}-{}/{}", offset, end, total))
+                    .header("Content-Length", chunk.len())
+                    .timeout(CHUNK_TIMEOUT)
+                    .body(throttled_bytes(chunk.to_vec()))
+            })
+            .await?;
+
+        if response.status() == StatusCode::CONFLICT {
+            let body: UploadSessionInfo = response
+                .json()
+                .await
+                .map_err(|e| ApiError::Decode(e.to_string()))?;
+            return Ok(ChunkAck::OffsetMismatch(body.offset));
+        }
+        let body: UploadSessionInfo = check(response)
+            .await?
+            .json()
+            .await
+            .map_err(|e| ApiError::Decode(e.to_string()))?;
+        Ok(ChunkAck::Accepted(body.offset))
+    }
+
+    pub async fn complete_upload_session(&self, upload_id: &str) -> Result<CompletedUploadSession, ApiError> {
+        self.send_json(&format!("upload-sessions/{}/complete", upload_id), |client, url| {
+            client.post(url)
+        })
+        .await
+    }
+
+    // ============ Precision scans ============
+
+    pub async fn upload_scan_results(
+        &self,
+        scan_id: &str,
+        request: &ScanResultsUpload,
+    ) -> Result<serde_json::Value, ApiError> {
+        let response = self
+            .send(&format!("precision-scans/{}/upload", scan_id), |client, url| {
+                let mut form = Form::new().text("jobId", request.job_id.clone());
+                for (format, model) in &request.models {
+                    form = model.attach(
+                        form,
+                        &format!("file_{}", format),
+                        &format!("upload_{}", format),
+                    );
+                }
+                if let Some(analysis) = &request.analysis {
+                    let part = Part::stream_with_length(throttled_bytes(analysis.clone()), analysis.len() as u64)
+                        .file_name("mesh_analysis.json")
+                        .mime_str("application/json")
+                        .expect("valid mime type");
+                    form = form.part("analysis", part);
+                }
+                client.post(url).multipart(form)
+            })
+            .await?;
+        Ok(response
+            .json()
+            .await
+            .unwrap_or_else(|_| serde_json::json!({"status": "ok"})))
+    }
+
+    // ============ Personal contacts ============
+
+    pub async fn import_contacts(&self, contacts: &[ImportContact]) -> Result<ImportContactsResult, ApiError> {
+        let body = serde_json::json!({ "contacts": contacts });
+        self.send_json("personal-contacts/import", |client, url| client.post(url).json(&body)).await
+    }
+
+    /// Delete a synced contact. Nexus answers 200 for a contact that is
+    /// already gone, so any error status is a real failure.
+    pub async fn delete_contact(&self, nexus_contact_id: &str) -> Result<(), ApiError> {
+        self.send(&format!("personal-contacts/{}", nexus_contact_id), |client, url| client.delete(url))
+            .await
+            .map(|_| ())
+    }
+
+    /// GET an API-relative path (authenticated), or an absolute URL as-is.
+    /// Absolute URLs on the API's own host still get the session's auth;
+    /// anything else (pre-signed storage URLs) is fetched without it.
+    pub async fn download(&self, path_or_url: &str) -> Result<Response, ApiError> {
+        let absolute = path_or_url.starts_with("http://") || path_or_url.starts_with("https://");
+        if !absolute {
+            return self.send(path_or_url, |client, url| client.get(url)).await;
+        }
+        match self.base_url() {
+            Some(base) if path_or_url.starts_with(&format!("{}/", base)) => {
+                let path = path_or_url[base.len()..].to_string();
+                self.send(&path, |client, url| client.get(url)).await
+            }
+            _ => {
+                let response = self
+                    .client
+                    .get(path_or_url)
+                    .header("X-App-Platform", PLATFORM)
+                    .header("X-App-Version", &self.app_version)
+                    .send()
+                    .await?;
+                check(response).await
+            }
+        }
+    }
+}
+
+/// Map a non-success response to an `ApiError`.
+async fn check(response: Response) -> Result<Response, ApiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let text = response.text().await.unwrap_or_default();
+    let json: Option<serde_json::Value> = serde_json::from_str(&text).ok();
+    let field = |name: &str| {
+        json.as_ref()
+            .and_then(|j| j.get(name))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    };
+
+    Err(match status {
+        StatusCode::UNAUTHORIZED => ApiError::Unauthorized,
+        StatusCode::PAYMENT_REQUIRED => ApiError::License {
+            code: field("error").unwrap_or_else(|| "LICENSE_ERROR".to_string()),
+            message: field("message").unwrap_or_else(|| "License expired".to_string()),
+            export_only: json
+                .as_ref()
+                .and_then(|j| j.get("exportOnly"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        },
+        StatusCode::UPGRADE_REQUIRED => ApiError::UpdateRequired {
+            message: field("message").unwrap_or_else(|| "Update required".to_string()),
+            min_version: field("minVersion"),
+            download_url: field("downloadUrl"),
+        },
+        _ => ApiError::Http {
+            status: status.as_u16(),
+            message: field("message").unwrap_or(text),
+        },
+    })
+}
+
+// ============ Request / response types ============
+
+/// Body of POST /document-import/upload-html. Exactly one of
+/// `html_content` / `html_upload_id` is set.
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadHtmlRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html_content: Option<String>,
+    /// Upload session holding the HTML when it was too large to inline
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html_upload_id: Option<String>,
+    pub title: String,
+    pub category: String,
+    pub original_format: String,
+    pub word_count: u32,
+    pub folder_name: String,
+    pub breadcrumb: Vec<String>,
+    #[serde(skip_serializing_if = "DocumentMetadata::is_empty")]
+    pub metadata: DocumentMetadata,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadHtmlResponse {
+    pub id: Option<String>,
+    #[serde(rename = "documentId")]
+    pub document_id: Option<String>,
+}
+
+impl UploadHtmlResponse {
+    pub fn nexus_doc_id(self) -> Option<String> {
+        self.id.or(self.document_id)
+    }
+}
+
+/// Where a file's bytes come from in a multipart upload.
+#[derive(Debug, Clone)]
+pub enum FileContent {
+    /// Already sent through an upload session; referenced by id
+    UploadSession(String),
+    /// Streamed from disk as a file part
+    Stream {
+        path: PathBuf,
+        file_name: String,
+        content_type: String,
+        size: u64,
+    },
+}
+
+impl FileContent {
+    /// Add the file to `form`, as a file part named `part_name` or as the
+    /// session id in the text field `id_field`.
+    fn attach(&self, form: Form, part_name: &str, id_field: &str) -> Form {
+        match self {
+            Self::UploadSession(upload_id) => form.text(id_field.to_string(), upload_id.clone()),
+            Self::Stream {
+                path,
+                file_name,
+                content_type,
+                size,
+            } => {
+                let body = || throttled_file(path.clone(), *size);
+                let part = Part::stream_with_length(body(), *size).file_name(file_name.clone());
+                let part = match part.mime_str(content_type) {
+                    Ok(part) => part,
+                    Err(_) => Part::stream_with_length(body(), *size).file_name(file_name.clone()),
+                };
+                form.part(part_name.to_string(), part)
+            }
+        }
+    }
+}
+
+/// An original file plus the hash and size the server checks it against.
+#[derive(Debug, Clone)]
+pub struct OriginalFile {
+    pub sha256: String,
+    pub size: u64,
+    pub content: FileContent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateUploadSession {
+    pub file_name: String,
+    pub content_type: String,
+    pub size: u64,
+    pub sha256: String,
+    pub chunk_size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSessionInfo {
+    #[serde(alias = "id")]
+    pub upload_id: Option<String>,
+    #[serde(default)]
+    pub offset: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkAck {
+    /// Stored; the server is now at this offset
+    Accepted(u64),
+    /// Rejected because the server is at this offset instead
+    OffsetMismatch(u64),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletedUploadSession {
+    pub sha256: Option<String>,
+    pub size: Option<u64>,
+}
+
+/// Body of POST /precision-scans/{id}/upload.
+#[derive(Debug, Clone)]
+pub struct ScanResultsUpload {
+    pub job_id: String,
+    /// (format, file) — sent as `file_{format}` or `upload_{format}`
+    pub models: Vec<(String, FileContent)>,
+    /// mesh_analysis.json
+    pub analysis: Option<Vec<u8>>,
+}
+
+/// One device contact in POST /personal-contacts/import.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportContact {
+    /// The contact's id in Nexus when it was synced before, so Nexus updates
+    /// that record instead of matching by email/phone
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub display_name: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub all_emails: Vec<String>,
+    pub all_phones: Vec<String>,
+    /// "MACOS", "WINDOWS", "IOS", "ANDROID" or "UPLOAD"
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportContactsResult {
+    #[serde(default)]
+    pub count: u32,
+    #[serde(default)]
+    pub created_count: u32,
+    #[serde(default)]
+    pub updated_count: u32,
+    /// The imported contacts, in request order
+    #[serde(default)]
+    pub contacts: Vec<PersonalContact>,
+}
+
+/// A contact as stored in Nexus (only the fields the app uses).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersonalContact {
+    pub id: String,
+    pub display_name: Option<String>,
+}
@@ -13,7 +13,9 @@
 // All heavy processing targets /Volumes/4T Data/precision-scans/{jobId}/
 // ---------------------------------------------------------------------------
 
-use reqwest::Client;
+use crate::bandwidth::{limiter, Direction};
+use crate::chunked_upload::{self, ChunkedUploader, UploadSource};
+use crate::nexus_api::{Credentials, FileContent, NexusApi, ScanResultsUpload};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tauri::Emitter;
@@ -164,6 +166,40 @@ pub struct UploadScanResult {
     pub api_response: serde_json::Value,
 }
 
+/// Hold a transfer until the bandwidth settings allow it, reporting the
+/// reason on the progress channel while it waits.
+async fn wait_for_transfer_window(app: &tauri::AppHandle, job_id: &str, stage: &str) {
+    limiter()
+        .wait_until_allowed(&|reason| {
+            let _ = app.emit(
+                "nexcad-progress",
+                serde_json::json!({
+                    "stage": stage,
+                    "message": reason,
+                    "waiting": true,
+                    "jobId": job_id,
+                }),
+            );
+        })
+        .await;
+}
+
+/// A one-off API session when the job payload names its own API and token;
+/// otherwise the job uses the app's signed-in session.
+fn job_api(api_url: Option<String>, token: Option<String>) -> Option<NexusApi> {
+    match (api_url, token) {
+        (Some(api_url), Some(token)) => Some(NexusApi::with_session(
+            env!("CARGO_PKG_VERSION"),
+            &api_url,
+            Credentials {
+                access_token: token,
+                refresh_token: None,
+            },
+        )),
+        _ => None,
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tauri Commands
 // ---------------------------------------------------------------------------
@@ -171,12 +207,13 @@ pub struct UploadScanResult {
 /// Download scan images from the API to local SSD storage.
 ///
 /// Expects `image_urls` — a list of signed URLs (or API-relative paths).
-/// If `api_url` and `token` are provided, relative paths are resolved against
-/// the API. If the URLs are already absolute (e.g. pre-signed S3/MinIO), they
-/// are fetched directly.
+/// Relative paths are fetched from the signed-in API (or from `api_url` with
+/// `token`, when the job provides them). If the URLs are already absolute
+/// (e.g. pre-signed S3/MinIO), they are fetched directly.
 #[tauri::command]
 pub async fn download_scan_images(
     app: tauri::AppHandle,
+    state: tauri::State<'_, crate::app::AppState>,
     job_id: String,
     image_urls: Vec<String>,
     api_url: Option<String>,
@@ -187,12 +224,14 @@ pub async fn download_scan_images(
         .await
         .map_err(|e| format!("Failed to create images dir: {}", e))?;
 
-    let client = Client::new();
+    let job_api = job_api(api_url, token);
+    let api = job_api.as_ref().unwrap_or(&state.nexus_api);
     let mut total_bytes: u64 = 0;
     let count = image_urls.len();
 
     for (i, url) in image_urls.iter().enumerate() {
         let pct = ((i as f64 / count as f64) * 100.0) as u32;
+        wait_for_transfer_window(&app, &job_id, "download").await;
         let _ = app.emit(
             "nexcad-progress",
             serde_json::json!({
@@ -203,36 +242,21 @@ pub async fn download_scan_images(
             }),
         );
 
-        // Resolve URL — absolute or relative to API
-        let full_url = if url.starts_with("http://") || url.starts_with("https://") {
-            url.clone()
-        } else {
-            let base = api_url.as_deref().unwrap_or("http://localhost:8001");
-            format!("{}/{}", base.trim_end_matches('/'), url.trim_start_matches('/'))
-        };
-
-        let mut req = client.get(&full_url);
-        if let Some(ref t) = token {
-            req = req.header("Authorization", format!("Bearer {}", t));
-        }
-
-        let resp = req
-            .send()
+        let mut resp = api
+            .download(url)
             .await
-            .map_err(|e| format!("Download failed for {}: {}", full_url, e))?;
-
-        if !resp.status().is_success() {
-            return Err(format!(
-                "HTTP {} downloading {}",
-                resp.status(),
-                full_url
-            ));
-        }
+            .map_err(|e| format!("Download failed for {}: {}", url, e))?;
 
-        let bytes = resp
-            .bytes()
+        // Read in chunks so the download cap can pace the transfer
+        let mut bytes = Vec::new();
+        while let Some(chunk) = resp
+            .chunk()
             .await
-            .map_err(|e| format!("Failed to read body: {}", e))?;
+            .map_err(|e| format!("Failed to read body: {}", e))?
+        {
+            limiter().acquire(Direction::Download, chunk.len() as u64).await;
+            bytes.extend_from_slice(&chunk);
+        }
 
         // Determine extension from URL or default to .jpg
         let ext = Path::new(url)
@@ -673,17 +697,24 @@ pub async fn analyze_mesh(
 ///
 /// `job_id`  — mesh job UUID (used for local file path resolution)
 /// `scan_id` — precision scan cuid (used in the API upload URL)
+///
+/// Models larger than `chunked_upload::CHUNK_THRESHOLD` are sent through a
+/// resumable upload session and referenced as `upload_{fmt}`; smaller ones
+/// are streamed as `file_{fmt}` parts. Uploads go to the signed-in API unless
+/// the job provides its own `api_url` and `token`.
 #[tauri::command]
 pub async fn upload_scan_results(
     app: tauri::AppHandle,
+    state: tauri::State<'_, crate::app::AppState>,
     job_id: String,
     scan_id: String,
-    api_url: String,
-    token: String,
+    api_url: Option<String>,
+    token: Option<String>,
     formats: Option<Vec<String>>,
 ) -> Result<UploadScanResult, String> {
     let out_dir = output_dir(&job_id);
-    let client = Client::new();
+    let job_api = job_api(api_url, token);
+    let api = job_api.as_ref().unwrap_or(&state.nexus_api);
 
     // Determine which files to upload
     let target_formats = formats.unwrap_or_else(|| {
@@ -695,8 +726,12 @@ pub async fn upload_scan_results(
         ]
     });
 
+    // Model files are streamed from disk; the large ones go through a
+    // resumable upload session recorded alongside the job's files.
+    let chunked = ChunkedUploader::new(Some(job_dir(&job_id).join(chunked_upload::SESSIONS_FILE)));
+
     let mut uploaded_files = Vec::new();
-    let mut form = reqwest::multipart::Form::new().text("jobId", job_id.clone());
+    let mut models = Vec::new();
 
     for fmt in &target_formats {
         let file_path = out_dir.join(format!("model.{}", fmt));
@@ -704,19 +739,8 @@ pub async fn upload_scan_results(
             continue;
         }
 
-        let bytes = tokio::fs::read(&file_path)
-            .await
-            .map_err(|e| format!("Failed to read {}: {}", fmt, e))?;
-
         let filename = format!("model.{}", fmt);
-        let part = reqwest::multipart::Part::bytes(bytes)
-            .file_name(filename.clone())
-            .mime_str("application/octet-stream")
-            .map_err(|e| format!("Multipart error: {}", e))?;
-
-        form = form.part(format!("file_{}", fmt), part);
-        uploaded_files.push(filename);
-
+        wait_for_transfer_window(&app, &job_id, "upload").await;
         let _ = app.emit(
             "nexcad-progress",
             serde_json::json!({
@@ -725,47 +749,77 @@ pub async fn upload_scan_results(
                 "jobId": &job_id,
             }),
         );
+
+        let size = tokio::fs::metadata(&file_path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", fmt, e))?
+            .len();
+
+        if ChunkedUploader::should_chunk(size) {
+            let progress = |sent: u64, total: u64| {
+                let _ = app.emit(
+                    "nexcad-progress",
+                    serde_json::json!({
+                        "stage": "upload",
+                        "pct": (sent * 100 / total.max(1)) as u32,
+                        "message": format!(
+                            "Uploading {} ({:.1} / {:.1} MB)",
+                            filename,
+                            sent as f64 / 1_048_576.0,
+                            total as f64 / 1_048_576.0
+                        ),
+                        "jobId": &job_id,
+                    }),
+                );
+            };
+            let completed = chunked
+                .upload(
+                    api,
+                    &filename,
+                    "application/octet-stream",
+                    &UploadSource::File(file_path.clone()),
+                    &progress,
+                )
+                .await
+                .map_err(|e| format!("Failed to upload {}: {}", filename, e))?;
+            models.push((fmt.clone(), FileContent::UploadSession(completed.upload_id)));
+        } else {
+            models.push((
+                fmt.clone(),
+                FileContent::Stream {
+                    path: file_path.clone(),
+                    file_name: filename.clone(),
+                    content_type: "application/octet-stream".to_string(),
+                    size,
+                },
+            ));
+        }
+        uploaded_files.push(filename);
     }
 
     // Attach mesh analysis if it exists
     let analysis_path = out_dir.join("mesh_analysis.json");
+    let mut analysis = None;
     if analysis_path.exists() {
         let bytes = tokio::fs::read(&analysis_path)
             .await
             .map_err(|e| format!("Failed to read analysis: {}", e))?;
-        let part = reqwest::multipart::Part::bytes(bytes)
-            .file_name("mesh_analysis.json")
-            .mime_str("application/json")
-            .map_err(|e| format!("Multipart error: {}", e))?;
-        form = form.part("analysis", part);
+        analysis = Some(bytes);
         uploaded_files.push("mesh_analysis.json".to_string());
     }
 
     // Use the precision scan ID (not mesh job ID) for the API endpoint
-    let url = format!(
-        "{}/precision-scans/{}/upload",
-        api_url.trim_end_matches('/'),
-        scan_id
-    );
-
-    let resp = client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .multipart(form)
-        .send()
-        .await
-        .map_err(|e| format!("Upload request failed: {}", e))?;
-
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
-        return Err(format!("Upload failed HTTP {}: {}", status, body));
-    }
-
-    let api_response: serde_json::Value = resp
-        .json()
+    let api_response = api
+        .upload_scan_results(
+            &scan_id,
+            &ScanResultsUpload {
+                job_id: job_id.clone(),
+                models,
+                analysis,
+            },
+        )
         .await
-        .unwrap_or_else(|_| serde_json::json!({"status": "ok"}));
+        .map_err(|e| format!("Upload failed: {}", e))?;
 
     let _ = app.emit(
         "nexcad-progress",
@@ -0,0 +1,574 @@
+// ---------------------------------------------------------------------------
+// render — Markdown and plain-text output for converted documents
+// ---------------------------------------------------------------------------
+// The converter always produces a full HTML document. This module walks that
+// HTML (a small, well-formed subset for our own converters; best effort for
+// passthrough .html files) and renders it as:
+//
+//   - CommonMark (GFM tables), with images either kept as data URIs or
+//     written out as sibling files and linked relatively
+//   - normalized plain text: collapsed whitespace, blank-line-separated
+//     blocks, "#" heading markers, "- " list items, tab-separated table rows
+//
+// Used by the AI-assist pipeline and for diffing conversions.
+// ---------------------------------------------------------------------------
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::path::Path;
+
+/// Where images found in the HTML end up in Markdown output.
+pub enum ImageOutput<'a> {
+    /// Keep `data:` URIs inline in the Markdown
+    Inline,
+    /// Decode embedded images into `dir` as `{stem}-image-N.{ext}` and link to
+    /// them as `{dir name}/{file}` — the Markdown is meant to be saved in
+    /// `dir`'s parent.
+    Files { dir: &'a Path, stem: &'a str },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Rendered {
+    pub content: String,
+    /// Image files written for `ImageOutput::Files`
+    pub image_files: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Flavor {
+    Markdown,
+    Text,
+}
+
+/// Render converter HTML as CommonMark.
+pub fn html_to_markdown(html: &str, images: &ImageOutput) -> Result<Rendered, String> {
+    let mut renderer = Renderer::new(Flavor::Markdown, images);
+    renderer.run(html)?;
+    Ok(Rendered {
+        content: normalize_blocks(&renderer.out),
+        image_files: renderer.image_files,
+    })
+}
+
+/// Render converter HTML as normalized plain text with heading markers.
+pub fn html_to_text(html: &str) -> String {
+    let mut renderer = Renderer::new(Flavor::Text, &ImageOutput::Inline);
+    // Text output never writes files, so this can't fail
+    let _ = renderer.run(html);
+    normalize_blocks(&renderer.out)
+}
+
+enum Token<'h> {
+    Open { name: String, attrs: &'h str },
+    Close(String),
+    Text(&'h str),
+}
+
+/// Split HTML into tags and text. Comments, doctypes and the contents of
+/// head/style/script/title are dropped.
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    let mut skip_until: Option<String> = None;
+
+    while !rest.is_empty() {
+        if let Some(stripped) = rest.strip_prefix("<!--") {
+            rest = match stripped.find("-->") {
+                Some(end) => &stripped[end + 3..],
+                None => "",
+            };
+            continue;
+        }
+        if rest.starts_with('<') {
+            let end = match rest.find('>') {
+                Some(e) => e,
+                None => {
+                    tokens.push(Token::Text(rest));
+                    break;
+                }
+            };
+            let inner = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            if inner.starts_with('!') || inner.starts_with('?') {
+                continue;
+            }
+            let (is_close, body) = match inner.strip_prefix('/') {
+                Some(b) => (true, b),
+                None => (false, inner),
+            };
+            let name_end = body
+                .find(|c: char| c.is_whitespace() || c == '/')
+                .unwrap_or(body.len());
+            let name = body[..name_end].to_ascii_lowercase();
+
+            if let Some(ref skip) = skip_until {
+                if is_close && &name == skip {
+                    skip_until = None;
+                }
+                continue;
+            }
+            if is_close {
+                tokens.push(Token::Close(name));
+            } else if matches!(name.as_str(), "head" | "style" | "script" | "title") {
+                skip_until = Some(name);
+            } else {
+                tokens.push(Token::Open { name, attrs: &body[name_end..] });
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            if skip_until.is_none() {
+                tokens.push(Token::Text(&rest[..end]));
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    tokens
+}
+
+/// Read one attribute value from a tag's attribute string.
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let lower = attrs.to_ascii_lowercase();
+    let mut search = 0;
+    while let Some(pos) = lower[search..].find(name) {
+        let start = search + pos;
+        let before_ok = start == 0 || lower.as_bytes()[start - 1].is_ascii_whitespace();
+        let after = lower[start + name.len()..].trim_start();
+        if before_ok && after.starts_with('=') {
+            let value_start = attrs.len() - after.len() + 1;
+            let value = attrs[value_start..].trim_start();
+            return Some(match value.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let inner = &value[1..];
+                    decode_entities(&inner[..inner.find(q).unwrap_or(inner.len())])
+                }
+                _ => decode_entities(
+                    value.split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or(""),
+                ),
+            });
+        }
+        search = start + name.len();
+    }
+    None
+}
+
+pub(crate) fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let end = match rest.find(';') {
+            Some(e) if e <= 10 => e,
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escape characters that would otherwise be read as Markdown syntax.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+struct ListState {
+    ordered: bool,
+    counter: u32,
+}
+
+struct TableState {
+    rows: Vec<Vec<String>>,
+    cell_start: Option<usize>,
+}
+
+struct Renderer<'a> {
+    flavor: Flavor,
+    images: &'a ImageOutput<'a>,
+    out: String,
+    image_files: Vec<String>,
+    lists: Vec<ListState>,
+    tables: Vec<TableState>,
+    /// Output offsets where open <a> and <blockquote> content begins
+    links: Vec<(usize, Option<String>)>,
+    quotes: Vec<usize>,
+    pre_depth: u32,
+    code_depth: u32,
+    image_counter: u32,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(flavor: Flavor, images: &'a ImageOutput<'a>) -> Self {
+        Self {
+            flavor,
+            images,
+            out: String::new(),
+            image_files: Vec::new(),
+            lists: Vec::new(),
+            tables: Vec::new(),
+            links: Vec::new(),
+            quotes: Vec::new(),
+            pre_depth: 0,
+            code_depth: 0,
+            image_counter: 0,
+        }
+    }
+
+    fn markdown(&self) -> bool {
+        self.flavor == Flavor::Markdown
+    }
+
+    fn block_break(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push_str(if self.out.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+    }
+
+    fn line_break(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn run(&mut self, html: &str) -> Result<(), String> {
+        for token in tokenize(html) {
+            match token {
+                Token::Text(text) => self.text(text),
+                Token::Open { name, attrs } => self.open(&name, attrs)?,
+                Token::Close(name) => self.close(&name),
+            }
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, raw: &str) {
+        let decoded = decode_entities(raw);
+        if self.pre_depth > 0 {
+            self.out.push_str(&decoded);
+            return;
+        }
+        let collapsed = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
+        if collapsed.is_empty() {
+            if decoded.chars().any(char::is_whitespace) && !self.out.ends_with([' ', '\n']) && !self.out.is_empty() {
+                self.out.push(' ');
+            }
+            return;
+        }
+        let leading = decoded.starts_with(char::is_whitespace);
+        let trailing = decoded.ends_with(char::is_whitespace);
+        if leading && !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+            self.out.push(' ');
+        }
+        if self.markdown() && self.code_depth == 0 {
+            self.out.push_str(&escape_markdown(&collapsed));
+        } else {
+            self.out.push_str(&collapsed);
+        }
+        if trailing {
+            self.out.push(' ');
+        }
+    }
+
+    fn open(&mut self, name: &str, attrs: &str) -> Result<(), String> {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.block_break();
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            "p" | "div" | "section" | "article" | "header" | "footer" => self.block_break(),
+            "br" => {
+                if self.markdown() && self.pre_depth == 0 {
+                    self.out.push_str("\\\n");
+                } else {
+                    self.out.push('\n');
+                }
+            }
+            "hr" => {
+                self.block_break();
+                self.out.push_str("---\n\n");
+            }
+            "pre" => {
+                self.block_break();
+                if self.markdown() {
+                    self.out.push_str("```\n");
+                }
+                self.pre_depth += 1;
+            }
+            "code" if self.pre_depth == 0 && self.markdown() => {
+                self.code_depth += 1;
+                self.out.push('`');
+            }
+            "strong" | "b" if self.markdown() => self.out.push_str("**"),
+            "em" | "i" if self.markdown() => self.out.push('*'),
+            "a" => self.links.push((self.out.len(), attr(attrs, "href"))),
+            "blockquote" => {
+                self.block_break();
+                self.quotes.push(self.out.len());
+            }
+            "ul" | "ol" => {
+                if self.lists.is_empty() {
+                    self.block_break();
+                } else {
+                    self.line_break();
+                }
+                self.lists.push(ListState { ordered: name == "ol", counter: 0 });
+            }
+            "li" => {
+                self.line_break();
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(list) if list.ordered && self.flavor == Flavor::Markdown => {
+                        list.counter += 1;
+                        format!("{}. ", list.counter)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.out.push_str(&"   ".repeat(depth));
+                self.out.push_str(&marker);
+            }
+            "table" => {
+                self.block_break();
+                self.tables.push(TableState { rows: Vec::new(), cell_start: None });
+            }
+            "tr" => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            "td" | "th" => {
+                let pos = self.out.len();
+                if let Some(table) = self.tables.last_mut() {
+                    if table.rows.is_empty() {
+                        table.rows.push(Vec::new());
+                    }
+                    table.cell_start = Some(pos);
+                }
+            }
+            "img" => self.image(attrs)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "p" | "div" | "section" | "article"
+            | "header" | "footer" => self.block_break(),
+            "pre" => {
+                self.pre_depth = self.pre_depth.saturating_sub(1);
+                self.line_break();
+                if self.markdown() {
+                    self.out.push_str("```");
+                }
+                self.block_break();
+            }
+            "code" if self.pre_depth == 0 && self.markdown() => {
+                self.code_depth = self.code_depth.saturating_sub(1);
+                self.out.push('`');
+            }
+            "strong" | "b" if self.markdown() => self.out.push_str("**"),
+            "em" | "i" if self.markdown() => self.out.push('*'),
+            "a" => {
+                if let Some((start, href)) = self.links.pop() {
+                    if let (true, Some(href)) = (self.markdown(), href) {
+                        let label = self.out.split_off(start.min(self.out.len()));
+                        let label = label.trim();
+                        if label.is_empty() {
+                            self.out.push_str(&format!("<{}>", href));
+                        } else {
+                            self.out.push_str(&format!("[{}]({})", label, href.replace(' ', "%20")));
+                        }
+                    }
+                }
+            }
+            "blockquote" => {
+                if let Some(start) = self.quotes.pop() {
+                    let inner = self.out.split_off(start.min(self.out.len()));
+                    let prefix = if self.markdown() { "> " } else { "    " };
+                    let quoted: Vec<String> = inner
+                        .trim()
+                        .lines()
+                        .map(|l| if l.is_empty() { prefix.trim_end().to_string() } else { format!("{}{}", prefix, l) })
+                        .collect();
+                    self.out.push_str(&quoted.join("\n"));
+                    self.block_break();
+                }
+            }
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block_break();
+                }
+            }
+            "td" | "th" => {
+                let out_len = self.out.len();
+                if let Some(table) = self.tables.last_mut() {
+                    if let Some(start) = table.cell_start.take() {
+                        let cell = self.out.split_off(start.min(out_len));
+                        let cell = cell.split_whitespace().collect::<Vec<_>>().join(" ");
+                        if let Some(row) = table.rows.last_mut() {
+                            row.push(cell);
+                        }
+                    }
+                }
+            }
+            "table" => {
+                if let Some(table) = self.tables.pop() {
+                    let rendered = self.render_table(table);
+                    self.out.push_str(&rendered);
+                    self.block_break();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn render_table(&self, table: TableState) -> String {
+        let rows: Vec<Vec<String>> = table.rows.into_iter().filter(|r| !r.is_empty()).collect();
+        if rows.is_empty() {
+            return String::new();
+        }
+        if !self.markdown() {
+            return rows.iter().map(|r| r.join("\t")).collect::<Vec<_>>().join("\n");
+        }
+
+        let columns = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let line = |row: &Vec<String>| {
+            let mut cells: Vec<String> = row.clone();
+            cells.resize(columns, String::new());
+            format!("| {} |", cells.join(" | "))
+        };
+        let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+        lines.extend(rows[1..].iter().map(line));
+        lines.join("\n")
+    }
+
+    fn image(&mut self, attrs: &str) -> Result<(), String> {
+        let src = match attr(attrs, "src") {
+            Some(s) if !s.is_empty() => s,
+            _ => return Ok(()),
+        };
+        let alt = attr(attrs, "alt").unwrap_or_default();
+
+        if !self.markdown() {
+            if !alt.is_empty() {
+                self.out.push_str(&format!("[image: {}]", alt));
+            }
+            return Ok(());
+        }
+
+        let target = match (self.images, src.strip_prefix("data:")) {
+            (ImageOutput::Files { dir, stem }, Some(data)) => {
+                self.image_counter += 1;
+                self.write_image(dir, stem, data)?.unwrap_or(src)
+            }
+            _ => src,
+        };
+        self.out.push_str(&format!("![{}]({})", escape_markdown(&alt), target));
+        Ok(())
+    }
+
+    /// Decode a `data:` URI body into a sibling file and return its relative link.
+    fn write_image(&mut self, dir: &Path, stem: &str, data: &str) -> Result<Option<String>, String> {
+        let (meta, payload) = match data.split_once(',') {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+        if !meta.ends_with(";base64") {
+            return Ok(None);
+        }
+        let ext = match meta.trim_end_matches(";base64") {
+            "image/png" => "png",
+            "image/jpeg" => "jpg",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/bmp" => "bmp",
+            "image/svg+xml" => "svg",
+            "image/jp2" => "jp2",
+            "image/x-emf" => "emf",
+            "image/x-wmf" => "wmf",
+            _ => "bin",
+        };
+        let bytes = match BASE64.decode(payload.trim()) {
+            Ok(b) => b,
+            Err(_) => return Ok(None),
+        };
+
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create image directory: {}", e))?;
+        let file_name = format!("{}-image-{}.{}", stem, self.image_counter, ext);
+        let path = dir.join(&file_name);
+        std::fs::write(&path, &bytes)
+            .map_err(|e| format!("Failed to write {}: {}", file_name, e))?;
+        self.image_files.push(path.to_string_lossy().to_string());
+
+        let dir_name = dir.file_name().and_then(|n| n.to_str()).unwrap_or(".");
+        Ok(Some(format!("{}/{}", dir_name, file_name).replace(' ', "%20")))
+    }
+}
+
+/// Trim trailing whitespace on each line and collapse runs of blank lines.
+fn normalize_blocks(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut blank_run = 0;
+    for line in s.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    let trimmed = out.trim();
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", trimmed)
+    }
+}
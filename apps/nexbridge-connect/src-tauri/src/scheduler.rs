@@ -0,0 +1,355 @@
+// ---------------------------------------------------------------------------
+// scheduler — background auto-sync
+// ---------------------------------------------------------------------------
+// While auto sync is on (SyncSettings.auto_sync_enabled), a background task
+// runs a sync every `sync_interval_minutes`:
+//
+//   1. contacts — changes to the selected device contacts are pushed to
+//      Nexus (see contact_sync.rs)
+//   2. folders  — every folder passed to `scan_folder` is rescanned
+//   3. uploads  — CONVERTED documents with cached HTML are uploaded
+//
+// The next run is timed from `last_sync_at`, so restarting the app doesn't
+// reset the schedule, and is moved by up to ±10% of the interval (at most
+// 5 minutes) so machines that start together don't all sync together.
+// Settings changes wake the loop to recompute it.
+//
+// A scheduled run is skipped while on battery below `min_battery_pct`;
+// skipped runs leave `last_sync_at` alone and are retried a few minutes
+// later, as is a run that finds a "Sync now" still in progress. (The timing
+// and battery rules are in sync_schedule.rs.) Steps that need a session or
+// unlocked local data are left out when those aren't available.
+//
+// The last HISTORY_LIMIT runs are kept in memory for `get_sync_history`,
+// and each finished run is emitted as "auto-sync-completed".
+// ---------------------------------------------------------------------------
+
+use crate::contact_providers;
+use crate::contact_sync;
+use crate::documents;
+use crate::sync_schedule;
+use crate::index::DocumentStatus;
+use crate::system_info;
+use crate::uploader::UploadRequest;
+use crate::app::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+const HISTORY_LIMIT: usize = 50;
+/// Let the app finish starting before the first run
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+/// How soon a run skipped for low battery is tried again
+const SKIP_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+/// Actor recorded in the status history for documents the scheduler uploads
+const ACTOR: &str = "auto-sync";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncTrigger {
+    Scheduled,
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOutcome {
+    Completed,
+    /// Nothing ran (see `skipped_reason`)
+    Skipped,
+    /// Ran, but at least one step failed (see `errors`)
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncRun {
+    pub started_at: String,
+    pub finished_at: String,
+    pub trigger: SyncTrigger,
+    pub outcome: SyncOutcome,
+    pub skipped_reason: Option<String>,
+    pub contacts_created: u32,
+    pub contacts_updated: u32,
+    pub contacts_deleted: u32,
+    pub folders_rescanned: u32,
+    pub documents_new: u32,
+    pub documents_updated: u32,
+    pub uploads_completed: u32,
+    pub uploads_failed: u32,
+    pub errors: Vec<String>,
+}
+
+impl SyncRun {
+    fn start(trigger: SyncTrigger) -> Self {
+        Self {
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: String::new(),
+            trigger,
+            outcome: SyncOutcome::Completed,
+            skipped_reason: None,
+            contacts_created: 0,
+            contacts_updated: 0,
+            contacts_deleted: 0,
+            folders_rescanned: 0,
+            documents_new: 0,
+            documents_updated: 0,
+            uploads_completed: 0,
+            uploads_failed: 0,
+            errors: vec![],
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        self.finished_at = chrono::Utc::now().to_rfc3339();
+        if self.skipped_reason.is_some() {
+            self.outcome = SyncOutcome::Skipped;
+        } else if !self.errors.is_empty() {
+            self.outcome = SyncOutcome::Failed;
+        }
+        self
+    }
+}
+
+pub struct SyncScheduler {
+    history: Mutex<VecDeque<SyncRun>>,
+    running: AtomicBool,
+    wake: Notify,
+}
+
+impl SyncScheduler {
+    pub fn new() -> Self {
+        Self {
+            history: Mutex::new(VecDeque::with_capacity(HISTORY_LIMIT)),
+            running: AtomicBool::new(false),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Recompute the next run time (called when the settings change).
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Most recent runs first.
+    pub fn history(&self, limit: usize) -> Vec<SyncRun> {
+        self.history.lock().unwrap().iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Run a sync now unless one is already in progress.
+    pub async fn sync(&self, app: &AppHandle, trigger: SyncTrigger) -> Result<SyncRun, String> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err("A sync is already running".to_string());
+        }
+        let run = perform_sync(app, trigger).await;
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(reason) = &run.skipped_reason {
+            eprintln!("[scheduler] Sync skipped: {}", reason);
+        }
+        for error in &run.errors {
+            eprintln!("[scheduler] {}", error);
+        }
+        {
+            let mut history = self.history.lock().unwrap();
+            if history.len() == HISTORY_LIMIT {
+                history.pop_front();
+            }
+            history.push_back(run.clone());
+        }
+        let _ = app.emit("auto-sync-completed", &run);
+        Ok(run)
+    }
+}
+
+impl Default for SyncScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The scheduler loop; spawned once at startup.
+pub async fn run(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let scheduler = &state.scheduler;
+    let mut not_before = Instant::now() + STARTUP_DELAY;
+
+    loop {
+        let settings = state.settings.lock().unwrap().clone();
+        if !settings.auto_sync_enabled {
+            scheduler.wake.notified().await;
+            continue;
+        }
+
+        let interval = Duration::from_secs(u64::from(settings.sync_interval_minutes) * 60);
+        let due = next_due(settings.last_sync_at.as_deref(), interval).max(not_before);
+        tokio::select! {
+            _ = tokio::time::sleep_until(due) => {}
+            _ = scheduler.wake.notified() => continue,
+        }
+
+        match scheduler.sync(&app, SyncTrigger::Scheduled).await {
+            Ok(run) if run.outcome != SyncOutcome::Skipped => {}
+            // Skipped, or another sync is running and `last_sync_at` won't
+            // move until it finishes: don't spin on the same due time
+            _ => not_before = Instant::now() + SKIP_RETRY_DELAY,
+        }
+    }
+}
+
+/// When the next run is due (see `sync_schedule::next_delay`).
+fn next_due(last_sync_at: Option<&str>, interval: Duration) -> Instant {
+    Instant::now() + sync_schedule::next_delay(last_sync_at, interval, chrono::Utc::now())
+}
+
+async fn perform_sync(app: &AppHandle, trigger: SyncTrigger) -> SyncRun {
+    let state = app.state::<AppState>();
+    let settings = state.settings.lock().unwrap().clone();
+    let mut run = SyncRun::start(trigger);
+
+    // Only scheduled runs wait for the charger; "Sync now" always runs
+    if trigger == SyncTrigger::Scheduled {
+        if let Some(reason) = low_battery(settings.min_battery_pct).await {
+            run.skipped_reason = Some(reason);
+            return run.finish();
+        }
+    }
+
+    let signed_in = state.nexus_api.base_url().is_some();
+    let unlocked = state.data_key.lock().unwrap().is_some();
+
+    if signed_in && unlocked {
+        if let Err(e) = sync_contacts(app, &settings.selected_contact_ids, &mut run).await {
+            run.errors.push(format!("Contact sync failed: {}", e));
+        }
+    }
+    if unlocked {
+        rescan_folders(app, &mut run).await;
+    }
+    if signed_in && unlocked {
+        if let Err(e) = upload_converted(app, &mut run).await {
+            run.errors.push(format!("Uploads failed: {}", e));
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    if let Err(e) = crate::app::change_settings(app, &state, |settings| settings.last_sync_at = Some(now)) {
+        run.errors.push(format!("Failed to record sync time: {}", e));
+    }
+    run.finish()
+}
+
+/// Why a run should wait, if the machine is on battery below `min_pct`.
+async fn low_battery(min_pct: u32) -> Option<String> {
+    if min_pct == 0 {
+        return None;
+    }
+    let (battery_pct, on_ac) = tauri::async_runtime::spawn_blocking(system_info::read_battery)
+        .await
+        .ok()?;
+    sync_schedule::battery_skip_reason(min_pct, battery_pct, on_ac)
+}
+
+async fn sync_contacts(app: &AppHandle, selected_ids: &[String], run: &mut SyncRun) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    // Nothing selected and nothing synced before: don't ask the OS for contacts
+    if selected_ids.is_empty() && state.contact_groups.get_sync_states().map_err(|e| e.to_string())?.is_empty() {
+        return Ok(());
+    }
+
+    let device_contacts = contact_providers::load_contacts(&state).await?;
+    let summary = contact_sync::sync_contacts(&state.nexus_api, &state.contact_groups, &device_contacts, selected_ids).await?;
+    run.contacts_created = summary.created;
+    run.contacts_updated = summary.updated;
+    run.contacts_deleted = summary.deleted;
+    if summary.failed > 0 {
+        run.errors.push(format!("{} contact(s) failed to sync", summary.failed));
+    }
+    Ok(())
+}
+
+async fn rescan_folders(app: &AppHandle, run: &mut SyncRun) {
+    let state = app.state::<AppState>();
+    let roots = match state.document_index.get_scan_roots() {
+        Ok(roots) => roots,
+        Err(e) => {
+            run.errors.push(format!("Failed to list scanned folders: {}", e));
+            return;
+        }
+    };
+
+    for root in roots {
+        let handle = app.clone();
+        let path = root.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            let state = handle.state::<AppState>();
+            let result = documents::scan_directory(&path, &state.document_index)?;
+            let _ = state.document_index.record_scan_root(&path);
+            Ok::<_, String>(result)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+
+        match result {
+            Ok(result) => {
+                run.folders_rescanned += 1;
+                run.documents_new += result.documents_new;
+                run.documents_updated += result.documents_updated;
+            }
+            Err(e) => run.errors.push(format!("Rescan of {} failed: {}", root, e)),
+        }
+    }
+}
+
+/// Upload every CONVERTED document, unless the user has uploads paused or a
+/// batch from the Documents tab is still going.
+async fn upload_converted(app: &AppHandle, run: &mut SyncRun) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let queue = &state.upload_queue;
+    let progress = queue.get_progress();
+    if progress.is_paused || progress.completed + progress.failed < progress.total {
+        return Ok(());
+    }
+
+    let docs = state.document_index
+        .get_documents_by_status(DocumentStatus::Converted)
+        .map_err(|e| e.to_string())?;
+    if docs.is_empty() {
+        return Ok(());
+    }
+    queue.reset();
+    queue.total.store(docs.len() as u32, Ordering::SeqCst);
+
+    for doc in docs {
+        let html = match state.conversion_cache.load(&state.document_index, &doc.id) {
+            Ok(html) => html,
+            Err(e) => {
+                run.uploads_failed += 1;
+                run.errors.push(format!("{}: {}", doc.file_name, e));
+                continue;
+            }
+        };
+        let category = doc.category.as_deref().unwrap_or("local-upload");
+        let request = UploadRequest::for_document(&doc, html, category);
+        let result = queue.upload_document(&state.nexus_api, request).await;
+
+        if result.success {
+            run.uploads_completed += 1;
+            let _ = match &result.nexus_doc_id {
+                Some(nexus_id) => state.document_index.mark_uploaded(&doc.id, nexus_id, ACTOR),
+                None => state.document_index.update_status(&doc.id, DocumentStatus::Uploaded, None, ACTOR, None),
+            };
+        } else {
+            let msg = result.error.unwrap_or_else(|| "Upload failed".to_string());
+            run.uploads_failed += 1;
+            run.errors.push(format!("{}: {}", doc.file_name, msg));
+            let _ = state.document_index.update_status(&doc.id, DocumentStatus::Failed, Some(&msg), ACTOR, None);
+        }
+    }
+    Ok(())
+}
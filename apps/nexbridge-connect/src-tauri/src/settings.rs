@@ -0,0 +1,209 @@
+// ---------------------------------------------------------------------------
+// settings — persisted SyncSettings
+// ---------------------------------------------------------------------------
+// Settings live in {app_data_dir}/sync_settings.json:
+//
+//   { "version": 1, "settings": { ...SyncSettings... } }
+//
+// Fields missing from the file (older versions, hand edits) take their
+// defaults, so new settings can be added without a migration; `version`
+// is bumped only when an existing field changes meaning. Writes go through a
+// temp file and a rename so a crash can't leave the file half-written.
+//
+// Before this file existed the frontend kept the settings in its own store
+// (settings.json, key "syncSettings"); the first load imports them from
+// there.
+// ---------------------------------------------------------------------------
+
+use crate::bandwidth::TransferLimits;
+use crate::cache::DEFAULT_CACHE_BUDGET_BYTES;
+use crate::contact_providers::ContactProviderConfig;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const SETTINGS_FILE: &str = "sync_settings.json";
+const SETTINGS_VERSION: u32 = 1;
+/// The frontend store the settings used to live in
+const LEGACY_STORE_FILE: &str = "settings.json";
+
+pub const MIN_SYNC_INTERVAL_MINUTES: u32 = 5;
+pub const MAX_SYNC_INTERVAL_MINUTES: u32 = 24 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub auto_sync_enabled: bool,
+    pub sync_interval_minutes: u32,
+    pub selected_contact_ids: Vec<String>,
+    pub launch_at_startup: bool,
+    pub last_sync_at: Option<String>,
+    /// Auto-sync skips runs while on battery below this charge (0 = never skip)
+    pub min_battery_pct: u32,
+    /// Bandwidth caps, transfer windows and metered-connection pause
+    pub transfer_limits: TransferLimits,
+    /// CardDAV accounts and vCard folders read alongside the native contacts
+    pub contact_providers: Vec<ContactProviderConfig>,
+    /// Size budget for the converted-HTML cache (compressed bytes on disk)
+    pub cache_budget_bytes: u64,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            auto_sync_enabled: false,
+            sync_interval_minutes: 15,
+            selected_contact_ids: vec![],
+            launch_at_startup: false,
+            last_sync_at: None,
+            min_battery_pct: 20,
+            transfer_limits: TransferLimits::default(),
+            contact_providers: vec![],
+            cache_budget_bytes: DEFAULT_CACHE_BUDGET_BYTES,
+        }
+    }
+}
+
+impl SyncSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_SYNC_INTERVAL_MINUTES..=MAX_SYNC_INTERVAL_MINUTES).contains(&self.sync_interval_minutes) {
+            return Err(format!(
+                "Sync interval must be between {} minutes and {} hours",
+                MIN_SYNC_INTERVAL_MINUTES,
+                MAX_SYNC_INTERVAL_MINUTES / 60
+            ));
+        }
+        if self.min_battery_pct > 100 {
+            return Err("Minimum battery level must be a percentage (0-100)".to_string());
+        }
+        if self.cache_budget_bytes == 0 {
+            return Err("Cache budget must be greater than 0".to_string());
+        }
+        if let Some(at) = &self.last_sync_at {
+            chrono::DateTime::parse_from_rfc3339(at)
+                .map_err(|_| format!("Invalid last sync time '{}'", at))?;
+        }
+        for (i, provider) in self.contact_providers.iter().enumerate() {
+            provider.validate()?;
+            if self.contact_providers[..i].iter().any(|p| p.id() == provider.id()) {
+                return Err(format!("Duplicate contact source id '{}'", provider.id()));
+            }
+        }
+        self.transfer_limits.validate()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    version: u32,
+    settings: SyncSettings,
+}
+
+/// Load the saved settings, falling back to the frontend's old store and
+/// then to defaults. Invalid saved values are clamped or reset rather than
+/// refusing to start.
+pub fn load(app_dir: &Path) -> SyncSettings {
+    let path = app_dir.join(SETTINGS_FILE);
+    let settings = match std::fs::read_to_string(&path) {
+        Ok(raw) => match serde_json::from_str::<SettingsFile>(&raw) {
+            Ok(file) => {
+                if file.version > SETTINGS_VERSION {
+                    eprintln!(
+                        "[settings] {} is from a newer version ({}); unknown fields are ignored",
+                        path.display(),
+                        file.version
+                    );
+                }
+                file.settings
+            }
+            Err(e) => {
+                eprintln!("[settings] Ignoring unreadable {}: {}", path.display(), e);
+                SyncSettings::default()
+            }
+        },
+        Err(_) => match load_legacy(app_dir) {
+            Some(settings) => {
+                eprintln!("[settings] Imported settings from {}", LEGACY_STORE_FILE);
+                let settings = sanitize(settings);
+                if let Err(e) = save(app_dir, &settings) {
+                    eprintln!("[settings] {}", e);
+                }
+                return settings;
+            }
+            None => SyncSettings::default(),
+        },
+    };
+    sanitize(settings)
+}
+
+/// Write sync_settings.json via a temp file so a crash can't leave it half-written.
+pub fn save(app_dir: &Path, settings: &SyncSettings) -> Result<(), String> {
+    let path = app_dir.join(SETTINGS_FILE);
+    let tmp = app_dir.join(format!("{}.tmp", SETTINGS_FILE));
+    let file = SettingsFile {
+        version: SETTINGS_VERSION,
+        settings: settings.clone(),
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Clamp the interval and reset whichever other parts fail validation.
+fn sanitize(mut settings: SyncSettings) -> SyncSettings {
+    let defaults = SyncSettings::default();
+    if settings.validate().is_ok() {
+        return settings;
+    }
+    eprintln!("[settings] Correcting invalid saved values");
+    settings.sync_interval_minutes = settings
+        .sync_interval_minutes
+        .clamp(MIN_SYNC_INTERVAL_MINUTES, MAX_SYNC_INTERVAL_MINUTES);
+    settings.min_battery_pct = settings.min_battery_pct.min(100);
+    if settings
+        .last_sync_at
+        .as_deref()
+        .is_some_and(|at| chrono::DateTime::parse_from_rfc3339(at).is_err())
+    {
+        settings.last_sync_at = None;
+    }
+    if settings.cache_budget_bytes == 0 {
+        settings.cache_budget_bytes = defaults.cache_budget_bytes;
+    }
+    if settings.transfer_limits.validate().is_err() {
+        settings.transfer_limits = defaults.transfer_limits;
+    }
+    let mut ids = std::collections::HashSet::new();
+    settings
+        .contact_providers
+        .retain(|p| p.validate().is_ok() && ids.insert(p.id().to_string()));
+    settings
+}
+
+/// Settings as the frontend stored them (camelCase, under "syncSettings").
+fn load_legacy(app_dir: &Path) -> Option<SyncSettings> {
+    let raw = std::fs::read_to_string(app_dir.join(LEGACY_STORE_FILE)).ok()?;
+    let mut store: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    let legacy = store.get_mut("syncSettings")?.take();
+    serde_json::from_value(snake_case_keys(legacy)).ok()
+}
+
+fn snake_case_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| {
+                let mut snake = String::with_capacity(key.len() + 4);
+                for c in key.chars() {
+                    if c.is_ascii_uppercase() {
+                        snake.push('_');
+                        snake.push(c.to_ascii_lowercase());
+                    } else {
+                        snake.push(c);
+                    }
+                }
+                (snake, snake_case_keys(value))
+            })
+            .collect(),
+        other => other,
+    }
+}
@@ -0,0 +1,367 @@
+// ---------------------------------------------------------------------------
+// smart_groups — contact groups defined by saved rules
+// ---------------------------------------------------------------------------
+// A group with `SmartRules` (contact_groups.rules, JSON) is a smart group.
+// Its conditions each name a contact field, an operator and values:
+//
+//   company contains "Electric"         substring, case-insensitive
+//   state equals "TX"                   "Texas" and "tx" match too
+//   email_domain equals [a.com, b.com]  equal to any of the values
+//   mail_score at_least 60              construction score from Mail.app
+//                                       (mail_index.rs); macOS only
+//
+// and `mode` says whether all or any of them must hold. Membership is
+// worked out on demand against the current contacts, never stored:
+//
+//   (rule matches + static members) - exclusions - ignored contacts
+//
+// Static members are added by hand as for any group; removing a matched
+// contact by hand records an exclusion. Ignored contacts are left out
+// unless `exclude_ignored` is off.
+//
+// Mail.app scores are read at most once every ten minutes. When they can't
+// be read, mail score conditions match no one and the result carries a
+// warning instead of failing.
+// ---------------------------------------------------------------------------
+
+use crate::contact_duplicates::normalize_phone;
+use crate::contact_groups::ContactGroupIndex;
+use crate::contact_normalizer;
+use crate::contacts::Contact;
+#[cfg(target_os = "macos")]
+use crate::mail_index;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long Mail.app scores are reused before reading the mailboxes again.
+const MAIL_SCORES_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartRules {
+    #[serde(default)]
+    pub mode: RuleMode,
+    pub conditions: Vec<SmartCondition>,
+    #[serde(default = "default_true")]
+    pub exclude_ignored: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleMode {
+    #[default]
+    All,
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartCondition {
+    pub field: ContactRuleField,
+    pub op: ConditionOp,
+    /// Matched against any of them; one number for `at_least`, none for
+    /// `is_empty`/`is_not_empty`
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContactRuleField {
+    /// Display name, else first and last name
+    Name,
+    Company,
+    JobTitle,
+    /// Any of the contact's emails
+    Email,
+    /// The part after "@" of any of the contact's emails
+    EmailDomain,
+    /// Any of the contact's phones
+    Phone,
+    City,
+    State,
+    Zip,
+    Country,
+    /// Highest Mail.app construction score of the contact's emails (0-100)
+    MailScore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOp {
+    Contains,
+    NotContains,
+    Equals,
+    NotEquals,
+    StartsWith,
+    IsEmpty,
+    IsNotEmpty,
+    /// Only for `mail_score`
+    AtLeast,
+}
+
+/// Mail.app construction scores by lowercased email address.
+pub type MailScores = HashMap<String, u32>;
+
+/// Hand-made membership of one group.
+#[derive(Debug, Clone, Default)]
+pub struct GroupLists {
+    pub members: HashSet<String>,
+    pub excluded: HashSet<String>,
+    pub ignored: HashSet<String>,
+}
+
+/// Members of one group, worked out against the current contacts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMembers {
+    pub group_id: String,
+    pub contact_ids: Vec<String>,
+    /// Why some conditions couldn't be checked (e.g. no Mail.app data);
+    /// they match no one
+    pub warning: Option<String>,
+}
+
+/// Contacts some rules match now, for the smart group editor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartGroupPreview {
+    pub contact_ids: Vec<String>,
+    /// As for GroupMembers
+    pub warning: Option<String>,
+}
+
+/// Validate rules before they are saved.
+pub fn validate_rules(rules: &SmartRules) -> Result<(), String> {
+    if rules.conditions.is_empty() {
+        return Err("A smart group needs at least one condition".to_string());
+    }
+    for condition in &rules.conditions {
+        let values: Vec<&str> = condition.values.iter().map(|v| v.trim()).filter(|v| !v.is_empty()).collect();
+        match (condition.field, condition.op) {
+            (ContactRuleField::MailScore, ConditionOp::AtLeast) => {
+                let score = values.first().and_then(|v| v.parse::<u32>().ok());
+                if values.len() != 1 || score.is_none_or(|s| s > 100) {
+                    return Err("Mail score needs one number from 0 to 100".to_string());
+                }
+            }
+            (ContactRuleField::MailScore, _) => {
+                return Err("Mail score can only be compared with \"at least\"".to_string());
+            }
+            (_, ConditionOp::AtLeast) => {
+                return Err("\"At least\" only applies to the mail score".to_string());
+            }
+            (_, ConditionOp::IsEmpty | ConditionOp::IsNotEmpty) => {}
+            _ if values.is_empty() => {
+                return Err("Each condition needs a value".to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub fn uses_mail_score(rules: &SmartRules) -> bool {
+    rules.conditions.iter().any(|c| c.field == ContactRuleField::MailScore)
+}
+
+/// Whether `contact` satisfies the rules. Without `mail_scores`, mail
+/// score conditions don't hold.
+pub fn matches(rules: &SmartRules, contact: &Contact, mail_scores: Option<&MailScores>) -> bool {
+    let mut results = rules.conditions.iter().map(|c| condition_holds(c, contact, mail_scores));
+    match rules.mode {
+        RuleMode::All => results.all(|r| r),
+        RuleMode::Any => results.any(|r| r),
+    }
+}
+
+/// Ids of the group's members among `contacts`, in contact order.
+pub fn evaluate(
+    rules: &SmartRules,
+    contacts: &[Contact],
+    lists: &GroupLists,
+    mail_scores: Option<&MailScores>,
+) -> Vec<String> {
+    contacts
+        .iter()
+        .filter(|c| lists.members.contains(&c.id) || matches(rules, c, mail_scores))
+        .filter(|c| !lists.excluded.contains(&c.id))
+        .filter(|c| !(rules.exclude_ignored && lists.ignored.contains(&c.id)))
+        .map(|c| c.id.clone())
+        .collect()
+}
+
+/// Members of every group, or just `only`: smart groups evaluated against
+/// `contacts`, static groups as stored. Mail.app is only read when a rule
+/// needs it.
+pub fn group_members(
+    index: &ContactGroupIndex,
+    contacts: &[Contact],
+    only: Option<&str>,
+) -> Result<Vec<GroupMembers>, String> {
+    let mut groups = index.list_groups().map_err(|e| e.to_string())?;
+    groups.retain(|g| only.is_none_or(|id| g.id == id));
+    let mut members = index.get_group_members().map_err(|e| e.to_string())?;
+    let mut exclusions = index.get_group_exclusions().map_err(|e| e.to_string())?;
+    let ignored: HashSet<String> = index.get_ignored_contacts().map_err(|e| e.to_string())?.into_iter().collect();
+    // Duplicates merged into another contact belong to no group
+    let merged: HashSet<String> = index.get_merges().map_err(|e| e.to_string())?.into_iter().map(|(m, _)| m).collect();
+    let contacts: Vec<Contact> = contacts.iter().filter(|c| !merged.contains(&c.id)).cloned().collect();
+
+    let mail_scores = if groups.iter().flat_map(|g| &g.rules).any(uses_mail_score) {
+        Some(mail_scores())
+    } else {
+        None
+    };
+
+    Ok(groups
+        .into_iter()
+        .map(|group| {
+            let static_members = members.remove(&group.id).unwrap_or_default();
+            let Some(rules) = &group.rules else {
+                return GroupMembers { group_id: group.id, contact_ids: static_members, warning: None };
+            };
+            let lists = GroupLists {
+                members: static_members.into_iter().collect(),
+                excluded: exclusions.remove(&group.id).unwrap_or_default().into_iter().collect(),
+                ignored: ignored.clone(),
+            };
+            let (scores, warning) = usable_scores(mail_scores.as_ref().filter(|_| uses_mail_score(rules)));
+            GroupMembers {
+                contact_ids: evaluate(rules, &contacts, &lists, scores),
+                group_id: group.id,
+                warning,
+            }
+        })
+        .collect())
+}
+
+/// What the rules match among `contacts`, with the same warning as
+/// group_members when mail score conditions can't be checked.
+pub fn preview(rules: &SmartRules, contacts: &[Contact], lists: &GroupLists) -> SmartGroupPreview {
+    let mail_scores = uses_mail_score(rules).then(mail_scores);
+    let (scores, warning) = usable_scores(mail_scores.as_ref());
+    SmartGroupPreview { contact_ids: evaluate(rules, contacts, lists, scores), warning }
+}
+
+/// Construction scores from Mail.app, which only exists on macOS. Read at
+/// most once per MAIL_SCORES_TTL; every group and preview shares them.
+pub fn mail_scores() -> Result<Arc<MailScores>, String> {
+    type Cached = Option<(Instant, Result<Arc<MailScores>, String>)>;
+    static CACHE: OnceLock<Mutex<Cached>> = OnceLock::new();
+    let mut cached = CACHE.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    if let Some((read_at, scores)) = cached.as_ref() {
+        if read_at.elapsed() < MAIL_SCORES_TTL {
+            return scores.clone();
+        }
+    }
+    let scores = read_mail_scores().map(Arc::new);
+    *cached = Some((Instant::now(), scores.clone()));
+    scores
+}
+
+fn read_mail_scores() -> Result<MailScores, String> {
+    #[cfg(target_os = "macos")]
+    return mail_index::construction_scores();
+    #[cfg(not(target_os = "macos"))]
+    Err("Mail scores need Mail.app (macOS only)".to_string())
+}
+
+/// The scores to evaluate with, or why mail score conditions were skipped.
+fn usable_scores(mail_scores: Option<&Result<Arc<MailScores>, String>>) -> (Option<&MailScores>, Option<String>) {
+    match mail_scores {
+        Some(Ok(scores)) => (Some(scores), None),
+        Some(Err(e)) => (None, Some(format!("Mail score conditions skipped: {}", e))),
+        None => (None, None),
+    }
+}
+
+// ============ Conditions ============
+
+fn condition_holds(condition: &SmartCondition, contact: &Contact, mail_scores: Option<&MailScores>) -> bool {
+    let needles: Vec<String> = condition
+        .values
+        .iter()
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect();
+
+    if condition.field == ContactRuleField::MailScore {
+        let threshold = needles.first().and_then(|v| v.parse::<u32>().ok());
+        let score = mail_scores.map(|scores| {
+            emails(contact)
+                .filter_map(|e| scores.get(&e.trim().to_lowercase()).copied())
+                .max()
+                .unwrap_or(0)
+        });
+        return matches!((score, threshold, condition.op), (Some(s), Some(t), ConditionOp::AtLeast) if s >= t);
+    }
+
+    let values = field_values(contact, condition.field);
+    let any = |f: &dyn Fn(&str, &str) -> bool| values.iter().any(|v| needles.iter().any(|n| f(v, n)));
+    let equals = |value: &str, needle: &str| values_equal(condition.field, value, needle);
+    match condition.op {
+        ConditionOp::Contains => any(&|v, n| v.contains(n)),
+        ConditionOp::NotContains => !any(&|v, n| v.contains(n)),
+        ConditionOp::Equals => any(&equals),
+        ConditionOp::NotEquals => !any(&equals),
+        ConditionOp::StartsWith => any(&|v, n| v.starts_with(n)),
+        ConditionOp::IsEmpty => values.is_empty(),
+        ConditionOp::IsNotEmpty => !values.is_empty(),
+        ConditionOp::AtLeast => false,
+    }
+}
+
+/// Lowercased, non-blank values of a field.
+fn field_values(contact: &Contact, field: ContactRuleField) -> Vec<String> {
+    let values: Vec<String> = match field {
+        ContactRuleField::Name => {
+            let parts = [contact.first_name.as_deref(), contact.last_name.as_deref()];
+            let from_parts = parts.iter().flatten().copied().collect::<Vec<_>>().join(" ");
+            vec![contact.display_name.clone().filter(|d| !d.trim().is_empty()).unwrap_or(from_parts)]
+        }
+        ContactRuleField::Company => contact.company.iter().cloned().collect(),
+        ContactRuleField::JobTitle => contact.job_title.iter().cloned().collect(),
+        ContactRuleField::Email => emails(contact).map(String::from).collect(),
+        ContactRuleField::EmailDomain => emails(contact)
+            .filter_map(|e| e.rsplit_once('@').map(|(_, domain)| domain.to_string()))
+            .collect(),
+        ContactRuleField::Phone => contact.phone.iter().chain(&contact.all_phones).cloned().collect(),
+        ContactRuleField::City => contact.city.iter().cloned().collect(),
+        ContactRuleField::State => contact.state.iter().cloned().collect(),
+        ContactRuleField::Zip => contact.zip.iter().cloned().collect(),
+        ContactRuleField::Country => contact.country.iter().cloned().collect(),
+        ContactRuleField::MailScore => vec![],
+    };
+    values
+        .into_iter()
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+fn emails(contact: &Contact) -> impl Iterator<Item = &str> {
+    contact.email.iter().chain(&contact.all_emails).map(String::as_str)
+}
+
+/// Equality that knows "Texas" is "TX", "(512) 555-0100" is "+15125550100"
+/// and "@example.com" is "example.com".
+fn values_equal(field: ContactRuleField, value: &str, needle: &str) -> bool {
+    if value == needle {
+        return true;
+    }
+    match field {
+        ContactRuleField::State => {
+            let value = contact_normalizer::state_code(value);
+            value.is_some() && value == contact_normalizer::state_code(needle)
+        }
+        ContactRuleField::Phone => {
+            let value = normalize_phone(value);
+            value.is_some() && value == normalize_phone(needle)
+        }
+        ContactRuleField::EmailDomain => value == needle.trim_start_matches('@'),
+        _ => false,
+    }
+}
@@ -0,0 +1,53 @@
+// ---------------------------------------------------------------------------
+// sync_schedule — when the auto-sync scheduler runs
+// ---------------------------------------------------------------------------
+// The timing rules behind scheduler.rs, kept free of Tauri so they can be
+// tested headless:
+//
+//   - the next run is `sync_interval_minutes` after `last_sync_at`, moved by
+//     up to ±10% of the interval (at most MAX_JITTER) so machines that start
+//     together don't all sync together; overdue runs are due now;
+//   - a scheduled run is skipped while on battery below `min_battery_pct`
+//     (0 never skips).
+// ---------------------------------------------------------------------------
+
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+pub const MAX_JITTER: Duration = Duration::from_secs(5 * 60);
+
+/// How far either side of the interval a run may land.
+pub fn jitter_spread(interval: Duration) -> Duration {
+    (interval / 10).min(MAX_JITTER)
+}
+
+/// How long from `now` until the next run: `interval` after the last sync,
+/// give or take the jitter. Never synced (or an unreadable time) is due now.
+pub fn next_delay(last_sync_at: Option<&str>, interval: Duration, now: DateTime<Utc>) -> Duration {
+    let Some(last) = last_sync_at.and_then(|at| DateTime::parse_from_rfc3339(at).ok()) else {
+        return Duration::ZERO;
+    };
+    let elapsed = (now - last.with_timezone(&Utc)).to_std().unwrap_or_default();
+
+    let spread = jitter_spread(interval);
+    // Uniform in [-spread, +spread]; v4 UUIDs are random enough for this
+    let offset = (uuid::Uuid::new_v4().as_u128() % (2 * spread.as_millis() + 1)) as u64;
+    let target = (interval + Duration::from_millis(offset)).saturating_sub(spread);
+
+    target.saturating_sub(elapsed)
+}
+
+/// Why a scheduled run should wait, given a battery reading of
+/// (charge %, on AC) as `system_info::read_battery` returns it.
+pub fn battery_skip_reason(min_pct: u32, battery_pct: Option<f64>, on_ac: bool) -> Option<String> {
+    if min_pct == 0 || on_ac {
+        return None;
+    }
+    match battery_pct {
+        Some(pct) if pct < f64::from(min_pct) => Some(format!(
+            "On battery at {:.0}% (below {}%)",
+            pct, min_pct
+        )),
+        _ => None,
+    }
+}
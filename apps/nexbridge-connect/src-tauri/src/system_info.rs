@@ -19,7 +19,7 @@ fn detect_platform() -> String {
 
 /// Best-effort battery reading via system commands.
 /// Returns (battery_pct, on_ac).
-fn read_battery() -> (Option<f64>, bool) {
+pub(crate) fn read_battery() -> (Option<f64>, bool) {
     #[cfg(target_os = "macos")]
     {
         // pmset -g batt → "Now drawing from 'AC Power'" or "'Battery Power'"
@@ -77,7 +77,86 @@ fn read_battery() -> (Option<f64>, bool) {
     (None, true)
 }
 
-#[tauri::command]
+/// Best-effort check for a metered connection (cellular, phone hotspot).
+/// Returns None where the OS doesn't say.
+pub(crate) fn read_metered() -> Option<bool> {
+    #[cfg(target_os = "linux")]
+    {
+        // NetworkManager's global Metered property → "u 1"
+        // (NMMetered: 1 yes, 2 no, 3 guess-yes, 4 guess-no, 0 unknown)
+        if let Ok(output) = std::process::Command::new("busctl")
+            .args([
+                "get-property",
+                "org.freedesktop.NetworkManager",
+                "/org/freedesktop/NetworkManager",
+                "org.freedesktop.NetworkManager",
+                "Metered",
+            ])
+            .output()
+        {
+            let text = String::from_utf8_lossy(&output.stdout);
+            match text.split_whitespace().nth(1).and_then(|v| v.parse::<u32>().ok()) {
+                Some(1) | Some(3) => return Some(true),
+                Some(2) | Some(4) => return Some(false),
+                _ => {}
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // WinRT connection cost of the internet profile: Unrestricted / Fixed / Variable
+        if let Ok(output) = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "[Windows.Networking.Connectivity.NetworkInformation,Windows.Networking.Connectivity,ContentType=WindowsRuntime]::GetInternetConnectionProfile().GetConnectionCost().NetworkCostType",
+            ])
+            .output()
+        {
+            match String::from_utf8_lossy(&output.stdout).trim() {
+                "Fixed" | "Variable" => return Some(true),
+                "Unrestricted" => return Some(false),
+                _ => {}
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // macOS only exposes its "expensive" flag through the Network
+        // framework, so look at the default interface's DHCP lease instead:
+        // Android hotspots send the ANDROID_METERED vendor option, and iPhone
+        // Personal Hotspot always hands out 172.20.10.0/28.
+        let interface = std::process::Command::new("route")
+            .args(["-n", "get", "default"])
+            .output()
+            .ok()
+            .and_then(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix("interface:").map(|i| i.trim().to_string()))
+            });
+        if let Some(interface) = interface {
+            if let Ok(output) = std::process::Command::new("ipconfig")
+                .args(["getpacket", &interface])
+                .output()
+            {
+                let text = String::from_utf8_lossy(&output.stdout);
+                if text.trim().is_empty() {
+                    return None; // not DHCP (e.g. a static wired link)
+                }
+                let hotspot = text.contains("ANDROID_METERED")
+                    || text.lines().any(|l| l.trim().starts_with("yiaddr = 172.20.10."));
+                return Some(hotspot);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
 pub fn get_system_info() -> SystemInfo {
     let mut sys = System::new_all();
     sys.refresh_all();
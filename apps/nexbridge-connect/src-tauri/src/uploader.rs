@@ -1,5 +1,12 @@
-use reqwest::Client;
+use crate::bandwidth::limiter;
+use crate::chunked_upload::{ChunkedUploader, UploadSource};
+use crate::converter;
+use crate::documents::calculate_file_hash;
+use crate::index::IndexedDocument;
+use crate::metadata::DocumentMetadata;
+use crate::nexus_api::{ApiError, FileContent, OriginalFile, NexusApi, UploadHtmlRequest};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 
@@ -13,6 +20,44 @@ pub struct UploadRequest {
     pub word_count: u32,
     pub folder_name: String,
     pub breadcrumb: Vec<String>,
+    /// Embedded metadata from the index (author, dates, EXIF, ...)
+    #[serde(default)]
+    pub metadata: DocumentMetadata,
+    /// Source file to upload alongside the HTML and link to it, if any
+    #[serde(default)]
+    pub original_path: Option<String>,
+}
+
+impl UploadRequest {
+    /// Build the upload payload the same way the Documents tab does for bulk uploads.
+    pub fn for_document(doc: &IndexedDocument, html: String, category: &str) -> Self {
+        let path = Path::new(&doc.file_path);
+        let title = path
+            .file_stem()
+            .and_then(|n| n.to_str())
+            .unwrap_or(&doc.file_name)
+            .to_string();
+        let folder_name = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .unwrap_or("Local Upload Files")
+            .to_string();
+        let word_count = converter::strip_html_tags(&html).split_whitespace().count() as u32;
+
+        Self {
+            document_id: doc.id.clone(),
+            html_content: html,
+            title,
+            category: category.to_string(),
+            original_format: doc.file_type.clone().unwrap_or_else(|| "unknown".to_string()),
+            word_count,
+            folder_name,
+            breadcrumb: doc.breadcrumb.clone(),
+            metadata: doc.metadata.clone(),
+            original_path: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +66,12 @@ pub struct UploadResult {
     pub document_id: String,
     pub nexus_doc_id: Option<String>,
     pub error: Option<String>,
+    /// SHA-256 of the original file, set once it has been uploaded
+    #[serde(default)]
+    pub original_sha256: Option<String>,
+    /// Why the original file couldn't be attached (the HTML upload still counts)
+    #[serde(default)]
+    pub original_error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,10 +81,16 @@ pub struct UploadProgress {
     pub failed: u32,
     pub current_file: Option<String>,
     pub is_paused: bool,
+    /// Why uploads are held back (transfer window, metered connection), if they are
+    #[serde(default)]
+    pub waiting_reason: Option<String>,
+    /// Upload cap in effect, in KB/s
+    #[serde(default)]
+    pub upload_limit_kbps: Option<u32>,
 }
 
 pub struct UploadQueue {
-    client: Client,
+    chunked: ChunkedUploader,
     pub paused: Arc<AtomicBool>,
     pub total: Arc<AtomicU32>,
     pub completed: Arc<AtomicU32>,
@@ -41,9 +98,16 @@ pub struct UploadQueue {
 }
 
 impl UploadQueue {
+    /// Queue whose interrupted chunked uploads only resume within this process.
     pub fn new() -> Self {
+        Self::with_session_file(None)
+    }
+
+    /// Queue that records chunked upload sessions in `session_file`, so they
+    /// resume after a restart.
+    pub fn with_session_file(session_file: Option<PathBuf>) -> Self {
         Self {
-            client: Client::new(),
+            chunked: ChunkedUploader::new(session_file),
             paused: Arc::new(AtomicBool::new(false)),
             total: Arc::new(AtomicU32::new(0)),
             completed: Arc::new(AtomicU32::new(0)),
@@ -58,6 +122,8 @@ impl UploadQueue {
             failed: self.failed.load(Ordering::SeqCst),
             current_file: None,
             is_paused: self.paused.load(Ordering::SeqCst),
+            waiting_reason: limiter().waiting_reason(),
+            upload_limit_kbps: limiter().limits().upload_limit_kbps,
         }
     }
 
@@ -76,12 +142,7 @@ impl UploadQueue {
     }
 
     /// Upload a single document to Nexus API
-    pub async fn upload_document(
-        &self,
-        api_url: &str,
-        token: &str,
-        request: UploadRequest,
-    ) -> UploadResult {
+    pub async fn upload_document(&self, api: &NexusApi, request: UploadRequest) -> UploadResult {
         // Check if paused
         if self.paused.load(Ordering::SeqCst) {
             return UploadResult {
@@ -89,81 +150,107 @@ impl UploadQueue {
                 document_id: request.document_id,
                 nexus_doc_id: None,
                 error: Some("Upload paused".to_string()),
+                original_sha256: None,
+                original_error: None,
             };
         }
 
-        let url = format!("{}/document-import/upload-html", api_url);
-        
-        #[derive(Serialize)]
-        struct ApiPayload {
-            html_content: String,
-            title: String,
-            category: String,
-            original_format: String,
-            word_count: u32,
-            folder_name: String,
-            breadcrumb: Vec<String>,
+        // Hold off outside the transfer window / on a metered connection
+        limiter().wait_until_allowed(&|_| {}).await;
+
+        let original_path = request.original_path.clone();
+        let mut result = self.upload_html(api, request).await;
+        if !result.success {
+            return result;
         }
 
-        let payload = ApiPayload {
-            html_content: request.html_content,
+        if let Some(path) = original_path {
+            match &result.nexus_doc_id {
+                Some(nexus_doc_id) => {
+                    match self.upload_original(api, nexus_doc_id, Path::new(&path)).await {
+                        Ok(sha256) => result.original_sha256 = Some(sha256),
+                        Err(e) => {
+                            eprintln!("[uploader] Original file not attached for {}: {}", path, e);
+                            result.original_error = Some(e);
+                        }
+                    }
+                }
+                None => {
+                    result.original_error =
+                        Some("Server returned no document id to attach the original file to".to_string());
+                }
+            }
+        }
+
+        result
+    }
+
+    /// POST the converted HTML and its metadata
+    async fn upload_html(&self, api: &NexusApi, request: UploadRequest) -> UploadResult {
+        let (html_content, html_upload_id) =
+            if ChunkedUploader::should_chunk(request.html_content.len() as u64) {
+                let upload = self
+                    .chunked
+                    .upload(
+                        api,
+                        &format!("{}.html", request.document_id),
+                        "text/html",
+                        &UploadSource::Bytes(request.html_content.into_bytes()),
+                        &|_, _| {},
+                    )
+                    .await;
+                match upload {
+                    Ok(completed) => (None, Some(completed.upload_id)),
+                    Err(e) => {
+                        self.failed.fetch_add(1, Ordering::SeqCst);
+                        return UploadResult {
+                            success: false,
+                            document_id: request.document_id,
+                            nexus_doc_id: None,
+                            error: Some(format!("Chunked upload failed: {}", e)),
+                            original_sha256: None,
+                            original_error: None,
+                        };
+                    }
+                }
+            } else {
+                (Some(request.html_content), None)
+            };
+
+        let payload = UploadHtmlRequest {
+            html_content,
+            html_upload_id,
             title: request.title,
             category: request.category,
             original_format: request.original_format,
             word_count: request.word_count,
             folder_name: request.folder_name,
             breadcrumb: request.breadcrumb,
+            metadata: request.metadata,
         };
 
-        match self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", token))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await
-        {
+        match api.upload_html(&payload).await {
             Ok(response) => {
-                if response.status().is_success() {
-                    #[derive(Deserialize)]
-                    struct ApiResponse {
-                        id: Option<String>,
-                        #[serde(rename = "documentId")]
-                        document_id: Option<String>,
-                    }
-
-                    match response.json::<ApiResponse>().await {
-                        Ok(api_response) => {
-                            self.completed.fetch_add(1, Ordering::SeqCst);
-                            UploadResult {
-                                success: true,
-                                document_id: request.document_id,
-                                nexus_doc_id: api_response.id.or(api_response.document_id),
-                                error: None,
-                            }
-                        }
-                        Err(e) => {
-                            // Response was success but couldn't parse - still count as success
-                            self.completed.fetch_add(1, Ordering::SeqCst);
-                            UploadResult {
-                                success: true,
-                                document_id: request.document_id,
-                                nexus_doc_id: None,
-                                error: Some(format!("Response parse warning: {}", e)),
-                            }
-                        }
-                    }
-                } else {
-                    let status = response.status();
-                    let error_text = response.text().await.unwrap_or_default();
-                    self.failed.fetch_add(1, Ordering::SeqCst);
-                    UploadResult {
-                        success: false,
-                        document_id: request.document_id,
-                        nexus_doc_id: None,
-                        error: Some(format!("HTTP {}: {}", status, error_text)),
-                    }
+                self.completed.fetch_add(1, Ordering::SeqCst);
+                UploadResult {
+                    success: true,
+                    document_id: request.document_id,
+                    nexus_doc_id: response.nexus_doc_id(),
+                    error: None,
+                    original_sha256: None,
+                    original_error: None,
+                }
+            }
+            Err(ApiError::Decode(e)) => {
+                // Response was success but couldn't parse - still count as success
+                self.completed.fetch_add(1, Ordering::SeqCst);
+                UploadResult {
+                    success: true,
+                    document_id: request.document_id,
+                    nexus_doc_id: None,
+                    error: Some(format!("Response parse warning: {}", e)),
+                    original_sha256: None,
+                    original_error: None,
                 }
             }
             Err(e) => {
@@ -172,11 +259,95 @@ impl UploadQueue {
                     success: false,
                     document_id: request.document_id,
                     nexus_doc_id: None,
-                    error: Some(format!("Request failed: {}", e)),
+                    error: Some(e.to_string()),
+                    original_sha256: None,
+                    original_error: None,
                 }
             }
         }
     }
+
+    /// Stream the original source file to Nexus and link it to the uploaded
+    /// HTML document.
+    async fn upload_original(&self, api: &NexusApi, nexus_doc_id: &str, path: &Path) -> Result<String, String> {
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+            .len();
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "original".to_string());
+
+        // Large originals go through an upload session and are linked by id;
+        // the rest are streamed as a multipart part.
+        let original = if ChunkedUploader::should_chunk(size) {
+            let completed = self
+                .chunked
+                .upload(
+                    api,
+                    &file_name,
+                    original_mime_type(path),
+                    &UploadSource::File(path.to_path_buf()),
+                    &|_, _| {},
+                )
+                .await?;
+            OriginalFile {
+                sha256: completed.sha256,
+                size,
+                content: FileContent::UploadSession(completed.upload_id),
+            }
+        } else {
+            // Hash in a separate pass first so it can be sent ahead of the file
+            // part; neither pass holds the whole file in memory.
+            let hash_path: PathBuf = path.to_path_buf();
+            let sha256 = tokio::task::spawn_blocking(move || calculate_file_hash(&hash_path))
+                .await
+                .map_err(|e| format!("Hashing task failed: {}", e))?
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            OriginalFile {
+                sha256,
+                size,
+                content: FileContent::Stream {
+                    path: path.to_path_buf(),
+                    file_name,
+                    content_type: original_mime_type(path).to_string(),
+                    size,
+                },
+            }
+        };
+
+        api.upload_original(nexus_doc_id, &original).await?;
+        Ok(original.sha256)
+    }
+}
+
+/// Content type for the original file part, by extension
+fn original_mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "rtf" => "application/rtf",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "tif" | "tiff" => "image/tiff",
+        "heic" => "image/heic",
+        _ => "application/octet-stream",
+    }
 }
 
 impl Default for UploadQueue {
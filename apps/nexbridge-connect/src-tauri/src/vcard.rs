@@ -0,0 +1,701 @@
+// ---------------------------------------------------------------------------
+// vcard — vCard (.vcf) parsing and writing
+// ---------------------------------------------------------------------------
+// Reads vCard 2.1, 3.0 and 4.0 and writes 3.0 or 4.0 (RFC 2426 / RFC 6350):
+//
+//   - folded lines (CRLF or LF followed by a space or tab) are unfolded, and
+//     2.1 quoted-printable values continue across soft line breaks ("=");
+//   - QUOTED-PRINTABLE and CHARSET (UTF-8, ISO-8859-1/Windows-1252) are
+//     decoded, and text escapes (\n \, \; \\) undone;
+//   - TEL/EMAIL/ADR keep every instance with its TYPEs; the preferred one
+//     (TYPE=pref in 3.0, PREF=n in 4.0) becomes the Contact's primary;
+//   - PHOTO is read inline (ENCODING=b/BASE64 or a data: URI) or as a URI.
+//
+// Written lines are folded at 75 octets without splitting a UTF-8 character.
+//
+// Imported files are copied into {app_data_dir}/vcards/, which is read like
+// any other vCard folder (see contact_providers.rs). Their contacts get ids
+// "vcard:{UID}" so they stay stable across re-imports.
+// ---------------------------------------------------------------------------
+
+use crate::contacts::Contact;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Directory under the app data dir holding imported .vcf files
+pub const VCARD_DIR: &str = "vcards";
+/// Prefix of contact ids that come from imported vCards
+pub const VCARD_ID_PREFIX: &str = "vcard:";
+const FOLD_WIDTH: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VCardVersion {
+    #[serde(rename = "3.0")]
+    V3,
+    #[serde(rename = "4.0")]
+    V4,
+}
+
+impl VCardVersion {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim() {
+            "3.0" | "3" => Ok(Self::V3),
+            "4.0" | "4" => Ok(Self::V4),
+            other => Err(format!("Unsupported vCard version '{}' (use 3.0 or 4.0)", other)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::V3 => "3.0",
+            Self::V4 => "4.0",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VCard {
+    pub uid: Option<String>,
+    pub formatted_name: Option<String>,
+    pub family_name: Option<String>,
+    pub given_name: Option<String>,
+    pub organization: Option<String>,
+    pub title: Option<String>,
+    pub emails: Vec<TypedValue>,
+    pub phones: Vec<TypedValue>,
+    pub addresses: Vec<Address>,
+    pub categories: Vec<String>,
+    pub photo: Option<Photo>,
+}
+
+/// A TEL or EMAIL with its TYPEs (lowercased, "pref" removed).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TypedValue {
+    pub value: String,
+    pub types: Vec<String>,
+    /// 1 is most preferred; None when not marked
+    pub pref: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Address {
+    pub types: Vec<String>,
+    pub pref: Option<u8>,
+    pub street: Option<String>,
+    pub locality: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Photo {
+    Inline { media_type: Option<String>, data: Vec<u8> },
+    Uri(String),
+}
+
+// ============ Parsing ============
+
+/// Parse every vCard in a .vcf file. Files that aren't UTF-8 are read as
+/// Windows-1252, which covers most older exports.
+pub fn parse_bytes(bytes: &[u8]) -> Result<Vec<VCard>, String> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => parse(text),
+        Err(_) => parse(&decode_charset(bytes, Some("WINDOWS-1252"))),
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<VCard>, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut cards = Vec::new();
+    let mut current: Option<VCard> = None;
+    // Nested cards (2.1 AGENT) are skipped
+    let mut depth = 0usize;
+
+    for line in unfold(text) {
+        let Some(property) = Property::parse(&line) else { continue };
+        match property.name.as_str() {
+            "BEGIN" if property.value.eq_ignore_ascii_case("VCARD") => {
+                depth += 1;
+                if depth == 1 {
+                    current = Some(VCard::default());
+                }
+            }
+            "END" if property.value.eq_ignore_ascii_case("VCARD") => {
+                if depth == 1 {
+                    cards.extend(current.take());
+                }
+                depth = depth.saturating_sub(1);
+            }
+            _ if depth == 1 => {
+                if let Some(card) = current.as_mut() {
+                    card.apply(&property);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if depth > 0 {
+        return Err("vCard file ends in the middle of a card (missing END:VCARD)".to_string());
+    }
+    Ok(cards)
+}
+
+/// Join folded lines, and quoted-printable soft line breaks.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut qp_continues = false;
+
+    for raw in text.split('\n') {
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+        match lines.last_mut() {
+            Some(last) if qp_continues => {
+                last.pop(); // the soft break's "="
+                last.push_str(line.trim_start_matches([' ', '\t']));
+            }
+            Some(last) if line.starts_with([' ', '\t']) => last.push_str(&line[1..]),
+            _ if line.trim().is_empty() => {
+                qp_continues = false;
+                continue;
+            }
+            _ => lines.push(line.to_string()),
+        }
+        let last = lines.last().map(String::as_str).unwrap_or_default();
+        qp_continues = last.ends_with('=') && is_quoted_printable(last);
+    }
+    lines
+}
+
+fn is_quoted_printable(line: &str) -> bool {
+    let head = &line[..value_start(line).unwrap_or(line.len())];
+    head.to_ascii_uppercase().contains("QUOTED-PRINTABLE")
+}
+
+/// Index of the ':' that ends the property name and parameters (colons
+/// inside quoted parameter values don't count).
+fn value_start(line: &str) -> Option<usize> {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+struct Property {
+    /// Upper case, without any group prefix ("item1.EMAIL" → "EMAIL")
+    name: String,
+    /// (upper-case key, value) — 2.1 bare parameters become TYPE/ENCODING
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Self> {
+        let colon = value_start(line)?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+        let mut parts = split_unquoted(head, ';').into_iter();
+        let name = parts.next()?;
+        let name = name.rsplit('.').next().unwrap_or(&name).trim().to_ascii_uppercase();
+
+        let mut params = Vec::new();
+        for part in parts {
+            let (key, values) = match part.split_once('=') {
+                Some((k, v)) => (k.trim().to_ascii_uppercase(), v.to_string()),
+                None => {
+                    let bare = part.trim().to_ascii_uppercase();
+                    let key = match bare.as_str() {
+                        "QUOTED-PRINTABLE" | "BASE64" | "B" | "8BIT" | "7BIT" => "ENCODING",
+                        _ => "TYPE",
+                    };
+                    (key.to_string(), bare)
+                }
+            };
+            for v in split_unquoted(&values, ',') {
+                params.push((key.clone(), v.trim_matches('"').to_string()));
+            }
+        }
+
+        let mut property = Self {
+            name,
+            params,
+            value: value.to_string(),
+        };
+        if property.param("ENCODING").is_some_and(|e| e.eq_ignore_ascii_case("QUOTED-PRINTABLE")) {
+            let bytes = decode_quoted_printable(&property.value);
+            property.value = decode_charset(&bytes, property.param("CHARSET"));
+        }
+        Some(property)
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn types(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .filter(|(k, _)| k == "TYPE")
+            .map(|(_, v)| v.to_ascii_lowercase())
+            .filter(|t| t != "pref" && t != "internet")
+            .collect()
+    }
+
+    fn pref(&self) -> Option<u8> {
+        if let Some(pref) = self.param("PREF").and_then(|p| p.parse::<u8>().ok()) {
+            return Some(pref.max(1));
+        }
+        self.params
+            .iter()
+            .any(|(k, v)| k == "TYPE" && v.eq_ignore_ascii_case("pref"))
+            .then_some(1)
+    }
+
+    fn text(&self) -> Option<String> {
+        non_empty(unescape(&self.value))
+    }
+
+    /// Components of a structured value (N, ADR, ORG), unescaped.
+    fn components(&self) -> Vec<Option<String>> {
+        split_escaped(&self.value, ';').into_iter().map(|c| non_empty(unescape(&c))).collect()
+    }
+}
+
+impl VCard {
+    fn apply(&mut self, p: &Property) {
+        match p.name.as_str() {
+            "UID" => self.uid = p.text().map(|u| u.trim_start_matches("urn:uuid:").to_string()),
+            "FN" => self.formatted_name = p.text(),
+            "N" => {
+                let parts = p.components();
+                self.family_name = parts.first().cloned().flatten();
+                self.given_name = parts.get(1).cloned().flatten();
+            }
+            "ORG" => self.organization = p.components().into_iter().next().flatten(),
+            "TITLE" => self.title = p.text(),
+            "EMAIL" => {
+                if let Some(value) = p.text() {
+                    self.emails.push(TypedValue { value: value.trim_start_matches("mailto:").to_string(), types: p.types(), pref: p.pref() });
+                }
+            }
+            "TEL" => {
+                if let Some(value) = p.text() {
+                    self.phones.push(TypedValue { value: value.trim_start_matches("tel:").to_string(), types: p.types(), pref: p.pref() });
+                }
+            }
+            "ADR" => {
+                let parts = p.components();
+                let part = |i: usize| parts.get(i).cloned().flatten();
+                // Extended address (suite, unit) goes on the street line
+                let street = match (part(2), part(1)) {
+                    (Some(street), Some(extended)) => Some(format!("{}, {}", street, extended)),
+                    (street, extended) => street.or(extended),
+                };
+                let address = Address {
+                    types: p.types(),
+                    pref: p.pref(),
+                    street,
+                    locality: part(3),
+                    region: part(4),
+                    postal_code: part(5),
+                    country: part(6),
+                };
+                if address.street.is_some() || address.locality.is_some() || address.region.is_some()
+                    || address.postal_code.is_some() || address.country.is_some()
+                {
+                    self.addresses.push(address);
+                }
+            }
+            "CATEGORIES" => {
+                for category in split_escaped(&p.value, ',') {
+                    if let Some(category) = non_empty(unescape(&category)) {
+                        if !self.categories.contains(&category) {
+                            self.categories.push(category);
+                        }
+                    }
+                }
+            }
+            "PHOTO" => self.photo = parse_photo(p),
+            _ => {}
+        }
+    }
+
+    /// The contact this card describes, with id "vcard:{UID}" (or a hash
+    /// of its name and first email/phone when it has no UID).
+    pub fn to_contact(&self) -> Contact {
+        let display_name = self.formatted_name.clone().or_else(|| {
+            let parts = [self.given_name.as_deref(), self.family_name.as_deref()];
+            non_empty(parts.iter().flatten().copied().collect::<Vec<_>>().join(" "))
+        });
+        let key = self.uid.clone().unwrap_or_else(|| {
+            let seed = format!(
+                "{}\n{}\n{}",
+                display_name.as_deref().unwrap_or_default(),
+                self.emails.first().map(|e| e.value.as_str()).unwrap_or_default(),
+                self.phones.first().map(|p| p.value.as_str()).unwrap_or_default(),
+            );
+            hex::encode(&Sha256::digest(seed.as_bytes())[..12])
+        });
+        let address = preferred(&self.addresses, |a| a.pref);
+
+        Contact {
+            id: format!("{}{}", VCARD_ID_PREFIX, key),
+            display_name,
+            first_name: self.given_name.clone(),
+            last_name: self.family_name.clone(),
+            email: preferred(&self.emails, |e| e.pref).map(|e| e.value.clone()),
+            phone: preferred(&self.phones, |p| p.pref).map(|p| p.value.clone()),
+            all_emails: self.emails.iter().map(|e| e.value.clone()).collect(),
+            all_phones: self.phones.iter().map(|p| p.value.clone()).collect(),
+            street: address.and_then(|a| a.street.clone()),
+            city: address.and_then(|a| a.locality.clone()),
+            state: address.and_then(|a| a.region.clone()),
+            zip: address.and_then(|a| a.postal_code.clone()),
+            country: address.and_then(|a| a.country.clone()),
+            company: self.organization.clone(),
+            job_title: self.title.clone(),
+        }
+    }
+
+    /// A card for `contact`; its primary email/phone are marked preferred.
+    pub fn from_contact(contact: &Contact, categories: Vec<String>) -> Self {
+        let typed = |primary: &Option<String>, all: &[String]| -> Vec<TypedValue> {
+            let mut values: Vec<String> = primary.iter().cloned().collect();
+            values.extend(all.iter().filter(|v| Some(*v) != primary.as_ref()).cloned());
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| TypedValue {
+                    value,
+                    types: vec![],
+                    pref: (i == 0 && primary.is_some()).then_some(1),
+                })
+                .collect()
+        };
+        let address = Address {
+            types: vec![],
+            pref: None,
+            street: contact.street.clone(),
+            locality: contact.city.clone(),
+            region: contact.state.clone(),
+            postal_code: contact.zip.clone(),
+            country: contact.country.clone(),
+        };
+        let has_address = address != Address::default();
+
+        Self {
+            uid: Some(contact.id.strip_prefix(VCARD_ID_PREFIX).unwrap_or(&contact.id).to_string()),
+            formatted_name: contact.display_name.clone(),
+            family_name: contact.last_name.clone(),
+            given_name: contact.first_name.clone(),
+            organization: contact.company.clone(),
+            title: contact.job_title.clone(),
+            emails: typed(&contact.email, &contact.all_emails),
+            phones: typed(&contact.phone, &contact.all_phones),
+            addresses: if has_address { vec![address] } else { vec![] },
+            categories,
+            photo: None,
+        }
+    }
+}
+
+fn preferred<T>(values: &[T], pref: impl Fn(&T) -> Option<u8>) -> Option<&T> {
+    values
+        .iter()
+        .enumerate()
+        .min_by_key(|(i, v)| (pref(v).unwrap_or(u8::MAX), *i))
+        .map(|(_, v)| v)
+}
+
+fn parse_photo(p: &Property) -> Option<Photo> {
+    let value = p.value.trim();
+    if let Some(data_uri) = value.strip_prefix("data:") {
+        // data:image/jpeg;base64,....
+        let (meta, data) = data_uri.split_once(',')?;
+        let media_type = meta.split(';').next().filter(|m| !m.is_empty()).map(String::from);
+        return decode_base64(data).map(|data| Photo::Inline { media_type, data });
+    }
+    let inline = p
+        .param("ENCODING")
+        .is_some_and(|e| e.eq_ignore_ascii_case("b") || e.eq_ignore_ascii_case("BASE64"));
+    if inline {
+        let media_type = p
+            .params
+            .iter()
+            .find(|(k, v)| k == "TYPE" && !v.eq_ignore_ascii_case("pref"))
+            .map(|(_, v)| match v.to_ascii_lowercase().as_str() {
+                t if t.contains('/') => t.to_string(),
+                t => format!("image/{}", t),
+            });
+        return decode_base64(value).map(|data| Photo::Inline { media_type, data });
+    }
+    non_empty(value.to_string()).map(Photo::Uri)
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let compact: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    BASE64.decode(compact.as_bytes()).ok()
+}
+
+fn decode_quoted_printable(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Text from `bytes` in `charset` (UTF-8 unless it says Latin-1/1252).
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let latin = charset.is_some_and(|c| {
+        let c = c.to_ascii_uppercase();
+        c.contains("8859-1") || c.contains("1252") || c == "LATIN1"
+    });
+    if latin {
+        bytes.iter().map(|&b| b as char).collect()
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+fn split_unquoted(s: &str, sep: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                parts.last_mut().unwrap().push(c);
+            }
+            c if c == sep && !quoted => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Split on `sep` except where it is backslash-escaped (escapes are kept).
+fn split_escaped(s: &str, sep: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().unwrap();
+        if c == '\\' {
+            part.push(c);
+            if let Some(next) = chars.next() {
+                part.push(next);
+            }
+        } else if c == sep {
+            parts.push(String::new());
+        } else {
+            part.push(c);
+        }
+    }
+    parts
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn non_empty(s: String) -> Option<String> {
+    let trimmed = s.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+// ============ Writing ============
+
+/// Serialize `cards` as one .vcf document.
+pub fn write(cards: &[VCard], version: VCardVersion) -> String {
+    let mut out = String::new();
+    for card in cards {
+        write_card(&mut out, card, version);
+    }
+    out
+}
+
+fn write_card(out: &mut String, card: &VCard, version: VCardVersion) {
+    let mut line = |s: String| fold_into(out, &s);
+    line("BEGIN:VCARD".to_string());
+    line(format!("VERSION:{}", version.as_str()));
+
+    if let Some(uid) = &card.uid {
+        line(format!("UID:{}", escape(uid)));
+    }
+    // FN is required in both versions, N in 3.0
+    let formatted_name = card.formatted_name.clone().unwrap_or_else(|| {
+        [card.given_name.as_deref(), card.family_name.as_deref()]
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+    line(format!("FN:{}", escape(&formatted_name)));
+    if version == VCardVersion::V3 || card.family_name.is_some() || card.given_name.is_some() {
+        line(format!(
+            "N:{};{};;;",
+            escape(card.family_name.as_deref().unwrap_or_default()),
+            escape(card.given_name.as_deref().unwrap_or_default())
+        ));
+    }
+    if let Some(org) = &card.organization {
+        line(format!("ORG:{}", escape(org)));
+    }
+    if let Some(title) = &card.title {
+        line(format!("TITLE:{}", escape(title)));
+    }
+    for email in &card.emails {
+        let types = if version == VCardVersion::V3 {
+            let mut types = vec!["internet".to_string()];
+            types.extend(email.types.iter().cloned());
+            types
+        } else {
+            email.types.clone()
+        };
+        line(format!("EMAIL{}:{}", type_params(&types, email.pref, version), escape(&email.value)));
+    }
+    for phone in &card.phones {
+        line(format!("TEL{}:{}", type_params(&phone.types, phone.pref, version), escape(&phone.value)));
+    }
+    for address in &card.addresses {
+        let part = |v: &Option<String>| escape(v.as_deref().unwrap_or_default());
+        line(format!(
+            "ADR{}:;;{};{};{};{};{}",
+            type_params(&address.types, address.pref, version),
+            part(&address.street),
+            part(&address.locality),
+            part(&address.region),
+            part(&address.postal_code),
+            part(&address.country)
+        ));
+    }
+    if !card.categories.is_empty() {
+        let categories: Vec<String> = card.categories.iter().map(|c| escape(c)).collect();
+        line(format!("CATEGORIES:{}", categories.join(",")));
+    }
+    match (&card.photo, version) {
+        (Some(Photo::Inline { media_type, data }), VCardVersion::V3) => {
+            let kind = media_type
+                .as_deref()
+                .map(|m| m.trim_start_matches("image/").to_ascii_uppercase())
+                .unwrap_or_else(|| "JPEG".to_string());
+            line(format!("PHOTO;ENCODING=b;TYPE={}:{}", kind, BASE64.encode(data)));
+        }
+        (Some(Photo::Inline { media_type, data }), VCardVersion::V4) => {
+            let media_type = media_type.as_deref().unwrap_or("image/jpeg");
+            line(format!("PHOTO:data:{};base64,{}", media_type, BASE64.encode(data)));
+        }
+        (Some(Photo::Uri(uri)), VCardVersion::V3) => line(format!("PHOTO;VALUE=uri:{}", uri)),
+        (Some(Photo::Uri(uri)), VCardVersion::V4) => line(format!("PHOTO:{}", uri)),
+        (None, _) => {}
+    }
+    line("END:VCARD".to_string());
+}
+
+fn type_params(types: &[String], pref: Option<u8>, version: VCardVersion) -> String {
+    let mut params = String::new();
+    match version {
+        VCardVersion::V3 => {
+            let mut all: Vec<&str> = types.iter().map(String::as_str).collect();
+            if pref.is_some() {
+                all.push("pref");
+            }
+            if !all.is_empty() {
+                params.push_str(&format!(";TYPE={}", all.join(",")));
+            }
+        }
+        VCardVersion::V4 => {
+            if !types.is_empty() {
+                params.push_str(&format!(";TYPE={}", types.join(",")));
+            }
+            if let Some(pref) = pref {
+                params.push_str(&format!(";PREF={}", pref));
+            }
+        }
+    }
+    params
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ',' => out.push_str("\\,"),
+            ';' => out.push_str("\\;"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Append `line` with CRLF, folded at FOLD_WIDTH octets on char boundaries.
+fn fold_into(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > FOLD_WIDTH {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out.push_str("\r\n");
+}
+
+// ============ Imported files ============
+
+/// Copy a .vcf file into `dir` so its contacts are read with the native
+/// ones. Returns how many contacts it holds.
+pub fn import_file(source: &Path, dir: &Path) -> Result<(PathBuf, usize), String> {
+    let bytes = std::fs::read(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    let cards = parse_bytes(&bytes)?;
+    if cards.is_empty() {
+        return Err(format!("No contacts found in {}", source.display()));
+    }
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("contacts");
+    let mut dest = dir.join(format!("{}.vcf", stem));
+    let mut n = 2;
+    while dest.exists() {
+        dest = dir.join(format!("{} ({}).vcf", stem, n));
+        n += 1;
+    }
+    std::fs::write(&dest, &bytes).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    Ok((dest, cards.len()))
+}
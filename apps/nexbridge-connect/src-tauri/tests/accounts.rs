@@ -0,0 +1,83 @@
+// ---------------------------------------------------------------------------
+// Integration tests for per-account partitions of the local data
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::accounts::{self, AccountKey, Partition};
+
+mod common;
+
+fn key(company_id: &str, user_id: &str) -> AccountKey {
+    AccountKey { company_id: company_id.to_string(), user_id: user_id.to_string() }
+}
+
+#[test]
+fn partitions_by_company_and_user() {
+    let app = common::temp_dir("layout");
+    let acme = Partition::for_account(&app, &key("cmp_acme", "usr-1"));
+    assert_eq!(acme.dir, app.join("accounts").join("cmp_acme").join("usr-1"));
+    assert_eq!(acme.documents_db, acme.dir.join("documents.db"));
+    assert_eq!(acme.converted_dir, acme.dir.join("converted"));
+
+    // Ids that aren't plain are hashed rather than used as paths
+    for odd in ["../../etc", "Acme Inc.", "", "C:\\evil"] {
+        let partition = Partition::for_account(&app, &key(odd, "usr-1"));
+        let segment = partition.dir.parent().unwrap().file_name().unwrap().to_str().unwrap().to_string();
+        assert!(segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'), "{:?} -> {}", odd, segment);
+        assert!(partition.dir.starts_with(app.join("accounts")));
+    }
+    assert_ne!(
+        Partition::for_account(&app, &key("Acme Inc.", "u")).dir,
+        Partition::for_account(&app, &key("Acme Inc!", "u")).dir
+    );
+}
+
+#[test]
+fn registry_tracks_the_active_account() {
+    let app = common::temp_dir("registry");
+    let mut registry = accounts::load_registry(&app);
+    assert!(registry.accounts.is_empty() && registry.active.is_none());
+
+    registry.activate(&key("c1", "u1"), Some("Acme".to_string()));
+    registry.activate(&key("c2", "u1"), None);
+    // Re-activating keeps the known name unless a new one is given
+    let again = registry.activate(&key("c1", "u1"), None);
+    assert_eq!(again.company_name.as_deref(), Some("Acme"));
+    assert_eq!(registry.accounts.len(), 2);
+    assert_eq!(registry.active, Some(key("c1", "u1")));
+
+    // Not migrated yet: still on the legacy files
+    accounts::save_registry(&app, &registry).unwrap();
+    assert_eq!(accounts::active_partition(&app).dir, app.path());
+
+    registry.legacy_migrated = true;
+    accounts::save_registry(&app, &registry).unwrap();
+    let loaded = accounts::load_registry(&app);
+    assert_eq!(loaded.accounts.len(), 2);
+    assert_eq!(accounts::active_partition(&app).dir, Partition::for_account(&app, &key("c1", "u1")).dir);
+
+    // A damaged registry is ignored rather than fatal
+    std::fs::write(app.join("accounts.json"), "{ not json").unwrap();
+    assert!(accounts::load_registry(&app).active.is_none());
+}
+
+#[test]
+fn hands_legacy_data_to_the_first_account() {
+    let app = common::temp_dir("legacy");
+    let legacy = Partition::legacy(&app);
+    assert!(!legacy.has_data());
+    std::fs::write(&legacy.documents_db, b"db").unwrap();
+    std::fs::write(app.join("documents.db-wal"), b"wal").unwrap();
+    std::fs::create_dir_all(legacy.converted_dir.join("ab")).unwrap();
+    std::fs::write(legacy.converted_dir.join("ab").join("blob.html.zst"), b"blob").unwrap();
+    assert!(legacy.has_data());
+
+    let target = Partition::for_account(&app, &key("c1", "u1"));
+    std::fs::create_dir_all(&target.dir).unwrap();
+    accounts::move_legacy_cache(&app, &target).unwrap();
+    assert!(target.converted_dir.join("ab").join("blob.html.zst").exists());
+    assert!(!legacy.converted_dir.exists());
+
+    accounts::remove_legacy_databases(&app);
+    assert!(!legacy.has_data());
+    assert!(!app.join("documents.db-wal").exists());
+}
@@ -0,0 +1,112 @@
+// ---------------------------------------------------------------------------
+// Integration tests for transfer limits, windows and pacing
+// ---------------------------------------------------------------------------
+// The limiter is process-wide, so the tests that configure it take `serial()`
+// first.
+// ---------------------------------------------------------------------------
+
+use chrono::{Duration as ChronoDuration, Local};
+use nexbridge_connect_lib::bandwidth::{limiter, Direction, TransferLimits, TransferWindow};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+async fn serial() -> tokio::sync::MutexGuard<'static, ()> {
+    static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| tokio::sync::Mutex::new(())).lock().await
+}
+
+fn window(start: &str, end: &str) -> TransferWindow {
+    TransferWindow { start: start.to_string(), end: end.to_string() }
+}
+
+/// A window from `from` to `to` hours away from now, local time.
+fn window_around_now(from: i64, to: i64) -> TransferWindow {
+    let now = Local::now();
+    let at = |hours: i64| (now + ChronoDuration::hours(hours)).format("%H:%M").to_string();
+    window(&at(from), &at(to))
+}
+
+fn unmetered(windows: Vec<TransferWindow>, upload_limit_kbps: Option<u32>) -> TransferLimits {
+    TransferLimits { upload_limit_kbps, windows, pause_on_metered: false, ..Default::default() }
+}
+
+// ============ Validation ============
+
+#[test]
+fn rejects_bad_windows_and_zero_caps() {
+    assert!(TransferLimits::default().validate().is_ok());
+    assert!(unmetered(vec![window("18:00", "07:00")], Some(256)).validate().is_ok());
+
+    let err = unmetered(vec![window("6pm", "07:00")], None).validate().unwrap_err();
+    assert!(err.contains("'6pm'"), "{}", err);
+    assert!(unmetered(vec![window("18:00", "18:00")], None).validate().unwrap_err().contains("empty"));
+    assert!(unmetered(vec![], Some(0)).validate().is_err());
+    let zero_download = TransferLimits { download_limit_kbps: Some(0), ..Default::default() };
+    assert!(zero_download.validate().is_err());
+}
+
+#[test]
+fn older_settings_pause_on_metered_by_default() {
+    let limits: TransferLimits = serde_json::from_str("{}").unwrap();
+    assert_eq!(limits, TransferLimits::default());
+    assert!(limits.pause_on_metered);
+    assert!(limits.upload_limit_kbps.is_none() && limits.windows.is_empty());
+}
+
+// ============ Windows ============
+
+#[tokio::test]
+async fn transfers_wait_outside_every_window() {
+    let _serial = serial().await;
+
+    limiter().configure(&unmetered(vec![window_around_now(2, 3), window_around_now(5, 6)], None)).unwrap();
+    let reason = limiter().blocked_reason().expect("outside both windows");
+    assert!(reason.starts_with("Waiting for transfer window ("), "{}", reason);
+    assert_eq!(limiter().status().blocked_reason, Some(reason));
+
+    // One window covering now is enough, including one that wraps past midnight
+    limiter().configure(&unmetered(vec![window_around_now(2, 3), window_around_now(-1, 1)], None)).unwrap();
+    assert_eq!(limiter().blocked_reason(), None);
+    limiter().configure(&unmetered(vec![window_around_now(-12, 11)], None)).unwrap();
+    assert_eq!(limiter().blocked_reason(), None);
+
+    // Allowed transfers don't wait and clear the waiting reason
+    let started = Instant::now();
+    limiter().wait_until_allowed(&|_| panic!("should not wait")).await;
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(limiter().waiting_reason(), None);
+
+    // An invalid configuration leaves the current one in place
+    assert!(limiter().configure(&unmetered(vec![window("25:00", "07:00")], None)).is_err());
+    assert_eq!(limiter().limits().windows.len(), 1);
+}
+
+// ============ Pacing ============
+
+#[tokio::test]
+async fn upload_cap_paces_reservations_and_downloads_are_separate() {
+    let _serial = serial().await;
+
+    // 200 KB/s: the first 100 KB starts at once, the next has to wait ~0.5 s
+    limiter().configure(&unmetered(vec![], Some(200))).unwrap();
+    let started = Instant::now();
+    limiter().acquire(Direction::Upload, 100 * 1024).await;
+    assert!(started.elapsed() < Duration::from_millis(200));
+    limiter().acquire(Direction::Upload, 100 * 1024).await;
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(450), "{:?}", elapsed);
+
+    // Downloads have no cap here
+    let started = Instant::now();
+    for _ in 0..10 {
+        limiter().acquire(Direction::Download, 1024 * 1024).await;
+    }
+    assert!(started.elapsed() < Duration::from_millis(200));
+
+    // Lifting the cap takes effect straight away
+    limiter().configure(&unmetered(vec![], None)).unwrap();
+    let started = Instant::now();
+    limiter().acquire(Direction::Upload, 10 * 1024 * 1024).await;
+    limiter().acquire(Direction::Upload, 10 * 1024 * 1024).await;
+    assert!(started.elapsed() < Duration::from_millis(200));
+}
@@ -0,0 +1,179 @@
+// ---------------------------------------------------------------------------
+// Integration tests for the converted-HTML cache
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::cache::ConversionCache;
+use nexbridge_connect_lib::encryption::{self, DataKey};
+use nexbridge_connect_lib::index::{DocumentIndex, DocumentStatus, IndexedDocument};
+use common::TempDir;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+mod common;
+
+/// Deriving a key is deliberately slow, so every test shares one.
+fn data_key() -> DataKey {
+    static KEY: OnceLock<DataKey> = OnceLock::new();
+    KEY.get_or_init(|| encryption::unlock_with_passphrase(&common::temp_dir("key"), "correct horse battery").unwrap())
+        .clone()
+}
+
+/// A PDF in its own folder under /share.
+fn document(id: &str, file_name: &str, hash: &str, status: DocumentStatus) -> IndexedDocument {
+    IndexedDocument { file_hash: Some(hash.to_string()), ..common::document(id, &format!("{}/{}", id, file_name), status) }
+}
+
+/// HTML that doesn't compress away, so blob sizes are predictable.
+fn html(seed: &str, len: usize) -> String {
+    let mut out = String::from("<html>");
+    let mut n: u64 = seed.bytes().fold(7, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
+    while out.len() < len {
+        n = n.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        out.push_str(&format!("{:x}", n));
+    }
+    out
+}
+
+fn setup(name: &str) -> (TempDir, DocumentIndex, ConversionCache) {
+    let dir = common::temp_dir(name);
+    let key = data_key();
+    let index = DocumentIndex::new(&dir.join("documents.db"), Some(&key)).unwrap();
+    let cache = ConversionCache::new(dir.join("converted"), Some(key));
+    (dir, index, cache)
+}
+
+fn blob_path(dir: &Path, index: &DocumentIndex, document_id: &str) -> PathBuf {
+    let blob = index.get_cache_entry(document_id).unwrap().unwrap().blob_key;
+    dir.join("converted").join(&blob[..2]).join(format!("{}.html.zst", blob))
+}
+
+// ============ Accounting and integrity ============
+
+#[test]
+fn accounts_for_compressed_and_logical_sizes() {
+    let (dir, index, cache) = setup("stats");
+    for id in ["a", "b"] {
+        index.upsert_document(&document(id, "sop.pdf", "same-hash", DocumentStatus::Import)).unwrap();
+    }
+    let page = "<p>repeated</p>".repeat(1_000);
+    cache.store(&index, "a", Some("same-hash".to_string()), &page).unwrap();
+    cache.store(&index, "b", Some("same-hash".to_string()), &page).unwrap();
+
+    let stats = cache.stats(&index).unwrap();
+    let on_disk = std::fs::metadata(blob_path(&dir, &index, "a")).unwrap().len();
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.blobs, 1);
+    assert_eq!(stats.total_bytes, on_disk);
+    assert_eq!(stats.logical_bytes, 2 * page.len() as u64);
+    assert!(stats.total_bytes < page.len() as u64 / 10);
+}
+
+#[test]
+fn drops_every_document_sharing_a_damaged_blob() {
+    let (dir, index, cache) = setup("damaged");
+    for id in ["a", "b"] {
+        index.upsert_document(&document(id, "sop.pdf", "same-hash", DocumentStatus::Import)).unwrap();
+        cache.store(&index, id, Some("same-hash".to_string()), &html("sop", 2_000)).unwrap();
+        index.update_status(id, DocumentStatus::Converted, None, "converter", None).unwrap();
+    }
+
+    std::fs::write(blob_path(&dir, &index, "a"), b"not a blob").unwrap();
+    assert!(cache.load(&index, "a").is_err());
+
+    for id in ["a", "b"] {
+        assert!(index.get_cache_entry(id).unwrap().is_none());
+        assert_eq!(index.get_document(id).unwrap().unwrap().status, DocumentStatus::Import);
+    }
+}
+
+#[test]
+fn prune_reconciles_blobs_on_disk_with_the_index() {
+    let (dir, index, cache) = setup("prune");
+    for id in ["kept", "missing"] {
+        index.upsert_document(&document(id, &format!("{}.pdf", id), id, DocumentStatus::Import)).unwrap();
+        cache.store(&index, id, Some(id.to_string()), &html(id, 2_000)).unwrap();
+    }
+    std::fs::remove_file(blob_path(&dir, &index, "missing")).unwrap();
+    let orphan = dir.join("converted").join("ff").join("ff-orphan.html.zst");
+    std::fs::create_dir_all(orphan.parent().unwrap()).unwrap();
+    std::fs::write(&orphan, b"left behind").unwrap();
+
+    let result = cache.prune(&index).unwrap();
+    assert_eq!(result.removed_entries, 1);
+    assert!(!orphan.exists());
+    assert!(index.get_cache_entry("missing").unwrap().is_none());
+    assert!(cache.load(&index, "kept").is_ok());
+}
+
+// ============ Storage ============
+
+#[test]
+fn stores_encrypted_blobs_and_reads_them_back() {
+    let (dir, index, cache) = setup("roundtrip");
+    index.upsert_document(&document("a", "sop.pdf", "hash-a", DocumentStatus::Import)).unwrap();
+
+    let page = html("a", 4_000);
+    cache.store(&index, "a", Some("hash-a".to_string()), &page).unwrap();
+    assert_eq!(cache.load(&index, "a").unwrap(), page);
+
+    assert!(encryption::is_encrypted_blob(&std::fs::read(blob_path(&dir, &index, "a")).unwrap()));
+
+    // Locked: no key, no reads
+    cache.set_key(None);
+    assert!(cache.load(&index, "a").is_err());
+}
+
+#[test]
+fn shares_blobs_only_between_identical_sources_and_html() {
+    let (_dir, index, cache) = setup("dedupe");
+    for id in ["a", "b", "c"] {
+        index.upsert_document(&document(id, "sop.pdf", "same-hash", DocumentStatus::Import)).unwrap();
+    }
+    let page = html("sop", 2_000);
+    cache.store(&index, "a", Some("same-hash".to_string()), &page).unwrap();
+    cache.store(&index, "b", Some("same-hash".to_string()), &page).unwrap();
+    // Same source, different file name, so a different <title>
+    cache.store(&index, "c", Some("same-hash".to_string()), &format!("{}<title>c</title>", page)).unwrap();
+
+    let stats = cache.stats(&index).unwrap();
+    assert_eq!(stats.entries, 3);
+    assert_eq!(stats.blobs, 2);
+}
+
+#[test]
+fn drops_entries_whose_source_changed() {
+    let (_dir, index, cache) = setup("stale");
+    index.upsert_document(&document("a", "sop.pdf", "old-hash", DocumentStatus::Import)).unwrap();
+    cache.store(&index, "a", Some("old-hash".to_string()), &html("a", 1_000)).unwrap();
+
+    // A rescan records a new hash
+    index.upsert_document(&document("a", "sop.pdf", "new-hash", DocumentStatus::Import)).unwrap();
+    assert!(cache.load(&index, "a").unwrap_err().contains("out of date"));
+    assert!(index.get_cache_entry("a").unwrap().is_none());
+}
+
+// ============ Budget ============
+
+#[test]
+fn evicts_least_recently_read_but_never_the_blob_just_stored() {
+    let (_dir, index, cache) = setup("budget");
+    index.upsert_document(&document("old", "old.pdf", "hash-old", DocumentStatus::Import)).unwrap();
+    index.upsert_document(&document("new", "new.pdf", "hash-new", DocumentStatus::Import)).unwrap();
+
+    cache.store(&index, "old", Some("hash-old".to_string()), &html("old", 8_000)).unwrap();
+    index.update_status("old", DocumentStatus::Converted, None, "converter", None).unwrap();
+
+    // Smaller than either blob: "old" goes, "new" survives its own store
+    cache.set_budget(100);
+    let page = html("new", 8_000);
+    cache.store(&index, "new", Some("hash-new".to_string()), &page).unwrap();
+
+    assert!(index.get_cache_entry("old").unwrap().is_none());
+    assert_eq!(index.get_document("old").unwrap().unwrap().status, DocumentStatus::Import);
+    assert_eq!(cache.load(&index, "new").unwrap(), page);
+
+    // An explicit budget check still applies it
+    let result = cache.enforce_budget(&index).unwrap();
+    assert_eq!(result.removed_entries, 1);
+    assert_eq!(cache.stats(&index).unwrap().entries, 0);
+}
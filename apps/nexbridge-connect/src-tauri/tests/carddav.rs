@@ -0,0 +1,469 @@
+// ---------------------------------------------------------------------------
+// Integration tests for the CardDAV contact provider
+// ---------------------------------------------------------------------------
+// A small in-process CardDAV stand-in (hyper on 127.0.0.1:0) plays the
+// server: discovery via /.well-known/carddav, the principal and the address
+// book home, sync-collection with expiring tokens, etag listings and
+// addressbook-multiget, behind Basic auth. Tests change its address book
+// between fetches and check what the provider reports and requests.
+// ---------------------------------------------------------------------------
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server};
+use nexbridge_connect_lib::carddav::CardDavProvider;
+use nexbridge_connect_lib::contact_providers::{ContactChanges, ContactProvider};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+const USERNAME: &str = "alice";
+const PASSWORD: &str = "app-password";
+const BOOK: &str = "/dav/alice/contacts/";
+
+#[derive(Debug, Clone)]
+struct Recorded {
+    method: String,
+    path: String,
+    body: String,
+}
+
+struct Card {
+    etag: String,
+    /// None once deleted (kept as a tombstone for sync-collection)
+    vcard: Option<String>,
+    changed_at: u64,
+}
+
+#[derive(Default)]
+struct AddressBook {
+    cards: BTreeMap<String, Card>,
+    revision: u64,
+    /// Tokens from before this revision are rejected as expired
+    oldest_token: u64,
+    /// Answer REPORT sync-collection with 501, like servers without RFC 6578
+    no_sync_collection: bool,
+}
+
+impl AddressBook {
+    fn put(&mut self, name: &str, uid: &str, full_name: &str, email: &str) {
+        self.revision += 1;
+        let vcard = format!(
+            "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:{}\r\nFN:{}\r\nEMAIL;TYPE=INTERNET:{}\r\nEND:VCARD\r\n",
+            uid, full_name, email
+        );
+        self.cards.insert(
+            name.to_string(),
+            Card { etag: format!("\"{}-{}\"", name, self.revision), vcard: Some(vcard), changed_at: self.revision },
+        );
+    }
+
+    fn delete(&mut self, name: &str) {
+        self.revision += 1;
+        let card = self.cards.get_mut(name).expect("card exists");
+        card.vcard = None;
+        card.changed_at = self.revision;
+    }
+
+    fn token(&self) -> String {
+        format!("http://standin.test/sync/{}", self.revision)
+    }
+}
+
+struct Reply {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+fn reply(status: u16, body: impl Into<String>) -> Reply {
+    Reply { status, headers: vec![], body: body.into() }
+}
+
+struct StandIn {
+    url: String,
+    book: Arc<Mutex<AddressBook>>,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl StandIn {
+    async fn start() -> Self {
+        let book = Arc::new(Mutex::new(AddressBook::default()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let (state, log) = (book.clone(), requests.clone());
+
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            let log = log.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: hyper::Request<Body>| {
+                    let state = state.clone();
+                    let log = log.clone();
+                    async move {
+                        let (parts, body) = req.into_parts();
+                        let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+                        let recorded = Recorded {
+                            method: parts.method.to_string(),
+                            path: parts.uri.path().to_string(),
+                            body: String::from_utf8_lossy(&body).into_owned(),
+                        };
+                        let expected = format!("Basic {}", BASE64.encode(format!("{}:{}", USERNAME, PASSWORD)));
+                        let authorized = parts
+                            .headers
+                            .get("authorization")
+                            .and_then(|v| v.to_str().ok())
+                            .is_some_and(|v| v == expected);
+                        let depth = parts.headers.get("depth").and_then(|v| v.to_str().ok()).unwrap_or("0").to_string();
+                        let reply = if authorized {
+                            handle(&mut state.lock().unwrap(), &recorded, &depth)
+                        } else {
+                            reply(401, "")
+                        };
+                        log.lock().unwrap().push(recorded);
+
+                        let mut response = Response::builder()
+                            .status(reply.status)
+                            .header("Content-Type", "application/xml; charset=utf-8");
+                        for (name, value) in reply.headers {
+                            response = response.header(name, value);
+                        }
+                        Ok::<_, Infallible>(response.body(Body::from(reply.body)).unwrap())
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        Self { url, book, requests }
+    }
+
+    fn requests(&self) -> Vec<Recorded> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+
+    fn provider(&self, path: &str, password: &str) -> CardDavProvider {
+        CardDavProvider::new("acct", &format!("{}{}", self.url, path), USERNAME, password).unwrap()
+    }
+}
+
+fn multistatus(responses: &str, sync_token: Option<&str>) -> Reply {
+    let token = sync_token.map(|t| format!("<d:sync-token>{}</d:sync-token>", t)).unwrap_or_default();
+    reply(
+        207,
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:card="urn:ietf:params:xml:ns:carddav">{}{}</d:multistatus>"#,
+            responses, token
+        ),
+    )
+}
+
+fn prop_response(href: &str, props: &str) -> String {
+    format!(
+        "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        href, props
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn handle(book: &mut AddressBook, req: &Recorded, depth: &str) -> Reply {
+    match (req.method.as_str(), req.path.as_str()) {
+        ("PROPFIND", "/.well-known/carddav") => Reply {
+            status: 301,
+            headers: vec![("Location", "/".to_string())],
+            body: String::new(),
+        },
+        ("PROPFIND", "/") => multistatus(
+            &prop_response("/", "<d:resourcetype><d:collection/></d:resourcetype><d:current-user-principal><d:href>/principals/alice/</d:href></d:current-user-principal>"),
+            None,
+        ),
+        ("PROPFIND", "/principals/alice/") => multistatus(
+            &prop_response("/principals/alice/", "<d:resourcetype><d:principal/></d:resourcetype><card:addressbook-home-set><d:href>/dav/alice/</d:href></card:addressbook-home-set>"),
+            None,
+        ),
+        ("PROPFIND", "/dav/alice/") => multistatus(
+            &[
+                prop_response("/dav/alice/", "<d:resourcetype><d:collection/></d:resourcetype>"),
+                prop_response(BOOK, "<d:resourcetype><d:collection/><card:addressbook/></d:resourcetype>"),
+                prop_response("/dav/alice/calendar/", "<d:resourcetype><d:collection/></d:resourcetype>"),
+            ]
+            .concat(),
+            None,
+        ),
+        ("PROPFIND", BOOK) => {
+            let mut responses = prop_response(BOOK, "<d:resourcetype><d:collection/><card:addressbook/></d:resourcetype>");
+            if depth == "1" {
+                for (name, card) in book.cards.iter().filter(|(_, c)| c.vcard.is_some()) {
+                    responses.push_str(&prop_response(
+                        &format!("{}{}", BOOK, name),
+                        &format!("<d:resourcetype/><d:getetag>{}</d:getetag>", xml_escape(&card.etag)),
+                    ));
+                }
+            }
+            multistatus(&responses, None)
+        }
+        ("REPORT", BOOK) if req.body.contains("sync-collection") => {
+            if book.no_sync_collection {
+                return reply(501, "");
+            }
+            let token = between(&req.body, "sync-token>", "</").unwrap_or_default();
+            let since = if token.is_empty() {
+                0
+            } else {
+                match token.rsplit('/').next().and_then(|n| n.parse::<u64>().ok()) {
+                    Some(n) if n >= book.oldest_token => n,
+                    _ => {
+                        return reply(
+                            403,
+                            r#"<d:error xmlns:d="DAV:"><d:valid-sync-token/></d:error>"#,
+                        )
+                    }
+                }
+            };
+            let mut responses = String::new();
+            for (name, card) in book.cards.iter().filter(|(_, c)| c.changed_at > since) {
+                let href = format!("{}{}", BOOK, name);
+                match &card.vcard {
+                    Some(_) => responses.push_str(&prop_response(&href, &format!("<d:getetag>{}</d:getetag>", xml_escape(&card.etag)))),
+                    // A first sync lists only what exists
+                    None if since > 0 => responses.push_str(&format!(
+                        "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                        href
+                    )),
+                    None => {}
+                }
+            }
+            multistatus(&responses, Some(&book.token()))
+        }
+        ("REPORT", BOOK) if req.body.contains("addressbook-multiget") => {
+            let mut responses = String::new();
+            for href in all_between(&req.body, "<d:href>", "</d:href>") {
+                let name = href.trim_start_matches(BOOK);
+                match book.cards.get(name) {
+                    Some(Card { etag, vcard: Some(vcard), .. }) => responses.push_str(&prop_response(
+                        &href,
+                        &format!(
+                            "<d:getetag>{}</d:getetag><card:address-data>{}</card:address-data>",
+                            xml_escape(etag),
+                            xml_escape(vcard)
+                        ),
+                    )),
+                    _ => responses.push_str(&format!(
+                        "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                        href
+                    )),
+                }
+            }
+            multistatus(&responses, None)
+        }
+        _ => reply(404, ""),
+    }
+}
+
+fn between<'a>(s: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let from = s.find(start)? + start.len();
+    let to = s[from..].find(end)? + from;
+    Some(&s[from..to])
+}
+
+fn all_between(s: &str, start: &str, end: &str) -> Vec<String> {
+    s.split(start).skip(1).filter_map(|part| part.split(end).next()).map(String::from).collect()
+}
+
+/// What the app's cache would hold after applying each fetch.
+#[derive(Default)]
+struct Cache {
+    etags: HashMap<String, Option<String>>,
+    ids: HashMap<String, String>,
+    token: Option<String>,
+}
+
+impl Cache {
+    fn apply(&mut self, changes: &ContactChanges) {
+        for key in &changes.removed {
+            self.etags.remove(key);
+            self.ids.remove(key);
+        }
+        for upsert in &changes.upserts {
+            self.etags.insert(upsert.key.clone(), upsert.etag.clone());
+            self.ids.insert(upsert.key.clone(), upsert.contact.id.clone());
+        }
+        self.token = changes.sync_token.clone();
+    }
+
+    async fn fetch(&mut self, provider: &CardDavProvider) -> ContactChanges {
+        let changes = provider.fetch(self.token.as_deref(), &self.etags).await.expect("fetch succeeds");
+        self.apply(&changes);
+        changes
+    }
+}
+
+fn keys(changes: &ContactChanges) -> Vec<String> {
+    let mut keys: Vec<String> = changes.upserts.iter().map(|u| u.key.clone()).collect();
+    keys.sort();
+    keys
+}
+
+fn multiget_hrefs(requests: &[Recorded]) -> Vec<String> {
+    requests
+        .iter()
+        .filter(|r| r.body.contains("addressbook-multiget"))
+        .flat_map(|r| all_between(&r.body, "<d:href>", "</d:href>"))
+        .collect()
+}
+
+// ============ Sync ============
+
+#[tokio::test]
+async fn discovers_address_book_and_syncs_incrementally() {
+    let server = StandIn::start().await;
+    {
+        let mut book = server.book.lock().unwrap();
+        book.put("ada.vcf", "uid-ada", "Ada Lovelace", "ada@example.com");
+        book.put("bo.vcf", "uid-bo", "Bo Diddley", "bo@example.com");
+    }
+    let provider = server.provider("/.well-known/carddav", PASSWORD);
+    let mut cache = Cache::default();
+
+    // First fetch: well-known redirect → principal → home → address book
+    let first = cache.fetch(&provider).await;
+    assert_eq!(keys(&first), vec![format!("{}ada.vcf", BOOK), format!("{}bo.vcf", BOOK)]);
+    assert!(first.removed.is_empty());
+    let ada = first.upserts.iter().find(|u| u.key.ends_with("ada.vcf")).unwrap();
+    assert_eq!(ada.contact.id, "carddav:acct:uid-ada");
+    assert_eq!(ada.contact.email.as_deref(), Some("ada@example.com"));
+    let paths: Vec<String> = server.requests().iter().map(|r| format!("{} {}", r.method, r.path)).collect();
+    assert_eq!(
+        &paths[..5],
+        &[
+            "PROPFIND /.well-known/carddav",
+            "PROPFIND /",
+            "PROPFIND /principals/alice/",
+            "PROPFIND /dav/alice/",
+            "REPORT /dav/alice/contacts/"
+        ]
+    );
+
+    // Nothing changed: one report, no multiget, no discovery
+    let unchanged = cache.fetch(&provider).await;
+    assert!(unchanged.upserts.is_empty() && unchanged.removed.is_empty());
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].body.contains("http://standin.test/sync/2"));
+
+    // Edit one, delete one, add one
+    {
+        let mut book = server.book.lock().unwrap();
+        book.put("ada.vcf", "uid-ada", "Ada King", "ada@example.com");
+        book.delete("bo.vcf");
+        book.put("cy.vcf", "uid-cy", "Cy Young", "cy@example.com");
+    }
+    let delta = cache.fetch(&provider).await;
+    assert_eq!(keys(&delta), vec![format!("{}ada.vcf", BOOK), format!("{}cy.vcf", BOOK)]);
+    assert_eq!(delta.removed, vec![format!("{}bo.vcf", BOOK)]);
+    assert_eq!(multiget_hrefs(&server.requests()).len(), 2);
+    assert_eq!(cache.ids.len(), 2);
+}
+
+#[tokio::test]
+async fn expired_sync_token_falls_back_to_a_full_listing() {
+    let server = StandIn::start().await;
+    {
+        let mut book = server.book.lock().unwrap();
+        book.put("ada.vcf", "uid-ada", "Ada", "ada@example.com");
+        book.put("bo.vcf", "uid-bo", "Bo", "bo@example.com");
+    }
+    let provider = server.provider(BOOK, PASSWORD);
+    let mut cache = Cache::default();
+    cache.fetch(&provider).await;
+    server.requests();
+
+    {
+        let mut book = server.book.lock().unwrap();
+        book.put("bo.vcf", "uid-bo", "Bo Jr", "bo@example.com");
+        book.delete("ada.vcf");
+        book.oldest_token = book.revision;
+    }
+    let changes = cache.fetch(&provider).await;
+    assert_eq!(keys(&changes), vec![format!("{}bo.vcf", BOOK)]);
+    assert_eq!(changes.removed, vec![format!("{}ada.vcf", BOOK)]);
+
+    let requests = server.requests();
+    let reports: Vec<&Recorded> = requests.iter().filter(|r| r.body.contains("sync-collection")).collect();
+    assert_eq!(reports.len(), 2, "retried without the token");
+    assert!(reports[1].body.contains("<d:sync-token></d:sync-token>"));
+    assert_eq!(multiget_hrefs(&requests), vec![format!("{}bo.vcf", BOOK)]);
+}
+
+#[tokio::test]
+async fn compares_etags_when_sync_collection_is_unsupported() {
+    let server = StandIn::start().await;
+    {
+        let mut book = server.book.lock().unwrap();
+        book.no_sync_collection = true;
+        book.put("ada.vcf", "uid-ada", "Ada", "ada@example.com");
+        book.put("bo.vcf", "uid-bo", "Bo", "bo@example.com");
+    }
+    let provider = server.provider(BOOK, PASSWORD);
+    let mut cache = Cache::default();
+    let first = cache.fetch(&provider).await;
+    assert_eq!(first.upserts.len(), 2);
+    server.requests();
+
+    {
+        let mut book = server.book.lock().unwrap();
+        book.put("ada.vcf", "uid-ada", "Ada B", "ada@example.com");
+        book.delete("bo.vcf");
+    }
+    let changes = cache.fetch(&provider).await;
+    assert_eq!(keys(&changes), vec![format!("{}ada.vcf", BOOK)]);
+    assert_eq!(changes.removed, vec![format!("{}bo.vcf", BOOK)]);
+    assert_eq!(multiget_hrefs(&server.requests()), vec![format!("{}ada.vcf", BOOK)]);
+}
+
+#[tokio::test]
+async fn rediscovers_when_the_address_book_moves() {
+    let server = StandIn::start().await;
+    server.book.lock().unwrap().put("ada.vcf", "uid-ada", "Ada", "ada@example.com");
+    let provider = server.provider("/", PASSWORD);
+    let mut cache = Cache {
+        token: Some(r#"{"books":{"http://127.0.0.1:9/gone/":"old"}}"#.to_string()),
+        ..Default::default()
+    };
+    // The saved address book is on a dead port: the fetch fails outright
+    assert!(provider.fetch(cache.token.as_deref(), &cache.etags).await.is_err());
+
+    let moved = format!(r#"{{"books":{{"{}/dav/alice/old-contacts/":"x"}}}}"#, server.url);
+    cache.token = Some(moved);
+    cache.etags.insert("/dav/alice/old-contacts/ada.vcf".to_string(), Some("\"old\"".to_string()));
+    let changes = cache.fetch(&provider).await;
+    assert_eq!(keys(&changes), vec![format!("{}ada.vcf", BOOK)]);
+    assert_eq!(changes.removed, vec!["/dav/alice/old-contacts/ada.vcf".to_string()]);
+    assert!(cache.token.as_deref().unwrap().contains(BOOK));
+}
+
+// ============ Errors ============
+
+#[tokio::test]
+async fn reports_a_wrong_password() {
+    let server = StandIn::start().await;
+    let provider = server.provider("/", "wrong");
+    let err = provider.fetch(None, &HashMap::new()).await.unwrap_err();
+    assert!(err.contains("sign-in failed"), "{}", err);
+}
+
+#[tokio::test]
+async fn falls_back_to_well_known_for_other_urls() {
+    let server = StandIn::start().await;
+    server.book.lock().unwrap().put("ada.vcf", "uid-ada", "Ada", "ada@example.com");
+    // Not an address book, principal or home: found via /.well-known/carddav
+    let provider = server.provider("/dav/alice/calendar/", PASSWORD);
+    let changes = provider.fetch(None, &HashMap::new()).await.unwrap();
+    assert_eq!(keys(&changes), vec![format!("{}ada.vcf", BOOK)]);
+}
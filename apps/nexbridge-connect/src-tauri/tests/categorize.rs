@@ -0,0 +1,131 @@
+// ---------------------------------------------------------------------------
+// Integration tests for rule-based categorization
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::cache::ConversionCache;
+use nexbridge_connect_lib::categorize;
+use nexbridge_connect_lib::index::{CategoryRule, DocumentIndex, DocumentStatus, IndexedDocument, RuleField};
+
+mod common;
+
+/// A document at `path` under /share, ready to be categorized.
+fn document(id: &str, path: &str) -> IndexedDocument {
+    common::document(id, path, DocumentStatus::Import)
+}
+
+fn rule(category: &str, field: RuleField, keywords: &[&str], priority: i32) -> CategoryRule {
+    CategoryRule {
+        id: 0,
+        name: format!("{} rule", category),
+        category: category.to_string(),
+        field,
+        keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        priority,
+        enabled: true,
+    }
+}
+
+fn category(rules: &[CategoryRule], doc: &IndexedDocument, text: Option<&str>) -> Option<String> {
+    categorize::match_rule(rules, doc, text).map(|(r, _)| r.category.clone())
+}
+
+// ============ Matching ============
+
+#[test]
+fn matches_keywords_on_word_boundaries() {
+    let rules = [rule("sop", RuleField::FileName, &["SOP"], 0)];
+    assert_eq!(category(&rules, &document("a", "Jobs/Site SOP v2.pdf"), None).as_deref(), Some("sop"));
+    assert_eq!(category(&rules, &document("b", "Jobs/site-sop_v2.pdf"), None).as_deref(), Some("sop"));
+    assert_eq!(category(&rules, &document("c", "Jobs/philosophy.pdf"), None), None);
+    assert_eq!(category(&rules, &document("d", "Jobs/SOPs.pdf"), None), None);
+
+    // Multi-word keywords match as a phrase
+    let phrase = [rule("sop", RuleField::FileName, &["work instruction"], 0)];
+    assert!(category(&phrase, &document("e", "Jobs/Crane Work Instruction.docx"), None).is_some());
+    assert!(category(&phrase, &document("f", "Jobs/Instruction for work.docx"), None).is_none());
+}
+
+#[test]
+fn looks_only_at_the_rule_field() {
+    let folder = [rule("safety", RuleField::Breadcrumb, &["osha"], 0)];
+    assert!(category(&folder, &document("a", "Jobs/OSHA Logs/2024.xlsx"), None).is_some());
+    // The file itself is not a folder
+    assert!(category(&folder, &document("b", "Jobs/osha.xlsx"), None).is_none());
+
+    let extension = [rule("drawing", RuleField::Extension, &[".DWG"], 0)];
+    assert!(category(&extension, &document("c", "Plans/level1.dwg"), None).is_some());
+    assert!(category(&extension, &document("d", "Plans/dwg-list.pdf"), None).is_none());
+
+    let text = [rule("invoice", RuleField::Text, &["amount due"], 0)];
+    let doc = document("e", "Billing/scan-0042.pdf");
+    assert!(category(&text, &doc, Some("Total AMOUNT DUE: $1,200")).is_some());
+    assert!(category(&text, &doc, Some("amount dueling")).is_none());
+    // Not converted yet: text rules don't match
+    assert!(category(&text, &doc, None).is_none());
+}
+
+#[test]
+fn first_enabled_rule_in_order_wins() {
+    let mut rules = vec![
+        rule("contract", RuleField::FileName, &["agreement"], 20),
+        rule("safety", RuleField::Breadcrumb, &["safety"], 10),
+    ];
+    let doc = document("a", "Safety/Subcontract Agreement.pdf");
+    let (matched, keyword) = categorize::match_rule(&rules, &doc, None).unwrap();
+    assert_eq!((matched.category.as_str(), keyword.as_str()), ("contract", "agreement"));
+
+    rules[0].enabled = false;
+    assert_eq!(category(&rules, &doc, None).as_deref(), Some("safety"));
+}
+
+#[test]
+fn rejects_rules_without_name_category_or_keywords() {
+    assert!(categorize::validate_rule(&rule("sop", RuleField::FileName, &["sop"], 0)).is_ok());
+    assert!(categorize::validate_rule(&rule(" ", RuleField::FileName, &["sop"], 0)).is_err());
+    assert!(categorize::validate_rule(&rule("sop", RuleField::FileName, &["", "  "], 0)).is_err());
+    let mut unnamed = rule("sop", RuleField::FileName, &["sop"], 0);
+    unnamed.name = String::new();
+    assert!(categorize::validate_rule(&unnamed).is_err());
+}
+
+// ============ Index ============
+
+#[test]
+fn new_indexes_are_seeded_with_ordered_default_rules() {
+    let dir = common::temp_dir("defaults");
+    let index = DocumentIndex::new(&dir.join("documents.db"), None).unwrap();
+    let rules = index.list_category_rules().unwrap();
+    assert_eq!(rules.len(), categorize::default_rules().len());
+    assert!(rules.windows(2).all(|w| w[0].priority >= w[1].priority));
+
+    // A safety folder loses to an invoice file name
+    let doc = document("a", "Safety/Invoices/invoice 118.pdf");
+    assert_eq!(category(&rules, &doc, None).as_deref(), Some("invoice"));
+}
+
+#[test]
+fn applies_proposals_only_when_asked() {
+    let dir = common::temp_dir("apply");
+    let index = DocumentIndex::new(&dir.join("documents.db"), None).unwrap();
+    for id in index.list_category_rules().unwrap().iter().map(|r| r.id) {
+        index.delete_category_rule(id).unwrap();
+    }
+    let saved = index.save_category_rule(&rule("permit", RuleField::Breadcrumb, &["permits"], 5)).unwrap();
+
+    let docs = [document("a", "City/Permits/electrical.pdf"), document("b", "City/Letters/hello.pdf")];
+    for doc in &docs {
+        index.upsert_document(doc).unwrap();
+    }
+    let cache = ConversionCache::new(dir.join("converted"), None);
+
+    let dry_run = categorize::categorize_documents(&index, &cache, &docs, false).unwrap();
+    assert_eq!(dry_run[0].proposed_category.as_deref(), Some("permit"));
+    assert_eq!(dry_run[0].rule_id, Some(saved));
+    assert_eq!(dry_run[0].matched_keyword.as_deref(), Some("permits"));
+    assert_eq!(dry_run[1].proposed_category, None);
+    assert_eq!(index.get_document("a").unwrap().unwrap().category, None);
+
+    categorize::categorize_documents(&index, &cache, &docs, true).unwrap();
+    assert_eq!(index.get_document("a").unwrap().unwrap().category.as_deref(), Some("permit"));
+    assert_eq!(index.get_document("b").unwrap().unwrap().category, None);
+}
@@ -0,0 +1,92 @@
+// ---------------------------------------------------------------------------
+// Integration tests for the nexbridge CLI, run as a separate process
+// ---------------------------------------------------------------------------
+// Each test gets its own --data-dir. There is no OS credential store here, so
+// the local data key comes from NEXBRIDGE_PASSPHRASE; API credentials are
+// cleared so nothing reaches a real server.
+// ---------------------------------------------------------------------------
+
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+mod common;
+
+struct Output {
+    code: i32,
+    stdout: Value,
+    stderr: String,
+}
+
+/// Run `nexbridge --data-dir {dir}/data <args>`.
+fn nexbridge(dir: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_nexbridge"))
+        .arg("--data-dir")
+        .arg(dir.join("data"))
+        .args(args)
+        .env("NEXBRIDGE_PASSPHRASE", "cli-test-passphrase")
+        .env_remove("NEXBRIDGE_API_URL")
+        .env_remove("NEXBRIDGE_TOKEN")
+        .env_remove("NEXBRIDGE_REFRESH_TOKEN")
+        .output()
+        .expect("nexbridge runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Output {
+        code: output.status.code().unwrap_or(-1),
+        stdout: serde_json::from_str(&stdout).unwrap_or(Value::Null),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    }
+}
+
+/// The {"error": ...} line printed to stderr.
+fn error_of(output: &Output) -> String {
+    let line = output.stderr.lines().rev().find(|l| l.starts_with('{')).unwrap_or_default();
+    let json: Value = serde_json::from_str(line).unwrap_or(Value::Null);
+    json["error"].as_str().unwrap_or_default().to_string()
+}
+
+// ============ Commands ============
+
+#[test]
+fn scans_a_folder_and_reports_it() {
+    let dir = common::temp_dir("scan");
+    let docs = dir.join("docs");
+    std::fs::create_dir_all(docs.join("jobs")).unwrap();
+    std::fs::write(docs.join("quote.html"), "<html><body><h1>Quote</h1></body></html>").unwrap();
+    std::fs::write(docs.join("jobs").join("notes.txt"), "Site visit").unwrap();
+
+    let scan = nexbridge(&dir, &["scan", docs.to_str().unwrap()]);
+    assert_eq!(scan.code, 0, "{}", scan.stderr);
+    assert_eq!(scan.stdout["documents_found"], 2);
+    assert_eq!(scan.stdout["documents_new"], 2);
+
+    let stats = nexbridge(&dir, &["stats"]);
+    assert_eq!(stats.code, 0, "{}", stats.stderr);
+    assert_eq!((stats.stdout["total"].as_u64(), stats.stdout["pending"].as_u64()), (Some(2), Some(2)));
+
+    let status = nexbridge(&dir, &["status", "--status", "PENDING"]);
+    let mut names: Vec<&str> = status.stdout.as_array().unwrap().iter().map(|d| d["file_name"].as_str().unwrap()).collect();
+    names.sort();
+    assert_eq!(names, ["notes.txt", "quote.html"]);
+
+    // Scanning again doesn't index the files twice
+    let again = nexbridge(&dir, &["scan", docs.to_str().unwrap()]);
+    assert_eq!(again.stdout["documents_found"], 2);
+    assert_eq!(nexbridge(&dir, &["stats"]).stdout["total"], 2);
+}
+
+// ============ Errors ============
+
+#[test]
+fn failures_print_a_json_error_and_exit_1() {
+    let dir = common::temp_dir("errors");
+
+    let unknown = nexbridge(&dir, &["bogus"]);
+    assert_eq!(unknown.code, 1);
+    assert_eq!(error_of(&unknown), "Unknown command: bogus");
+    assert_eq!(unknown.stdout, Value::Null);
+
+    let upload = nexbridge(&dir, &["upload"]);
+    assert_eq!(upload.code, 1);
+    assert_eq!(error_of(&upload), "No API URL configured (set NEXBRIDGE_API_URL)");
+}
@@ -0,0 +1,78 @@
+// ---------------------------------------------------------------------------
+// Fixtures shared by the integration tests
+// ---------------------------------------------------------------------------
+// Each test file pulls this in with `mod common;` and uses only part of it.
+// ---------------------------------------------------------------------------
+
+#![allow(dead_code)]
+
+use nexbridge_connect_lib::index::{DocumentStatus, IndexedDocument};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A scratch directory that is removed again when the test is done with it.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// An empty directory named after the test binary, `name` and this process.
+pub fn temp_dir(name: &str) -> TempDir {
+    let dir = std::env::temp_dir().join(format!("{}-test-{}-{}", env!("CARGO_CRATE_NAME"), name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+}
+
+/// An indexed document at `path`. A relative path is placed under `/share`;
+/// the breadcrumb is every path segment below the share root.
+pub fn document(id: &str, path: &str, status: DocumentStatus) -> IndexedDocument {
+    let now = chrono::Utc::now().to_rfc3339();
+    let file_path = if path.starts_with('/') || path.contains(":\\") {
+        path.to_string()
+    } else {
+        format!("/share/{}", path)
+    };
+    let breadcrumb: Vec<String> = file_path.split(['/', '\\']).filter(|s| !s.is_empty()).skip(1).map(|s| s.to_string()).collect();
+    let file_name = breadcrumb.last().unwrap().clone();
+    IndexedDocument {
+        id: id.to_string(),
+        file_path,
+        file_type: file_name.rsplit('.').next().map(|e| e.to_string()),
+        file_name,
+        file_size: 1024,
+        file_hash: Some(format!("hash-{}", id)),
+        breadcrumb,
+        status,
+        error_message: None,
+        scanned_at: now.clone(),
+        updated_at: now,
+        uploaded_at: None,
+        nexus_doc_id: None,
+        category: None,
+        metadata: Default::default(),
+    }
+}
@@ -0,0 +1,219 @@
+// ---------------------------------------------------------------------------
+// Integration tests for duplicate detection and merging
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::contact_duplicates::{find_duplicates, MergeProposal};
+use nexbridge_connect_lib::contact_groups::ContactGroupIndex;
+use nexbridge_connect_lib::contacts::Contact;
+
+mod common;
+
+fn contact(id: &str, name: &str) -> Contact {
+    Contact {
+        id: id.to_string(),
+        display_name: Some(name.to_string()),
+        first_name: None,
+        last_name: None,
+        email: None,
+        phone: None,
+        all_emails: vec![],
+        all_phones: vec![],
+        street: None,
+        city: None,
+        state: None,
+        zip: None,
+        country: None,
+        company: None,
+        job_title: None,
+    }
+}
+
+fn with_phone(mut contact: Contact, phone: &str) -> Contact {
+    contact.phone = Some(phone.to_string());
+    contact.all_phones.push(phone.to_string());
+    contact
+}
+
+fn with_email(mut contact: Contact, email: &str) -> Contact {
+    contact.email = Some(email.to_string());
+    contact.all_emails.push(email.to_string());
+    contact
+}
+
+fn with_company(mut contact: Contact, company: &str) -> Contact {
+    contact.company = Some(company.to_string());
+    contact
+}
+
+fn only(proposals: &[MergeProposal]) -> &MergeProposal {
+    assert_eq!(proposals.len(), 1, "{:#?}", proposals);
+    &proposals[0]
+}
+
+fn sorted_ids(proposal: &MergeProposal) -> Vec<&str> {
+    let mut ids: Vec<&str> = proposal.contact_ids.iter().map(String::as_str).collect();
+    ids.sort();
+    ids
+}
+
+// ============ Signals ============
+
+#[test]
+fn same_phone_in_any_format_is_a_duplicate() {
+    let contacts = [
+        with_phone(contact("a", "Dana Reyes"), "(512) 555-0100"),
+        with_phone(contact("b", "D. R."), "+1 512 555 0100 x12"),
+        // Too short to compare
+        with_phone(contact("c", "Front desk"), "555-0100"),
+    ];
+    let proposal = only(&find_duplicates(&contacts)).clone();
+    assert_eq!(sorted_ids(&proposal), ["a", "b"]);
+    assert_eq!(proposal.reasons, ["Same phone +15125550100"]);
+    assert_eq!(proposal.confidence, 0.9);
+}
+
+#[test]
+fn same_email_ignores_case_and_plus_tags() {
+    let contacts = [
+        with_email(contact("a", "Ada Lovelace"), "Ada@Example.com"),
+        with_email(contact("b", "Countess"), "ada+invoices@example.com"),
+        with_email(contact("c", "Someone else"), "ada@example.org"),
+    ];
+    let proposal = only(&find_duplicates(&contacts)).clone();
+    assert_eq!(sorted_ids(&proposal), ["a", "b"]);
+    assert_eq!(proposal.reasons, ["Same email ada@example.com"]);
+
+    // Both addresses are kept: plus-tagged mail is still delivered separately
+    assert_eq!(proposal.merged.all_emails.len(), 2);
+}
+
+#[test]
+fn similar_names_depend_on_the_company() {
+    let jonathan = || contact("a", "Jonathan Smith");
+    let jonathon = || contact("b", "Jonathon Smith");
+
+    // A near-identical name alone is enough
+    let proposal = only(&find_duplicates(&[jonathan(), jonathon()])).clone();
+    assert_eq!(proposal.reasons, ["Similar names"]);
+    assert_eq!(proposal.confidence, 0.6);
+
+    // Word order doesn't matter
+    let proposal = only(&find_duplicates(&[jonathan(), contact("b", "Smith Jonathan")])).clone();
+    assert_eq!(sorted_ids(&proposal), ["a", "b"]);
+
+    // The same company raises it, a different one rules it out
+    let proposals = find_duplicates(&[
+        with_company(jonathan(), "Acme Roofing LLC"),
+        with_company(jonathon(), "acme roofing"),
+    ]);
+    assert_eq!(only(&proposals).reasons, ["Similar names at the same company"]);
+    assert_eq!(only(&proposals).confidence, 0.85);
+    assert!(find_duplicates(&[
+        with_company(jonathan(), "Acme Roofing"),
+        with_company(jonathon(), "Summit Electric"),
+    ])
+    .is_empty());
+
+    // Sharing a surname isn't
+    assert!(find_duplicates(&[jonathan(), contact("b", "Margaret Smith")]).is_empty());
+}
+
+#[test]
+fn a_name_that_matches_the_other_contacts_company() {
+    let contacts = [
+        contact("a", "Mike Plumbing"),
+        with_company(contact("b", "Mike Johnson"), "Johnson Plumbing Inc"),
+        with_company(contact("c", "Sara Johnson"), "Johnson Plumbing Inc"),
+    ];
+    let proposal = only(&find_duplicates(&contacts)).clone();
+    assert_eq!(sorted_ids(&proposal), ["a", "b"]);
+    assert_eq!(proposal.reasons, ["Name matches the other's company"]);
+}
+
+// ============ Proposals ============
+
+#[test]
+fn matches_chain_into_one_cluster_at_the_weakest_link() {
+    let contacts = [
+        with_phone(contact("a", "Dana Reyes"), "512-555-0100"),
+        with_email(with_phone(contact("b", "Dana"), "5125550100"), "dana@reyes.example"),
+        with_email(contact("c", "Reyes Accounts"), "DANA@reyes.example"),
+        with_phone(contact("d", "Unrelated"), "512-555-0199"),
+        contact("e", "Jonathan Smith"),
+        contact("f", "Jonathon Smith"),
+    ];
+    let proposals = find_duplicates(&contacts);
+    assert_eq!(proposals.len(), 2);
+
+    // Most confident first
+    assert_eq!(sorted_ids(&proposals[0]), ["a", "b", "c"]);
+    assert_eq!(proposals[0].confidence, 0.9);
+    assert_eq!(proposals[0].reasons, ["Same email dana@reyes.example", "Same phone +15125550100"]);
+    assert_eq!(sorted_ids(&proposals[1]), ["e", "f"]);
+}
+
+#[test]
+fn keeps_the_most_complete_contact_and_lists_every_candidate() {
+    let mut full = with_email(with_phone(contact("full", "Dana Reyes"), "512-555-0100"), "dana@reyes.example");
+    full.city = Some("Austin".to_string());
+    full.company = Some("Reyes Electric".to_string());
+    let mut sparse = with_phone(contact("sparse", "Dana R"), "+1 (512) 555-0100");
+    sparse.all_phones.push("512-555-0177".to_string());
+    sparse.job_title = Some("Owner".to_string());
+    sparse.company = Some("Reyes Electric LLC".to_string());
+
+    let proposal = only(&find_duplicates(&[sparse.clone(), full.clone()])).clone();
+    assert_eq!(proposal.primary_id, "full");
+    assert_eq!(proposal.contact_ids, ["full", "sparse"]);
+
+    let field = |name: &str| proposal.fields.iter().find(|f| f.field == name).unwrap();
+    // The primary's value wins a conflict; the other is offered
+    let display_name = field("display_name");
+    assert_eq!(display_name.value.as_deref(), Some("Dana Reyes"));
+    assert!(display_name.conflict);
+    let offered: Vec<&str> = display_name.candidates.iter().map(|c| c.value.as_str()).collect();
+    assert_eq!(offered, ["Dana Reyes", "Dana R"]);
+    // Missing on the primary: taken from the other contact
+    assert_eq!(field("job_title").value.as_deref(), Some("Owner"));
+    assert_eq!(field("job_title").source_id.as_deref(), Some("sparse"));
+    assert!(!field("city").conflict);
+
+    assert_eq!(proposal.merged.id, "full");
+    assert_eq!(proposal.merged.job_title.as_deref(), Some("Owner"));
+    // The same number in another format is listed once
+    assert_eq!(proposal.merged.all_phones, ["512-555-0100", "512-555-0177"]);
+
+    // The id doesn't depend on the order the contacts came in
+    let again = find_duplicates(&[full, sparse]);
+    assert_eq!(only(&again).id, proposal.id);
+}
+
+// ============ Applying a merge ============
+
+#[test]
+fn merging_moves_group_membership_to_the_kept_contact() {
+    let dir = common::temp_dir("merge");
+    let groups = ContactGroupIndex::new(&dir.join("contact_groups.db"), None).unwrap();
+    let crew = groups.create_group("Crew", None, None, None).unwrap();
+    let ids = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    groups.add_contacts_to_group(&ids(&["b", "c"]), &crew.id).unwrap();
+
+    assert_eq!(groups.merge_contacts("a", &ids(&["a", "b", "c"])).unwrap(), 2);
+    let mut merges = groups.get_merges().unwrap();
+    merges.sort();
+    assert_eq!(merges, [("b".to_string(), "a".to_string()), ("c".to_string(), "a".to_string())]);
+    assert_eq!(groups.get_group_members().unwrap()[&crew.id], ["a"]);
+
+    // Merging "a" into "d" carries the earlier merges along
+    groups.merge_contacts("d", &ids(&["a"])).unwrap();
+    let kept: Vec<String> = groups.get_merges().unwrap().into_iter().map(|(_, kept)| kept).collect();
+    assert_eq!(kept, ["d", "d", "d"]);
+    assert_eq!(groups.get_group_members().unwrap()[&crew.id], ["d"]);
+
+    // And merging "d" back into "a" undoes that
+    groups.merge_contacts("a", &ids(&["d"])).unwrap();
+    let mut merges = groups.get_merges().unwrap();
+    merges.sort();
+    assert_eq!(merges.iter().filter(|(merged, _)| merged == "a").count(), 0);
+    assert!(merges.iter().all(|(_, kept)| kept == "a"));
+}
@@ -0,0 +1,171 @@
+// ---------------------------------------------------------------------------
+// Integration tests for contact_normalizer
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::contact_normalizer::{self, ContactField, FieldChange, NormalizeOptions, StateStyle};
+use nexbridge_connect_lib::contacts::Contact;
+
+fn contact(id: &str) -> Contact {
+    Contact {
+        id: id.to_string(),
+        display_name: None,
+        first_name: None,
+        last_name: None,
+        email: None,
+        phone: None,
+        all_emails: vec![],
+        all_phones: vec![],
+        street: None,
+        city: None,
+        state: None,
+        zip: None,
+        country: None,
+        company: None,
+        job_title: None,
+    }
+}
+
+fn change(field: ContactField, before: &str, after: &str) -> FieldChange {
+    FieldChange { field, before: before.to_string(), after: after.to_string() }
+}
+
+// ============ Field rules ============
+
+#[test]
+fn formats_phones_as_e164() {
+    let cases = [
+        ("(555) 010-2000", "US", "+15550102000"),
+        ("1-555-010-2000", "US", "+15550102000"),
+        ("555.010.2000 ext. 12", "US", "+15550102000 x12"),
+        ("011 44 20 7946 0958", "US", "+442079460958"),
+        ("+44 (0)20 7946 0958", "US", "+442079460958"),
+        ("020 7946 0958", "GB", "+442079460958"),
+        ("06 12 34 56 78", "FR", "+33612345678"),
+        ("0049 30 123456", "US", "+4930123456"),
+        ("+15550102000 x12", "US", "+15550102000 x12"),
+    ];
+    for (raw, region, expected) in cases {
+        assert_eq!(contact_normalizer::format_phone(raw, region).as_deref(), Ok(expected), "{}", raw);
+    }
+
+    assert!(contact_normalizer::format_phone("1-800-FLOWERS", "US").is_err());
+    assert!(contact_normalizer::format_phone("555-0100", "US").is_err());
+    assert!(contact_normalizer::format_phone("(155) 010-2000", "US").is_err());
+    assert!(contact_normalizer::format_phone("555 010 2000", "ZZ").is_err());
+}
+
+#[test]
+fn title_cases_names_typed_in_one_case() {
+    let cases = [
+        ("JOHN", "John"),
+        ("mary-kate", "Mary-Kate"),
+        ("o'brien", "O'Brien"),
+        ("MCDONALD", "McDonald"),
+        ("ludwig van beethoven", "Ludwig van Beethoven"),
+        ("henry ford iii", "Henry Ford III"),
+        ("  ana   maria ", "Ana Maria"),
+        // Mixed case was typed on purpose
+        ("DeShawn", "DeShawn"),
+        ("van der Berg", "van der Berg"),
+    ];
+    for (raw, expected) in cases {
+        assert_eq!(contact_normalizer::normalize_name(raw), expected, "{}", raw);
+    }
+}
+
+#[test]
+fn lowercases_emails_and_flags_invalid_ones() {
+    assert_eq!(contact_normalizer::normalize_email(" Ana.Diaz@Example.COM ").as_deref(), Ok("ana.diaz@example.com"));
+    assert_eq!(contact_normalizer::normalize_email("mailto:bo@example.com").as_deref(), Ok("bo@example.com"));
+    for invalid in ["ana", "ana@localhost", "@example.com", "ana diaz@example.com", "a@b@example.com"] {
+        assert!(contact_normalizer::normalize_email(invalid).is_err(), "{}", invalid);
+    }
+}
+
+// ============ Contacts ============
+
+#[test]
+fn previews_changes_and_issues_per_contact() {
+    let mut messy = contact("c1");
+    messy.first_name = Some("JANE".to_string());
+    messy.last_name = Some("o'neil".to_string());
+    messy.display_name = Some("JANE o'neil".to_string());
+    messy.email = Some("Jane@Example.com".to_string());
+    messy.all_emails = vec!["Jane@Example.com".to_string(), "jane at home".to_string()];
+    messy.phone = Some("(555) 010-2000".to_string());
+    messy.all_phones = vec!["(555) 010-2000".to_string()];
+    messy.state = Some("california".to_string());
+    messy.zip = Some("941021234".to_string());
+
+    let mut clean = contact("c2");
+    clean.display_name = Some("Acme Plumbing".to_string());
+    clean.phone = Some("+15550103000".to_string());
+    clean.all_phones = vec!["+15550103000".to_string()];
+
+    let preview = contact_normalizer::preview(&[messy, clean], &NormalizeOptions::default());
+    assert_eq!(preview.len(), 1);
+    let result = &preview[0];
+    assert_eq!(result.contact_id, "c1");
+    assert_eq!(result.name, "Jane O'Neil");
+    assert_eq!(
+        result.changes,
+        vec![
+            change(ContactField::FirstName, "JANE", "Jane"),
+            change(ContactField::LastName, "o'neil", "O'Neil"),
+            change(ContactField::DisplayName, "JANE o'neil", "Jane O'Neil"),
+            change(ContactField::Email, "Jane@Example.com", "jane@example.com"),
+            change(ContactField::Phone, "(555) 010-2000", "+15550102000"),
+            change(ContactField::State, "california", "CA"),
+            change(ContactField::Zip, "941021234", "94102-1234"),
+        ]
+    );
+    assert_eq!(result.issues.len(), 1);
+    assert_eq!(result.issues[0].field, ContactField::Email);
+    assert_eq!(result.issues[0].value, "jane at home");
+
+    let normalized = &result.normalized;
+    assert_eq!(normalized.all_emails, vec!["jane@example.com", "jane at home"]);
+    assert_eq!(normalized.phone.as_deref(), Some("+15550102000"));
+
+    // Running it again finds nothing more to change
+    let again = contact_normalizer::normalize(normalized, &NormalizeOptions::default());
+    assert!(again.changes.is_empty());
+}
+
+#[test]
+fn normalizes_addresses_by_country() {
+    let options = NormalizeOptions::default();
+    let address = |state: Option<&str>, zip: &str, country: Option<&str>| {
+        let mut c = contact("a");
+        c.state = state.map(String::from);
+        c.zip = Some(zip.to_string());
+        c.country = country.map(String::from);
+        contact_normalizer::normalize(&c, &options).normalized
+    };
+
+    let canada = address(Some("Ontario"), "k1a0b1", None);
+    assert_eq!((canada.state.as_deref(), canada.zip.as_deref()), (Some("ON"), Some("K1A 0B1")));
+
+    let boston = address(Some("MA"), "2134", Some("USA"));
+    assert_eq!(boston.zip.as_deref(), Some("02134"));
+
+    let dc = address(Some("Washington, D.C."), "20500", None);
+    assert_eq!(dc.state.as_deref(), Some("DC"));
+
+    // Other countries are left alone
+    let berlin = address(Some("Berlin"), "10117", Some("Germany"));
+    assert_eq!((berlin.state.as_deref(), berlin.zip.as_deref()), (Some("Berlin"), Some("10117")));
+
+    // A state that doesn't belong to the country is flagged, not changed
+    let mut mismatch = contact("m");
+    mismatch.state = Some("TX".to_string());
+    mismatch.country = Some("Canada".to_string());
+    let result = contact_normalizer::normalize(&mismatch, &options);
+    assert!(result.changes.is_empty());
+    assert_eq!(result.issues[0].message, "Not a Canadian province");
+
+    let spelled = NormalizeOptions { state_style: StateStyle::Name, ..NormalizeOptions::default() };
+    let mut nyc = contact("n");
+    nyc.state = Some("n.y.".to_string());
+    assert_eq!(contact_normalizer::normalize(&nyc, &spelled).normalized.state.as_deref(), Some("New York"));
+}
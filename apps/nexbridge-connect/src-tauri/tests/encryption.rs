@@ -0,0 +1,173 @@
+// ---------------------------------------------------------------------------
+// Integration tests for local data encryption
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::encryption::{self, DataKey};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+mod common;
+
+/// Argon2 at 64 MiB is slow; derive the shared test key once.
+fn key() -> &'static DataKey {
+    static KEY: OnceLock<DataKey> = OnceLock::new();
+    KEY.get_or_init(|| encryption::unlock_with_passphrase(&common::temp_dir("key"), "correct horse battery").unwrap())
+}
+
+/// Every file in `dir` whose bytes contain `needle`.
+fn files_containing(dir: &Path, needle: &[u8]) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| std::fs::read(p).map(|bytes| bytes.windows(needle.len()).any(|w| w == needle)).unwrap_or(false))
+        .collect()
+}
+
+// ============ Blobs ============
+
+#[test]
+fn blobs_round_trip_and_reject_tampering() {
+    let key = key();
+    let plain = b"<html><body>Site safety plan</body></html>";
+    let blob = encryption::encrypt_bytes(key, plain).unwrap();
+    assert!(encryption::is_encrypted_blob(&blob));
+    assert!(!blob.windows(11).any(|w| w == b"Site safety"));
+    assert_eq!(encryption::decrypt_bytes(key, &blob).unwrap(), plain);
+
+    // A fresh nonce each time
+    assert_ne!(encryption::encrypt_bytes(key, plain).unwrap(), blob);
+
+    let mut tampered = blob.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(encryption::decrypt_bytes(key, &tampered).is_err());
+
+    assert!(!encryption::is_encrypted_blob(plain));
+    assert_eq!(encryption::decrypt_bytes(key, plain).unwrap_err(), "Not an encrypted blob");
+}
+
+// ============ Passphrase ============
+
+#[test]
+fn first_passphrase_is_chosen_and_later_ones_are_checked() {
+    let dir = common::temp_dir("passphrase");
+    assert!(encryption::unlock_with_passphrase(&dir, "too short").is_err());
+    assert!(!dir.join("data_key.json").exists());
+
+    let first = encryption::unlock_with_passphrase(&dir, "a long enough passphrase").unwrap();
+    assert!(dir.join("data_key.json").exists());
+
+    assert_eq!(
+        encryption::unlock_with_passphrase(&dir, "not the passphrase").err().as_deref(),
+        Some("Incorrect passphrase")
+    );
+
+    // The same passphrase derives the same key
+    let again = encryption::unlock_with_passphrase(&dir, "a long enough passphrase").unwrap();
+    let blob = encryption::encrypt_bytes(&first, b"check").unwrap();
+    assert_eq!(encryption::decrypt_bytes(&again, &blob).unwrap(), b"check");
+    assert!(encryption::decrypt_bytes(key(), &blob).is_err());
+}
+
+// ============ Databases ============
+
+fn write_plaintext_database(path: &Path) {
+    let conn = Connection::open(path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE notes (body TEXT);
+         INSERT INTO notes VALUES ('plaintext-marker-1');",
+    )
+    .unwrap();
+}
+
+fn notes(conn: &Connection) -> Vec<String> {
+    let mut stmt = conn.prepare("SELECT body FROM notes ORDER BY body").unwrap();
+    let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+    rows.map(|r| r.unwrap()).collect()
+}
+
+#[test]
+fn plaintext_databases_are_encrypted_in_place() {
+    let dir = common::temp_dir("in-place");
+    let db = dir.join("documents.db");
+    write_plaintext_database(&db);
+
+    let conn = encryption::open_database(&db, Some(key())).unwrap();
+    assert_eq!(notes(&conn), ["plaintext-marker-1"]);
+    drop(conn);
+
+    assert!(!std::fs::read(&db).unwrap().starts_with(b"SQLite format 3"));
+    assert!(files_containing(&dir, b"plaintext-marker").is_empty());
+    let names: Vec<String> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    assert_eq!(names, ["documents.db"]);
+
+    // Already encrypted: opened as-is, and only with the right key
+    let conn = encryption::open_database(&db, Some(key())).unwrap();
+    assert_eq!(notes(&conn), ["plaintext-marker-1"]);
+    drop(conn);
+    let other = encryption::unlock_with_passphrase(&common::temp_dir("other-key"), "some other passphrase").unwrap();
+    assert!(encryption::open_database(&db, Some(&other)).is_err());
+}
+
+#[test]
+fn rows_left_in_a_wal_are_kept_and_scrubbed() {
+    let dir = common::temp_dir("wal");
+    let source = dir.join("source.db");
+    let conn = Connection::open(&source).unwrap();
+    conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(())).unwrap();
+    conn.execute_batch(
+        "PRAGMA wal_autocheckpoint = 0;
+         CREATE TABLE notes (body TEXT);
+         INSERT INTO notes VALUES ('plaintext-marker-1');
+         INSERT INTO notes VALUES ('plaintext-marker-2');",
+    )
+    .unwrap();
+
+    // What a crash leaves behind: the rows are only in the -wal
+    let crashed = common::temp_dir("wal-crashed");
+    let db = crashed.join("documents.db");
+    std::fs::copy(&source, &db).unwrap();
+    std::fs::copy(dir.join("source.db-wal"), crashed.join("documents.db-wal")).unwrap();
+    drop(conn);
+    assert!(!files_containing(&crashed, b"plaintext-marker").is_empty());
+
+    let conn = encryption::open_database(&db, Some(key())).unwrap();
+    assert_eq!(notes(&conn), ["plaintext-marker-1", "plaintext-marker-2"]);
+    drop(conn);
+    assert!(files_containing(&crashed, b"plaintext-marker").is_empty());
+    assert!(!crashed.join("documents.db-wal").exists());
+}
+
+#[test]
+fn snapshots_keep_the_source_key() {
+    let dir = common::temp_dir("snapshot");
+    let db = dir.join("documents.db");
+    write_plaintext_database(&db);
+    let conn = encryption::open_database(&db, Some(key())).unwrap();
+
+    let copy = dir.join("copy.db");
+    encryption::snapshot_database(&conn, &copy, Some(key())).unwrap();
+    assert!(!std::fs::read(&copy).unwrap().starts_with(b"SQLite format 3"));
+    assert_eq!(notes(&encryption::open_database(&copy, Some(key())).unwrap()), ["plaintext-marker-1"]);
+}
+
+// ============ Wipe ============
+
+#[test]
+fn wipe_removes_everything_under_the_app_directory() {
+    let dir = common::temp_dir("wipe");
+    encryption::unlock_with_passphrase(&dir, "a long enough passphrase").unwrap();
+    std::fs::create_dir_all(dir.join("converted/ab")).unwrap();
+    std::fs::write(dir.join("converted/ab/doc.html"), vec![7u8; 100_000]).unwrap();
+    write_plaintext_database(&dir.join("documents.db"));
+
+    let result = encryption::wipe_local_data(&dir);
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.files_removed, 3);
+    assert!(result.bytes_overwritten >= 100_000);
+    // A passphrase setup has no stored key; removing data_key.json removes it
+    assert!(result.key_removed);
+    assert!(dir.exists());
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+}
@@ -0,0 +1,285 @@
+// ---------------------------------------------------------------------------
+// Integration tests for the document index
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::index::{
+    DocumentDateField, DocumentIndex, DocumentQuery, DocumentSortKey, DocumentStatus, IndexedDocument, StatusError,
+};
+mod common;
+
+use common::TempDir;
+
+fn open(name: &str) -> (TempDir, DocumentIndex) {
+    let dir = common::temp_dir(name);
+    let index = DocumentIndex::new(&dir.join("documents.db"), None).unwrap();
+    (dir, index)
+}
+
+/// A document at `path` under /share with the given size.
+fn document(id: &str, path: &str, size: i64, status: DocumentStatus) -> IndexedDocument {
+    IndexedDocument { file_size: size, ..common::document(id, path, status) }
+}
+
+fn ids(docs: &[IndexedDocument]) -> Vec<&str> {
+    docs.iter().map(|d| d.id.as_str()).collect()
+}
+
+// ============ Queries ============
+
+fn query_fixture(name: &str) -> (TempDir, DocumentIndex) {
+    let (dir, index) = open(name);
+    let docs = [
+        document("a", "Jobs/Smith/plan.pdf", 300, DocumentStatus::Import),
+        document("b", "Jobs/Smith/photo.jpg", 100, DocumentStatus::Pending),
+        document("c", "Jobs/Smithfield/bid.pdf", 200, DocumentStatus::Import),
+        document("d", "Jobs/Jones/Smith/notes.docx", 100, DocumentStatus::Ignore),
+        document("e", "Office/Plan B.pdf", 500, DocumentStatus::Pending),
+    ];
+    for doc in &docs {
+        index.upsert_document(doc).unwrap();
+    }
+    (dir, index)
+}
+
+#[test]
+fn pages_through_results_with_a_stable_cursor() {
+    let (_dir, index) = query_fixture("cursor");
+    let mut query = DocumentQuery { sort: DocumentSortKey::FileSize, limit: Some(2), ..Default::default() };
+
+    let mut seen = Vec::new();
+    loop {
+        let page = index.query_documents(&query).unwrap();
+        assert_eq!(page.total, 5);
+        assert!(page.documents.len() <= 2);
+        seen.extend(page.documents.iter().map(|d| d.id.clone()));
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => break,
+        }
+    }
+    // Equal sizes are ordered by id
+    assert_eq!(seen, ["b", "d", "c", "a", "e"]);
+
+    query.cursor = None;
+    query.descending = true;
+    let first = index.query_documents(&query).unwrap();
+    assert_eq!(ids(&first.documents), ["e", "a"]);
+
+    // A cursor from another sort order is rejected rather than misread
+    let stale = DocumentQuery { cursor: first.next_cursor, ..Default::default() };
+    assert!(index.query_documents(&stale).is_err());
+    let garbage = DocumentQuery { cursor: Some("not-a-cursor".to_string()), ..Default::default() };
+    assert!(index.query_documents(&garbage).is_err());
+}
+
+#[test]
+fn filters_by_folder_components_name_and_size() {
+    let (_dir, index) = query_fixture("filters");
+    let run = |query: DocumentQuery| ids(&index.query_documents(&query).unwrap().documents).join(",");
+
+    // "Smith" must be a whole folder, not a prefix of "Smithfield"
+    assert_eq!(run(DocumentQuery { path_prefix: Some("/share/Jobs/Smith".to_string()), ..Default::default() }), "b,a");
+    assert_eq!(run(DocumentQuery { path_prefix: Some("/share/Jobs/Smith/".to_string()), ..Default::default() }), "b,a");
+    assert_eq!(run(DocumentQuery { breadcrumb_segment: Some("Smith".to_string()), ..Default::default() }), "d,b,a");
+    assert_eq!(
+        run(DocumentQuery { name_contains: Some("PLAN".to_string()), sort: DocumentSortKey::FileName, ..Default::default() }),
+        "e,a"
+    );
+    assert_eq!(run(DocumentQuery { min_size: Some(150), max_size: Some(300), ..Default::default() }), "a,c");
+    assert_eq!(
+        run(DocumentQuery { file_types: vec!["pdf".to_string()], statuses: vec![DocumentStatus::Pending], ..Default::default() }),
+        "e"
+    );
+
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let dated = DocumentQuery {
+        date_field: DocumentDateField::ScannedAt,
+        date_from: Some(today.clone()),
+        date_to: Some(today),
+        ..Default::default()
+    };
+    assert_eq!(index.query_documents(&dated).unwrap().total, 5);
+    let before = DocumentQuery { date_to: Some("2000-01-01".to_string()), ..Default::default() };
+    assert_eq!(index.query_documents(&before).unwrap().total, 0);
+}
+
+#[test]
+fn facets_ignore_their_own_filter() {
+    let (_dir, index) = query_fixture("facets");
+    let query = DocumentQuery {
+        statuses: vec![DocumentStatus::Import],
+        file_types: vec!["pdf".to_string()],
+        ..Default::default()
+    };
+    let page = index.query_documents(&query).unwrap();
+    assert_eq!(page.total, 2);
+
+    // Statuses among the PDFs
+    assert_eq!(page.facets.by_status.get("IMPORT"), Some(&2));
+    assert_eq!(page.facets.by_status.get("PENDING"), Some(&1));
+    assert_eq!(page.facets.by_status.get("IGNORE"), None);
+    // Types among the IMPORT documents
+    assert_eq!(page.facets.by_type.get("pdf"), Some(&2));
+    assert_eq!(page.facets.by_type.len(), 1);
+}
+
+// ============ Bulk operations ============
+
+fn bulk_fixture(name: &str) -> (TempDir, DocumentIndex) {
+    let (dir, index) = open(name);
+    let docs = [
+        document("a", "Jobs/Smith/plan.pdf", 1, DocumentStatus::Pending),
+        document("b", "Jobs/Smith/Photos/gate.jpg", 1, DocumentStatus::Converted),
+        document("c", "Jobs/Smithfield/bid.pdf", 1, DocumentStatus::Pending),
+        document("d", "Jobs/Smith/final.pdf", 1, DocumentStatus::Uploaded),
+        document("e", "Jobs/50%_done/list.pdf", 1, DocumentStatus::Pending),
+        document("f", "Jobs/50xxdone/list.pdf", 1, DocumentStatus::Pending),
+    ];
+    for doc in &docs {
+        index.upsert_document(doc).unwrap();
+    }
+    (dir, index)
+}
+
+fn status(index: &DocumentIndex, id: &str) -> DocumentStatus {
+    index.get_document(id).unwrap().unwrap().status
+}
+
+#[test]
+fn folder_operations_match_whole_path_components() {
+    let (_dir, index) = bulk_fixture("folder");
+
+    let op = index.ignore_documents_in_folder("/share/Jobs/Smith/", "user").unwrap();
+    let mut affected = op.affected_ids.clone();
+    affected.sort();
+    assert_eq!(affected, ["a", "b"]);
+    assert_eq!(op.count, 2);
+    // UPLOADED is terminal
+    assert_eq!(op.rejected_ids, ["d"]);
+    assert_eq!(status(&index, "c"), DocumentStatus::Pending);
+
+    // % and _ are literal
+    let op = index.ignore_documents_in_folder("/share/Jobs/50%_done", "user").unwrap();
+    assert_eq!(op.affected_ids, ["e"]);
+
+    // Importing a folder leaves its ignored documents alone
+    let op = index.import_documents_in_folder("/share/Jobs", "user").unwrap();
+    let mut affected = op.affected_ids.clone();
+    affected.sort();
+    assert_eq!(affected, ["c", "f"]);
+    assert_eq!(status(&index, "a"), DocumentStatus::Ignore);
+
+    // Nothing to change: nothing logged
+    let op = index.import_documents_in_folder("/share/Jobs/Smithfield", "user").unwrap();
+    assert_eq!((op.operation_id, op.count), (None, 0));
+}
+
+#[test]
+fn undo_restores_status_and_updated_at() {
+    let (_dir, index) = bulk_fixture("undo");
+    let updated_at = |id: &str| index.get_document(id).unwrap().unwrap().updated_at;
+    let before = updated_at("b");
+
+    index.ignore_documents_in_folder("/share/Jobs/Smith", "user").unwrap();
+    assert_ne!(updated_at("b"), before);
+    // Changed again since the operation: left alone
+    index.update_status("a", DocumentStatus::Import, None, "user", None).unwrap();
+    index.update_status("c", DocumentStatus::Import, None, "user", None).unwrap();
+    let op = index.bulk_set_status(&["c".to_string(), "x".to_string()], DocumentStatus::Ignore, "user").unwrap();
+    assert_eq!(op.affected_ids, ["c"]);
+
+    // Most recent first
+    let undone = index.undo_last_bulk_operation("user").unwrap().unwrap();
+    assert_eq!(undone.kind, "bulk_status");
+    assert_eq!(undone.restored_ids, ["c"]);
+    assert_eq!(status(&index, "c"), DocumentStatus::Import);
+
+    let undone = index.undo_last_bulk_operation("user").unwrap().unwrap();
+    assert_eq!(undone.kind, "ignore_folder");
+    assert_eq!(undone.target, "/share/Jobs/Smith");
+    assert_eq!(undone.restored_ids, ["b"]);
+    assert_eq!(undone.skipped, 1);
+    assert_eq!(status(&index, "a"), DocumentStatus::Import);
+    // CONVERTED without cached HTML comes back as IMPORT, as of before the operation
+    assert_eq!(status(&index, "b"), DocumentStatus::Import);
+    assert_eq!(updated_at("b"), before);
+
+    let history = index.get_status_history("b").unwrap();
+    let last = history.last().unwrap();
+    assert_eq!((last.from_status.clone(), last.to_status.clone()), (Some(DocumentStatus::Ignore), DocumentStatus::Import));
+    assert!(last.reason.as_deref().unwrap().starts_with("Undo of ignore_folder"));
+
+    assert!(index.undo_last_bulk_operation("user").unwrap().is_none());
+}
+
+// ============ Status state machine ============
+
+#[test]
+fn parses_status_names_strictly() {
+    assert_eq!(DocumentStatus::parse(" converted ").unwrap(), DocumentStatus::Converted);
+    assert!(DocumentStatus::parse("DONE").is_err());
+    assert!(DocumentStatus::parse("").is_err());
+}
+
+#[test]
+fn uploaded_is_terminal_and_ignore_only_lifts_to_pending_or_import() {
+    use DocumentStatus::*;
+    let all = [Pending, Import, Ignore, Converted, Uploaded, Failed];
+    for to in &all {
+        assert_eq!(Uploaded.can_transition_to(to), *to == Uploaded, "UPLOADED -> {:?}", to);
+        assert_eq!(Ignore.can_transition_to(to), matches!(to, Pending | Import | Ignore), "IGNORE -> {:?}", to);
+    }
+    for from in [Pending, Import, Converted, Failed] {
+        assert!(from.can_transition_to(&Uploaded));
+        assert!(from.can_transition_to(&Ignore));
+    }
+}
+
+#[test]
+fn refuses_invalid_transitions_and_records_valid_ones() {
+    let (_dir, index) = open("transitions");
+    index.upsert_document(&document("a", "Jobs/plan.pdf", 1, DocumentStatus::Pending)).unwrap();
+
+    index.update_status("a", DocumentStatus::Ignore, None, "user", Some("not ours")).unwrap();
+    match index.update_status("a", DocumentStatus::Converted, None, "converter", None) {
+        Err(StatusError::InvalidTransition { from, to, .. }) => {
+            assert_eq!((from, to), (DocumentStatus::Ignore, DocumentStatus::Converted));
+        }
+        other => panic!("expected InvalidTransition, got {:?}", other),
+    }
+    assert!(index.check_status_transition("a", &DocumentStatus::Converted).is_err());
+    assert_eq!(status(&index, "a"), DocumentStatus::Ignore);
+
+    index.update_status("a", DocumentStatus::Import, None, "user", None).unwrap();
+    index.update_status("a", DocumentStatus::Failed, Some("corrupt"), "converter", None).unwrap();
+    // Same status again only updates the message
+    index.update_status("a", DocumentStatus::Failed, Some("still corrupt"), "converter", None).unwrap();
+    assert_eq!(index.get_document("a").unwrap().unwrap().error_message.as_deref(), Some("still corrupt"));
+
+    index.mark_uploaded("a", "nexus-1", "uploader").unwrap();
+    let doc = index.get_document("a").unwrap().unwrap();
+    assert_eq!((doc.status, doc.nexus_doc_id.as_deref()), (DocumentStatus::Uploaded, Some("nexus-1")));
+    assert!(doc.uploaded_at.is_some());
+    assert!(index.update_status("a", DocumentStatus::Import, None, "user", None).is_err());
+
+    let history: Vec<(Option<DocumentStatus>, DocumentStatus, String)> = index
+        .get_status_history("a")
+        .unwrap()
+        .into_iter()
+        .map(|h| (h.from_status, h.to_status, h.actor))
+        .collect();
+    use DocumentStatus::*;
+    assert_eq!(history, [
+        (None, Pending, "scanner".to_string()),
+        (Some(Pending), Ignore, "user".to_string()),
+        (Some(Ignore), Import, "user".to_string()),
+        (Some(Import), Failed, "converter".to_string()),
+        (Some(Failed), Uploaded, "uploader".to_string()),
+    ]);
+    assert_eq!(index.get_status_history("a").unwrap()[1].reason.as_deref(), Some("not ours"));
+
+    assert!(matches!(
+        index.update_status("missing", DocumentStatus::Import, None, "user", None),
+        Err(StatusError::NotFound(_))
+    ));
+}
@@ -0,0 +1,146 @@
+// ---------------------------------------------------------------------------
+// Integration tests for exporting and importing the document index
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::index::{DocumentIndex, DocumentStatus, IndexedDocument};
+use nexbridge_connect_lib::index_transfer::{self, ExportFormat, PathMapping};
+use common::document;
+
+mod common;
+
+fn mapping(from: &str, to: &str) -> PathMapping {
+    PathMapping { from: from.to_string(), to: to.to_string() }
+}
+
+/// An exported index from a Mac: one uploaded, one ignored, one converted,
+/// one with a comma, quotes and a non-ASCII name.
+fn exported() -> Vec<IndexedDocument> {
+    let mut uploaded = document("u", "/Volumes/Share/Jobs/Smith/plan.pdf", DocumentStatus::Uploaded);
+    uploaded.nexus_doc_id = Some("nexus-7".to_string());
+    uploaded.uploaded_at = Some("2024-03-03T09:00:00+00:00".to_string());
+    uploaded.category = Some("permit".to_string());
+    uploaded.metadata.author = Some("Maria Lopez".to_string());
+
+    let ignored = document("i", "/Volumes/Share/Jobs/Smith/Thumbs.db.jpg", DocumentStatus::Ignore);
+    let converted = document("c", "/Volumes/Share/Jobs/Smith/sop.docx", DocumentStatus::Converted);
+
+    let mut odd = document("o", "/Volumes/Share/Jobs/Peña, \"Bid\" v2.xlsx", DocumentStatus::Failed);
+    odd.error_message = Some("Unsupported: \"encrypted\", sorry".to_string());
+
+    vec![uploaded, ignored, converted, odd]
+}
+
+// ============ Path mapping ============
+
+#[test]
+fn remaps_the_longest_matching_prefix_on_component_boundaries() {
+    let mappings = [
+        mapping("/Volumes/Share", "Z:\\Share"),
+        mapping("/Volumes/Share/Jobs/", "J:"),
+    ];
+    assert_eq!(index_transfer::remap_path("/Volumes/Share/Office/a.pdf", &mappings).as_deref(), Some("Z:\\Share\\Office\\a.pdf"));
+    assert_eq!(index_transfer::remap_path("/Volumes/Share/Jobs/Smith/a.pdf", &mappings).as_deref(), Some("J:\\Smith\\a.pdf"));
+    assert_eq!(index_transfer::remap_path("/Volumes/Share", &mappings).as_deref(), Some("Z:\\Share"));
+    // "/Volumes/ShareOld" is another folder
+    assert_eq!(index_transfer::remap_path("/Volumes/ShareOld/a.pdf", &mappings), None);
+    assert_eq!(index_transfer::remap_path("/Users/pm/a.pdf", &mappings), None);
+
+    // Windows back to a Unix mount
+    let back = [mapping("Z:\\Share", "/mnt/share")];
+    assert_eq!(index_transfer::remap_path("Z:\\Share\\Jobs\\a.pdf", &back).as_deref(), Some("/mnt/share/Jobs/a.pdf"));
+}
+
+#[test]
+fn parses_cli_mappings_and_formats() {
+    let parsed = PathMapping::parse("/Volumes/Share=Z:\\Share").unwrap();
+    assert_eq!((parsed.from.as_str(), parsed.to.as_str()), ("/Volumes/Share", "Z:\\Share"));
+    assert!(PathMapping::parse("/Volumes/Share").is_err());
+    assert!(PathMapping::parse("=Z:").is_err());
+
+    assert_eq!(ExportFormat::parse("NDJSON").unwrap(), ExportFormat::Jsonl);
+    assert!(ExportFormat::parse("pdf").is_err());
+    assert_eq!(ExportFormat::from_path(std::path::Path::new("index.XLSX")), Some(ExportFormat::Xlsx));
+    assert_eq!(ExportFormat::from_path(std::path::Path::new("index")), None);
+}
+
+// ============ Round trips ============
+
+fn round_trip(format: ExportFormat, extension: &str) {
+    let dir = common::temp_dir(extension);
+    let file = dir.join(format!("index.{}", extension));
+    let docs = exported();
+    let written = index_transfer::export_documents(&docs, &file, format).unwrap();
+    assert_eq!(written.documents, docs.len());
+
+    // A new Windows machine that has already scanned the share once
+    let index = DocumentIndex::new(&dir.join("documents.db"), None).unwrap();
+    let rescanned = document("local-id", "Z:\\Share\\Jobs\\Smith\\plan.pdf", DocumentStatus::Pending);
+    index.upsert_document(&rescanned).unwrap();
+
+    let result = index_transfer::import_file(&index, &file, None, &[mapping("/Volumes/Share", "Z:\\Share")]).unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!((result.read, result.inserted, result.updated, result.remapped), (4, 3, 1, 4));
+
+    let all = index.get_all_documents().unwrap();
+    let by_path = |path: &str| all.iter().find(|d| d.file_path == path).unwrap_or_else(|| panic!("{} missing", path));
+
+    // Matched by mapped path: keeps the local id, takes the exported decisions
+    let plan = by_path("Z:\\Share\\Jobs\\Smith\\plan.pdf");
+    assert_eq!(plan.id, "local-id");
+    assert_eq!(plan.status, DocumentStatus::Uploaded);
+    assert_eq!(plan.nexus_doc_id.as_deref(), Some("nexus-7"));
+    assert_eq!(plan.category.as_deref(), Some("permit"));
+
+    assert_eq!(by_path("Z:\\Share\\Jobs\\Smith\\Thumbs.db.jpg").status, DocumentStatus::Ignore);
+    // No cached HTML here, so it has to be converted again
+    assert_eq!(by_path("Z:\\Share\\Jobs\\Smith\\sop.docx").status, DocumentStatus::Import);
+
+    let odd = by_path("Z:\\Share\\Jobs\\Peña, \"Bid\" v2.xlsx");
+    assert_eq!(odd.file_name, "Peña, \"Bid\" v2.xlsx");
+    assert_eq!(odd.status, DocumentStatus::Failed);
+    assert_eq!(odd.error_message.as_deref(), Some("Unsupported: \"encrypted\", sorry"));
+    assert_eq!(odd.breadcrumb, ["Share", "Jobs", "Peña, \"Bid\" v2.xlsx"]);
+    assert_eq!(odd.file_size, 1024);
+
+    let history = index.get_status_history("local-id").unwrap();
+    assert!(history.last().unwrap().reason.as_deref().unwrap().starts_with("Imported from"));
+}
+
+#[test]
+fn round_trips_csv() {
+    round_trip(ExportFormat::Csv, "csv");
+}
+
+#[test]
+fn round_trips_jsonl() {
+    round_trip(ExportFormat::Jsonl, "jsonl");
+}
+
+#[test]
+fn round_trips_xlsx() {
+    round_trip(ExportFormat::Xlsx, "xlsx");
+}
+
+#[test]
+fn round_trips_json() {
+    round_trip(ExportFormat::Json, "json");
+}
+
+#[test]
+fn jsonl_keeps_full_metadata_and_reports_bad_rows() {
+    let dir = common::temp_dir("jsonl-rows");
+    let file = dir.join("index.jsonl");
+    index_transfer::export_documents(&exported(), &file, ExportFormat::Jsonl).unwrap();
+    let mut text = std::fs::read_to_string(&file).unwrap();
+    text.push_str("{\"not\": \"a document\"}\n\n");
+    std::fs::write(&file, text).unwrap();
+
+    let index = DocumentIndex::new(&dir.join("documents.db"), None).unwrap();
+    let result = index_transfer::import_file(&index, &file, None, &[]).unwrap();
+    assert_eq!((result.read, result.inserted), (5, 4));
+    assert_eq!(result.errors.len(), 1);
+    assert!(result.errors[0].starts_with("Row 5:"), "{:?}", result.errors);
+
+    let plan = index.get_document("u").unwrap().unwrap();
+    assert_eq!(plan.metadata.author.as_deref(), Some("Maria Lopez"));
+}
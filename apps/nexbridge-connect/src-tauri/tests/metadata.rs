@@ -0,0 +1,126 @@
+// ---------------------------------------------------------------------------
+// Integration tests for embedded-metadata extraction and its use at scan time
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::documents;
+use nexbridge_connect_lib::index::{DocumentIndex, MetadataFilter};
+use nexbridge_connect_lib::metadata::{self, DocumentMetadata};
+use std::io::Write;
+use std::path::Path;
+
+mod common;
+
+fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+    for (name, body) in entries {
+        zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+        zip.write_all(body.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+fn write_docx(path: &Path, author: &str) {
+    let core = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/">
+  <dc:title>Crane Lift Plan</dc:title>
+  <dc:creator>{}</dc:creator>
+  <cp:keywords>crane, rigging</cp:keywords>
+  <dcterms:created xsi:type="dcterms:W3CDTF">2023-04-05T10:00:00-05:00</dcterms:created>
+</cp:coreProperties>"#,
+        author
+    );
+    write_zip(path, &[
+        ("docProps/core.xml", &core),
+        ("docProps/app.xml", "<Properties><Pages>12</Pages></Properties>"),
+        ("word/styles.xml", r#"<w:styles><w:rPrDefault><w:rPr><w:lang w:val="en-US"/></w:rPr></w:rPrDefault></w:styles>"#),
+    ]);
+}
+
+// ============ Extraction ============
+
+#[test]
+fn reads_office_core_properties() {
+    let dir = common::temp_dir("docx");
+    let path = dir.join("lift-plan.docx");
+    write_docx(&path, "Maria Lopez");
+
+    let meta = metadata::extract_metadata(&path);
+    assert_eq!(meta.title.as_deref(), Some("Crane Lift Plan"));
+    assert_eq!(meta.author.as_deref(), Some("Maria Lopez"));
+    assert_eq!(meta.keywords.as_deref(), Some("crane, rigging"));
+    // Normalized to UTC
+    assert_eq!(meta.created_at.as_deref(), Some("2023-04-05T15:00:00+00:00"));
+    assert_eq!(meta.page_count, Some(12));
+    // dc:language is empty, so Word's default run language is used
+    assert_eq!(meta.language.as_deref(), Some("en-US"));
+}
+
+#[test]
+fn reads_opendocument_meta() {
+    let dir = common::temp_dir("odt");
+    let path = dir.join("minutes.odt");
+    write_zip(&path, &[(
+        "meta.xml",
+        r#"<office:document-meta><office:meta>
+  <meta:initial-creator>Sam Ortiz</meta:initial-creator>
+  <dc:creator>Someone Else</dc:creator>
+  <meta:creation-date>2022-11-01T08:30:00</meta:creation-date>
+  <meta:document-statistic meta:page-count="3" meta:word-count="900"/>
+</office:meta></office:document-meta>"#,
+    )]);
+
+    let meta = metadata::extract_metadata(&path);
+    assert_eq!(meta.author.as_deref(), Some("Sam Ortiz"));
+    // No zone: kept as written
+    assert_eq!(meta.created_at.as_deref(), Some("2022-11-01T08:30:00"));
+    assert_eq!(meta.page_count, Some(3));
+}
+
+#[test]
+fn unreadable_files_have_no_metadata() {
+    let dir = common::temp_dir("broken");
+    let docx = dir.join("broken.docx");
+    std::fs::write(&docx, b"not a zip").unwrap();
+    let txt = dir.join("notes.txt");
+    std::fs::write(&txt, b"plain").unwrap();
+
+    assert!(metadata::extract_metadata(&docx).is_empty());
+    assert_eq!(metadata::extract_metadata(&txt), DocumentMetadata::default());
+    assert!(metadata::extract_metadata(&dir.join("missing.pdf")).is_empty());
+}
+
+// ============ Scanning ============
+
+#[test]
+fn scan_extracts_metadata_only_for_new_or_changed_files() {
+    let dir = common::temp_dir("scan");
+    let share = dir.join("share");
+    std::fs::create_dir_all(&share).unwrap();
+    let path = share.join("lift-plan.docx");
+    write_docx(&path, "Maria Lopez");
+
+    let index = DocumentIndex::new(&dir.join("documents.db"), None).unwrap();
+    documents::scan_directory(share.to_str().unwrap(), &index).unwrap();
+
+    let by_author = |author: &str| {
+        index
+            .find_documents_by_metadata(&MetadataFilter { author: Some(author.to_string()), ..Default::default() })
+            .unwrap()
+    };
+    let mut doc = by_author("lopez").pop().expect("indexed with its author");
+
+    // Stand-in for metadata extracted by an older build: an unchanged file
+    // keeps it on rescan instead of being re-read
+    doc.metadata.author = Some("Stored Author".to_string());
+    index.upsert_document(&doc).unwrap();
+    documents::scan_directory(share.to_str().unwrap(), &index).unwrap();
+    assert_eq!(by_author("stored").len(), 1);
+
+    // A changed file is read again
+    write_docx(&path, "Dana Kim");
+    documents::scan_directory(share.to_str().unwrap(), &index).unwrap();
+    assert!(by_author("stored").is_empty());
+    assert_eq!(by_author("dana").len(), 1);
+    assert_eq!(index.get_all_documents().unwrap().len(), 1);
+}
@@ -0,0 +1,982 @@
+// ---------------------------------------------------------------------------
+// Integration tests for nexus_api against an in-process mock HTTP server
+// ---------------------------------------------------------------------------
+// Each test starts its own hyper server on 127.0.0.1:0 with a handler that
+// plays the Nexus side of the conversation, and records every request so
+// the tests can check headers, bodies and the order of calls.
+// ---------------------------------------------------------------------------
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server};
+use nexbridge_connect_lib::chunked_upload::{ChunkedUploader, UploadSource};
+use nexbridge_connect_lib::bandwidth::{limiter, TransferLimits};
+use nexbridge_connect_lib::contact_groups::{ContactGroupIndex, ContactSyncStatus};
+use nexbridge_connect_lib::contact_sync::{contact_source, sync_contacts};
+use nexbridge_connect_lib::contacts::Contact;
+use nexbridge_connect_lib::nexus_api::{
+    ApiError, Credentials, FileContent, ImportContact, NexusApi, OriginalFile, UploadHtmlRequest,
+};
+use nexbridge_connect_lib::uploader::{UploadQueue, UploadRequest};
+use common::TempDir;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+const VERSION: &str = "1.4.0-test";
+
+#[derive(Debug, Clone)]
+struct Recorded {
+    method: String,
+    /// Path plus query string
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Recorded {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).expect("request body is JSON")
+    }
+}
+
+struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl MockServer {
+    /// Serve every request with `handler`, which returns (status, body).
+    async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&Recorded) -> (u16, String) + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            let log = log.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: hyper::Request<Body>| {
+                    let handler = handler.clone();
+                    let log = log.clone();
+                    async move {
+                        let (parts, body) = req.into_parts();
+                        let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+                        let recorded = Recorded {
+                            method: parts.method.to_string(),
+                            path: parts
+                                .uri
+                                .path_and_query()
+                                .map(|p| p.to_string())
+                                .unwrap_or_default(),
+                            headers: parts
+                                .headers
+                                .iter()
+                                .map(|(n, v)| (n.to_string(), v.to_str().unwrap_or_default().to_string()))
+                                .collect(),
+                            body: body.to_vec(),
+                        };
+                        let (status, text) = handler(&recorded);
+                        log.lock().unwrap().push(recorded);
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .header("Content-Type", "application/json")
+                                .body(Body::from(text))
+                                .unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        Self { url, requests }
+    }
+
+    fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn count(&self, path: &str) -> usize {
+        self.requests().iter().filter(|r| r.path == path).count()
+    }
+}
+
+fn client(url: &str, refresh_token: Option<&str>) -> NexusApi {
+    NexusApi::with_session(
+        VERSION,
+        url,
+        Credentials {
+            access_token: "access-1".to_string(),
+            refresh_token: refresh_token.map(|t| t.to_string()),
+        },
+    )
+}
+
+fn html_request(title: &str) -> UploadHtmlRequest {
+    UploadHtmlRequest {
+        html_content: Some("<p>Hello</p>".to_string()),
+        html_upload_id: None,
+        title: title.to_string(),
+        category: "contracts".to_string(),
+        original_format: "docx".to_string(),
+        word_count: 1,
+        folder_name: "Projects".to_string(),
+        breadcrumb: vec!["Projects".to_string()],
+        metadata: Default::default(),
+    }
+}
+
+/// Handler for a server that accepts only `access-2` and hands it out on
+/// refresh.
+fn rotating_tokens(req: &Recorded) -> (u16, String) {
+    if req.path == "/auth/refresh" {
+        return (200, json!({ "accessToken": "access-2", "refreshToken": "refresh-2" }).to_string());
+    }
+    match req.header("authorization") {
+        Some("Bearer access-2") => (200, json!({ "documentId": "doc-2" }).to_string()),
+        _ => (401, json!({ "message": "Token expired" }).to_string()),
+    }
+}
+
+// ============ Request pipeline ============
+
+#[tokio::test]
+async fn upload_html_sends_typed_payload_and_platform_headers() {
+    let server = MockServer::start(|_| (201, json!({ "id": "doc-1" }).to_string())).await;
+    let api = client(&server.url, None);
+
+    let response = api.upload_html(&html_request("Lease")).await.unwrap();
+    assert_eq!(response.nexus_doc_id().as_deref(), Some("doc-1"));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let req = &requests[0];
+    assert_eq!(req.method, "POST");
+    assert_eq!(req.path, "/document-import/upload-html");
+    assert_eq!(req.header("authorization"), Some("Bearer access-1"));
+    assert_eq!(req.header("x-app-platform"), Some("nexbridge"));
+    assert_eq!(req.header("x-app-version"), Some(VERSION));
+    assert!(req
+        .header("user-agent")
+        .unwrap()
+        .starts_with(&format!("NexBRIDGE-Connect/{} (", VERSION)));
+
+    let body = req.json();
+    assert_eq!(body["html_content"], "<p>Hello</p>");
+    assert_eq!(body["title"], "Lease");
+    assert_eq!(body["breadcrumb"], json!(["Projects"]));
+    assert!(body.get("html_upload_id").is_none());
+    assert!(body.get("metadata").is_none());
+}
+
+#[tokio::test]
+async fn requests_without_a_session_fail_before_sending() {
+    let api = NexusApi::new(VERSION);
+    assert_eq!(api.upload_html(&html_request("x")).await.unwrap_err(), ApiError::NotConfigured);
+    assert_eq!(api.base_url(), None);
+}
+
+#[tokio::test]
+async fn connection_failures_are_transient_network_errors() {
+    // Bind and drop a listener to get a port nothing is listening on
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let api = client(&format!("http://127.0.0.1:{}", port), None);
+
+    let err = api.upload_html(&html_request("x")).await.unwrap_err();
+    assert!(matches!(err, ApiError::Network(_)), "{:?}", err);
+    assert!(err.is_transient());
+}
+
+// ============ Token refresh ============
+
+#[tokio::test]
+async fn refreshes_once_on_401_and_retries_with_the_new_token() {
+    let server = MockServer::start(rotating_tokens).await;
+    let api = client(&server.url, Some("refresh-1"));
+    let refreshed = Arc::new(Mutex::new(Vec::new()));
+    let seen = refreshed.clone();
+    api.on_refresh(move |credentials| seen.lock().unwrap().push(credentials.clone()));
+
+    let response = api.upload_html(&html_request("Lease")).await.unwrap();
+    assert_eq!(response.nexus_doc_id().as_deref(), Some("doc-2"));
+
+    let requests = server.requests();
+    let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        ["/document-import/upload-html", "/auth/refresh", "/document-import/upload-html"]
+    );
+    assert_eq!(requests[1].json(), json!({ "refreshToken": "refresh-1" }));
+    assert_eq!(requests[1].header("authorization"), None);
+    assert_eq!(requests[2].header("authorization"), Some("Bearer access-2"));
+    // The retry carries the same body, rebuilt rather than consumed
+    assert_eq!(requests[0].body, requests[2].body);
+
+    assert_eq!(
+        *refreshed.lock().unwrap(),
+        vec![Credentials {
+            access_token: "access-2".to_string(),
+            refresh_token: Some("refresh-2".to_string()),
+        }]
+    );
+
+    // Later requests use the refreshed token straight away
+    api.upload_html(&html_request("Again")).await.unwrap();
+    assert_eq!(server.count("/auth/refresh"), 1);
+}
+
+#[tokio::test]
+async fn concurrent_401s_share_a_single_refresh() {
+    let server = MockServer::start(rotating_tokens).await;
+    let api = client(&server.url, Some("refresh-1"));
+
+    let (a, b, c) = (html_request("a"), html_request("b"), html_request("c"));
+    let (a, b, c) = tokio::join!(api.upload_html(&a), api.upload_html(&b), api.upload_html(&c));
+    assert!(a.is_ok() && b.is_ok() && c.is_ok());
+    assert_eq!(server.count("/auth/refresh"), 1);
+}
+
+#[tokio::test]
+async fn keeps_the_refresh_token_when_the_server_does_not_rotate_it() {
+    let server = MockServer::start(|req| match (req.path.as_str(), req.header("authorization")) {
+        ("/auth/refresh", _) => (200, json!({ "accessToken": "access-2" }).to_string()),
+        (_, Some("Bearer access-2")) => (200, json!({ "id": "doc" }).to_string()),
+        _ => (401, String::new()),
+    })
+    .await;
+    let api = client(&server.url, Some("refresh-1"));
+    let refreshed = Arc::new(Mutex::new(None));
+    let seen = refreshed.clone();
+    api.on_refresh(move |credentials| *seen.lock().unwrap() = Some(credentials.clone()));
+
+    api.upload_html(&html_request("x")).await.unwrap();
+    let credentials = refreshed.lock().unwrap().clone().unwrap();
+    assert_eq!(credentials.refresh_token.as_deref(), Some("refresh-1"));
+}
+
+#[tokio::test]
+async fn still_unauthorized_after_refresh_gives_up() {
+    let server = MockServer::start(|req| {
+        if req.path == "/auth/refresh" {
+            (200, json!({ "accessToken": "access-2", "refreshToken": "refresh-2" }).to_string())
+        } else {
+            (401, String::new())
+        }
+    })
+    .await;
+    let api = client(&server.url, Some("refresh-1"));
+
+    let err = api.upload_html(&html_request("x")).await.unwrap_err();
+    assert_eq!(err, ApiError::Unauthorized);
+    assert_eq!(err.status(), Some(401));
+    assert_eq!(server.count("/document-import/upload-html"), 2);
+    assert_eq!(server.count("/auth/refresh"), 1);
+}
+
+#[tokio::test]
+async fn without_a_refresh_token_401_is_unauthorized() {
+    let server = MockServer::start(|_| (401, String::new())).await;
+    let api = client(&server.url, None);
+
+    assert_eq!(api.upload_html(&html_request("x")).await.unwrap_err(), ApiError::Unauthorized);
+    assert_eq!(server.count("/auth/refresh"), 0);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn rejected_refresh_is_unauthorized() {
+    let server = MockServer::start(|_| (401, json!({ "message": "Refresh token revoked" }).to_string())).await;
+    let api = client(&server.url, Some("refresh-1"));
+    let calls = Arc::new(AtomicU32::new(0));
+    let seen = calls.clone();
+    api.on_refresh(move |_| {
+        seen.fetch_add(1, Ordering::SeqCst);
+    });
+
+    assert_eq!(api.upload_html(&html_request("x")).await.unwrap_err(), ApiError::Unauthorized);
+    assert_eq!(server.count("/auth/refresh"), 1);
+    assert_eq!(server.count("/document-import/upload-html"), 1);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+// ============ Error mapping ============
+
+#[tokio::test]
+async fn maps_license_and_update_required_responses() {
+    let server = MockServer::start(|req| {
+        if req.path == "/license-check" {
+            (
+                402,
+                json!({ "error": "LICENSE_EXPIRED", "message": "Your license has expired", "exportOnly": true })
+                    .to_string(),
+            )
+        } else {
+            (
+                426,
+                json!({
+                    "message": "Please update NexBRIDGE",
+                    "minVersion": "1.5.0",
+                    "downloadUrl": "https://example.com/download"
+                })
+                .to_string(),
+            )
+        }
+    })
+    .await;
+    let api = client(&server.url, None);
+
+    let license = api.download("license-check").await.unwrap_err();
+    assert_eq!(
+        license,
+        ApiError::License {
+            code: "LICENSE_EXPIRED".to_string(),
+            message: "Your license has expired".to_string(),
+            export_only: true,
+        }
+    );
+    assert_eq!(license.to_string(), "Your license has expired");
+    assert!(!license.is_transient());
+
+    let update = api.download("anything").await.unwrap_err();
+    assert_eq!(
+        update,
+        ApiError::UpdateRequired {
+            message: "Please update NexBRIDGE".to_string(),
+            min_version: Some("1.5.0".to_string()),
+            download_url: Some("https://example.com/download".to_string()),
+        }
+    );
+    assert_eq!(update.status(), Some(426));
+}
+
+#[tokio::test]
+async fn other_statuses_carry_the_server_message() {
+    let server = MockServer::start(|req| match req.path.as_str() {
+        "/maintenance" => (503, json!({ "message": "Down for maintenance" }).to_string()),
+        "/missing" => (404, "Not here".to_string()),
+        _ => (400, json!({ "message": "title is required" }).to_string()),
+    })
+    .await;
+    let api = client(&server.url, None);
+
+    let err = api.download("/maintenance").await.unwrap_err();
+    assert_eq!(err, ApiError::Http { status: 503, message: "Down for maintenance".to_string() });
+    assert!(err.is_transient());
+
+    let err = api.download("/missing").await.unwrap_err();
+    assert_eq!(err.to_string(), "HTTP 404: Not here");
+    assert!(err.is_gone());
+    assert!(!err.is_transient());
+
+    let err = api.upload_html(&html_request("")).await.unwrap_err();
+    assert_eq!(err, ApiError::Http { status: 400, message: "title is required".to_string() });
+}
+
+#[tokio::test]
+async fn undecodable_success_body_is_a_decode_error() {
+    let server = MockServer::start(|_| (200, "<html>proxy login</html>".to_string())).await;
+    let api = client(&server.url, None);
+
+    let err = api.upload_html(&html_request("x")).await.unwrap_err();
+    assert!(matches!(err, ApiError::Decode(_)), "{:?}", err);
+}
+
+// ============ Personal contacts ============
+
+#[tokio::test]
+async fn import_contacts_posts_camel_case_and_returns_ids_in_order() {
+    let server = MockServer::start(|_| {
+        let body = json!({
+            "count": 2,
+            "createdCount": 1,
+            "updatedCount": 1,
+            "contacts": [{ "id": "pc-1", "displayName": "Ada" }, { "id": "pc-2", "displayName": null }],
+        });
+        (200, body.to_string())
+    })
+    .await;
+    let api = client(&server.url, None);
+    let contact = |name: Option<&str>| ImportContact {
+        id: None,
+        display_name: name.map(String::from),
+        first_name: None,
+        last_name: None,
+        email: Some("ada@example.com".to_string()),
+        phone: None,
+        all_emails: vec!["ada@example.com".to_string()],
+        all_phones: vec![],
+        source: "MACOS".to_string(),
+    };
+
+    let result = api.import_contacts(&[contact(Some("Ada")), contact(None)]).await.unwrap();
+    assert_eq!((result.created_count, result.updated_count), (1, 1));
+    let ids: Vec<&str> = result.contacts.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, ["pc-1", "pc-2"]);
+
+    let req = &server.requests()[0];
+    assert_eq!(req.path, "/personal-contacts/import");
+    let body = req.json();
+    assert_eq!(body["contacts"][0]["displayName"], "Ada");
+    assert_eq!(body["contacts"][0]["allEmails"], json!(["ada@example.com"]));
+    assert_eq!(body["contacts"][1]["source"], "MACOS");
+}
+
+#[tokio::test]
+async fn deleting_a_contact_fails_on_any_error_status() {
+    let server = MockServer::start(|req| match req.path.as_str() {
+        "/personal-contacts/pc-1" => (200, json!({ "id": "pc-1", "deleted": true }).to_string()),
+        "/personal-contacts/pc-2" => (200, json!({ "id": "pc-2", "deleted": false }).to_string()),
+        "/personal-contacts/pc-3" => (404, json!({ "message": "Cannot DELETE" }).to_string()),
+        _ => (500, json!({ "message": "boom" }).to_string()),
+    })
+    .await;
+    let api = client(&server.url, None);
+
+    api.delete_contact("pc-1").await.unwrap();
+    // Already gone is answered with 200
+    api.delete_contact("pc-2").await.unwrap();
+    // A 404 means the route is missing, not that the contact was deleted
+    assert_eq!(api.delete_contact("pc-3").await.unwrap_err().status(), Some(404));
+    assert_eq!(api.delete_contact("pc-4").await.unwrap_err().status(), Some(500));
+    assert!(server.requests().iter().all(|r| r.method == "DELETE"));
+}
+
+fn device_contact(id: &str, name: &str) -> Contact {
+    Contact {
+        id: id.to_string(),
+        display_name: Some(name.to_string()),
+        first_name: None,
+        last_name: None,
+        email: None,
+        phone: Some("+15550100".to_string()),
+        all_emails: vec![],
+        all_phones: vec!["+15550100".to_string()],
+        street: None,
+        city: None,
+        state: None,
+        zip: None,
+        country: None,
+        company: None,
+        job_title: None,
+    }
+}
+
+#[tokio::test]
+async fn sync_fails_a_batch_whose_ids_do_not_match_the_request() {
+    let full = Arc::new(AtomicU32::new(0));
+    let respond_in_full = full.clone();
+    let server = MockServer::start(move |req| {
+        let ids = if respond_in_full.load(Ordering::SeqCst) == 0 { 1 } else { 2 };
+        let contacts: Vec<_> = (1..=ids).map(|i| json!({ "id": format!("pc-{}", i) })).collect();
+        let count = req.json()["contacts"].as_array().map_or(0, Vec::len);
+        (200, json!({ "count": count, "createdCount": count, "updatedCount": 0, "contacts": contacts }).to_string())
+    })
+    .await;
+    let api = client(&server.url, None);
+    let dir = common::temp_dir("contact-sync");
+    let groups = ContactGroupIndex::new(&dir.join("contact_groups.db"), None).unwrap();
+    let device = [device_contact("c-1", "Ada"), device_contact("c-2", "Grace")];
+    let selected = ["c-1".to_string(), "c-2".to_string()];
+
+    // One id for two contacts: neither gets an id, both retry next run
+    let summary = sync_contacts(&api, &groups, &device, &selected).await.unwrap();
+    assert_eq!((summary.created, summary.failed), (0, 2));
+    for state in groups.get_sync_states().unwrap() {
+        assert_eq!(state.status, ContactSyncStatus::Failed);
+        assert_eq!(state.nexus_id, None);
+        assert_eq!(state.error.as_deref(), Some("Unexpected response: Nexus returned 1 ids for 2 contacts"));
+    }
+
+    full.store(1, Ordering::SeqCst);
+    let summary = sync_contacts(&api, &groups, &device, &selected).await.unwrap();
+    assert_eq!((summary.created, summary.failed), (2, 0));
+    let mut ids: Vec<_> = groups.get_sync_states().unwrap().into_iter().map(|s| (s.contact_id, s.nexus_id)).collect();
+    ids.sort();
+    assert_eq!(ids, [("c-1".to_string(), Some("pc-1".to_string())), ("c-2".to_string(), Some("pc-2".to_string()))]);
+}
+
+#[tokio::test]
+async fn sync_skips_contacts_without_email_or_phone_and_updates_by_nexus_id() {
+    let server = MockServer::start(|req| {
+        let count = req.json()["contacts"].as_array().map_or(0, Vec::len);
+        let contacts: Vec<_> = (1..=count).map(|i| json!({ "id": format!("pc-{}", i) })).collect();
+        (200, json!({ "count": count, "createdCount": count, "updatedCount": 0, "contacts": contacts }).to_string())
+    })
+    .await;
+    let api = client(&server.url, None);
+    let dir = common::temp_dir("contact-sync-skip");
+    let groups = ContactGroupIndex::new(&dir.join("contact_groups.db"), None).unwrap();
+    let mut blank = device_contact("c-2", "Grace");
+    blank.phone = Some("  ".to_string());
+    blank.all_phones.clear();
+    let mut device = [device_contact("c-1", "Ada"), blank];
+    let selected = ["c-1".to_string(), "c-2".to_string()];
+
+    // Nexus would drop the blank contact; sending it would fail the whole batch
+    let summary = sync_contacts(&api, &groups, &device, &selected).await.unwrap();
+    assert_eq!((summary.created, summary.failed, summary.skipped), (1, 0, 1));
+    let body = server.requests()[0].json();
+    assert_eq!(body["contacts"].as_array().unwrap().len(), 1);
+    assert!(body["contacts"][0].get("id").is_none());
+
+    // Both email and phone changed: Nexus is told which record to update
+    device[0].phone = Some("+15550199".to_string());
+    device[0].all_phones = vec!["+15550199".to_string()];
+    device[0].email = Some("ada@example.com".to_string());
+    device[0].all_emails = vec!["ada@example.com".to_string()];
+    let summary = sync_contacts(&api, &groups, &device, &selected).await.unwrap();
+    assert_eq!((summary.updated, summary.failed, summary.skipped), (1, 0, 1));
+    let body = server.requests()[1].json();
+    assert_eq!(body["contacts"][0]["id"], "pc-1");
+    assert_eq!(body["contacts"][0]["phone"], "+15550199");
+}
+
+#[test]
+fn contact_source_follows_the_provider() {
+    assert_eq!(contact_source("vcard:3f2a"), "UPLOAD");
+    assert_eq!(contact_source("carddav:icloud:3f2a"), "UPLOAD");
+
+    let native = contact_source("ABCD-1234:ABPerson");
+    if cfg!(target_os = "macos") {
+        assert_eq!(native, "MACOS");
+    } else if cfg!(target_os = "windows") {
+        assert_eq!(native, "WINDOWS");
+    } else {
+        assert_eq!(native, "UPLOAD");
+    }
+}
+
+// ============ Downloads ============
+
+#[tokio::test]
+async fn download_authenticates_only_against_the_api() {
+    let api_server = MockServer::start(|_| (200, "api-bytes".to_string())).await;
+    let storage = MockServer::start(|_| (200, "storage-bytes".to_string())).await;
+    let api = client(&api_server.url, None);
+
+    let relative = api.download("scans/1/img_0001.jpg").await.unwrap();
+    assert_eq!(relative.text().await.unwrap(), "api-bytes");
+    let absolute = api.download(&format!("{}/scans/1/img_0002.jpg", api_server.url)).await.unwrap();
+    assert_eq!(absolute.text().await.unwrap(), "api-bytes");
+    let signed = api.download(&format!("{}/bucket/img_0003.jpg?sig=abc", storage.url)).await.unwrap();
+    assert_eq!(signed.text().await.unwrap(), "storage-bytes");
+
+    let api_requests = api_server.requests();
+    assert_eq!(api_requests[0].path, "/scans/1/img_0001.jpg");
+    assert_eq!(api_requests[1].path, "/scans/1/img_0002.jpg");
+    assert!(api_requests
+        .iter()
+        .all(|r| r.header("authorization") == Some("Bearer access-1")));
+
+    let storage_requests = storage.requests();
+    assert_eq!(storage_requests[0].path, "/bucket/img_0003.jpg?sig=abc");
+    assert_eq!(storage_requests[0].header("authorization"), None);
+}
+
+// ============ Chunked uploads ============
+
+/// Server side of the upload-session protocol, assembling chunks in memory.
+#[derive(Default)]
+struct UploadStore {
+    data: Vec<u8>,
+    puts: u32,
+}
+
+fn upload_sessions(store: Arc<Mutex<UploadStore>>, fail_put: u32, expire_token_after_put: u32) -> impl Fn(&Recorded) -> (u16, String) {
+    move |req| {
+        let mut store = store.lock().unwrap();
+        if req.path == "/auth/refresh" {
+            return (200, json!({ "accessToken": "access-2", "refreshToken": "refresh-2" }).to_string());
+        }
+        let token_expired = store.puts >= expire_token_after_put;
+        if token_expired && req.header("authorization") == Some("Bearer access-1") {
+            return (401, String::new());
+        }
+
+        let offset = store.data.len() as u64;
+        match (req.method.as_str(), req.path.as_str()) {
+            ("POST", "/upload-sessions") => {
+                let body = req.json();
+                assert_eq!(body["fileName"], "model.usdz");
+                assert!(body["chunkSize"].as_u64().unwrap() > 0);
+                (201, json!({ "uploadId": "up-1", "offset": 0 }).to_string())
+            }
+            ("GET", "/upload-sessions/up-1") => (200, json!({ "uploadId": "up-1", "offset": offset }).to_string()),
+            ("PUT", path) if path.starts_with("/upload-sessions/up-1?offset=") => {
+                store.puts += 1;
+                if store.puts == fail_put {
+                    return (503, json!({ "message": "Try again" }).to_string());
+                }
+                let sent: u64 = path.rsplit('=').next().unwrap().parse().unwrap();
+                if sent != offset {
+                    return (409, json!({ "offset": offset }).to_string());
+                }
+                let expected_range = format!("bytes {}-{}/", sent, sent + req.body.len() as u64 - 1);
+                assert!(req.header("content-range").unwrap().starts_with(&expected_range));
+                store.data.extend_from_slice(&req.body);
+                (200, json!({ "offset": store.data.len() }).to_string())
+            }
+            ("POST", "/upload-sessions/up-1/complete") => (
+                200,
+                json!({
+                    "uploadId": "up-1",
+                    "size": store.data.len(),
+                    "sha256": hex::encode(Sha256::digest(&store.data)),
+                })
+                .to_string(),
+            ),
+            _ => (404, String::new()),
+        }
+    }
+}
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[tokio::test]
+async fn chunked_upload_retries_a_failed_chunk_and_verifies_the_hash() {
+    let store = Arc::new(Mutex::new(UploadStore::default()));
+    let server = MockServer::start(upload_sessions(store.clone(), 2, u32::MAX)).await;
+    let api = client(&server.url, None);
+    let data = payload(20 * 1024 * 1024);
+
+    let progress = Arc::new(Mutex::new(Vec::new()));
+    let seen = progress.clone();
+    let completed = ChunkedUploader::new(None)
+        .upload(
+            &api,
+            "model.usdz",
+            "model/vnd.usdz+zip",
+            &UploadSource::Bytes(data.clone()),
+            &move |sent, total| seen.lock().unwrap().push((sent, total)),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(completed.upload_id, "up-1");
+    assert_eq!(completed.size, data.len() as u64);
+    assert_eq!(completed.sha256, hex::encode(Sha256::digest(&data)));
+    assert_eq!(completed.resumed_from, 0);
+    assert_eq!(store.lock().unwrap().data, data);
+    // Three chunks plus the one retried after the 503
+    assert_eq!(store.lock().unwrap().puts, 4);
+    assert_eq!(progress.lock().unwrap().last(), Some(&(data.len() as u64, data.len() as u64)));
+}
+
+#[tokio::test]
+async fn chunked_upload_survives_a_token_expiring_mid_upload() {
+    let store = Arc::new(Mutex::new(UploadStore::default()));
+    let server = MockServer::start(upload_sessions(store.clone(), 0, 1)).await;
+    let api = client(&server.url, Some("refresh-1"));
+    let data = payload(10 * 1024 * 1024);
+
+    let completed = ChunkedUploader::new(None)
+        .upload(&api, "model.usdz", "application/octet-stream", &UploadSource::Bytes(data.clone()), &|_, _| {})
+        .await
+        .unwrap();
+
+    assert_eq!(completed.sha256, hex::encode(Sha256::digest(&data)));
+    assert_eq!(store.lock().unwrap().data, data);
+    assert_eq!(server.count("/auth/refresh"), 1);
+    let last = server.requests().last().cloned().unwrap();
+    assert_eq!(last.path, "/upload-sessions/up-1/complete");
+    assert_eq!(last.header("authorization"), Some("Bearer access-2"));
+}
+
+// ============ Original files ============
+
+/// Ordinary uploads must not wait for a transfer window or a metered link.
+fn unthrottled() {
+    limiter()
+        .configure(&TransferLimits { pause_on_metered: false, ..Default::default() })
+        .unwrap();
+}
+
+/// A file named `name` in its own temp dir, removed with the dir.
+fn original_on_disk(name: &str, contents: &[u8]) -> (TempDir, std::path::PathBuf) {
+    let dir = common::temp_dir(name);
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    (dir, path)
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle.as_bytes())
+}
+
+fn upload_request(original: Option<&std::path::Path>) -> UploadRequest {
+    UploadRequest {
+        document_id: "local-1".to_string(),
+        html_content: "<p>Lease</p>".to_string(),
+        title: "Lease".to_string(),
+        category: "contracts".to_string(),
+        original_format: "pdf".to_string(),
+        word_count: 1,
+        folder_name: "Projects".to_string(),
+        breadcrumb: vec!["Projects".to_string()],
+        metadata: Default::default(),
+        original_path: original.map(|p| p.to_string_lossy().to_string()),
+    }
+}
+
+#[tokio::test]
+async fn upload_original_streams_a_multipart_file_with_its_hash() {
+    unthrottled();
+    let server = MockServer::start(|_| (201, "{}".to_string())).await;
+    let api = client(&server.url, None);
+    let contents = b"%PDF-1.7 lease original";
+    let (_dir, path) = original_on_disk("lease.pdf", contents);
+
+    let original = OriginalFile {
+        sha256: hex::encode(Sha256::digest(contents)),
+        size: contents.len() as u64,
+        content: FileContent::Stream {
+            path: path.clone(),
+            file_name: "lease.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            size: contents.len() as u64,
+        },
+    };
+    api.upload_original("doc-1", &original).await.unwrap();
+
+    let req = &server.requests()[0];
+    assert_eq!((req.method.as_str(), req.path.as_str()), ("POST", "/document-import/documents/doc-1/original"));
+    assert!(req.header("content-type").unwrap().starts_with("multipart/form-data; boundary="));
+    assert!(contains(&req.body, &format!("name=\"sha256\"\r\n\r\n{}", original.sha256)));
+    assert!(contains(&req.body, &format!("name=\"size\"\r\n\r\n{}", contents.len())));
+    assert!(contains(&req.body, "name=\"file\"; filename=\"lease.pdf\""));
+    assert!(contains(&req.body, "Content-Type: application/pdf"));
+    assert!(contains(&req.body, "%PDF-1.7 lease original"));
+    assert!(!contains(&req.body, "name=\"uploadId\""));
+
+    // Sent through an upload session: only the id goes in the form
+    let session = OriginalFile { content: FileContent::UploadSession("up-9".to_string()), ..original };
+    api.upload_original("doc-1", &session).await.unwrap();
+    let req = &server.requests()[1];
+    assert!(contains(&req.body, "name=\"uploadId\"\r\n\r\nup-9"));
+    assert!(!contains(&req.body, "filename="));
+}
+
+#[tokio::test]
+async fn queue_attaches_the_original_after_the_html() {
+    unthrottled();
+    let server = MockServer::start(|req| match req.path.as_str() {
+        "/document-import/upload-html" => (201, json!({ "id": "doc-1" }).to_string()),
+        _ => (201, "{}".to_string()),
+    })
+    .await;
+    let api = client(&server.url, None);
+    let contents = b"PK original docx bytes";
+    let (_dir, path) = original_on_disk("scope.docx", contents);
+
+    let result = UploadQueue::new().upload_document(&api, upload_request(Some(&path))).await;
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.nexus_doc_id.as_deref(), Some("doc-1"));
+    assert_eq!(result.original_sha256, Some(hex::encode(Sha256::digest(contents))));
+    assert_eq!(result.original_error, None);
+
+    let paths: Vec<String> = server.requests().iter().map(|r| r.path.clone()).collect();
+    assert_eq!(paths, ["/document-import/upload-html", "/document-import/documents/doc-1/original"]);
+    let original = &server.requests()[1];
+    assert!(contains(
+        &original.body,
+        "Content-Type: application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    ));
+}
+
+#[tokio::test]
+async fn a_failed_original_does_not_fail_the_html_upload() {
+    unthrottled();
+    let server = MockServer::start(|req| match req.path.as_str() {
+        "/document-import/upload-html" => (201, json!({ "documentId": "doc-1" }).to_string()),
+        _ => (413, json!({ "message": "File too large" }).to_string()),
+    })
+    .await;
+    let api = client(&server.url, None);
+    let queue = UploadQueue::new();
+
+    let (_dir, path) = original_on_disk("big.pdf", b"%PDF");
+    let result = queue.upload_document(&api, upload_request(Some(&path))).await;
+    assert!(result.success);
+    assert_eq!(result.original_sha256, None);
+    assert!(result.original_error.unwrap().contains("File too large"));
+
+    // Missing on disk: the HTML is still uploaded
+    let missing = path.with_file_name("moved.pdf");
+    let result = queue.upload_document(&api, upload_request(Some(&missing))).await;
+    assert!(result.success);
+    assert!(result.original_error.unwrap().starts_with("Failed to read"));
+    assert_eq!(queue.get_progress().completed, 2);
+    assert_eq!(server.count("/document-import/documents/doc-1/original"), 1);
+}
+
+#[tokio::test]
+async fn without_a_document_id_the_original_is_not_sent() {
+    unthrottled();
+    let server = MockServer::start(|_| (201, "{}".to_string())).await;
+    let api = client(&server.url, None);
+    let (_dir, path) = original_on_disk("orphan.pdf", b"%PDF");
+
+    let result = UploadQueue::new().upload_document(&api, upload_request(Some(&path))).await;
+    assert!(result.success);
+    assert!(result.original_error.is_some());
+    assert_eq!(server.requests().len(), 1);
+}
+
+// ============ Resuming chunked uploads ============
+
+/// An upload-session server that can misbehave on chosen PUTs (1-based).
+#[derive(Default)]
+struct FlakyStore {
+    data: Vec<u8>,
+    puts: u32,
+    creates: u32,
+    /// Store the chunk but answer 503, as if the ack was lost
+    lose_ack_on: u32,
+    /// Forget everything received so far and answer 409
+    rewind_on: u32,
+    /// Refuse with a 400, ending the upload
+    refuse_on: u32,
+}
+
+fn flaky_sessions(store: Arc<Mutex<FlakyStore>>) -> impl Fn(&Recorded) -> (u16, String) {
+    move |req| {
+        let mut store = store.lock().unwrap();
+        match (req.method.as_str(), req.path.as_str()) {
+            ("POST", "/upload-sessions") => {
+                store.creates += 1;
+                (201, json!({ "uploadId": "up-7", "offset": 0 }).to_string())
+            }
+            ("GET", "/upload-sessions/up-7") => {
+                (200, json!({ "uploadId": "up-7", "offset": store.data.len() }).to_string())
+            }
+            ("PUT", path) if path.starts_with("/upload-sessions/up-7?offset=") => {
+                store.puts += 1;
+                let put = store.puts;
+                if put == store.refuse_on {
+                    return (400, json!({ "message": "Chunk refused" }).to_string());
+                }
+                if put == store.rewind_on {
+                    store.data.clear();
+                    return (409, json!({ "message": "Expected offset 0", "offset": 0 }).to_string());
+                }
+                let sent: usize = path.rsplit('=').next().unwrap().parse().unwrap();
+                if sent != store.data.len() {
+                    return (409, json!({ "offset": store.data.len() }).to_string());
+                }
+                store.data.extend_from_slice(&req.body);
+                if put == store.lose_ack_on {
+                    return (503, String::new());
+                }
+                (200, json!({ "uploadId": "up-7", "offset": store.data.len() }).to_string())
+            }
+            ("POST", "/upload-sessions/up-7/complete") => (
+                200,
+                json!({
+                    "uploadId": "up-7",
+                    "size": store.data.len(),
+                    "sha256": hex::encode(Sha256::digest(&store.data)),
+                })
+                .to_string(),
+            ),
+            _ => (404, String::new()),
+        }
+    }
+}
+
+async fn upload_bytes(uploader: &ChunkedUploader, api: &NexusApi, data: &[u8]) -> Result<u64, String> {
+    uploader
+        .upload(api, "scan.e57", "application/octet-stream", &UploadSource::Bytes(data.to_vec()), &|_, _| {})
+        .await
+        .map(|completed| completed.resumed_from)
+}
+
+#[tokio::test]
+async fn chunked_upload_resumes_from_the_session_file_after_a_restart() {
+    unthrottled();
+    let store = Arc::new(Mutex::new(FlakyStore { refuse_on: 2, ..Default::default() }));
+    let server = MockServer::start(flaky_sessions(store.clone())).await;
+    let api = client(&server.url, None);
+    let data = payload(20 * 1024 * 1024);
+    let dir = common::temp_dir("sessions");
+    let session_file = dir.join("upload_sessions.json");
+
+    // The first run gets one chunk through, then stops
+    let first = ChunkedUploader::new(Some(session_file.clone()));
+    assert!(upload_bytes(&first, &api, &data).await.unwrap_err().contains("Chunk refused"));
+    drop(first);
+    let acknowledged = store.lock().unwrap().data.len() as u64;
+    assert!(acknowledged > 0);
+    let recorded: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&session_file).unwrap()).unwrap();
+    let session = recorded.as_object().unwrap().values().next().unwrap().clone();
+    assert_eq!((session["upload_id"].as_str(), session["offset"].as_u64()), (Some("up-7"), Some(acknowledged)));
+
+    // After a restart the same content picks up at the server's offset
+    let resumed = ChunkedUploader::new(Some(session_file.clone()));
+    assert_eq!(upload_bytes(&resumed, &api, &data).await.unwrap(), acknowledged);
+    assert_eq!(store.lock().unwrap().data, data);
+    assert_eq!(store.lock().unwrap().creates, 1);
+    assert_eq!(server.count("/upload-sessions/up-7"), 1);
+
+    // Finished sessions are forgotten
+    let recorded: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&session_file).unwrap()).unwrap();
+    assert!(recorded.as_object().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn chunked_upload_does_not_resend_a_chunk_whose_ack_was_lost() {
+    unthrottled();
+    let store = Arc::new(Mutex::new(FlakyStore { lose_ack_on: 2, ..Default::default() }));
+    let server = MockServer::start(flaky_sessions(store.clone())).await;
+    let api = client(&server.url, None);
+    let data = payload(20 * 1024 * 1024);
+
+    assert_eq!(upload_bytes(&ChunkedUploader::new(None), &api, &data).await.unwrap(), 0);
+    // Three chunks, each sent once; the server was asked where it was
+    assert_eq!(store.lock().unwrap().puts, 3);
+    assert_eq!(store.lock().unwrap().data, data);
+    assert_eq!(server.count("/upload-sessions/up-7"), 1);
+}
+
+#[tokio::test]
+async fn chunked_upload_follows_the_server_offset_on_409() {
+    unthrottled();
+    let store = Arc::new(Mutex::new(FlakyStore { rewind_on: 2, ..Default::default() }));
+    let server = MockServer::start(flaky_sessions(store.clone())).await;
+    let api = client(&server.url, None);
+    let data = payload(20 * 1024 * 1024);
+
+    upload_bytes(&ChunkedUploader::new(None), &api, &data).await.unwrap();
+    // The server lost the first chunk: it is sent again from offset 0
+    let store = store.lock().unwrap();
+    assert_eq!(store.data, data);
+    assert_eq!(store.puts, 5);
+    let offsets: Vec<String> = server
+        .requests()
+        .iter()
+        .filter(|r| r.method == "PUT")
+        .map(|r| r.path.rsplit('=').next().unwrap().to_string())
+        .collect();
+    let chunk = (8 * 1024 * 1024).to_string();
+    let second = (16 * 1024 * 1024).to_string();
+    assert_eq!(offsets, ["0", chunk.as_str(), "0", chunk.as_str(), second.as_str()]);
+}
@@ -0,0 +1,113 @@
+// ---------------------------------------------------------------------------
+// Integration tests for Markdown and plain-text rendering
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::converter::{self, ConversionResult, OutputFormat};
+use nexbridge_connect_lib::render::{self, ImageOutput};
+
+mod common;
+
+/// 1x1 transparent PNG
+const PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAQAAAC1HAwCAAAAC0lEQVR42mNkYAAAAAYAAjCB0C8AAAAASUVORK5CYII=";
+
+fn page(body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><title>Ignored</title><style>p {{ color: red; }}</style></head><body>{}</body></html>",
+        body
+    )
+}
+
+fn conversion(html: String) -> ConversionResult {
+    ConversionResult {
+        html,
+        title: "Site Safety Plan".to_string(),
+        word_count: 0,
+        has_images: true,
+        image_count: 1,
+        original_format: "docx".to_string(),
+        error: None,
+        format: OutputFormat::Html,
+        content: None,
+        image_files: vec![],
+    }
+}
+
+// ============ Markdown ============
+
+#[test]
+fn renders_blocks_and_inline_markup_as_markdown() {
+    let html = page(
+        "<h1>Site  Safety</h1>\
+         <p>Wear <strong>hard hats</strong> and <em>gloves</em> &amp; read <a href=\"https://example.com/sop\">the SOP</a>.</p>\
+         <ol><li>Sign in</li><li>Suit up<ul><li>Boots</li></ul></li></ol>\
+         <pre>let x = 1;\n  indented</pre>",
+    );
+    let md = render::html_to_markdown(&html, &ImageOutput::Inline).unwrap().content;
+
+    assert!(!md.contains("Ignored") && !md.contains("color"), "{}", md);
+    assert!(md.starts_with("# Site Safety\n\n"), "{}", md);
+    assert!(md.contains("Wear **hard hats** and *gloves* & read [the SOP](https://example.com/sop)."), "{}", md);
+    assert!(md.contains("1. Sign in\n2. Suit up\n   - Boots"), "{}", md);
+    assert!(md.contains("```\nlet x = 1;\n  indented\n```"), "{}", md);
+}
+
+#[test]
+fn escapes_markdown_syntax_in_text() {
+    let md = render::html_to_markdown(&page("<p>Use *only* [approved] #2 pipe | fittings</p>"), &ImageOutput::Inline)
+        .unwrap()
+        .content;
+    assert!(md.contains(r"Use \*only\* \[approved\] \#2 pipe \| fittings"), "{}", md);
+}
+
+#[test]
+fn renders_tables_with_a_header_row() {
+    let html = page("<table><tr><th>Item</th><th>Qty</th></tr><tr><td>Rebar</td><td>40</td></tr></table>");
+    let md = render::html_to_markdown(&html, &ImageOutput::Inline).unwrap().content;
+    assert!(md.contains("| Item | Qty |"), "{}", md);
+    assert!(md.contains("| Rebar | 40 |"), "{}", md);
+
+    let text = render::html_to_text(&html);
+    assert!(text.contains("Item\tQty\nRebar\t40"), "{}", text);
+}
+
+#[test]
+fn writes_embedded_images_next_to_the_markdown() {
+    let dir = common::temp_dir("images");
+
+    let mut result = conversion(page(&format!("<p>Gate</p><img src=\"data:image/png;base64,{}\" alt=\"Gate photo\">", PNG)));
+    converter::render_output(&mut result, OutputFormat::Markdown, Some(dir.path())).unwrap();
+
+    assert_eq!(result.format, OutputFormat::Markdown);
+    assert_eq!(result.image_files.len(), 1);
+    let file = std::path::Path::new(&result.image_files[0]);
+    assert_eq!(file.parent(), Some(dir.path()));
+    assert!(file.file_name().unwrap().to_str().unwrap().starts_with("Site_Safety_Plan-image-1."));
+    assert!(file.exists(), "{}", file.display());
+    let md = result.content.unwrap();
+    assert!(md.contains("![Gate photo]("), "{}", md);
+    assert!(!md.contains("base64"), "{}", md);
+
+    // Without a directory the data URI stays inline
+    let mut inline = conversion(page(&format!("<img src=\"data:image/png;base64,{}\">", PNG)));
+    converter::render_output(&mut inline, OutputFormat::Markdown, None).unwrap();
+    assert!(inline.content.unwrap().contains("data:image/png;base64,"));
+    assert!(inline.image_files.is_empty());
+}
+
+// ============ Plain text ============
+
+#[test]
+fn renders_normalized_plain_text() {
+    let html = page(
+        "<h2>Scope</h2><p>Pour   the\n slab</p><p>Cure <b>7</b> days</p><ol><li>Forms</li><li>Pour</li></ol>",
+    );
+    let text = render::html_to_text(&html);
+    assert_eq!(text.trim_end(), "## Scope\n\nPour the slab\n\nCure 7 days\n\n- Forms\n- Pour");
+
+    let mut result = conversion(html);
+    converter::render_output(&mut result, OutputFormat::Text, None).unwrap();
+    assert_eq!(result.content.as_deref(), Some(text.as_str()));
+
+    converter::render_output(&mut result, OutputFormat::Html, None).unwrap();
+    assert!(result.content.is_none());
+}
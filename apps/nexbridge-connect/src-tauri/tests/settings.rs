@@ -0,0 +1,169 @@
+// ---------------------------------------------------------------------------
+// Integration tests for persisted SyncSettings
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::bandwidth::{TransferLimits, TransferWindow};
+use nexbridge_connect_lib::cache::DEFAULT_CACHE_BUDGET_BYTES;
+use nexbridge_connect_lib::contact_providers::ContactProviderConfig;
+use nexbridge_connect_lib::settings::{self, SyncSettings, MAX_SYNC_INTERVAL_MINUTES, MIN_SYNC_INTERVAL_MINUTES};
+use serde_json::json;
+
+mod common;
+
+fn vcard_folder(id: &str, path: &str) -> ContactProviderConfig {
+    ContactProviderConfig::VcardDirectory {
+        id: id.to_string(),
+        name: "Exports".to_string(),
+        path: path.to_string(),
+    }
+}
+
+fn absolute(name: &str) -> String {
+    std::env::temp_dir().join(name).to_string_lossy().to_string()
+}
+
+// ============ Validation ============
+
+#[test]
+fn validates_interval_battery_and_timestamps() {
+    assert!(SyncSettings::default().validate().is_ok());
+
+    let with = |f: &dyn Fn(&mut SyncSettings)| {
+        let mut settings = SyncSettings::default();
+        f(&mut settings);
+        settings.validate()
+    };
+    assert!(with(&|s| s.sync_interval_minutes = MIN_SYNC_INTERVAL_MINUTES).is_ok());
+    assert!(with(&|s| s.sync_interval_minutes = MAX_SYNC_INTERVAL_MINUTES).is_ok());
+    let err = with(&|s| s.sync_interval_minutes = MIN_SYNC_INTERVAL_MINUTES - 1).unwrap_err();
+    assert_eq!(err, "Sync interval must be between 5 minutes and 24 hours");
+    assert!(with(&|s| s.sync_interval_minutes = MAX_SYNC_INTERVAL_MINUTES + 1).is_err());
+
+    assert!(with(&|s| s.min_battery_pct = 0).is_ok());
+    assert!(with(&|s| s.min_battery_pct = 101).is_err());
+
+    assert!(with(&|s| s.cache_budget_bytes = 1).is_ok());
+    assert_eq!(with(&|s| s.cache_budget_bytes = 0).unwrap_err(), "Cache budget must be greater than 0");
+
+    assert!(with(&|s| s.last_sync_at = Some("2024-05-01T10:00:00+02:00".to_string())).is_ok());
+    assert!(with(&|s| s.last_sync_at = Some("yesterday".to_string())).is_err());
+}
+
+#[test]
+fn validates_nested_limits_and_contact_sources() {
+    let mut settings = SyncSettings::default();
+    settings.transfer_limits.upload_limit_kbps = Some(0);
+    assert!(settings.validate().is_err());
+
+    let mut settings = SyncSettings {
+        contact_providers: vec![vcard_folder("exports", &absolute("exports"))],
+        ..Default::default()
+    };
+    assert!(settings.validate().is_ok());
+    settings.contact_providers.push(vcard_folder("exports", &absolute("other")));
+    assert_eq!(settings.validate().unwrap_err(), "Duplicate contact source id 'exports'");
+    settings.contact_providers = vec![vcard_folder("relative", "exports")];
+    assert!(settings.validate().is_err());
+}
+
+// ============ Persistence ============
+
+#[test]
+fn saves_and_loads_a_versioned_file() {
+    let dir = common::temp_dir("round-trip");
+    assert_eq!(settings::load(&dir), SyncSettings::default());
+
+    let saved = SyncSettings {
+        auto_sync_enabled: true,
+        sync_interval_minutes: 60,
+        selected_contact_ids: vec!["c-1".to_string(), "c-2".to_string()],
+        last_sync_at: Some("2024-05-01T08:00:00+00:00".to_string()),
+        min_battery_pct: 35,
+        transfer_limits: TransferLimits {
+            upload_limit_kbps: Some(512),
+            windows: vec![TransferWindow { start: "18:00".to_string(), end: "07:00".to_string() }],
+            ..Default::default()
+        },
+        contact_providers: vec![vcard_folder("exports", &absolute("exports"))],
+        cache_budget_bytes: 512 * 1024 * 1024,
+        ..Default::default()
+    };
+    settings::save(&dir, &saved).unwrap();
+    assert_eq!(settings::load(&dir), saved);
+
+    let raw: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("sync_settings.json")).unwrap()).unwrap();
+    assert_eq!(raw["version"], 1);
+    assert_eq!(raw["settings"]["sync_interval_minutes"], 60);
+    assert!(!dir.join("sync_settings.json.tmp").exists());
+}
+
+#[test]
+fn missing_fields_take_defaults_and_bad_values_are_corrected() {
+    let dir = common::temp_dir("defaults");
+    let file = json!({
+        "version": 1,
+        "settings": {
+            "auto_sync_enabled": true,
+            "sync_interval_minutes": 1,
+            "min_battery_pct": 250,
+            "cache_budget_bytes": 0,
+            "last_sync_at": "not a time",
+            "transfer_limits": { "windows": [{ "start": "18:00", "end": "18:00" }] },
+            "contact_providers": [
+                { "kind": "vcard_directory", "id": "a", "name": "A", "path": absolute("a") },
+                { "kind": "vcard_directory", "id": "a", "name": "Again", "path": absolute("b") },
+                { "kind": "vcard_directory", "id": "native", "name": "Native", "path": absolute("c") }
+            ],
+            "some_future_setting": 3
+        }
+    });
+    std::fs::write(dir.join("sync_settings.json"), file.to_string()).unwrap();
+
+    let loaded = settings::load(&dir);
+    assert!(loaded.auto_sync_enabled);
+    assert_eq!(loaded.sync_interval_minutes, MIN_SYNC_INTERVAL_MINUTES);
+    assert_eq!(loaded.min_battery_pct, 100);
+    assert_eq!(loaded.cache_budget_bytes, DEFAULT_CACHE_BUDGET_BYTES);
+    assert_eq!(loaded.last_sync_at, None);
+    assert_eq!(loaded.transfer_limits, TransferLimits::default());
+    let ids: Vec<&str> = loaded.contact_providers.iter().map(|p| p.id()).collect();
+    assert_eq!(ids, ["a"]);
+    // Not in the file
+    assert_eq!(loaded.selected_contact_ids, Vec::<String>::new());
+    assert!(loaded.validate().is_ok());
+}
+
+#[test]
+fn unreadable_files_fall_back_to_defaults() {
+    let dir = common::temp_dir("unreadable");
+    std::fs::write(dir.join("sync_settings.json"), "{ half-written").unwrap();
+    assert_eq!(settings::load(&dir), SyncSettings::default());
+}
+
+#[test]
+fn first_load_imports_the_frontend_store() {
+    let dir = common::temp_dir("legacy");
+    let store = json!({
+        "syncSettings": {
+            "autoSyncEnabled": true,
+            "syncIntervalMinutes": 30,
+            "selectedContactIds": ["c-9"],
+            "launchAtStartup": true
+        },
+        "theme": "dark"
+    });
+    std::fs::write(dir.join("settings.json"), store.to_string()).unwrap();
+
+    let loaded = settings::load(&dir);
+    assert!(loaded.auto_sync_enabled && loaded.launch_at_startup);
+    assert_eq!(loaded.sync_interval_minutes, 30);
+    assert_eq!(loaded.selected_contact_ids, ["c-9"]);
+
+    // Written to the new file, which wins from then on
+    assert!(dir.join("sync_settings.json").exists());
+    let mut changed = loaded.clone();
+    changed.sync_interval_minutes = 45;
+    settings::save(&dir, &changed).unwrap();
+    assert_eq!(settings::load(&dir).sync_interval_minutes, 45);
+}
@@ -0,0 +1,189 @@
+// ---------------------------------------------------------------------------
+// Integration tests for smart_groups
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::contacts::Contact;
+use nexbridge_connect_lib::smart_groups::{
+    self, ConditionOp, ContactRuleField, GroupLists, MailScores, RuleMode, SmartCondition, SmartRules,
+};
+
+fn contact(id: &str) -> Contact {
+    Contact {
+        id: id.to_string(),
+        display_name: None,
+        first_name: None,
+        last_name: None,
+        email: None,
+        phone: None,
+        all_emails: vec![],
+        all_phones: vec![],
+        street: None,
+        city: None,
+        state: None,
+        zip: None,
+        country: None,
+        company: None,
+        job_title: None,
+    }
+}
+
+fn condition(field: ContactRuleField, op: ConditionOp, values: &[&str]) -> SmartCondition {
+    SmartCondition { field, op, values: values.iter().map(|v| v.to_string()).collect() }
+}
+
+fn rules(mode: RuleMode, conditions: Vec<SmartCondition>) -> SmartRules {
+    SmartRules { mode, conditions, exclude_ignored: true }
+}
+
+fn ids(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+/// Texas electricians, a Californian at a bank, and a plumber with no address
+fn contacts() -> Vec<Contact> {
+    let mut sparky = contact("sparky");
+    sparky.company = Some("Lone Star Electric".to_string());
+    sparky.state = Some("Texas".to_string());
+    sparky.email = Some("joe@lonestar.com".to_string());
+    sparky.all_emails = vec!["joe@lonestar.com".to_string(), "joe@gmail.com".to_string()];
+    sparky.phone = Some("(512) 555-0100".to_string());
+
+    let mut volt = contact("volt");
+    volt.company = Some("Volt ELECTRIC Co".to_string());
+    volt.state = Some("tx".to_string());
+    volt.email = Some("ana@volt.example".to_string());
+
+    let mut banker = contact("banker");
+    banker.company = Some("First Bank".to_string());
+    banker.state = Some("CA".to_string());
+    banker.email = Some("kim@bank.example".to_string());
+
+    let mut plumber = contact("plumber");
+    plumber.display_name = Some("Pat the Plumber".to_string());
+    plumber.job_title = Some("Plumber".to_string());
+
+    vec![sparky, volt, banker, plumber]
+}
+
+fn matching(rules: &SmartRules, mail_scores: Option<&MailScores>) -> Vec<String> {
+    smart_groups::evaluate(rules, &contacts(), &GroupLists::default(), mail_scores)
+}
+
+// ============ Rules ============
+
+#[test]
+fn rejects_incomplete_rules() {
+    let invalid = [
+        rules(RuleMode::All, vec![]),
+        rules(RuleMode::All, vec![condition(ContactRuleField::Company, ConditionOp::Contains, &[" "])]),
+        rules(RuleMode::All, vec![condition(ContactRuleField::Company, ConditionOp::AtLeast, &["5"])]),
+        rules(RuleMode::All, vec![condition(ContactRuleField::MailScore, ConditionOp::Equals, &["5"])]),
+        rules(RuleMode::All, vec![condition(ContactRuleField::MailScore, ConditionOp::AtLeast, &["lots"])]),
+        rules(RuleMode::All, vec![condition(ContactRuleField::MailScore, ConditionOp::AtLeast, &["101"])]),
+    ];
+    for rules in &invalid {
+        assert!(smart_groups::validate_rules(rules).is_err(), "{:?}", rules);
+    }
+
+    let valid = rules(RuleMode::Any, vec![
+        condition(ContactRuleField::Zip, ConditionOp::IsEmpty, &[]),
+        condition(ContactRuleField::MailScore, ConditionOp::AtLeast, &["60"]),
+    ]);
+    assert_eq!(smart_groups::validate_rules(&valid), Ok(()));
+    assert!(smart_groups::uses_mail_score(&valid));
+}
+
+#[test]
+fn reads_rules_saved_by_the_frontend() {
+    let saved = r#"{"mode":"any","conditions":[{"field":"email_domain","op":"equals","values":["volt.example"]}]}"#;
+    let rules: SmartRules = serde_json::from_str(saved).unwrap();
+    assert_eq!(rules.mode, RuleMode::Any);
+    assert!(rules.exclude_ignored);
+    assert_eq!(rules.conditions[0].field, ContactRuleField::EmailDomain);
+}
+
+#[test]
+fn matches_fields_case_insensitively() {
+    let electric = rules(RuleMode::All, vec![condition(ContactRuleField::Company, ConditionOp::Contains, &["electric"])]);
+    assert_eq!(matching(&electric, None), ids(&["sparky", "volt"]));
+
+    // "Texas", "tx" and "TX" are the same state
+    let texas = rules(RuleMode::All, vec![condition(ContactRuleField::State, ConditionOp::Equals, &["TX"])]);
+    assert_eq!(matching(&texas, None), ids(&["sparky", "volt"]));
+    let not_texas = rules(RuleMode::All, vec![condition(ContactRuleField::State, ConditionOp::NotEquals, &["texas"])]);
+    assert_eq!(matching(&not_texas, None), ids(&["banker", "plumber"]));
+
+    // Any of the values, against any of the contact's emails
+    let domains = rules(RuleMode::All, vec![
+        condition(ContactRuleField::EmailDomain, ConditionOp::Equals, &["@gmail.com", "bank.example"]),
+    ]);
+    assert_eq!(matching(&domains, None), ids(&["sparky", "banker"]));
+
+    let phone = rules(RuleMode::All, vec![condition(ContactRuleField::Phone, ConditionOp::Equals, &["+1 512 555 0100"])]);
+    assert_eq!(matching(&phone, None), ids(&["sparky"]));
+
+    let name = rules(RuleMode::All, vec![condition(ContactRuleField::Name, ConditionOp::StartsWith, &["pat"])]);
+    assert_eq!(matching(&name, None), ids(&["plumber"]));
+
+    let no_address = rules(RuleMode::All, vec![condition(ContactRuleField::State, ConditionOp::IsEmpty, &[])]);
+    assert_eq!(matching(&no_address, None), ids(&["plumber"]));
+}
+
+#[test]
+fn combines_conditions_by_mode() {
+    let conditions = vec![
+        condition(ContactRuleField::State, ConditionOp::Equals, &["TX"]),
+        condition(ContactRuleField::Email, ConditionOp::NotContains, &["gmail"]),
+    ];
+    assert_eq!(matching(&rules(RuleMode::All, conditions.clone()), None), ids(&["volt"]));
+    assert_eq!(matching(&rules(RuleMode::Any, conditions), None), ids(&["sparky", "volt", "banker", "plumber"]));
+}
+
+#[test]
+fn compares_mail_scores_when_available() {
+    let active = rules(RuleMode::All, vec![condition(ContactRuleField::MailScore, ConditionOp::AtLeast, &["60"])]);
+    let scores: MailScores = [("joe@gmail.com".to_string(), 75), ("ana@volt.example".to_string(), 40)].into();
+
+    // The best score of the contact's emails counts
+    assert_eq!(matching(&active, Some(&scores)), ids(&["sparky"]));
+    // Without Mail.app the condition holds for no one
+    assert!(matching(&active, None).is_empty());
+}
+
+// ============ Membership ============
+
+#[test]
+fn applies_static_members_exclusions_and_ignored_contacts() {
+    let mut texas = rules(RuleMode::All, vec![condition(ContactRuleField::State, ConditionOp::Equals, &["TX"])]);
+    let lists = GroupLists {
+        members: ["plumber".to_string()].into(),
+        excluded: ["volt".to_string()].into(),
+        ignored: ["sparky".to_string()].into(),
+    };
+    assert_eq!(smart_groups::evaluate(&texas, &contacts(), &lists, None), ids(&["plumber"]));
+
+    texas.exclude_ignored = false;
+    assert_eq!(smart_groups::evaluate(&texas, &contacts(), &lists, None), ids(&["sparky", "plumber"]));
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn preview_warns_instead_of_failing_without_mail_app() {
+    let mixed = rules(
+        RuleMode::Any,
+        vec![
+            condition(ContactRuleField::MailScore, ConditionOp::AtLeast, &["60"]),
+            condition(ContactRuleField::Company, ConditionOp::Contains, &["bank"]),
+        ],
+    );
+    let preview = smart_groups::preview(&mixed, &contacts(), &GroupLists::default());
+    // The other conditions still match
+    assert_eq!(preview.contact_ids, ids(&["banker"]));
+    assert_eq!(
+        preview.warning.as_deref(),
+        Some("Mail score conditions skipped: Mail scores need Mail.app (macOS only)")
+    );
+
+    let banks = rules(RuleMode::All, vec![condition(ContactRuleField::Company, ConditionOp::Contains, &["bank"])]);
+    assert_eq!(smart_groups::preview(&banks, &contacts(), &GroupLists::default()).warning, None);
+}
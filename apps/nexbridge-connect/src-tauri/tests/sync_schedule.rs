@@ -0,0 +1,74 @@
+// ---------------------------------------------------------------------------
+// Integration tests for auto-sync timing and the low-battery skip
+// ---------------------------------------------------------------------------
+
+use chrono::{Duration as ChronoDuration, Utc};
+use nexbridge_connect_lib::sync_schedule::{self, MAX_JITTER};
+use std::collections::HashSet;
+use std::time::Duration;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+fn minutes_ago(minutes: i64) -> String {
+    (Utc::now() - ChronoDuration::minutes(minutes)).to_rfc3339()
+}
+
+// ============ Jitter ============
+
+#[test]
+fn spread_is_a_tenth_of_the_interval_up_to_five_minutes() {
+    assert_eq!(sync_schedule::jitter_spread(15 * MINUTE), 90 * Duration::from_secs(1));
+    assert_eq!(sync_schedule::jitter_spread(50 * MINUTE), 5 * MINUTE);
+    assert_eq!(sync_schedule::jitter_spread(24 * 60 * MINUTE), MAX_JITTER);
+}
+
+#[test]
+fn next_run_lands_within_the_jitter_of_the_interval() {
+    let interval = 60 * MINUTE;
+    let spread = sync_schedule::jitter_spread(interval);
+    let last = minutes_ago(20);
+    let now = Utc::now();
+
+    let delays: Vec<Duration> = (0..200)
+        .map(|_| sync_schedule::next_delay(Some(&last), interval, now))
+        .collect();
+    // 20 minutes of the hour have passed
+    let lowest = 40 * MINUTE - spread - Duration::from_secs(1);
+    let highest = 40 * MINUTE + spread;
+    for delay in &delays {
+        assert!((lowest..=highest).contains(delay), "{:?}", delay);
+    }
+    // Spread out, and on both sides of the interval
+    let distinct: HashSet<_> = delays.iter().collect();
+    assert!(distinct.len() > 100, "{} distinct delays", distinct.len());
+    assert!(delays.iter().any(|d| *d < 39 * MINUTE) && delays.iter().any(|d| *d > 41 * MINUTE));
+}
+
+#[test]
+fn never_synced_or_overdue_runs_are_due_now() {
+    let interval = 15 * MINUTE;
+    let now = Utc::now();
+    assert_eq!(sync_schedule::next_delay(None, interval, now), Duration::ZERO);
+    assert_eq!(sync_schedule::next_delay(Some("garbage"), interval, now), Duration::ZERO);
+    assert_eq!(sync_schedule::next_delay(Some(&minutes_ago(60)), interval, now), Duration::ZERO);
+
+    // A clock that moved backwards doesn't push the run past one interval
+    let future = (now + ChronoDuration::minutes(30)).to_rfc3339();
+    let delay = sync_schedule::next_delay(Some(&future), interval, now);
+    assert!(delay <= interval + sync_schedule::jitter_spread(interval));
+}
+
+// ============ Battery ============
+
+#[test]
+fn skips_only_on_battery_below_the_threshold() {
+    let reason = sync_schedule::battery_skip_reason(20, Some(12.4), false).unwrap();
+    assert_eq!(reason, "On battery at 12% (below 20%)");
+
+    // Charging, at or above the threshold, or no battery at all
+    assert_eq!(sync_schedule::battery_skip_reason(20, Some(12.0), true), None);
+    assert_eq!(sync_schedule::battery_skip_reason(20, Some(20.0), false), None);
+    assert_eq!(sync_schedule::battery_skip_reason(20, None, false), None);
+    // 0 never skips
+    assert_eq!(sync_schedule::battery_skip_reason(0, Some(1.0), false), None);
+}
@@ -0,0 +1,301 @@
+// ---------------------------------------------------------------------------
+// Integration tests for vcard parsing and writing
+// ---------------------------------------------------------------------------
+// Fixtures are written the way real exporters produce them (Apple Contacts
+// 3.0, Android 2.1 quoted-printable, RFC 6350 4.0), CRLF line endings and
+// all.
+// ---------------------------------------------------------------------------
+
+use nexbridge_connect_lib::contact_providers::{ContactChanges, ContactProvider, VCardDirectoryProvider};
+use nexbridge_connect_lib::contacts::Contact;
+use nexbridge_connect_lib::vcard::{self, Photo, VCard, VCardVersion};
+use std::collections::HashMap;
+
+mod common;
+
+fn crlf(lines: &[&str]) -> String {
+    lines.iter().map(|l| format!("{}\r\n", l)).collect()
+}
+
+// ============ Parsing ============
+
+#[test]
+fn parses_folded_lines_and_typed_values() {
+    let text = crlf(&[
+        "BEGIN:VCARD",
+        "VERSION:3.0",
+        "UID:4f1a",
+        "N:Nguyen;Anh;;;",
+        "FN:Anh Nguyen",
+        "ORG:Nguyen Build\\, LLC;Estimating",
+        "TITLE:Project ",
+        " Manager",
+        "item1.EMAIL;type=INTERNET;type=WORK:anh@nguyenbuild.com",
+        "EMAIL;TYPE=INTERNET,HOME,pref:anh@example.com",
+        "TEL;TYPE=CELL:(555) 010-2000",
+        "TEL;TYPE=WORK,VOICE,pref:+1 555 010 3000",
+        "ADR;TYPE=HOME:;;1 Elm St;Springfield;IL;62701;USA",
+        "ADR;TYPE=WORK,pref:;Suite 4;200 Main St\\nRear entrance;Chicago;IL;60601;USA",
+        "NOTE:Line one\\nLine two",
+        "END:VCARD",
+    ]);
+
+    let cards = vcard::parse(&text).unwrap();
+    assert_eq!(cards.len(), 1);
+    let card = &cards[0];
+    assert_eq!(card.title.as_deref(), Some("Project Manager"));
+    assert_eq!(card.organization.as_deref(), Some("Nguyen Build, LLC"));
+    assert_eq!(card.emails.len(), 2);
+    assert_eq!(card.emails[0].types, vec!["work"]);
+    assert_eq!(card.emails[1].pref, Some(1));
+    assert_eq!(card.phones[1].types, vec!["work", "voice"]);
+    assert_eq!(card.addresses.len(), 2);
+
+    let contact = card.to_contact();
+    assert_eq!(contact.id, "vcard:4f1a");
+    assert_eq!(contact.display_name.as_deref(), Some("Anh Nguyen"));
+    assert_eq!(contact.first_name.as_deref(), Some("Anh"));
+    assert_eq!(contact.last_name.as_deref(), Some("Nguyen"));
+    assert_eq!(contact.email.as_deref(), Some("anh@example.com"));
+    assert_eq!(contact.phone.as_deref(), Some("+1 555 010 3000"));
+    assert_eq!(contact.all_emails, vec!["anh@nguyenbuild.com", "anh@example.com"]);
+    assert_eq!(contact.street.as_deref(), Some("200 Main St\nRear entrance, Suite 4"));
+    assert_eq!(contact.city.as_deref(), Some("Chicago"));
+    assert_eq!(contact.zip.as_deref(), Some("60601"));
+}
+
+#[test]
+fn decodes_quoted_printable_with_soft_line_breaks() {
+    // Android 2.1 export: UTF-8 QP split across lines with "=" soft breaks
+    let text = crlf(&[
+        "BEGIN:VCARD",
+        "VERSION:2.1",
+        "N;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:M=C3=BCller;J=C3=BCrgen;;;",
+        "FN;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:J=C3=BCrgen M=C3=BC=",
+        "ller",
+        "ADR;HOME;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:;;Stra=C3=9Fe 1=0D=0A=",
+        "Hinterhaus;K=C3=B6ln;;50667;",
+        "TEL;CELL;PREF:+49 170 0000000",
+        "END:VCARD",
+        "BEGIN:VCARD",
+        "VERSION:2.1",
+        "FN;CHARSET=ISO-8859-1;ENCODING=QUOTED-PRINTABLE:Ren=E9e Faure",
+        "END:VCARD",
+    ]);
+
+    let cards = vcard::parse(&text).unwrap();
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards[0].formatted_name.as_deref(), Some("Jürgen Müller"));
+    assert_eq!(cards[0].family_name.as_deref(), Some("Müller"));
+    assert_eq!(cards[0].addresses[0].street.as_deref(), Some("Straße 1\r\nHinterhaus"));
+    assert_eq!(cards[0].addresses[0].locality.as_deref(), Some("Köln"));
+    assert_eq!(cards[0].phones[0].types, vec!["cell"]);
+    assert_eq!(cards[0].phones[0].pref, Some(1));
+    assert_eq!(cards[1].formatted_name.as_deref(), Some("Renée Faure"));
+}
+
+#[test]
+fn reads_photos_in_every_encoding() {
+    let text = crlf(&[
+        "BEGIN:VCARD",
+        "VERSION:3.0",
+        "FN:Three",
+        "PHOTO;ENCODING=b;TYPE=JPEG:/9j/4AAQ",
+        " SkZJRg==",
+        "END:VCARD",
+        "BEGIN:VCARD",
+        "VERSION:4.0",
+        "FN:Four",
+        "PHOTO:data:image/png;base64,iVBORw0K",
+        " GgoAAAA=",
+        "END:VCARD",
+        "BEGIN:VCARD",
+        "VERSION:2.1",
+        "FN:Two",
+        "PHOTO;PNG;ENCODING=BASE64:",
+        "  iVBORw0KGgoAAAA=",
+        "",
+        "TEL:555-0100",
+        "END:VCARD",
+        "BEGIN:VCARD",
+        "VERSION:4.0",
+        "FN:Linked",
+        "PHOTO:https://example.com/me.jpg",
+        "END:VCARD",
+    ]);
+
+    let cards = vcard::parse(&text).unwrap();
+    assert_eq!(cards.len(), 4);
+    match &cards[0].photo {
+        Some(Photo::Inline { media_type, data }) => {
+            assert_eq!(media_type.as_deref(), Some("image/jpeg"));
+            assert_eq!(&data[..4], &[0xff, 0xd8, 0xff, 0xe0]);
+            assert_eq!(&data[6..10], b"JFIF");
+        }
+        other => panic!("unexpected photo {:?}", other),
+    }
+    match &cards[1].photo {
+        Some(Photo::Inline { media_type, data }) => {
+            assert_eq!(media_type.as_deref(), Some("image/png"));
+            assert_eq!(&data[1..4], b"PNG");
+        }
+        other => panic!("unexpected photo {:?}", other),
+    }
+    assert!(matches!(&cards[2].photo, Some(Photo::Inline { data, .. }) if &data[1..4] == b"PNG"));
+    assert_eq!(cards[2].phones[0].value, "555-0100");
+    assert_eq!(cards[3].photo, Some(Photo::Uri("https://example.com/me.jpg".to_string())));
+}
+
+#[test]
+fn rejects_unterminated_cards() {
+    let err = vcard::parse("BEGIN:VCARD\nVERSION:3.0\nFN:Half\n").unwrap_err();
+    assert!(err.contains("END:VCARD"), "{}", err);
+}
+
+// ============ Writing ============
+
+fn sample_contact() -> Contact {
+    Contact {
+        id: "device-42".to_string(),
+        display_name: Some("Dana O'Neil, PE".to_string()),
+        first_name: Some("Dana".to_string()),
+        last_name: Some("O'Neil".to_string()),
+        email: Some("dana@work.example".to_string()),
+        phone: Some("+1 555 010 4000".to_string()),
+        all_emails: vec!["dana@home.example".to_string(), "dana@work.example".to_string()],
+        all_phones: vec!["+1 555 010 4000".to_string()],
+        street: Some("12 Harbor Rd; Unit 3".to_string()),
+        city: Some("Portland".to_string()),
+        state: Some("ME".to_string()),
+        zip: Some("04101".to_string()),
+        country: None,
+        company: Some("Harbor Structural".to_string()),
+        job_title: Some("Engineer of Record — Structural Steel & Concrete Retrofits".to_string()),
+    }
+}
+
+#[test]
+fn writes_and_reads_back_both_versions() {
+    let categories = vec!["Clients".to_string(), "Site, North".to_string()];
+    let card = VCard::from_contact(&sample_contact(), categories.clone());
+
+    for version in [VCardVersion::V3, VCardVersion::V4] {
+        let text = vcard::write(std::slice::from_ref(&card), version);
+        assert!(text.starts_with("BEGIN:VCARD\r\nVERSION:"));
+        for line in text.split("\r\n") {
+            assert!(line.len() <= 75, "line over 75 octets: {:?}", line);
+        }
+
+        let parsed = vcard::parse(&text).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].categories, categories);
+
+        let contact = parsed[0].to_contact();
+        let original = sample_contact();
+        assert_eq!(contact.id, "vcard:device-42");
+        assert_eq!(contact.display_name, original.display_name);
+        assert_eq!(contact.email, original.email);
+        assert_eq!(contact.all_emails, vec!["dana@work.example", "dana@home.example"]);
+        assert_eq!(contact.street, original.street);
+        assert_eq!(contact.zip, original.zip);
+        assert_eq!(contact.job_title, original.job_title);
+    }
+}
+
+#[test]
+fn marks_preferred_values_per_version() {
+    let card = VCard::from_contact(&sample_contact(), vec![]);
+    let v3 = vcard::write(std::slice::from_ref(&card), VCardVersion::V3);
+    let v4 = vcard::write(std::slice::from_ref(&card), VCardVersion::V4);
+
+    assert!(v3.contains("EMAIL;TYPE=internet,pref:dana@work.example\r\n"), "{}", v3);
+    assert!(v3.contains("N:O'Neil;Dana;;;\r\n"));
+    assert!(v4.contains("EMAIL;PREF=1:dana@work.example\r\n"), "{}", v4);
+    assert!(v4.contains("EMAIL:dana@home.example\r\n"));
+    assert!(v4.contains("ADR:;;12 Harbor Rd\\; Unit 3;Portland;ME;04101;\r\n"));
+}
+
+#[test]
+fn round_trips_photos() {
+    let card = VCard {
+        formatted_name: Some("Pic".to_string()),
+        photo: Some(Photo::Inline {
+            media_type: Some("image/png".to_string()),
+            data: (0..=255u8).collect(),
+        }),
+        ..Default::default()
+    };
+    for version in [VCardVersion::V3, VCardVersion::V4] {
+        let text = vcard::write(std::slice::from_ref(&card), version);
+        let parsed = vcard::parse(&text).unwrap();
+        assert_eq!(parsed[0].photo, card.photo, "{:?}", version);
+    }
+}
+
+// ============ Imported files and folders ============
+
+fn card(uid: &str, name: &str) -> String {
+    crlf(&["BEGIN:VCARD", "VERSION:3.0", &format!("UID:{}", uid), &format!("FN:{}", name), "END:VCARD"])
+}
+
+#[test]
+fn imports_files_under_unique_names() {
+    let dir = common::temp_dir("import");
+    let source = dir.join("team.vcf");
+    std::fs::write(&source, card("a1", "Ada") + &card("b2", "Bo")).unwrap();
+
+    let imports = dir.join("vcards");
+    let (first, count) = vcard::import_file(&source, &imports).unwrap();
+    let (second, _) = vcard::import_file(&source, &imports).unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(first.file_name().unwrap(), "team.vcf");
+    assert_eq!(second.file_name().unwrap(), "team (2).vcf");
+
+    std::fs::write(dir.join("empty.vcf"), "").unwrap();
+    assert!(vcard::import_file(&dir.join("empty.vcf"), &imports).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn folder_provider_reports_only_changed_files() {
+    let dir = common::temp_dir("folder");
+    std::fs::create_dir_all(dir.join("work")).unwrap();
+    std::fs::write(dir.join("family.vcf"), card("a1", "Ada") + &card("b2", "Bo")).unwrap();
+    std::fs::write(dir.join("work/team.vcf"), card("c3", "Cy")).unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a vCard").unwrap();
+    let provider = VCardDirectoryProvider::new("folder", dir.path());
+    let mut known: HashMap<String, Option<String>> = HashMap::new();
+    let apply = |known: &mut HashMap<String, Option<String>>, changes: &ContactChanges| {
+        for key in &changes.removed {
+            known.remove(key);
+        }
+        for upsert in &changes.upserts {
+            known.insert(upsert.key.clone(), upsert.etag.clone());
+        }
+    };
+
+    let first = provider.fetch(None, &known).await.unwrap();
+    let mut keys: Vec<&str> = first.upserts.iter().map(|u| u.key.as_str()).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["family.vcf#0", "family.vcf#1", "work/team.vcf#0"]);
+    assert_eq!(first.upserts[0].contact.id, "vcard:a1");
+    apply(&mut known, &first);
+
+    // Untouched folder: same token, nothing to do
+    let same = provider.fetch(first.sync_token.as_deref(), &known).await.unwrap();
+    assert!(same.upserts.is_empty() && same.removed.is_empty());
+    assert_eq!(same.sync_token, first.sync_token);
+
+    // One file edited, one deleted, one half-written
+    std::fs::write(dir.join("work/team.vcf"), card("c3", "Cy Young")).unwrap();
+    std::fs::remove_file(dir.join("family.vcf")).unwrap();
+    std::fs::write(dir.join("new.vcf"), "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Partial\r\n").unwrap();
+    let changes = provider.fetch(same.sync_token.as_deref(), &known).await.unwrap();
+    assert_eq!(changes.upserts.len(), 1);
+    assert_eq!(changes.upserts[0].key, "work/team.vcf#0");
+    assert_eq!(changes.upserts[0].contact.display_name.as_deref(), Some("Cy Young"));
+    let mut removed = changes.removed.clone();
+    removed.sort();
+    assert_eq!(removed, vec!["family.vcf#0", "family.vcf#1"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
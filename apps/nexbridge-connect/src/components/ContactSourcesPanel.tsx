@@ -0,0 +1,153 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { open } from "@tauri-apps/plugin-dialog";
+
+type ContactSourceConfig =
+  | { kind: "carddav"; id: string; name: string; url: string; username: string }
+  | { kind: "vcard_directory"; id: string; name: string; path: string };
+
+type ContactSource = ContactSourceConfig & {
+  contact_count: number;
+  last_synced_at: string | null;
+  error: string | null;
+};
+
+const inputClass =
+  "w-full px-3 py-2 border border-slate-300 rounded-lg text-sm focus:outline-none focus:ring-2 focus:ring-nexus-500";
+
+/** CardDAV accounts and vCard folders synced alongside the device contacts. */
+export function ContactSourcesPanel() {
+  const [sources, setSources] = useState<ContactSource[]>([]);
+  const [adding, setAdding] = useState(false);
+  const [name, setName] = useState("");
+  const [url, setUrl] = useState("");
+  const [username, setUsername] = useState("");
+  const [password, setPassword] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+
+  const load = () => invoke<ContactSource[]>("list_contact_providers").then(setSources).catch(console.error);
+
+  useEffect(() => {
+    load();
+  }, []);
+
+  const add = async (config: ContactSourceConfig, secret?: string) => {
+    setBusy(true);
+    setError(null);
+    try {
+      await invoke<ContactSource>("add_contact_provider", { config, password: secret ?? null });
+      setAdding(false);
+      setName("");
+      setUrl("");
+      setUsername("");
+      setPassword("");
+      await load();
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const addFolder = async () => {
+    const path = await open({ directory: true, multiple: false });
+    if (typeof path !== "string") return;
+    const folderName = path.split(/[\\/]/).filter(Boolean).pop() ?? path;
+    await add({ kind: "vcard_directory", id: "", name: folderName, path });
+  };
+
+  const remove = async (source: ContactSource) => {
+    if (!window.confirm(`Remove "${source.name}"? Its contacts are removed from Nexus on the next sync.`)) return;
+    try {
+      await invoke("remove_contact_provider", { id: source.id });
+      await load();
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  return (
+    <div className="bg-white rounded-xl p-4 shadow-sm border border-slate-200 space-y-4">
+      <h3 className="font-semibold text-slate-900">Contact Sources</h3>
+
+      <ul className="space-y-2">
+        <li className="text-sm text-slate-600">This computer's contacts</li>
+        {sources.map((source) => (
+          <li key={source.id} className="flex items-center justify-between gap-2">
+            <div className="min-w-0">
+              <p className="font-medium text-slate-800 truncate">{source.name}</p>
+              <p className="text-sm text-slate-500 truncate">
+                {source.kind === "carddav" ? source.url : source.path} · {source.contact_count} contacts
+              </p>
+              {source.error && <p className="text-sm text-amber-600">{source.error}</p>}
+            </div>
+            <button
+              type="button"
+              onClick={() => remove(source)}
+              className="px-3 py-1 rounded-lg border border-slate-300 text-slate-700 text-sm hover:bg-slate-50"
+            >
+              Remove
+            </button>
+          </li>
+        ))}
+      </ul>
+
+      {adding ? (
+        <div className="space-y-2">
+          <input placeholder="Name" value={name} onChange={(e) => setName(e.target.value)} className={inputClass} />
+          <input
+            placeholder="Server URL (e.g. https://contacts.icloud.com)"
+            value={url}
+            onChange={(e) => setUrl(e.target.value)}
+            className={inputClass}
+          />
+          <input placeholder="Username" value={username} onChange={(e) => setUsername(e.target.value)} className={inputClass} />
+          <input
+            type="password"
+            placeholder="App password"
+            value={password}
+            onChange={(e) => setPassword(e.target.value)}
+            className={inputClass}
+          />
+          <div className="flex gap-2">
+            <button
+              type="button"
+              onClick={() => add({ kind: "carddav", id: "", name: name || url, url, username }, password)}
+              disabled={busy || !url || !username || !password}
+              className="px-3 py-2 rounded-lg bg-nexus-600 text-white text-sm font-medium disabled:opacity-50"
+            >
+              {busy ? "Connecting…" : "Connect"}
+            </button>
+            <button
+              type="button"
+              onClick={() => setAdding(false)}
+              className="px-3 py-2 rounded-lg border border-slate-300 text-slate-700 text-sm"
+            >
+              Cancel
+            </button>
+          </div>
+        </div>
+      ) : (
+        <div className="flex gap-2">
+          <button
+            type="button"
+            onClick={() => setAdding(true)}
+            className="px-3 py-2 rounded-lg border border-slate-300 text-slate-700 text-sm font-medium hover:bg-slate-50"
+          >
+            Add CardDAV account
+          </button>
+          <button
+            type="button"
+            onClick={addFolder}
+            disabled={busy}
+            className="px-3 py-2 rounded-lg border border-slate-300 text-slate-700 text-sm font-medium hover:bg-slate-50 disabled:opacity-50"
+          >
+            Add vCard folder
+          </button>
+        </div>
+      )}
+      {error && <p className="text-sm text-red-600">{error}</p>}
+    </div>
+  );
+}
@@ -0,0 +1,128 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { clearAuth, clearCachedCredentials } from "../lib/auth";
+
+interface EncryptionStatus {
+  unlocked: boolean;
+  key_source: "keychain" | "passphrase" | null;
+  passphrase_set: boolean;
+  error: string | null;
+}
+
+interface WipeResult {
+  files_removed: number;
+  bytes_overwritten: number;
+  key_removed: boolean;
+  errors: string[];
+}
+
+/** Encryption status, passphrase unlock (Linux without a keyring) and the offboarding wipe. */
+export function LocalDataPanel() {
+  const [status, setStatus] = useState<EncryptionStatus | null>(null);
+  const [passphrase, setPassphrase] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [wiped, setWiped] = useState<WipeResult | null>(null);
+
+  useEffect(() => {
+    invoke<EncryptionStatus>("get_encryption_status").then(setStatus).catch(console.error);
+  }, []);
+
+  const unlock = async () => {
+    setBusy(true);
+    setError(null);
+    try {
+      setStatus(await invoke<EncryptionStatus>("unlock_local_data", { passphrase }));
+      setPassphrase("");
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const wipe = async () => {
+    const confirmed = window.confirm(
+      "Delete all local documents, contact groups and cached conversions from this computer? This cannot be undone.",
+    );
+    if (!confirmed) return;
+    setBusy(true);
+    setError(null);
+    try {
+      // Sign out first so the saved session is wiped along with everything else
+      await clearAuth().catch(() => {});
+      clearCachedCredentials();
+      setWiped(await invoke<WipeResult>("wipe_local_data"));
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  if (!status) return null;
+
+  return (
+    <div className="bg-white rounded-xl p-4 shadow-sm border border-slate-200 space-y-4">
+      <h3 className="font-semibold text-slate-900">Local Data</h3>
+
+      <div className="bg-slate-50 rounded-lg p-3 text-sm text-slate-600">
+        {status.unlocked
+          ? status.key_source === "passphrase"
+            ? "Encrypted with your passphrase."
+            : "Encrypted with a key stored in the system keychain."
+          : status.error ?? "Locked. Enter your passphrase to open local documents and contact groups."}
+      </div>
+
+      {!status.unlocked && status.key_source === "passphrase" && (
+        <div className="space-y-2">
+          <label className="block text-sm font-medium text-slate-700">
+            {status.passphrase_set ? "Passphrase" : "Choose a passphrase (at least 10 characters)"}
+          </label>
+          <div className="flex gap-2">
+            <input
+              type="password"
+              value={passphrase}
+              onChange={(e) => setPassphrase(e.target.value)}
+              onKeyDown={(e) => e.key === "Enter" && passphrase && unlock()}
+              className="flex-1 px-3 py-2 border border-slate-300 rounded-lg text-sm focus:outline-none focus:ring-2 focus:ring-nexus-500"
+            />
+            <button
+              type="button"
+              onClick={unlock}
+              disabled={busy || !passphrase}
+              className="px-3 py-2 rounded-lg bg-nexus-600 text-white text-sm font-medium disabled:opacity-50"
+            >
+              Unlock
+            </button>
+          </div>
+        </div>
+      )}
+
+      <div className="flex items-center justify-between">
+        <div>
+          <p className="font-medium text-slate-800">Wipe local data</p>
+          <p className="text-sm text-slate-500">Securely delete everything this app stores on this computer</p>
+        </div>
+        <button
+          type="button"
+          onClick={wipe}
+          disabled={busy || wiped !== null}
+          className="px-3 py-2 rounded-lg border border-red-300 text-red-700 text-sm font-medium hover:bg-red-50 disabled:opacity-50"
+        >
+          Wipe
+        </button>
+      </div>
+
+      {wiped && (
+        <p className="text-sm text-slate-600">
+          Removed {wiped.files_removed} files. Quit and restart the app to sign in again.
+          {wiped.errors.length > 0 && (
+            <span className="block text-amber-600 mt-1">{wiped.errors.length} items could not be removed.</span>
+          )}
+        </p>
+      )}
+      {error && <p className="text-sm text-red-600">{error}</p>}
+    </div>
+  );
+}
@@ -0,0 +1,131 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { TransferLimits } from "../hooks/useSettings";
+
+interface TransferStatus {
+  blocked_reason: string | null;
+  metered: boolean | null;
+}
+
+interface TransferLimitsPanelProps {
+  limits: TransferLimits;
+  onChange: (limits: TransferLimits) => Promise<void>;
+}
+
+const RATE_OPTIONS: { label: string; value: number | null }[] = [
+  { label: "Unlimited", value: null },
+  { label: "256 KB/s", value: 256 },
+  { label: "512 KB/s", value: 512 },
+  { label: "1 MB/s", value: 1024 },
+  { label: "2 MB/s", value: 2048 },
+  { label: "5 MB/s", value: 5120 },
+  { label: "10 MB/s", value: 10240 },
+];
+
+/** Bandwidth caps, transfer window and metered pause for background uploads/downloads. */
+export function TransferLimitsPanel({ limits, onChange }: TransferLimitsPanelProps) {
+  const [status, setStatus] = useState<TransferStatus | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const transferWindow = limits.windows[0] ?? null;
+
+  useEffect(() => {
+    invoke<TransferStatus>("get_transfer_status").then(setStatus).catch(console.error);
+  }, [limits]);
+
+  const update = async (patch: Partial<TransferLimits>) => {
+    setError(null);
+    try {
+      await onChange({ ...limits, ...patch });
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const rateSelect = (value: number | null, onSelect: (v: number | null) => void) => (
+    <select
+      value={value ?? ""}
+      onChange={(e) => onSelect(e.target.value === "" ? null : Number(e.target.value))}
+      className="px-3 py-2 border border-slate-300 rounded-lg text-sm focus:outline-none focus:ring-2 focus:ring-nexus-500"
+    >
+      {RATE_OPTIONS.map((o) => (
+        <option key={o.label} value={o.value ?? ""}>
+          {o.label}
+        </option>
+      ))}
+    </select>
+  );
+
+  return (
+    <div className="bg-white rounded-xl p-4 shadow-sm border border-slate-200 space-y-4">
+      <h3 className="font-semibold text-slate-900">Transfers</h3>
+
+      <div className="flex items-center justify-between">
+        <p className="text-sm text-slate-700">Upload limit</p>
+        {rateSelect(limits.uploadLimitKbps, (v) => update({ uploadLimitKbps: v }))}
+      </div>
+      <div className="flex items-center justify-between">
+        <p className="text-sm text-slate-700">Download limit</p>
+        {rateSelect(limits.downloadLimitKbps, (v) => update({ downloadLimitKbps: v }))}
+      </div>
+
+      {/* Transfer window */}
+      <div className="space-y-2">
+        <div className="flex items-center justify-between">
+          <div>
+            <p className="text-sm text-slate-700">Only transfer during set hours</p>
+            <p className="text-xs text-slate-500">Transfers outside the window wait until it opens</p>
+          </div>
+          <input
+            type="checkbox"
+            checked={transferWindow !== null}
+            onChange={(e) =>
+              update({ windows: e.target.checked ? [{ start: "18:00", end: "07:00" }] : [] })
+            }
+            className="w-4 h-4"
+          />
+        </div>
+        {transferWindow && (
+          <div className="flex items-center gap-2 pl-4 border-l-2 border-nexus-100 text-sm text-slate-700">
+            <input
+              type="time"
+              value={transferWindow.start}
+              onChange={(e) => update({ windows: [{ ...transferWindow, start: e.target.value }] })}
+              className="px-2 py-1 border border-slate-300 rounded-lg text-sm"
+            />
+            <span>to</span>
+            <input
+              type="time"
+              value={transferWindow.end}
+              onChange={(e) => update({ windows: [{ ...transferWindow, end: e.target.value }] })}
+              className="px-2 py-1 border border-slate-300 rounded-lg text-sm"
+            />
+          </div>
+        )}
+      </div>
+
+      <div className="flex items-center justify-between">
+        <div>
+          <p className="text-sm text-slate-700">Pause on metered connections</p>
+          <p className="text-xs text-slate-500">
+            {status?.metered === true
+              ? "This connection is metered"
+              : status?.metered === false
+                ? "This connection is not metered"
+                : "Not reported by this system"}
+          </p>
+        </div>
+        <input
+          type="checkbox"
+          checked={limits.pauseOnMetered}
+          onChange={(e) => update({ pauseOnMetered: e.target.checked })}
+          className="w-4 h-4"
+        />
+      </div>
+
+      {status?.blocked_reason && (
+        <p className="text-sm text-amber-700 bg-amber-50 rounded-lg p-3">{status.blocked_reason}</p>
+      )}
+      {error && <p className="text-sm text-red-600">{error}</p>}
+    </div>
+  );
+}
@@ -0,0 +1,181 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+type ContactField = "first_name" | "last_name" | "display_name" | "email" | "phone" | "state" | "zip";
+
+interface ContactNormalization {
+  contact_id: string;
+  name: string;
+  changes: { field: ContactField; before: string; after: string }[];
+  issues: { field: ContactField; value: string; message: string }[];
+  writable: boolean;
+}
+
+const FIELD_LABELS: Record<ContactField, string> = {
+  first_name: "First name",
+  last_name: "Last name",
+  display_name: "Name",
+  email: "Email",
+  phone: "Phone",
+  state: "State",
+  zip: "ZIP",
+};
+
+const REGIONS = ["US", "CA", "MX", "GB", "IE", "FR", "DE", "NL", "IT", "ES", "AU", "NZ", "IN"];
+
+interface ContactCleanupModalProps {
+  /** Contacts to check; all of them when null */
+  contactIds: string[] | null;
+  onClose: () => void;
+  onApplied: () => void;
+}
+
+/** Preview of the phone/address/name clean-ups (contact_normalizer.rs) before writing them back. */
+export function ContactCleanupModal({ contactIds, onClose, onApplied }: ContactCleanupModalProps) {
+  // Fixed for the life of the dialog, even if the selection changes behind it
+  const [ids] = useState(contactIds);
+  const [region, setRegion] = useState("US");
+  const [results, setResults] = useState<ContactNormalization[] | null>(null);
+  const [checked, setChecked] = useState<Set<string>>(new Set());
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [applied, setApplied] = useState<{ updated: number; read_only: number } | null>(null);
+
+  useEffect(() => {
+    setResults(null);
+    invoke<ContactNormalization[]>("preview_contact_normalization", {
+      contactIds: ids,
+      options: { default_region: region },
+    })
+      .then((preview) => {
+        setResults(preview);
+        setChecked(new Set(preview.filter((r) => r.writable && r.changes.length > 0).map((r) => r.contact_id)));
+      })
+      .catch((err) => setError(String(err)));
+  }, [ids, region]);
+
+  const toggle = (id: string) => {
+    setChecked((prev) => {
+      const next = new Set(prev);
+      if (next.has(id)) next.delete(id);
+      else next.add(id);
+      return next;
+    });
+  };
+
+  const apply = async () => {
+    setBusy(true);
+    setError(null);
+    try {
+      setApplied(
+        await invoke<{ updated: number; read_only: number }>("apply_contact_normalization", {
+          contactIds: Array.from(checked),
+          options: { default_region: region },
+        }),
+      );
+      onApplied();
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const anyWritable = results?.some((r) => r.writable) ?? false;
+
+  return (
+    <div className="fixed inset-0 bg-black/50 flex items-center justify-center z-50 p-4">
+      <div className="bg-white rounded-xl shadow-xl max-w-2xl w-full max-h-[80vh] flex flex-col">
+        <div className="px-6 py-4 border-b border-slate-200 flex items-start justify-between gap-4">
+          <div>
+            <h2 className="text-lg font-semibold text-slate-900">Clean Up Contacts</h2>
+            <p className="text-sm text-slate-500 mt-1">
+              Phone numbers, states, ZIP codes, names and emails in a consistent format.
+            </p>
+          </div>
+          <label className="text-sm text-slate-600 shrink-0">
+            Region{" "}
+            <select
+              value={region}
+              onChange={(e) => setRegion(e.target.value)}
+              className="ml-1 px-2 py-1 border border-slate-300 rounded-lg text-sm"
+            >
+              {REGIONS.map((r) => (
+                <option key={r} value={r}>
+                  {r}
+                </option>
+              ))}
+            </select>
+          </label>
+        </div>
+
+        <div className="flex-1 overflow-y-auto px-6 py-4 space-y-3">
+          {results === null && !error && <p className="text-sm text-slate-500">Checking contacts…</p>}
+          {results?.length === 0 && <p className="text-sm text-slate-500">Everything already looks tidy.</p>}
+          {results?.map((result) => (
+            <div key={result.contact_id} className="bg-slate-50 rounded-lg p-3">
+              <label className="flex items-center gap-2 font-medium text-slate-900">
+                <input
+                  type="checkbox"
+                  checked={checked.has(result.contact_id)}
+                  disabled={!result.writable || result.changes.length === 0}
+                  onChange={() => toggle(result.contact_id)}
+                  className="text-nexus-600 focus:ring-nexus-500"
+                />
+                {result.name}
+                {!result.writable && <span className="text-xs font-normal text-slate-400">(read-only source)</span>}
+              </label>
+              <ul className="mt-2 space-y-1 text-sm">
+                {result.changes.map((c, i) => (
+                  <li key={`c${i}`} className="text-slate-700">
+                    <span className="text-slate-500">{FIELD_LABELS[c.field]}:</span>{" "}
+                    <span className="line-through text-slate-400">{c.before}</span> → {c.after}
+                  </li>
+                ))}
+                {result.issues.map((issue, i) => (
+                  <li key={`i${i}`} className="text-amber-700">
+                    <span className="text-slate-500">{FIELD_LABELS[issue.field]}:</span> {issue.value} — {issue.message}
+                  </li>
+                ))}
+              </ul>
+            </div>
+          ))}
+        </div>
+
+        <div className="px-6 py-4 border-t border-slate-200 space-y-2">
+          {results && results.length > 0 && !anyWritable && (
+            <p className="text-sm text-slate-500">
+              These contacts can't be changed from here; edit them where they come from.
+            </p>
+          )}
+          {applied && (
+            <p className="text-sm text-green-700">
+              Updated {applied.updated} contacts
+              {applied.read_only > 0 && ` (${applied.read_only} from read-only sources were skipped)`}.
+            </p>
+          )}
+          {error && <p className="text-sm text-red-600">{error}</p>}
+          <div className="flex gap-2 justify-end">
+            <button
+              type="button"
+              onClick={onClose}
+              className="px-4 py-2 text-slate-600 hover:bg-slate-100 rounded-lg"
+            >
+              {applied ? "Done" : "Cancel"}
+            </button>
+            {!applied && (
+              <button
+                type="button"
+                onClick={apply}
+                disabled={busy || checked.size === 0}
+                className="px-4 py-2 bg-nexus-600 text-white rounded-lg hover:bg-nexus-700 disabled:opacity-50"
+              >
+                {busy ? "Applying…" : `Apply to ${checked.size} contacts`}
+              </button>
+            )}
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -1,13 +1,16 @@
 import { useState, useEffect, useMemo, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { ContactCard } from "./ContactCard";
 import { ContactReviewModal } from "./ContactReviewModal";
+import { ContactCleanupModal } from "./ContactCleanupModal";
+import { SmartGroupModal, type SmartRules } from "./SmartGroupModal";
 import { SyncStatus } from "./SyncStatus";
 import { SettingsPanel } from "../SettingsPanel";
 import { EnvironmentSelector } from "../EnvironmentSelector";
 import { useSettings } from "../../hooks/useSettings";
 import { useAutoSync } from "../../hooks/useAutoSync";
-import { importContacts, listContacts, type ImportContactInput } from "../../lib/api";
+import { listContacts } from "../../lib/api";
 
 interface DeviceContact {
   id: string;
@@ -35,6 +38,27 @@ interface ContactGroup {
   description: string | null;
   color: string | null;
   created_at: string;
+  /** Set for smart groups */
+  rules: SmartRules | null;
+}
+
+/** Result of the `get_contact_group_members` command */
+interface GroupMembers {
+  group_id: string;
+  contact_ids: string[];
+  warning: string | null;
+}
+
+/** Result of the `sync_contacts` command */
+interface ContactSyncSummary {
+  created: number;
+  updated: number;
+  deleted: number;
+  unchanged: number;
+  failed: number;
+  ignored: number;
+  /** Left out: no email or phone */
+  skipped: number;
 }
 
 type SyncState = "idle" | "loading" | "syncing" | "success" | "error";
@@ -49,6 +73,7 @@ export function ContactList() {
   const [error, setError] = useState<string | null>(null);
   const [syncResult, setSyncResult] = useState<{ created: number; updated: number } | null>(null);
   const [showReviewModal, setShowReviewModal] = useState(false);
+  const [showCleanupModal, setShowCleanupModal] = useState(false);
   const [primaryOverrides, setPrimaryOverrides] = useState<Map<string, { email: string | null; phone: string | null }>>(new Map());
   
   // Advanced filters
@@ -62,6 +87,9 @@ export function ContactList() {
   const [contactGroupMap, setContactGroupMap] = useState<Map<string, string[]>>(new Map());
   const [showGroupModal, setShowGroupModal] = useState(false);
   const [newGroupName, setNewGroupName] = useState("");
+  const [groupWarnings, setGroupWarnings] = useState<Map<string, string>>(new Map());
+  // Smart group being edited; `group: null` creates one
+  const [smartGroupModal, setSmartGroupModal] = useState<{ group: ContactGroup | null } | null>(null);
   
   // Ignored contacts
   const [ignoredContacts, setIgnoredContacts] = useState<Set<string>>(new Set());
@@ -81,13 +109,13 @@ export function ContactList() {
   // Auto-sync callback handlers
   const handleAutoSyncComplete = useCallback(
     async (result: { created: number; updated: number }) => {
+      // The scheduler has already recorded lastSyncAt
       setSyncResult(result);
       setSyncState("success");
-      await recordSync();
       await loadSyncedContacts();
       setTimeout(() => setSyncState("idle"), 3000);
     },
-    [recordSync]
+    []
   );
 
   const handleAutoSyncError = useCallback((err: string) => {
@@ -97,9 +125,6 @@ export function ContactList() {
 
   // Auto-sync hook
   useAutoSync({
-    enabled: settings.autoSyncEnabled,
-    intervalMinutes: settings.syncIntervalMinutes,
-    selectedContactIds: settings.selectedContactIds,
     onSyncComplete: handleAutoSyncComplete,
     onSyncError: handleAutoSyncError,
   });
@@ -124,16 +149,19 @@ export function ContactList() {
       const groupList = await invoke<ContactGroup[]>("list_contact_groups");
       setGroups(groupList);
       
-      // Load contact-group memberships
+      // Load contact-group memberships; smart groups are worked out against the current contacts
+      const members = await invoke<GroupMembers[]>("get_contact_group_members");
       const memberMap = new Map<string, string[]>();
-      for (const group of groupList) {
-        const contactIds = await invoke<string[]>("get_contacts_in_group", { groupId: group.id });
-        for (const contactId of contactIds) {
+      const warnings = new Map<string, string>();
+      for (const group of members) {
+        for (const contactId of group.contact_ids) {
           const existing = memberMap.get(contactId) || [];
-          memberMap.set(contactId, [...existing, group.id]);
+          memberMap.set(contactId, [...existing, group.group_id]);
         }
+        if (group.warning) warnings.set(group.group_id, group.warning);
       }
       setContactGroupMap(memberMap);
+      setGroupWarnings(warnings);
     } catch (err) {
       console.error("Failed to load groups:", err);
     }
@@ -155,6 +183,8 @@ export function ContactList() {
       const result = await invoke<DeviceContact[]>("get_contacts");
       setContacts(result || []);
       setSyncState("idle");
+      // Smart group membership follows the contacts just loaded
+      await loadGroups();
     } catch (err) {
       console.error("Failed to load contacts:", err);
       const errorMsg = typeof err === "string" ? err : (err as Error)?.message || "Failed to load contacts";
@@ -163,6 +193,39 @@ export function ContactList() {
     }
   };
 
+  // vCard files become an extra contact source (see vcard.rs)
+  const handleImportVcard = async () => {
+    try {
+      const selected = await open({
+        multiple: false,
+        title: "Import contacts from vCard",
+        filters: [{ name: "vCard", extensions: ["vcf", "vcard"] }],
+      });
+      if (selected && typeof selected === "string") {
+        await invoke<number>("import_vcard_file", { path: selected });
+        await loadContacts();
+      }
+    } catch (err) {
+      setError(String(err || "Failed to import vCard"));
+    }
+  };
+
+  const handleExportVcard = async (target: { groupId: string } | { contactIds: string[] }) => {
+    try {
+      const groupName = "groupId" in target ? groups.find((g) => g.id === target.groupId)?.name : null;
+      const path = await save({
+        title: "Export contacts as vCard",
+        defaultPath: `${groupName || "contacts"}.vcf`,
+        filters: [{ name: "vCard", extensions: ["vcf"] }],
+      });
+      if (path) {
+        await invoke<number>("export_contacts_vcard", { path, ...target });
+      }
+    } catch (err) {
+      setError(String(err || "Failed to export vCard"));
+    }
+  };
+
   const loadSyncedContacts = async () => {
     try {
       const synced = await listContacts(undefined, 200);
@@ -338,28 +401,13 @@ export function ContactList() {
     setSyncResult(null);
 
     try {
-      const toSync = contacts.filter((c) => selectedIds.has(c.id));
-      const platform = navigator.platform.toLowerCase().includes("mac")
-        ? "MACOS"
-        : "WINDOWS";
-
-      const payload: ImportContactInput[] = toSync.map((c) => {
-        // Use overridden primary email/phone if available
-        const override = overrides.get(c.id);
-        return {
-          displayName: c.displayName,
-          firstName: c.firstName,
-          lastName: c.lastName,
-          email: override?.email ?? c.email,
-          phone: override?.phone ?? c.phone,
-          allEmails: c.allEmails?.length ? c.allEmails : null,
-          allPhones: c.allPhones?.length ? c.allPhones : null,
-          source: platform as "MACOS" | "WINDOWS",
-        };
+      // Rust sends only what changed since the last sync and remembers the
+      // chosen primary email/phone for later (auto) syncs
+      const result = await invoke<ContactSyncSummary>("sync_contacts", {
+        contactIds: Array.from(selectedIds),
+        primaryOverrides: Object.fromEntries(overrides),
       });
-
-      const result = await importContacts(payload);
-      setSyncResult({ created: result.createdCount, updated: result.updatedCount });
+      setSyncResult({ created: result.created, updated: result.updated });
       setSyncState("success");
       
       // Clear overrides after successful sync
@@ -379,7 +427,8 @@ export function ContactList() {
       // Reset to idle after 3 seconds
       setTimeout(() => setSyncState("idle"), 3000);
     } catch (err) {
-      setError(err instanceof Error ? err.message : "Sync failed");
+      // Rust commands reject with a plain string
+      setError(err instanceof Error ? err.message : String(err || "Sync failed"));
       setSyncState("error");
     }
   };
@@ -430,6 +479,22 @@ export function ContactList() {
               />
             </svg>
           </button>
+          <button
+            type="button"
+            onClick={handleImportVcard}
+            className="px-3 py-2 text-sm text-slate-600 hover:text-slate-800 hover:bg-slate-100 rounded-lg"
+            title="Import contacts from a .vcf file"
+          >
+            Import vCard
+          </button>
+          <button
+            type="button"
+            onClick={() => setShowCleanupModal(true)}
+            className="px-3 py-2 text-sm text-slate-600 hover:text-slate-800 hover:bg-slate-100 rounded-lg"
+            title="Format phone numbers, states, ZIP codes and names consistently"
+          >
+            Clean up
+          </button>
         </div>
 
         <div className="flex items-center justify-between text-sm">
@@ -496,8 +561,28 @@ export function ContactList() {
             className="px-2 py-1 text-xs border border-slate-300 rounded bg-white"
           >
             <option value="">All Groups</option>
-            {groups.map(g => <option key={g.id} value={g.id}>{g.name}</option>)}
+            {groups.map(g => <option key={g.id} value={g.id}>{g.name}{g.rules ? " (smart)" : ""}</option>)}
           </select>
+          {groupFilter ? (
+            <button
+              type="button"
+              onClick={() => setSmartGroupModal({ group: groups.find(g => g.id === groupFilter) ?? null })}
+              className="px-2 py-1 text-xs text-nexus-600 hover:text-nexus-700"
+            >
+              {groups.find(g => g.id === groupFilter)?.rules ? "Edit rules" : "Make smart"}
+            </button>
+          ) : (
+            <button
+              type="button"
+              onClick={() => setSmartGroupModal({ group: null })}
+              className="px-2 py-1 text-xs text-nexus-600 hover:text-nexus-700"
+            >
+              + Smart group
+            </button>
+          )}
+          {groupFilter && groupWarnings.has(groupFilter) && (
+            <span className="text-xs text-amber-700">{groupWarnings.get(groupFilter)}</span>
+          )}
           {(stateFilter || cityFilter || companyFilter || groupFilter) && (
             <button
               type="button"
@@ -512,6 +597,15 @@ export function ContactList() {
               Clear filters
             </button>
           )}
+          {groupFilter && (
+            <button
+              type="button"
+              onClick={() => handleExportVcard({ groupId: groupFilter })}
+              className="px-2 py-1 text-xs text-nexus-600 hover:text-nexus-700"
+            >
+              Export group (.vcf)
+            </button>
+          )}
           <label className="flex items-center gap-1 text-xs text-slate-500 ml-auto">
             <input
               type="checkbox"
@@ -611,6 +705,7 @@ export function ContactList() {
                 try {
                   await invoke("ignore_contacts", { contactIds: Array.from(selectedIds) });
                   await loadIgnoredContacts();
+                  await loadGroups();
                   setSelectedIds(new Set());
                 } catch (err) {
                   console.error("Failed to ignore contacts:", err);
@@ -620,6 +715,13 @@ export function ContactList() {
             >
               ✕ Remove from Sync
             </button>
+            <button
+              type="button"
+              onClick={() => handleExportVcard({ contactIds: Array.from(selectedIds) })}
+              className="px-3 py-2 bg-slate-100 text-slate-700 rounded-lg text-sm hover:bg-slate-200"
+            >
+              Export .vcf
+            </button>
             {showIgnored && (
               <button
                 type="button"
@@ -627,6 +729,7 @@ export function ContactList() {
                   try {
                     await invoke("unignore_contacts", { contactIds: Array.from(selectedIds) });
                     await loadIgnoredContacts();
+                    await loadGroups();
                     setSelectedIds(new Set());
                   } catch (err) {
                     console.error("Failed to unignore contacts:", err);
@@ -670,6 +773,27 @@ export function ContactList() {
             >
               + New Group
             </button>
+            {groupFilter && (
+              <button
+                type="button"
+                onClick={async () => {
+                  try {
+                    await invoke("remove_contacts_from_group", {
+                      contactIds: Array.from(selectedIds),
+                      groupId: groupFilter,
+                    });
+                    await loadGroups();
+                    setSelectedIds(new Set());
+                  } catch (err) {
+                    console.error("Failed to remove from group:", err);
+                  }
+                }}
+                className="px-3 py-2 bg-slate-100 text-slate-700 rounded-lg text-sm hover:bg-slate-200"
+                title="Smart groups keep these contacts out even while they match"
+              >
+                Remove from group
+              </button>
+            )}
           </div>
           
           <button
@@ -700,6 +824,24 @@ export function ContactList() {
         />
       )}
       
+      {/* Clean-up Modal */}
+      {showCleanupModal && (
+        <ContactCleanupModal
+          contactIds={selectedIds.size > 0 ? Array.from(selectedIds) : null}
+          onClose={() => setShowCleanupModal(false)}
+          onApplied={loadContacts}
+        />
+      )}
+
+      {/* Smart Group Modal */}
+      {smartGroupModal && (
+        <SmartGroupModal
+          group={smartGroupModal.group}
+          onClose={() => setSmartGroupModal(null)}
+          onSaved={loadGroups}
+        />
+      )}
+
       {/* Create Group Modal */}
       {showGroupModal && (
         <div className="fixed inset-0 bg-black/50 flex items-center justify-center z-50">
@@ -733,6 +875,7 @@ export function ContactList() {
                         name: newGroupName.trim(),
                         description: null,
                         color: null,
+                        rules: null,
                       });
                       // Add selected contacts to the new group
                       if (selectedIds.size > 0) {
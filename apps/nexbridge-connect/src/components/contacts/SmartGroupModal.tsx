@@ -0,0 +1,308 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+type RuleField =
+  | "name"
+  | "company"
+  | "job_title"
+  | "email"
+  | "email_domain"
+  | "phone"
+  | "city"
+  | "state"
+  | "zip"
+  | "country"
+  | "mail_score";
+
+type RuleOp =
+  | "contains"
+  | "not_contains"
+  | "equals"
+  | "not_equals"
+  | "starts_with"
+  | "is_empty"
+  | "is_not_empty"
+  | "at_least";
+
+interface SmartCondition {
+  field: RuleField;
+  op: RuleOp;
+  values: string[];
+}
+
+/** Rules of a smart group (smart_groups.rs) */
+export interface SmartRules {
+  mode: "all" | "any";
+  conditions: SmartCondition[];
+  exclude_ignored: boolean;
+}
+
+const FIELD_LABELS: Record<RuleField, string> = {
+  name: "Name",
+  company: "Company",
+  job_title: "Job title",
+  email: "Email",
+  email_domain: "Email domain",
+  phone: "Phone",
+  city: "City",
+  state: "State",
+  zip: "ZIP",
+  country: "Country",
+  mail_score: "Mail score (Mail.app)",
+};
+
+const OP_LABELS: Record<RuleOp, string> = {
+  contains: "contains",
+  not_contains: "doesn't contain",
+  equals: "is",
+  not_equals: "is not",
+  starts_with: "starts with",
+  is_empty: "is empty",
+  is_not_empty: "is not empty",
+  at_least: "is at least",
+};
+
+const TEXT_OPS: RuleOp[] = ["contains", "not_contains", "equals", "not_equals", "starts_with", "is_empty", "is_not_empty"];
+
+const opsFor = (field: RuleField): RuleOp[] => (field === "mail_score" ? ["at_least"] : TEXT_OPS);
+
+const needsValue = (op: RuleOp) => op !== "is_empty" && op !== "is_not_empty";
+
+const emptyCondition = (): SmartCondition => ({ field: "company", op: "contains", values: [] });
+
+/** Result of the `preview_smart_group` command */
+interface SmartGroupPreview {
+  contact_ids: string[];
+  warning: string | null;
+}
+
+interface SmartGroupModalProps {
+  /** Group whose rules are edited; a new smart group is created when null */
+  group: { id: string; name: string; rules: SmartRules | null } | null;
+  onClose: () => void;
+  onSaved: () => void;
+}
+
+/** Builds the rules of a smart group, with a live count of the contacts they match. */
+export function SmartGroupModal({ group, onClose, onSaved }: SmartGroupModalProps) {
+  const [name, setName] = useState(group?.name ?? "");
+  const [rules, setRules] = useState<SmartRules>(
+    group?.rules ?? { mode: "all", conditions: [emptyCondition()], exclude_ignored: true },
+  );
+  // Values as typed; split on commas when the rules are sent
+  const [texts, setTexts] = useState<string[]>(rules.conditions.map((c) => c.values.join(", ")));
+  const [matchCount, setMatchCount] = useState<number | null>(null);
+  const [previewError, setPreviewError] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const withValues = (): SmartRules => ({
+    ...rules,
+    conditions: rules.conditions.map((c, i) => ({
+      ...c,
+      values: needsValue(c.op)
+        ? (texts[i] ?? "")
+            .split(",")
+            .map((v) => v.trim())
+            .filter(Boolean)
+        : [],
+    })),
+  });
+
+  // Live preview, a moment after the last edit
+  useEffect(() => {
+    const timer = setTimeout(() => {
+      invoke<SmartGroupPreview>("preview_smart_group", { rules: withValues() })
+        .then((preview) => {
+          setMatchCount(preview.contact_ids.length);
+          setPreviewError(preview.warning);
+        })
+        .catch((err) => {
+          setMatchCount(null);
+          setPreviewError(String(err));
+        });
+    }, 300);
+    return () => clearTimeout(timer);
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [rules, texts]);
+
+  const updateCondition = (index: number, change: Partial<SmartCondition>) => {
+    setRules((prev) => ({
+      ...prev,
+      conditions: prev.conditions.map((c, i) => {
+        if (i !== index) return c;
+        const next = { ...c, ...change };
+        if (!opsFor(next.field).includes(next.op)) next.op = opsFor(next.field)[0];
+        return next;
+      }),
+    }));
+  };
+
+  const addCondition = () => {
+    setRules((prev) => ({ ...prev, conditions: [...prev.conditions, emptyCondition()] }));
+    setTexts((prev) => [...prev, ""]);
+  };
+
+  const removeCondition = (index: number) => {
+    setRules((prev) => ({ ...prev, conditions: prev.conditions.filter((_, i) => i !== index) }));
+    setTexts((prev) => prev.filter((_, i) => i !== index));
+  };
+
+  const save = async () => {
+    setBusy(true);
+    setError(null);
+    try {
+      if (group) {
+        await invoke("set_contact_group_rules", { groupId: group.id, rules: withValues() });
+      } else {
+        await invoke("create_contact_group", {
+          name: name.trim(),
+          description: null,
+          color: null,
+          rules: withValues(),
+        });
+      }
+      onSaved();
+      onClose();
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div className="fixed inset-0 bg-black/50 flex items-center justify-center z-50 p-4">
+      <div className="bg-white rounded-xl shadow-xl max-w-2xl w-full max-h-[80vh] flex flex-col">
+        <div className="px-6 py-4 border-b border-slate-200">
+          <h2 className="text-lg font-semibold text-slate-900">{group ? `Rules for ${group.name}` : "New Smart Group"}</h2>
+          <p className="text-sm text-slate-500 mt-1">
+            Contacts join and leave as they start or stop matching. Contacts you add or remove by hand stay that way.
+          </p>
+        </div>
+
+        <div className="flex-1 overflow-y-auto px-6 py-4 space-y-3">
+          {!group && (
+            <input
+              type="text"
+              value={name}
+              onChange={(e) => setName(e.target.value)}
+              placeholder="Group name"
+              className="w-full px-3 py-2 border border-slate-300 rounded-lg"
+              autoFocus
+            />
+          )}
+          <p className="text-sm text-slate-700">
+            Contacts matching{" "}
+            <select
+              value={rules.mode}
+              onChange={(e) => setRules((prev) => ({ ...prev, mode: e.target.value as SmartRules["mode"] }))}
+              className="px-2 py-1 border border-slate-300 rounded-lg text-sm"
+            >
+              <option value="all">all</option>
+              <option value="any">any</option>
+            </select>{" "}
+            of these conditions:
+          </p>
+          {rules.conditions.map((condition, i) => (
+            <div key={i} className="flex gap-2 items-center">
+              <select
+                value={condition.field}
+                onChange={(e) => updateCondition(i, { field: e.target.value as RuleField })}
+                className="px-2 py-1 border border-slate-300 rounded-lg text-sm bg-white"
+              >
+                {(Object.keys(FIELD_LABELS) as RuleField[]).map((f) => (
+                  <option key={f} value={f}>
+                    {FIELD_LABELS[f]}
+                  </option>
+                ))}
+              </select>
+              <select
+                value={condition.op}
+                onChange={(e) => updateCondition(i, { op: e.target.value as RuleOp })}
+                className="px-2 py-1 border border-slate-300 rounded-lg text-sm bg-white"
+              >
+                {opsFor(condition.field).map((op) => (
+                  <option key={op} value={op}>
+                    {OP_LABELS[op]}
+                  </option>
+                ))}
+              </select>
+              {needsValue(condition.op) && (
+                <input
+                  type={condition.field === "mail_score" ? "number" : "text"}
+                  value={texts[i] ?? ""}
+                  onChange={(e) => setTexts((prev) => prev.map((t, j) => (j === i ? e.target.value : t)))}
+                  placeholder={condition.field === "mail_score" ? "0–100" : "Value, or several separated by commas"}
+                  className="flex-1 min-w-0 px-2 py-1 border border-slate-300 rounded-lg text-sm"
+                />
+              )}
+              <button
+                type="button"
+                onClick={() => removeCondition(i)}
+                disabled={rules.conditions.length === 1}
+                className="px-2 py-1 text-slate-400 hover:text-red-600 disabled:opacity-30"
+                title="Remove condition"
+              >
+                ✕
+              </button>
+            </div>
+          ))}
+          <button type="button" onClick={addCondition} className="text-sm text-nexus-600 hover:text-nexus-700">
+            + Add condition
+          </button>
+          <label className="flex items-center gap-2 text-sm text-slate-600">
+            <input
+              type="checkbox"
+              checked={rules.exclude_ignored}
+              onChange={(e) => setRules((prev) => ({ ...prev, exclude_ignored: e.target.checked }))}
+              className="text-nexus-600 focus:ring-nexus-500"
+            />
+            Leave out contacts removed from sync
+          </label>
+        </div>
+
+        <div className="px-6 py-4 border-t border-slate-200 space-y-2">
+          {matchCount !== null && (
+            <p className="text-sm text-slate-600">
+              Matches {matchCount} contact{matchCount === 1 ? "" : "s"} right now.
+            </p>
+          )}
+          {previewError && <p className="text-sm text-amber-700">{previewError}</p>}
+          {error && <p className="text-sm text-red-600">{error}</p>}
+          <div className="flex gap-2 justify-end">
+            {group?.rules && (
+              <button
+                type="button"
+                onClick={async () => {
+                  try {
+                    await invoke("set_contact_group_rules", { groupId: group.id, rules: null });
+                    onSaved();
+                    onClose();
+                  } catch (err) {
+                    setError(String(err));
+                  }
+                }}
+                className="px-4 py-2 text-slate-600 hover:bg-slate-100 rounded-lg mr-auto"
+                title="Keep only the contacts added by hand"
+              >
+                Make static
+              </button>
+            )}
+            <button type="button" onClick={onClose} className="px-4 py-2 text-slate-600 hover:bg-slate-100 rounded-lg">
+              Cancel
+            </button>
+            <button
+              type="button"
+              onClick={save}
+              disabled={busy || (!group && !name.trim())}
+              className="px-4 py-2 bg-nexus-600 text-white rounded-lg hover:bg-nexus-700 disabled:opacity-50"
+            >
+              {busy ? "Saving…" : group ? "Save rules" : "Create group"}
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -3,7 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
 import { PreviewPanel } from "./PreviewPanel";
 import { UploadQueue } from "./UploadQueue";
-import { getCachedToken, getCachedApiUrl } from "../../lib/auth";
+import { getCachedToken } from "../../lib/auth";
 
 interface IndexedDocument {
   id: string;
@@ -39,6 +39,8 @@ interface UploadResult {
   document_id: string;
   nexus_doc_id: string | null;
   error: string | null;
+  original_sha256: string | null;
+  original_error: string | null;
 }
 
 export function DocumentsTab() {
@@ -52,6 +54,7 @@ export function DocumentsTab() {
   const [search, setSearch] = useState("");
   const [previewDoc, setPreviewDoc] = useState<IndexedDocument | null>(null);
   const [isUploading, setIsUploading] = useState(false);
+  const [includeOriginals, setIncludeOriginals] = useState(false);
   const [bulkProgress, setBulkProgress] = useState<{
     total: number;
     converted: number;
@@ -130,7 +133,6 @@ export function DocumentsTab() {
     metadata: { title: string; category: string }
   ) => {
     const token = getCachedToken();
-    const apiUrl = getCachedApiUrl();
 
     if (!token) {
       setError("Not authenticated. Please sign in first.");
@@ -155,8 +157,6 @@ export function DocumentsTab() {
       const folderName = folderPath.split('/').pop() || 'Local Upload Files';
 
       const result = await invoke<UploadResult>("upload_document", {
-        apiUrl,
-        token,
         documentId: docId,
         htmlContent: html,
         title: metadata.title,
@@ -165,6 +165,7 @@ export function DocumentsTab() {
         wordCount,
         folderName,
         breadcrumb: doc?.breadcrumb || [],
+        includeOriginal: includeOriginals,
       });
 
       if (result.success) {
@@ -250,7 +251,6 @@ export function DocumentsTab() {
   /** Step 2: Bulk upload previously-converted documents to the API */
   const handleBulkUpload = async (ids: string[]) => {
     const token = getCachedToken();
-    const apiUrl = getCachedApiUrl();
     if (!token) {
       setError("Not authenticated. Please sign in first.");
       return;
@@ -281,8 +281,6 @@ export function DocumentsTab() {
         const wordCount = textContent ? textContent.split(' ').length : 0;
 
         const result = await invoke<UploadResult>("upload_document", {
-          apiUrl,
-          token,
           documentId: doc.id,
           htmlContent: cachedHtml,
           title: doc.file_name.replace(/\.[^/.]+$/, ''),
@@ -291,8 +289,12 @@ export function DocumentsTab() {
           wordCount,
           folderName,
           breadcrumb: doc.breadcrumb,
+          includeOriginal: includeOriginals,
         });
 
+        if (result.original_error) {
+          console.warn(`Original file not attached for ${doc.file_name}:`, result.original_error);
+        }
         if (result.success) {
           await invoke("update_document_status", { id: doc.id, status: "UPLOADED", errorMessage: null });
           succeeded++;
@@ -787,6 +789,16 @@ export function DocumentsTab() {
           {(() => {
             const uploadable = documents.filter(d => selectedIds.has(d.id) && d.status === 'CONVERTED');
             return uploadable.length > 0 ? (
+              <>
+              <label className="flex items-center gap-1 text-xs text-amber-800">
+                <input
+                  type="checkbox"
+                  checked={includeOriginals}
+                  onChange={(e) => setIncludeOriginals(e.target.checked)}
+                  className="w-3.5 h-3.5"
+                />
+                Include original files
+              </label>
               <button
                 type="button"
                 onClick={() => handleBulkUpload(Array.from(selectedIds))}
@@ -802,6 +814,7 @@ export function DocumentsTab() {
                   <>↑ Upload ({uploadable.length})</>
                 )}
               </button>
+              </>
             ) : null;
           })()}
           <button
@@ -1038,8 +1051,8 @@ export function DocumentsTab() {
           onIgnoreFolder={async (folderPath) => {
             console.log("[DEBUG] Ignoring folder:", folderPath);
             try {
-              const count = await invoke<number>("ignore_folder", { folderPath });
-              console.log(`[DEBUG] Ignored ${count} documents in folder`);
+              const result = await invoke<{ count: number }>("ignore_folder", { folderPath });
+              console.log(`[DEBUG] Ignored ${result.count} documents in folder`);
               await loadDocuments();
               await loadStats();
             } catch (err) {
@@ -1051,8 +1064,8 @@ export function DocumentsTab() {
           onImportFolder={async (folderPath) => {
             console.log("[DEBUG] Importing folder:", folderPath);
             try {
-              const count = await invoke<number>("import_folder", { folderPath });
-              console.log(`[DEBUG] Imported ${count} documents in folder`);
+              const result = await invoke<{ count: number }>("import_folder", { folderPath });
+              console.log(`[DEBUG] Imported ${result.count} documents in folder`);
               await loadDocuments();
               await loadStats();
             } catch (err) {
@@ -7,6 +7,8 @@ interface UploadProgress {
   failed: number;
   current_file: string | null;
   is_paused: boolean;
+  waiting_reason: string | null;
+  upload_limit_kbps: number | null;
 }
 
 interface UploadQueueProps {
@@ -84,7 +86,13 @@ export function UploadQueue({ isUploading, onComplete }: UploadQueueProps) {
             <div className="w-4 h-4 border-2 border-nexus-600 border-t-transparent rounded-full animate-spin" />
           )}
           <span className="font-medium text-slate-900">
-            {isComplete ? "Upload Complete" : progress.is_paused ? "Upload Paused" : "Uploading Documents..."}
+            {isComplete
+              ? "Upload Complete"
+              : progress.is_paused
+                ? "Upload Paused"
+                : progress.waiting_reason
+                  ? "Upload Waiting"
+                  : "Uploading Documents..."}
           </span>
         </div>
         
@@ -137,6 +145,15 @@ export function UploadQueue({ isUploading, onComplete }: UploadQueueProps) {
         <span className="text-slate-500 ml-auto">{percent}%</span>
       </div>
 
+      {progress.waiting_reason && !isComplete && (
+        <div className="text-xs text-amber-700">{progress.waiting_reason}</div>
+      )}
+      {progress.upload_limit_kbps && !isComplete && (
+        <div className="text-xs text-slate-400">
+          Limited to {progress.upload_limit_kbps >= 1024 ? `${progress.upload_limit_kbps / 1024} MB/s` : `${progress.upload_limit_kbps} KB/s`}
+        </div>
+      )}
+
       {progress.current_file && !isComplete && (
         <div className="text-xs text-slate-400 truncate">
           Current: {progress.current_file}
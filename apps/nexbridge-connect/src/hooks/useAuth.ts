@@ -2,6 +2,7 @@ import { useState, useEffect, useCallback, useRef } from "react";
 import { loadAuth, clearAuth, clearCachedCredentials } from "../lib/auth";
 import {
   setApiConfig,
+  listenForApiSessionRefresh,
   setAppVersion,
   setDeviceId,
   getLicenseStatus,
@@ -17,6 +18,7 @@ import {
 } from "../lib/api";
 import { getOrCreateDeviceId, getDeviceName, getDevicePlatform } from "../lib/device";
 import { getVersion } from "@tauri-apps/api/app";
+import { invoke } from "@tauri-apps/api/core";
 import { meshClient, type MeshStatus } from "../lib/mesh-client";
 import { meshJobRunner } from "../lib/mesh-job-runner";
 import { receiptOcrProcessor } from "../lib/processors/receipt-ocr";
@@ -124,6 +126,16 @@ export function useAuth() {
     };
   }, [state.authenticated]);
 
+  /** Point the local indexes at this company/user's partition (before any tab loads them). */
+  const activateAccount = useCallback(async (companyId?: string, userId?: string, companyName?: string | null) => {
+    if (!companyId || !userId) return;
+    try {
+      await invoke("switch_account", { companyId, userId, companyName: companyName ?? null });
+    } catch (err) {
+      console.warn("[auth] switch_account failed:", err);
+    }
+  }, []);
+
   /** Post-login: register device, check entitlements, connect mesh. */
   const postLoginSetup = useCallback(async (appVer: string, opts?: { userId?: string; companyId?: string; apiUrl?: string; globalRole?: string }) => {
     // --- Device registration ---
@@ -192,6 +204,14 @@ export function useAuth() {
     }
   }, []);
 
+  // Keep the saved session current when the Rust client refreshes tokens
+  useEffect(() => {
+    const unlisten = listenForApiSessionRefresh();
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Restore session on mount (with timeout so app never hangs)
   useEffect(() => {
     const timeout = setTimeout(() => {
@@ -205,7 +225,7 @@ export function useAuth() {
 
         const stored = await loadAuth();
         if (stored) {
-          setApiConfig(stored.apiUrl, stored.accessToken);
+          setApiConfig(stored.apiUrl, stored.accessToken, stored.refreshToken);
           // Decode JWT to get userId and companyId for mesh connection
           let userId: string | undefined;
           let companyId: string | undefined;
@@ -216,6 +236,7 @@ export function useAuth() {
             companyId = payload.companyId;
             globalRole = payload.globalRole;
           } catch { /* token decode failed — mesh will skip */ }
+          await activateAccount(companyId, userId, stored.companyName);
           setState((s) => ({
             ...s,
             loading: false,
@@ -237,12 +258,13 @@ export function useAuth() {
     })();
 
     return () => clearTimeout(timeout);
-  }, [postLoginSetup]);
+  }, [postLoginSetup, activateAccount]);
 
   const login = useCallback(
     async (apiUrl: string, email: string, password: string): Promise<LoginResponse> => {
       const data = await apiLogin(apiUrl, email, password);
       const appVer = await getVersion().catch(() => "1.0.0");
+      await activateAccount(data.company.id, data.user.id, data.company.name);
       setState((s) => ({
         ...s,
         loading: false,
@@ -266,7 +288,7 @@ export function useAuth() {
       });
       return data;
     },
-    [postLoginSetup],
+    [postLoginSetup, activateAccount],
   );
 
   const logout = useCallback(async () => {
@@ -1,118 +1,62 @@
 import { useEffect, useRef, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { importContacts, type ImportContactInput } from "../lib/api";
-import { getCachedToken } from "../lib/auth";
-
-interface DeviceContact {
-  id: string;
-  display_name: string | null;
-  first_name: string | null;
-  last_name: string | null;
-  email: string | null;
-  phone: string | null;
+import { listen } from "@tauri-apps/api/event";
+
+/** One run of the Rust auto-sync scheduler (see scheduler.rs). */
+export interface SyncRun {
+  started_at: string;
+  finished_at: string;
+  trigger: "scheduled" | "manual";
+  outcome: "completed" | "skipped" | "failed";
+  skipped_reason: string | null;
+  contacts_created: number;
+  contacts_updated: number;
+  contacts_deleted: number;
+  folders_rescanned: number;
+  documents_new: number;
+  documents_updated: number;
+  uploads_completed: number;
+  uploads_failed: number;
+  errors: string[];
 }
 
 interface UseAutoSyncOptions {
-  enabled: boolean;
-  intervalMinutes: number;
-  selectedContactIds: string[];
   onSyncComplete?: (result: { created: number; updated: number }) => void;
   onSyncError?: (error: string) => void;
 }
 
-export function useAutoSync({
-  enabled,
-  intervalMinutes,
-  selectedContactIds,
-  onSyncComplete,
-  onSyncError,
-}: UseAutoSyncOptions) {
-  const intervalRef = useRef<number | null>(null);
-  const isSyncingRef = useRef(false);
-
-  const performSync = useCallback(async () => {
-    // Skip if already syncing, no selection, or no auth
-    if (isSyncingRef.current || selectedContactIds.length === 0 || !getCachedToken()) {
-      return;
-    }
-
-    isSyncingRef.current = true;
-
-    try {
-      // Get contacts from device
-      const allContacts = await invoke<DeviceContact[]>("get_contacts");
+/**
+ * Follows the background scheduler, which runs on the Rust side at the
+ * interval in SyncSettings (and updates lastSyncAt itself).
+ */
+export function useAutoSync({ onSyncComplete, onSyncError }: UseAutoSyncOptions) {
+  // Keep the latest callbacks without re-subscribing on every render
+  const callbacksRef = useRef({ onSyncComplete, onSyncError });
+  callbacksRef.current = { onSyncComplete, onSyncError };
 
-      // Filter to selected ones
-      const toSync = allContacts.filter((c) =>
-        selectedContactIds.includes(c.id)
-      );
-
-      if (toSync.length === 0) {
-        isSyncingRef.current = false;
-        return;
+  useEffect(() => {
+    const unlisten = listen<SyncRun>("auto-sync-completed", ({ payload: run }) => {
+      const { onSyncComplete, onSyncError } = callbacksRef.current;
+      if (run.outcome === "skipped") return;
+      if (run.errors.length > 0) {
+        onSyncError?.(run.errors.join("\n"));
       }
-
-      // Determine platform
-      const platform = navigator.platform.toLowerCase().includes("mac")
-        ? "MACOS"
-        : "WINDOWS";
-
-      // Build payload
-      const payload: ImportContactInput[] = toSync.map((c) => ({
-        displayName: c.display_name,
-        firstName: c.first_name,
-        lastName: c.last_name,
-        email: c.email,
-        phone: c.phone,
-        source: platform as "MACOS" | "WINDOWS",
-      }));
-
-      // Sync to server
-      const result = await importContacts(payload);
-
       onSyncComplete?.({
-        created: result.createdCount,
-        updated: result.updatedCount,
+        created: run.contacts_created,
+        updated: run.contacts_updated,
       });
-    } catch (err) {
-      const message = err instanceof Error ? err.message : "Auto-sync failed";
-      onSyncError?.(message);
-    } finally {
-      isSyncingRef.current = false;
-    }
-  }, [selectedContactIds, onSyncComplete, onSyncError]);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
-  // Set up interval when enabled
-  useEffect(() => {
-    if (enabled && intervalMinutes > 0) {
-      // Perform initial sync after a short delay
-      const initialTimeout = setTimeout(() => {
-        performSync();
-      }, 5000);
-
-      // Set up recurring interval
-      intervalRef.current = window.setInterval(
-        () => {
-          performSync();
-        },
-        intervalMinutes * 60 * 1000
-      );
+  const performSync = useCallback(() => invoke<SyncRun>("run_sync_now"), []);
 
-      return () => {
-        clearTimeout(initialTimeout);
-        if (intervalRef.current) {
-          clearInterval(intervalRef.current);
-          intervalRef.current = null;
-        }
-      };
-    } else {
-      // Clear interval when disabled
-      if (intervalRef.current) {
-        clearInterval(intervalRef.current);
-        intervalRef.current = null;
-      }
-    }
-  }, [enabled, intervalMinutes, performSync]);
+  const getSyncHistory = useCallback(
+    (limit?: number) => invoke<SyncRun[]>("get_sync_history", { limit }),
+    []
+  );
 
-  return { performSync };
+  return { performSync, getSyncHistory };
 }
@@ -1,6 +1,18 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { load, Store } from "@tauri-apps/plugin-store";
+import { listen } from "@tauri-apps/api/event";
+
+export interface TransferWindow {
+  start: string; // "HH:MM"
+  end: string;
+}
+
+export interface TransferLimits {
+  uploadLimitKbps: number | null;
+  downloadLimitKbps: number | null;
+  windows: TransferWindow[];
+  pauseOnMetered: boolean;
+}
 
 export interface SyncSettings {
   autoSyncEnabled: boolean;
@@ -8,46 +20,106 @@ export interface SyncSettings {
   selectedContactIds: string[];
   launchAtStartup: boolean;
   lastSyncAt: string | null;
+  /** Auto-sync skips runs on battery below this percentage (0 = never) */
+  minBatteryPct: number;
+  transferLimits: TransferLimits;
+  /** Size budget for the converted-document cache, in bytes */
+  cacheBudgetBytes: number;
 }
 
+const DEFAULT_TRANSFER_LIMITS: TransferLimits = {
+  uploadLimitKbps: null,
+  downloadLimitKbps: null,
+  windows: [],
+  pauseOnMetered: true,
+};
+
 const DEFAULT_SETTINGS: SyncSettings = {
   autoSyncEnabled: false,
   syncIntervalMinutes: 15,
   selectedContactIds: [],
   launchAtStartup: false,
   lastSyncAt: null,
+  minBatteryPct: 20,
+  transferLimits: DEFAULT_TRANSFER_LIMITS,
+  cacheBudgetBytes: 2 * 1024 * 1024 * 1024,
 };
 
-let store: Store | null = null;
+interface RustSyncSettings {
+  auto_sync_enabled: boolean;
+  sync_interval_minutes: number;
+  selected_contact_ids: string[];
+  launch_at_startup: boolean;
+  last_sync_at: string | null;
+  min_battery_pct: number;
+  transfer_limits: {
+    upload_limit_kbps: number | null;
+    download_limit_kbps: number | null;
+    windows: TransferWindow[];
+    pause_on_metered: boolean;
+  };
+  cache_budget_bytes: number;
+}
 
-async function getStore(): Promise<Store> {
-  if (!store) {
-    store = await load("settings.json", {
-      autoSave: true,
-      defaults: {
-        syncSettings: DEFAULT_SETTINGS,
-      },
-    });
-  }
-  return store;
+/** Shape expected by the `update_sync_settings` command. */
+function toRustSettings(settings: SyncSettings): RustSyncSettings {
+  const limits = settings.transferLimits ?? DEFAULT_TRANSFER_LIMITS;
+  return {
+    auto_sync_enabled: settings.autoSyncEnabled,
+    sync_interval_minutes: settings.syncIntervalMinutes,
+    selected_contact_ids: settings.selectedContactIds,
+    launch_at_startup: settings.launchAtStartup,
+    last_sync_at: settings.lastSyncAt,
+    min_battery_pct: settings.minBatteryPct,
+    transfer_limits: {
+      upload_limit_kbps: limits.uploadLimitKbps,
+      download_limit_kbps: limits.downloadLimitKbps,
+      windows: limits.windows,
+      pause_on_metered: limits.pauseOnMetered,
+    },
+    cache_budget_bytes: settings.cacheBudgetBytes ?? DEFAULT_SETTINGS.cacheBudgetBytes,
+  };
+}
+
+function fromRustSettings(settings: RustSyncSettings): SyncSettings {
+  const limits = settings.transfer_limits;
+  return {
+    autoSyncEnabled: settings.auto_sync_enabled,
+    syncIntervalMinutes: settings.sync_interval_minutes,
+    selectedContactIds: settings.selected_contact_ids,
+    launchAtStartup: settings.launch_at_startup,
+    lastSyncAt: settings.last_sync_at,
+    minBatteryPct: settings.min_battery_pct,
+    transferLimits: {
+      uploadLimitKbps: limits.upload_limit_kbps,
+      downloadLimitKbps: limits.download_limit_kbps,
+      windows: limits.windows,
+      pauseOnMetered: limits.pause_on_metered,
+    },
+    cacheBudgetBytes: settings.cache_budget_bytes,
+  };
 }
 
 export function useSettings() {
   const [settings, setSettings] = useState<SyncSettings>(DEFAULT_SETTINGS);
   const [isLoading, setIsLoading] = useState(true);
 
-  // Load settings on mount
+  // Load settings on mount, then follow changes from any window or service
   useEffect(() => {
     loadSettings();
+    const unlisten = listen<RustSyncSettings>("settings-changed", ({ payload }) => {
+      setSettings(fromRustSettings(payload));
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
   }, []);
 
   const loadSettings = async () => {
     try {
-      const s = await getStore();
-      const saved = await s.get<SyncSettings>("syncSettings");
-      if (saved) {
-        setSettings(saved);
-      }
+      // Settings are stored (and validated) on the Rust side
+      const saved = await invoke<RustSyncSettings>("get_sync_settings");
+      setSettings(fromRustSettings(saved));
     } catch (err) {
       console.error("Failed to load settings:", err);
     } finally {
@@ -57,22 +129,13 @@ export function useSettings() {
 
   const saveSettings = useCallback(async (newSettings: SyncSettings) => {
     try {
-      const s = await getStore();
-      await s.set("syncSettings", newSettings);
-      setSettings(newSettings);
-
-      // Also update Tauri state
-      await invoke("update_sync_settings", {
-        settings: {
-          auto_sync_enabled: newSettings.autoSyncEnabled,
-          sync_interval_minutes: newSettings.syncIntervalMinutes,
-          selected_contact_ids: newSettings.selectedContactIds,
-          launch_at_startup: newSettings.launchAtStartup,
-          last_sync_at: newSettings.lastSyncAt,
-        },
+      const saved = await invoke<RustSyncSettings>("update_sync_settings", {
+        settings: toRustSettings(newSettings),
       });
+      setSettings(fromRustSettings(saved));
     } catch (err) {
       console.error("Failed to save settings:", err);
+      throw err;
     }
   }, []);
 
@@ -81,7 +144,7 @@ export function useSettings() {
       console.log("[useSettings] setAutoSync:", enabled);
       const newSettings = { ...settings, autoSyncEnabled: enabled };
       setSettings(newSettings); // Update UI immediately
-      saveSettings(newSettings); // Save in background
+      saveSettings(newSettings).catch(() => {}); // Save in background (errors logged above)
     },
     [settings, saveSettings]
   );
@@ -90,7 +153,7 @@ export function useSettings() {
     (minutes: number) => {
       const newSettings = { ...settings, syncIntervalMinutes: minutes };
       setSettings(newSettings);
-      saveSettings(newSettings);
+      saveSettings(newSettings).catch(() => {});
     },
     [settings, saveSettings]
   );
@@ -99,7 +162,7 @@ export function useSettings() {
     (ids: string[]) => {
       const newSettings = { ...settings, selectedContactIds: ids };
       setSettings(newSettings);
-      saveSettings(newSettings);
+      saveSettings(newSettings).catch(() => {});
     },
     [settings, saveSettings]
   );
@@ -108,16 +171,20 @@ export function useSettings() {
     (enabled: boolean) => {
       const newSettings = { ...settings, launchAtStartup: enabled };
       setSettings(newSettings);
-      saveSettings(newSettings);
+      saveSettings(newSettings).catch(() => {});
     },
     [settings, saveSettings]
   );
 
+  const setTransferLimits = useCallback(
+    (transferLimits: TransferLimits) => saveSettings({ ...settings, transferLimits }),
+    [settings, saveSettings]
+  );
+
   const recordSync = useCallback(async () => {
-    const newSettings = { ...settings, lastSyncAt: new Date().toISOString() };
-    await saveSettings(newSettings);
+    // Updates lastSyncAt through the settings-changed event
     await invoke("record_sync");
-  }, [settings, saveSettings]);
+  }, []);
 
   return {
     settings,
@@ -126,6 +193,7 @@ export function useSettings() {
     setSyncInterval,
     setSelectedContacts,
     setLaunchAtStartup,
+    setTransferLimits,
     recordSync,
     saveSettings,
   };
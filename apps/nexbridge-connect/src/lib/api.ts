@@ -1,4 +1,6 @@
 import { fetch } from "@tauri-apps/plugin-http";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { loadAuth, saveAuth, clearAuth, setCachedCredentials, clearCachedCredentials } from "./auth";
 
 // ---------------------------------------------------------------------------
@@ -16,10 +18,33 @@ export function setDeviceId(id: string) { deviceId = id; }
 export function getLicenseStatus() { return licenseStatus; }
 export function getGraceEndsAt() { return graceEndsAt; }
 
-export function setApiConfig(url: string, token: string) {
+export function setApiConfig(url: string, token: string, refreshToken?: string) {
   baseUrl = url.replace(/\/$/, "");
   accessToken = token;
   setCachedCredentials(token, baseUrl);
+  // Uploads and scans call the API from Rust with the same session
+  invoke("set_api_session", { apiUrl: baseUrl, accessToken: token, refreshToken: refreshToken ?? null }).catch(
+    (err) => console.warn("[api] Failed to pass session to Rust:", err),
+  );
+}
+
+/** Store tokens the Rust API client refreshed on its own. */
+export function listenForApiSessionRefresh(): Promise<UnlistenFn> {
+  return listen<{ access_token: string; refresh_token: string | null }>(
+    "api-session-refreshed",
+    async ({ payload }) => {
+      accessToken = payload.access_token;
+      setCachedCredentials(payload.access_token, baseUrl);
+      const stored = await loadAuth();
+      if (stored) {
+        await saveAuth({
+          ...stored,
+          accessToken: payload.access_token,
+          refreshToken: payload.refresh_token ?? stored.refreshToken,
+        });
+      }
+    },
+  );
 }
 
 export function getAccessToken() {
@@ -155,7 +180,7 @@ export async function login(
 
   const data = JSON.parse(await res.text()) as LoginResponse;
 
-  setApiConfig(url, data.accessToken);
+  setApiConfig(url, data.accessToken, data.refreshToken);
   await saveAuth({
     accessToken: data.accessToken,
     refreshToken: data.refreshToken,
@@ -180,8 +205,7 @@ async function refreshAccessToken(): Promise<boolean> {
     if (!res.ok) return false;
 
     const data = JSON.parse(await res.text()) as { accessToken: string; refreshToken: string };
-    accessToken = data.accessToken;
-    setCachedCredentials(data.accessToken, baseUrl);
+    setApiConfig(baseUrl, data.accessToken, data.refreshToken);
 
     await saveAuth({ ...stored, accessToken: data.accessToken, refreshToken: data.refreshToken });
     return true;
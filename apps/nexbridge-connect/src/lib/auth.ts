@@ -1,4 +1,5 @@
 import { Store } from "@tauri-apps/plugin-store";
+import { invoke } from "@tauri-apps/api/core";
 
 const STORE_PATH = "nexbridge-auth.json";
 
@@ -58,4 +59,6 @@ export function setCachedCredentials(token: string, apiUrl: string): void {
 export function clearCachedCredentials(): void {
   cachedToken = null;
   cachedApiUrl = null;
+  // Sign the Rust API client out as well
+  invoke("clear_api_session").catch(() => {});
 }
@@ -105,8 +105,9 @@ export const precisionScanProcessor: JobProcessor = {
     const doUpload = p.uploadResults !== false;
     const doCleanup = p.cleanupAfter === true;
 
-    // Use NexBRIDGE's own API credentials as fallback (the API doesn't
-    // pass these in the payload — NexBRIDGE is already authenticated)
+    // The Rust side uses NexBRIDGE's own API session unless the payload names
+    // its own API (the API normally doesn't — NexBRIDGE is already
+    // authenticated); these are only checked to skip the upload when signed out
     const apiUrl = p.apiUrl || getBaseUrl();
     const token = p.token || getAccessToken();
 
@@ -217,8 +218,8 @@ export const precisionScanProcessor: JobProcessor = {
         uploadResult = await invoke<UploadScanResult>("upload_scan_results", {
           jobId: meshJobId,
           scanId,
-          apiUrl,
-          token,
+          apiUrl: p.apiUrl,
+          token: p.token,
           formats: allFormats,
         });
 
@@ -1,6 +1,9 @@
 import { useSettings } from "../hooks/useSettings";
 import { SettingsPanel } from "../components/SettingsPanel";
+import { ContactSourcesPanel } from "../components/ContactSourcesPanel";
 import { EnvironmentSelector } from "../components/EnvironmentSelector";
+import { LocalDataPanel } from "../components/LocalDataPanel";
+import { TransferLimitsPanel } from "../components/TransferLimitsPanel";
 
 export default function Settings() {
   const {
@@ -9,6 +12,7 @@ export default function Settings() {
     setAutoSync,
     setSyncInterval,
     setLaunchAtStartup,
+    setTransferLimits,
   } = useSettings();
 
   if (isLoading) {
@@ -32,6 +36,12 @@ export default function Settings() {
         onLaunchAtStartupChange={setLaunchAtStartup}
         selectedCount={settings.selectedContactIds.length}
       />
+
+      <ContactSourcesPanel />
+
+      <TransferLimitsPanel limits={settings.transferLimits} onChange={setTransferLimits} />
+
+      <LocalDataPanel />
     </div>
   );
 }
@@ -0,0 +1,6 @@
+-- AlterTable
+ALTER TABLE "StagedDocument" ADD COLUMN     "originalFileName" TEXT,
+ADD COLUMN     "originalFileUri" TEXT,
+ADD COLUMN     "originalSha256" TEXT,
+ADD COLUMN     "originalSize" BIGINT,
+ADD COLUMN     "originalUploadedAt" TIMESTAMP(3);